@@ -0,0 +1,149 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use move_binary_format::file_format::{DatatypeHandleIndex, FunctionDefinition, SignatureToken, Visibility};
+use move_binary_format::CompiledModule;
+
+/// Loads a compiled Move module from disk.
+pub fn load_module(path: &Path) -> Result<CompiledModule> {
+    let bytes = fs::read(path)
+        .with_context(|| format!("could not read compiled module {}", path.display()))?;
+    CompiledModule::deserialize_with_defaults(&bytes)
+        .with_context(|| format!("could not deserialize compiled module {}", path.display()))
+}
+
+pub fn function_name(module: &CompiledModule, def: &FunctionDefinition) -> String {
+    module
+        .identifier_at(module.function_handle_at(def.function).name)
+        .to_string()
+}
+
+pub fn find_function<'a>(module: &'a CompiledModule, name: &str) -> Option<&'a FunctionDefinition> {
+    module
+        .function_defs()
+        .iter()
+        .find(|def| function_name(module, def) == name)
+}
+
+/// Number of bytecode instructions in the functions defined by the module.
+pub fn instruction_count(module: &CompiledModule) -> usize {
+    module
+        .function_defs()
+        .iter()
+        .filter_map(|def| def.code.as_ref())
+        .map(|code| code.code.len())
+        .sum()
+}
+
+/// Functions that can be called from outside the module.
+pub fn public_functions(module: &CompiledModule) -> impl Iterator<Item = &FunctionDefinition> {
+    module
+        .function_defs()
+        .iter()
+        .filter(|def| def.visibility == Visibility::Public || def.is_entry)
+}
+
+pub fn format_type(module: &CompiledModule, token: &SignatureToken) -> String {
+    match token {
+        SignatureToken::Bool => "bool".to_string(),
+        SignatureToken::U8 => "u8".to_string(),
+        SignatureToken::U16 => "u16".to_string(),
+        SignatureToken::U32 => "u32".to_string(),
+        SignatureToken::U64 => "u64".to_string(),
+        SignatureToken::U128 => "u128".to_string(),
+        SignatureToken::U256 => "u256".to_string(),
+        SignatureToken::Address => "address".to_string(),
+        SignatureToken::Signer => "signer".to_string(),
+        SignatureToken::Vector(inner) => format!("vector<{}>", format_type(module, inner)),
+        SignatureToken::Datatype(idx) => module
+            .identifier_at(module.datatype_handle_at(*idx).name)
+            .to_string(),
+        SignatureToken::DatatypeInstantiation(inst) => {
+            let (idx, type_args) = &**inst;
+            let type_args = type_args
+                .iter()
+                .map(|t| format_type(module, t))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!(
+                "{}<{}>",
+                module.identifier_at(module.datatype_handle_at(*idx).name),
+                type_args
+            )
+        }
+        SignatureToken::Reference(inner) => format!("&{}", format_type(module, inner)),
+        SignatureToken::MutableReference(inner) => format!("&mut {}", format_type(module, inner)),
+        SignatureToken::TypeParameter(idx) => format!("T{}", idx),
+    }
+}
+
+/// Formats a type as written in the source of another module, datatypes
+/// qualified with their address and module, e.g. `0x2::coin::Coin<0x2::sui::SUI>`.
+pub fn format_qualified_type(module: &CompiledModule, token: &SignatureToken) -> String {
+    let datatype = |idx: &DatatypeHandleIndex| {
+        let handle = module.datatype_handle_at(*idx);
+        let owner = module.module_handle_at(handle.module);
+        format!(
+            "{}::{}::{}",
+            module.address_identifier_at(owner.address).to_hex_literal(),
+            module.identifier_at(owner.name),
+            module.identifier_at(handle.name)
+        )
+    };
+    match token {
+        SignatureToken::Vector(inner) => format!("vector<{}>", format_qualified_type(module, inner)),
+        SignatureToken::Datatype(idx) => datatype(idx),
+        SignatureToken::DatatypeInstantiation(inst) => {
+            let (idx, type_args) = &**inst;
+            let type_args = type_args
+                .iter()
+                .map(|t| format_qualified_type(module, t))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{}<{}>", datatype(idx), type_args)
+        }
+        SignatureToken::Reference(inner) => format!("&{}", format_qualified_type(module, inner)),
+        SignatureToken::MutableReference(inner) => format!("&mut {}", format_qualified_type(module, inner)),
+        _ => format_type(module, token),
+    }
+}
+
+/// Formats a function as `name(arg0: T0, ...): R`.
+pub fn format_function_signature(module: &CompiledModule, def: &FunctionDefinition) -> String {
+    let handle = module.function_handle_at(def.function);
+    let params = module
+        .signature_at(handle.parameters)
+        .0
+        .iter()
+        .enumerate()
+        .map(|(i, t)| format!("arg{}: {}", i, format_type(module, t)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let returns = module
+        .signature_at(handle.return_)
+        .0
+        .iter()
+        .map(|t| format_type(module, t))
+        .collect::<Vec<_>>();
+    let type_params = if handle.type_parameters.is_empty() {
+        String::new()
+    } else {
+        let names = (0..handle.type_parameters.len())
+            .map(|i| format!("T{}", i))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("<{}>", names)
+    };
+
+    match returns.len() {
+        0 => format!("{}{}({})", function_name(module, def), type_params, params),
+        1 => format!("{}{}({}): {}", function_name(module, def), type_params, params, returns[0]),
+        _ => format!(
+            "{}{}({}): ({})",
+            function_name(module, def),
+            type_params,
+            params,
+            returns.join(", ")
+        ),
+    }
+}
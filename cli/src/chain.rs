@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Value};
+
+/// Default JSON-RPC endpoint packages are fetched from.
+pub const DEFAULT_RPC_URL: &str = "https://fullnode.mainnet.sui.io:443";
+
+/// Bytecode of an on-chain package.
+#[derive(Clone, Debug)]
+pub struct Package {
+    /// Compiled modules, by name
+    pub modules: BTreeMap<String, Vec<u8>>,
+    /// Ids of the packages it is linked against
+    pub dependencies: Vec<String>,
+}
+
+/// Downloads the package `id` from the fullnode at `rpc` with `sui_getObject`.
+pub fn fetch_package(rpc: &str, id: &str) -> Result<Package> {
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "sui_getObject",
+        "params": [id, { "showBcs": true }],
+    });
+    let response: Value = ureq::post(rpc)
+        .send_json(request)
+        .with_context(|| format!("failed to request package {} from {}", id, rpc))?
+        .into_json()
+        .with_context(|| format!("invalid response from {}", rpc))?;
+    if let Some(error) = response.get("error").or_else(|| response["result"].get("error")) {
+        bail!("could not fetch package {}: {}", id, error);
+    }
+
+    let bcs = &response["result"]["data"]["bcs"];
+    if bcs["dataType"] != "package" {
+        bail!("object {} is not a package", id);
+    }
+    let modules = bcs["moduleMap"]
+        .as_object()
+        .with_context(|| format!("package {} has no modules", id))?
+        .iter()
+        .map(|(name, bytes)| {
+            let bytes = bytes
+                .as_str()
+                .and_then(|b| STANDARD.decode(b).ok())
+                .with_context(|| format!("invalid bytecode for module {} of package {}", name, id))?;
+            Ok((name.clone(), bytes))
+        })
+        .collect::<Result<_>>()?;
+    // Dependencies are linked against the version the package was published with
+    let dependencies = bcs["linkageTable"]
+        .as_object()
+        .map(|table| {
+            table
+                .values()
+                .filter_map(|link| link["upgraded_id"].as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(Package { modules, dependencies })
+}
+
+/// Default REST endpoint Aptos modules are fetched from.
+pub const DEFAULT_APTOS_NODE_URL: &str = "https://fullnode.mainnet.aptoslabs.com";
+
+/// Downloads the bytecode of the module `address::name` from the Aptos node at `node`.
+pub fn fetch_aptos_module(node: &str, address: &str, name: &str) -> Result<Vec<u8>> {
+    let url = format!("{}/v1/accounts/{}/module/{}", node.trim_end_matches('/'), address, name);
+    let response: Value = ureq::get(&url)
+        .call()
+        .with_context(|| format!("failed to request module {}::{} from {}", address, name, node))?
+        .into_json()
+        .with_context(|| format!("invalid response from {}", url))?;
+    let bytecode = response["bytecode"]
+        .as_str()
+        .with_context(|| format!("no bytecode for module {}::{}", address, name))?;
+    decode_hex(bytecode.trim_start_matches("0x"))
+        .with_context(|| format!("invalid bytecode for module {}::{}", address, name))
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -0,0 +1,284 @@
+use std::{collections::BTreeMap, fs, path::{Path, PathBuf}};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{BuildOptions, Target};
+
+pub(crate) const CONFIG_FILE: &str = "fuzz.toml";
+
+/// Contents of the `fuzz.toml` file at the root of the fuzz project.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct FuzzConfig {
+    /// Path to the `move-fuzzer-worker` binary
+    pub worker_path: Option<PathBuf>,
+    /// Where `corpus push` and `corpus pull` share the corpora, as an
+    /// `s3://`, `gs://` or `file://` URL or a directory
+    pub corpus_url: Option<String>,
+    #[serde(default)]
+    pub targets: BTreeMap<String, TargetConfig>,
+}
+
+/// Settings persisted for a single fuzz target. Flags given on the command
+/// line take precedence over these.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TargetConfig {
+    /// Module containing the target function
+    pub module: Option<String>,
+    /// Function to fuzz
+    pub function: Option<String>,
+    /// libFuzzer arguments passed before the ones given on the command line
+    #[serde(default)]
+    pub libfuzzer_args: Vec<String>,
+    /// libFuzzer's `-rss_limit_mb`
+    pub rss_limit_mb: Option<u64>,
+    /// libFuzzer's `-timeout`, in seconds
+    pub timeout: Option<u64>,
+    /// libFuzzer's `-max_len`
+    pub max_len: Option<usize>,
+    /// Dictionaries given to libFuzzer, relative to the fuzz project, replaced
+    /// by those given on the command line
+    #[serde(default)]
+    pub dictionaries: Vec<PathBuf>,
+    /// Input directories read along with the corpus, relative to the fuzz project
+    #[serde(default)]
+    pub corpus_dirs: Vec<PathBuf>,
+    /// Gas available to each execution
+    pub gas_limit: Option<u64>,
+    /// Abort codes that are not reported as crashes
+    #[serde(default)]
+    pub allowed_abort_codes: Vec<u64>,
+    /// Actions taken on failed executions, as `class=action`
+    #[serde(default)]
+    pub on_error: Vec<String>,
+    /// Whether the target returns `false` to report a violated property
+    #[serde(default)]
+    pub oracle_bool: bool,
+    /// Function called after each execution, as `module::function`
+    pub invariant: Option<String>,
+    /// Functions called before the target on every input, as `module::function`
+    #[serde(default)]
+    pub setup: Vec<String>,
+    /// Parameters pinned to a constant value, as `argN=value`
+    #[serde(default)]
+    pub fix: Vec<String>,
+    /// Addresses signers are picked from, as hex literals or `deployer`
+    #[serde(default)]
+    pub signers: Vec<String>,
+    /// Addresses the named addresses are published at, as `name=0x...`
+    #[serde(default)]
+    pub addresses: Vec<String>,
+    /// Number of test accounts added to the signer pool
+    pub test_signers: Option<u64>,
+    /// Whether random signers are still generated besides the pooled ones
+    #[serde(default)]
+    pub random_signers: bool,
+    /// Weights of the generated addresses, as `named=3,core=1,zero=1,random=3`
+    pub address_weights: Option<String>,
+    /// Percentage of the integers picked among boundary values
+    pub special_int_percent: Option<u8>,
+    /// Whether each worker samples its own generation profile
+    #[serde(default)]
+    pub swarm: bool,
+    /// Maximum nesting of the generated values
+    pub max_input_depth: Option<usize>,
+    /// Maximum number of values generated from one input
+    pub max_input_values: Option<usize>,
+    /// Elements per vector counted when bounding the input length
+    pub vector_budget: Option<usize>,
+    /// Whether the Move coverage of the dependencies is recorded too
+    #[serde(default)]
+    pub include_deps: bool,
+    /// Whether `Option`, `FixedPoint32` and Sui object values are generated
+    /// field by field
+    #[serde(default)]
+    pub raw_std_types: bool,
+    /// REST API of the node the forked on-chain state is read from
+    pub fork_url: Option<String>,
+    /// Ledger version the forked state is read at
+    pub fork_version: Option<u64>,
+    /// State machine model inputs are interpreted with, relative to the fuzz project
+    pub model: Option<PathBuf>,
+    /// Corpus directory, relative to the fuzz project
+    pub corpus: Option<PathBuf>,
+    /// Artifacts directory, relative to the fuzz project
+    pub artifacts: Option<PathBuf>,
+}
+
+impl FuzzConfig {
+    /// Reads the configuration, returning an empty one if the file doesn't exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(FuzzConfig::default());
+        }
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("could not read the config file: {}", path.display()))?;
+        toml::from_str(&data)
+            .with_context(|| format!("could not decode the config file at {}", path.display()))
+    }
+
+    /// Writes the configuration back, comments and formatting are not kept.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = toml::to_string_pretty(self).context("could not encode the config")?;
+        fs::write(path, data)
+            .with_context(|| format!("could not write the config file: {}", path.display()))
+    }
+
+    /// Finds the settings of a target, either by name or by module and function.
+    pub fn target(&self, target: &Target) -> Option<&TargetConfig> {
+        if let Some(name) = &target.target_name {
+            if let Some(config) = self.targets.get(name) {
+                return Some(config);
+            }
+        }
+        let module = target.get_module_name();
+        let function = target.get_target_function();
+        self.targets.values().find(|config| {
+            config.module.as_deref() == Some(module.as_str())
+                && config.function.as_deref() == Some(function.as_str())
+        })
+    }
+}
+
+impl TargetConfig {
+    /// The libFuzzer flags of the settings, followed by `libfuzzer_args`.
+    pub fn libfuzzer_flags(&self, fuzz_dir: &Path) -> Vec<String> {
+        let mut flags = vec![];
+        if let Some(rss_limit_mb) = self.rss_limit_mb {
+            flags.push(format!("-rss_limit_mb={}", rss_limit_mb));
+        }
+        if let Some(timeout) = self.timeout {
+            flags.push(format!("-timeout={}", timeout));
+        }
+        if let Some(max_len) = self.max_len {
+            flags.push(format!("-max_len={}", max_len));
+        }
+        for dictionary in &self.dictionaries {
+            flags.push(format!("-dict={}", fuzz_dir.join(dictionary).display()));
+        }
+        flags.extend(self.libfuzzer_args.iter().cloned());
+        flags
+    }
+
+    /// Fills the options not given on the command line with the persisted ones.
+    pub fn apply(&self, build: &mut BuildOptions) {
+        if build.target.target_module.is_none() {
+            build.target.target_module = self.module.clone();
+        }
+        if build.target.target_function.is_none() {
+            build.target.target_function = self.function.clone();
+        }
+        if build.worker.gas_limit.is_none() {
+            build.worker.gas_limit = self.gas_limit;
+        }
+        if build.worker.allowed_abort_codes.is_empty() {
+            build.worker.allowed_abort_codes = self.allowed_abort_codes.clone();
+        }
+        if build.worker.error_actions.is_empty() {
+            build.worker.error_actions = self.on_error.clone();
+        }
+        build.worker.oracle_bool |= self.oracle_bool;
+        if build.worker.invariant.is_none() {
+            build.worker.invariant = self.invariant.clone();
+        }
+        if build.worker.setup_functions.is_empty() {
+            build.worker.setup_functions = self.setup.clone();
+        }
+        if build.worker.fixed_args.is_empty() {
+            build.worker.fixed_args = self.fix.clone();
+        }
+        if build.worker.signers.is_empty() {
+            build.worker.signers = self.signers.clone();
+        }
+        if build.worker.addresses.is_empty() {
+            build.worker.addresses = self.addresses.clone();
+        }
+        if build.worker.test_signers == 0 {
+            build.worker.test_signers = self.test_signers.unwrap_or_default();
+        }
+        build.worker.random_signers |= self.random_signers;
+        if build.worker.address_weights.is_none() {
+            build.worker.address_weights = self.address_weights.clone();
+        }
+        if build.worker.special_int_percent.is_none() {
+            build.worker.special_int_percent = self.special_int_percent;
+        }
+        build.worker.swarm |= self.swarm;
+        if build.worker.max_input_depth.is_none() {
+            build.worker.max_input_depth = self.max_input_depth;
+        }
+        if build.worker.max_input_values.is_none() {
+            build.worker.max_input_values = self.max_input_values;
+        }
+        if build.worker.vector_budget.is_none() {
+            build.worker.vector_budget = self.vector_budget;
+        }
+        build.worker.include_deps |= self.include_deps;
+        build.worker.raw_std_types |= self.raw_std_types;
+        if build.worker.fork_url.is_none() {
+            build.worker.fork_url = self.fork_url.clone();
+        }
+        if build.worker.fork_version.is_none() {
+            build.worker.fork_version = self.fork_version;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_config() {
+        let config: FuzzConfig = toml::from_str(
+            r#"
+[targets.transfer]
+module = "coin"
+function = "transfer"
+libfuzzer-args = ["-max_len=128"]
+gas-limit = 100000
+allowed-abort-codes = [1, 2]
+"#,
+        )
+        .unwrap();
+
+        let target = &config.targets["transfer"];
+        assert_eq!(target.module.as_deref(), Some("coin"));
+        assert_eq!(target.function.as_deref(), Some("transfer"));
+        assert_eq!(target.libfuzzer_args, vec!["-max_len=128".to_string()]);
+        assert_eq!(target.gas_limit, Some(100000));
+        assert_eq!(target.allowed_abort_codes, vec![1, 2]);
+        assert_eq!(target.corpus, None);
+    }
+
+    #[test]
+    fn libfuzzer_flags_come_before_libfuzzer_args() {
+        let config: FuzzConfig = toml::from_str(
+            r#"
+[targets.transfer]
+libfuzzer-args = ["-max_len=64"]
+rss-limit-mb = 4096
+timeout = 10
+max-len = 128
+dictionaries = ["coin.dict"]
+corpus-dirs = ["seeds"]
+"#,
+        )
+        .unwrap();
+
+        let target = &config.targets["transfer"];
+        assert_eq!(
+            target.libfuzzer_flags(Path::new("fuzz")),
+            vec![
+                "-rss_limit_mb=4096",
+                "-timeout=10",
+                "-max_len=128",
+                "-dict=fuzz/coin.dict",
+                "-max_len=64"
+            ]
+        );
+        assert_eq!(target.corpus_dirs, vec![PathBuf::from("seeds")]);
+    }
+}
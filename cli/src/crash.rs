@@ -0,0 +1,170 @@
+use std::{fmt, process::Output};
+
+/// Prefix of the line printed by the worker when an execution fails.
+pub const MOVE_ERROR_PREFIX: &str = "Move error: ";
+
+/// Prefix of the lines printed by the worker to detail a failed execution.
+pub const MOVE_REPORT_PREFIX: &str = "Move report: ";
+
+/// Returns the lines detailing a failed execution (VM status, location and
+/// stack trace) from the worker output.
+pub fn vm_report(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .filter_map(|l| l.strip_prefix(MOVE_REPORT_PREFIX))
+        .map(String::from)
+        .collect()
+}
+
+/// What happened when replaying a single input.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Outcome {
+    /// The input ran without failing
+    NoCrash,
+    /// The Move execution failed, with the error reported by the worker
+    MoveError(String),
+    /// libFuzzer killed the execution because it took too long
+    Timeout,
+    /// libFuzzer killed the execution because it used too much memory
+    OutOfMemory,
+    /// The worker failed for another reason, e.g. a panic in the runner
+    Other(String),
+}
+
+impl Outcome {
+    /// Classifies the output of a worker that replayed a single input.
+    pub fn from_output(output: &Output) -> Self {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        if let Some(error) = stderr
+            .lines()
+            .chain(stdout.lines())
+            .find_map(|l| l.strip_prefix(MOVE_ERROR_PREFIX))
+        {
+            return Outcome::MoveError(error.trim().to_string());
+        }
+        if output.status.success() {
+            return Outcome::NoCrash;
+        }
+        if stderr.contains("ERROR: libFuzzer: timeout") {
+            return Outcome::Timeout;
+        }
+        if stderr.contains("ERROR: libFuzzer: out-of-memory") {
+            return Outcome::OutOfMemory;
+        }
+
+        let reason = stderr
+            .lines()
+            .find(|l| l.contains("panicked at") || l.contains("ERROR:"))
+            .map(|l| l.trim().to_string())
+            .unwrap_or_else(|| output.status.to_string());
+        Outcome::Other(reason)
+    }
+
+    /// Short name of the kind of failure, without its details.
+    pub fn class(&self) -> String {
+        match self {
+            Outcome::NoCrash => String::from("NoCrash"),
+            Outcome::MoveError(error) => error.split(" - ").next().unwrap_or(error).to_string(),
+            Outcome::Timeout => String::from("Timeout"),
+            Outcome::OutOfMemory => String::from("OutOfMemory"),
+            Outcome::Other(_) => String::from("Other"),
+        }
+    }
+
+    /// Details of the failure after its class, such as
+    /// `code 3 in 0x2::coin::mint+12` for an abort.
+    pub fn details(&self) -> Option<&str> {
+        match self {
+            Outcome::MoveError(error) => error.split_once(" - ").map(|(_, details)| details),
+            Outcome::Other(reason) => Some(reason),
+            _ => None,
+        }
+    }
+
+    /// Abort code of a failed `abort` or `assert!` and the error constant
+    /// the worker named it after, from `code 5 in ...` or
+    /// `code E_INSUFFICIENT_BALANCE (5) in ...`.
+    fn abort(&self) -> Option<(Option<&str>, u64)> {
+        let code = self.details()?.strip_prefix("code ")?.rsplit_once(" in ")?.0;
+        match code.strip_suffix(')').and_then(|code| code.rsplit_once(" (")) {
+            Some((constant, code)) => Some((Some(constant), code.parse().ok()?)),
+            None => Some((None, code.parse().ok()?)),
+        }
+    }
+
+    pub fn abort_code(&self) -> Option<u64> {
+        self.abort().map(|(_, code)| code)
+    }
+
+    pub fn abort_constant(&self) -> Option<&str> {
+        self.abort().and_then(|(constant, _)| constant)
+    }
+
+    /// Where a Move error happened, as `address::module::function+offset`
+    /// and the source line the worker found for it, e.g. `coin.move:42`.
+    fn code_location(&self) -> Option<(&str, Option<&str>)> {
+        let location = match self {
+            Outcome::MoveError(_) => self.details()?.rsplit_once(" in ")?.1,
+            _ => return None,
+        };
+        match location.strip_suffix(')').and_then(|l| l.split_once(" (")) {
+            Some((location, line)) => Some((location, Some(line))),
+            None => Some((location, None)),
+        }
+    }
+
+    pub fn location(&self) -> Option<&str> {
+        self.code_location().map(|(location, _)| location)
+    }
+
+    pub fn source_line(&self) -> Option<&str> {
+        self.code_location().and_then(|(_, line)| line)
+    }
+
+    pub fn is_crash(&self) -> bool {
+        *self != Outcome::NoCrash
+    }
+
+    /// Start of the name given to an artifact failing this way, such as
+    /// `abort-3-coin` for an abort with code 3 in module `coin`.
+    pub fn artifact_prefix(&self) -> String {
+        match self {
+            Outcome::MoveError(_) => {}
+            // An artifact passing when replayed failed only in the campaign
+            Outcome::NoCrash => return String::from("flaky"),
+            Outcome::Timeout => return String::from("timeout"),
+            Outcome::OutOfMemory => return String::from("oom"),
+            Outcome::Other(_) => return String::from("other"),
+        }
+        let mut prefix = String::new();
+        for (i, c) in self.class().chars().enumerate() {
+            if c.is_ascii_uppercase() && i > 0 {
+                prefix.push('-');
+            }
+            prefix.push(c.to_ascii_lowercase());
+        }
+        if let Some(code) = self.abort_code() {
+            prefix.push('-');
+            prefix.push_str(&code.to_string());
+        }
+        if let Some(module) = self.location().and_then(|location| location.split("::").nth(1)) {
+            prefix.push('-');
+            prefix.push_str(module);
+        }
+        prefix
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::NoCrash => write!(f, "NoCrash"),
+            Outcome::MoveError(error) => write!(f, "{}", error),
+            Outcome::Timeout => write!(f, "Timeout"),
+            Outcome::OutOfMemory => write!(f, "OutOfMemory"),
+            Outcome::Other(reason) => write!(f, "Other - {}", reason),
+        }
+    }
+}
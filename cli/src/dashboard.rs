@@ -0,0 +1,85 @@
+use std::{collections::VecDeque, io::Write};
+
+use colored::Colorize;
+
+use crate::stats::CampaignStats;
+
+/// Other libFuzzer lines kept below the statistics, such as crash reports.
+const LOG_LINES: usize = 6;
+
+/// A live summary of a fuzzing campaign, redrawn in place on stderr from the
+/// libFuzzer output instead of printing it line by line.
+#[derive(Default)]
+pub struct Dashboard {
+    log: VecDeque<String>,
+    /// Lines drawn last time, erased before drawing again
+    drawn: usize,
+}
+
+impl Dashboard {
+    /// Redraws the dashboard after `line`, kept below the statistics unless
+    /// it is a status line.
+    pub fn update(&mut self, stats: &CampaignStats, line: &str, status: bool) {
+        if !status {
+            if self.log.len() == LOG_LINES {
+                self.log.pop_front();
+            }
+            self.log.push_back(line.to_owned());
+        }
+        self.draw(stats);
+    }
+
+    fn lines(&self, stats: &CampaignStats) -> Vec<String> {
+        let or_dash = |value: Option<String>| value.unwrap_or_else(|| String::from("-"));
+        let coverage = match (stats.coverage_percent(), stats.module_instructions) {
+            (Some(percent), Some(total)) => format!("{}/{} ({:.1}%)", stats.covered, total, percent),
+            _ => stats.covered.to_string(),
+        };
+        let mut lines = vec![
+            format!("{} {}", "Fuzzing".bold(), stats.target.get_command()),
+            format!("  Elapsed:            {}s", stats.timer.elapsed().as_secs()),
+            format!("  Executions:         {}", or_dash(stats.runs.map(|n| n.to_string()))),
+            format!("  Executions/s:       {}", or_dash(stats.exec_per_sec.map(|n| n.to_string()))),
+            format!("  Corpus:             {}", or_dash(stats.corpus.clone())),
+            format!("  Move coverage:      {}", coverage),
+            format!(
+                "  Last new coverage:  {}",
+                or_dash(stats.last_new_coverage.map(|t| format!("{}s ago", t.elapsed().as_secs())))
+            ),
+            format!(
+                "  Move executions:    {}",
+                match stats.workers.executions {
+                    0 => String::from("-"),
+                    executions => format!(
+                        "{} ({:.1}% undecodable, {:.1}% rejected, {:.1} argument bytes each)",
+                        executions,
+                        stats.workers.percent(stats.workers.undecodable),
+                        stats.workers.percent(stats.workers.rejected),
+                        stats.workers.argument_bytes_per_execution()
+                    ),
+                }
+            ),
+            format!(
+                "  Last new Move code: {}",
+                or_dash(stats.workers.since_new_coverage().map(|t| format!("{}s ago", t.as_secs())))
+            ),
+            format!("  Artifacts:          {}", stats.artifacts.to_string().red()),
+            String::new(),
+        ];
+        lines.extend(self.log.iter().cloned());
+        lines
+    }
+
+    fn draw(&mut self, stats: &CampaignStats) {
+        let lines = self.lines(stats);
+        let mut stderr = std::io::stderr().lock();
+        // Move back to the first line drawn and clear everything below
+        if self.drawn > 0 {
+            let _ = write!(stderr, "\x1b[{}F\x1b[J", self.drawn);
+        }
+        for line in &lines {
+            let _ = writeln!(stderr, "{}", line);
+        }
+        self.drawn = lines.len();
+    }
+}
@@ -0,0 +1,134 @@
+use std::{io::IsTerminal, path::Path};
+
+use colored::Colorize;
+use move_binary_format::CompiledModule;
+
+use crate::{abi::load_module, crash::Outcome};
+
+/// Bit set in the abort codes built from Move 2024 error constants, the
+/// "clever errors" carrying the constant and source line of the abort.
+const CLEVER_ERROR_TAG: u64 = 1 << 63;
+
+/// Identifier index of a clever error aborting without a constant, such as
+/// an `assert!` without an error code.
+const NO_IDENTIFIER: u16 = u16::MAX;
+
+/// Source line and identifier index of the constant a clever abort code was
+/// built from, `None` for a plain abort code.
+fn clever_error_parts(code: u64) -> Option<(u16, u16)> {
+    if code & CLEVER_ERROR_TAG == 0 {
+        return None;
+    }
+    let line = (code >> 32) as u16;
+    let identifier = (code >> 16) as u16;
+    (identifier != NO_IDENTIFIER).then_some((line, identifier))
+}
+
+/// Names the error constant `code` was built from, with its source line,
+/// when `module` is the one that aborted and was compiled with clever errors.
+pub fn error_constant(module: &CompiledModule, code: u64) -> Option<String> {
+    let (line, identifier) = clever_error_parts(code)?;
+    module
+        .identifiers()
+        .get(identifier as usize)
+        .map(|name| format!("{} (line {})", name, line))
+}
+
+/// Finds the compiled module an error location such as
+/// `0x2::coin::mint+12` points to among the ones under `modules_dir`.
+fn aborting_module(modules_dir: &Path, location: &str) -> Option<CompiledModule> {
+    let mut parts = location.split("::");
+    let (address, name) = (parts.next()?, parts.next()?);
+    let id = format!("{}::{}", address, name);
+    walkdir::WalkDir::new(modules_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().file_stem().map_or(false, |stem| stem == name))
+        .filter_map(|e| load_module(e.path()).ok())
+        .find(|module| module.self_id().to_string() == id)
+}
+
+/// A failing input rendered for the terminal: the error class, the abort
+/// code and the constant it names, the failing function, the arguments the
+/// input decodes to and the commands to reproduce it.
+pub struct Diagnostic<'a> {
+    pub outcome: &'a Outcome,
+    pub artifact: &'a Path,
+    /// Lines detailing the VM error, see `vm_report`
+    pub report: &'a [String],
+    /// Arguments the input decodes to, one per line
+    pub arguments: Option<&'a str>,
+    /// Commands for the failure, each after what it does
+    pub commands: Vec<(&'static str, String)>,
+}
+
+impl Diagnostic<'_> {
+    /// Renders the diagnostic, looking the error constant up in the modules
+    /// under `modules_dir`.
+    pub fn render(&self, modules_dir: &Path) -> String {
+        let mut lines = vec![format!(
+            "{}: {}",
+            format!("error[{}]", self.outcome.class()).red().bold(),
+            self.outcome.details().unwrap_or_default()
+        )];
+        let field = |name: &str, value: &dyn std::fmt::Display| format!("  {:>9} {}", name.bold(), value);
+        lines.push(field("input:", &self.artifact.display()));
+        if let Some(code) = self.outcome.abort_code() {
+            lines.push(field("code:", &code));
+            // Named by the worker when the build left source maps
+            let constant = self.outcome.abort_constant().map(String::from).or_else(|| {
+                self.outcome
+                    .location()
+                    .and_then(|location| aborting_module(modules_dir, location))
+                    .and_then(|module| error_constant(&module, code))
+            });
+            if let Some(constant) = constant {
+                lines.push(field("constant:", &constant.yellow()));
+            }
+        }
+        if let Some(location) = self.outcome.location() {
+            let function = location.rsplit_once('+').map_or(location, |(function, _)| function);
+            lines.push(field("function:", &function.cyan()));
+        }
+        if let Some(line) = self.outcome.source_line() {
+            lines.push(field("source:", &line));
+        }
+        let block = |lines: &mut Vec<String>, title: &str, body: &mut dyn Iterator<Item = &str>| {
+            lines.push(format!("\n  {}", title.bold()));
+            lines.extend(body.map(|l| format!("      {}", l)));
+        };
+        if let Some(arguments) = self.arguments {
+            block(&mut lines, "Arguments", &mut arguments.lines());
+        }
+        if !self.report.is_empty() {
+            block(&mut lines, "Move VM", &mut self.report.iter().map(String::as_str));
+        }
+        for (title, command) in &self.commands {
+            lines.push(format!("\n  {}", title.bold()));
+            lines.push(format!("      {}", command.green()));
+        }
+        lines.join("\n")
+    }
+
+    /// Prints the diagnostic on stderr, in color when it is a terminal.
+    pub fn print(&self, modules_dir: &Path) {
+        let color = std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        colored::control::set_override(color);
+        eprintln!("{}\n", self.render(modules_dir));
+        colored::control::unset_override();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clever_error_parts_decodes_the_abort_code() {
+        // Line 42, identifier 7, constant 3
+        let code = CLEVER_ERROR_TAG | (42 << 32) | (7 << 16) | 3;
+        assert_eq!(clever_error_parts(code), Some((42, 7)));
+        assert_eq!(clever_error_parts(CLEVER_ERROR_TAG | (42 << 32) | (0xffff << 16) | 0xffff), None);
+        assert_eq!(clever_error_parts(3), None);
+    }
+}
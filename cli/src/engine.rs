@@ -0,0 +1,333 @@
+use std::{ffi::OsString, fmt, fs, path::{Path, PathBuf}, process::Command};
+
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+
+use crate::{options::BuildOptions, project::{FuzzProject, WORKER_BIN}, utils::find_in_path};
+
+/// Fuzzing engines the worker can be built for and driven by.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FuzzEngine {
+    /// libFuzzer, linked into the worker
+    #[default]
+    #[value(name = "libfuzzer")]
+    LibFuzzer,
+    /// AFL++, running a worker built with `install-worker --engine afl` in its forkserver
+    Afl,
+}
+
+impl fmt::Display for FuzzEngine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        write!(f, "{}", value.get_name())
+    }
+}
+
+impl FuzzEngine {
+    pub fn engine(self) -> Box<dyn Engine> {
+        match self {
+            FuzzEngine::LibFuzzer => Box::new(LibFuzzer),
+            FuzzEngine::Afl => Box::new(Afl),
+        }
+    }
+}
+
+/// What a fuzzing campaign of one target is asked to do, whatever the engine.
+pub struct Campaign {
+    /// Corpus directories, the first one receiving the new inputs
+    pub corpus: Vec<PathBuf>,
+    pub artifacts: PathBuf,
+    /// Directory the engine keeps its own state in
+    pub work_dir: PathBuf,
+    pub jobs: u16,
+    pub runs: Option<u64>,
+    pub max_total_time: Option<u64>,
+    /// Whether slow executions are reported as findings, AFL++ keeping them
+    /// apart as hangs anyway
+    pub timeouts: bool,
+    /// Seed of the engine's random choices, its own if `None`
+    pub seed: Option<u32>,
+    /// Additional arguments given to the engine on the command line
+    pub args: Vec<String>,
+    /// Dictionary learned by earlier campaigns, used along with the one given
+    /// in the arguments if any
+    pub dictionary: Option<PathBuf>,
+}
+
+/// The engine-specific parts of fuzzing with the worker: the binary holding
+/// the engine's entry point and how a campaign is passed to it.
+pub trait Engine {
+    /// Name of the worker binary built for the engine.
+    fn worker_bin(&self) -> &'static str;
+
+    /// Cargo features the worker binary is built with.
+    fn worker_features(&self) -> &'static [&'static str];
+
+    /// Flags the worker binary is built with, on top of the common ones.
+    fn worker_rustflags(&self) -> &'static str;
+
+    /// The command running `campaign` on the worker of `build`.
+    fn fuzz_command(&self, project: &FuzzProject, build: &BuildOptions, campaign: &Campaign) -> Result<Command>;
+
+    /// Moves what the engine found to the corpus and artifacts directories
+    /// once it exits, returning the number of failing inputs.
+    fn collect(&self, campaign: &Campaign) -> Result<usize>;
+}
+
+/// Splits the arguments given to libFuzzer, in fuzz.toml and after `--`, into
+/// flags, `-flag=value` for libFuzzer and `--flag=value` for the worker,
+/// which libFuzzer ignores, and directories of inputs.
+pub(crate) fn split_libfuzzer_args(args: &[String]) -> (Vec<String>, Vec<PathBuf>) {
+    let (flags, dirs): (Vec<_>, Vec<_>) = args.iter().cloned().partition(|arg| arg.starts_with('-'));
+    (flags, dirs.into_iter().map(PathBuf::from).collect())
+}
+
+/// The dictionary libFuzzer is given for `campaign`: the ones of the `-dict=`
+/// flags and the learned one, merged into the work directory when there are
+/// several, since libFuzzer reads a single dictionary. `None` when the flags
+/// already name the only one.
+fn libfuzzer_dictionary(flags: &[String], campaign: &Campaign) -> Result<Option<PathBuf>> {
+    let mut dictionaries: Vec<PathBuf> = flags
+        .iter()
+        .filter_map(|flag| flag.strip_prefix("-dict="))
+        .map(PathBuf::from)
+        .collect();
+    dictionaries.dedup();
+    if let Some(learned) = &campaign.dictionary {
+        dictionaries.push(learned.clone());
+    }
+    match dictionaries.len() {
+        0 => return Ok(None),
+        1 => return Ok(campaign.dictionary.clone()),
+        _ => {}
+    }
+    let mut merged = String::new();
+    for dictionary in &dictionaries {
+        merged += &fs::read_to_string(dictionary)
+            .with_context(|| format!("failed to read the dictionary {}", dictionary.display()))?;
+        if !merged.is_empty() && !merged.ends_with('\n') {
+            merged.push('\n');
+        }
+    }
+    fs::create_dir_all(&campaign.work_dir)
+        .with_context(|| format!("could not make the directory {}", campaign.work_dir.display()))?;
+    let path = campaign.work_dir.join("merged.dict");
+    fs::write(&path, merged).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(Some(path))
+}
+
+pub struct LibFuzzer;
+
+impl Engine for LibFuzzer {
+    fn worker_bin(&self) -> &'static str {
+        WORKER_BIN
+    }
+
+    fn worker_features(&self) -> &'static [&'static str] {
+        &["link_libfuzzer"]
+    }
+
+    fn worker_rustflags(&self) -> &'static str {
+        ""
+    }
+
+    fn fuzz_command(&self, project: &FuzzProject, build: &BuildOptions, campaign: &Campaign) -> Result<Command> {
+        let mut cmd = project.get_run_fuzzer_command(build)?;
+
+        let (flags, input_dirs) = split_libfuzzer_args(&project.libfuzzer_args(&build.target, &campaign.args));
+        // libFuzzer keeps the last value of a flag, the defaults go first
+        if !campaign.timeouts {
+            cmd.arg("-timeout=0");
+        }
+        // The worker seeds its swarm profile with it too
+        if let Some(seed) = campaign.seed {
+            cmd.arg(format!("-seed={}", seed));
+        }
+        // Longer inputs only differ by bytes the worker never reads
+        if !flags.iter().any(|arg| arg.starts_with("-max_len=")) {
+            if let Some(len) = project.max_input_len(build)? {
+                cmd.arg(format!("-max_len={}", len));
+            }
+        }
+        let dictionary = libfuzzer_dictionary(&flags, campaign)?;
+        for flag in flags {
+            cmd.arg(flag);
+        }
+        if let Some(dictionary) = dictionary {
+            let mut dict_arg = OsString::from("-dict=");
+            dict_arg.push(dictionary);
+            cmd.arg(dict_arg);
+        }
+        // New inputs are written to the first directory, the target's corpus
+        for corpus in campaign.corpus.iter().chain(&input_dirs) {
+            cmd.arg(corpus);
+        }
+
+        if campaign.jobs != 1 {
+            cmd.arg(format!("-fork={}", campaign.jobs));
+        }
+        if let Some(runs) = campaign.runs {
+            cmd.arg(format!("-runs={}", runs));
+        }
+        if let Some(max_total_time) = campaign.max_total_time {
+            cmd.arg(format!("-max_total_time={}", max_total_time));
+        }
+        Ok(cmd)
+    }
+
+    fn collect(&self, _campaign: &Campaign) -> Result<usize> {
+        // libFuzzer writes to the corpus and artifacts directories itself
+        Ok(0)
+    }
+}
+
+pub struct Afl;
+
+/// Name of an input after its contents, the hex SHA-1 libFuzzer names them
+/// with, so that inputs already in a libFuzzer corpus are not copied again.
+fn content_name(bytes: &[u8]) -> String {
+    Sha1::digest(bytes).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Copies the inputs AFL++ wrote in `dir` (`id:000000,...`) to `dest`, named
+/// `prefix` followed by their contents' hash, returning how many were new.
+fn copy_inputs(dir: &Path, dest: &Path, prefix: &str) -> Result<usize> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(0),
+    };
+    let mut copied = 0;
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        let is_input = path.file_name().map_or(false, |name| name.to_string_lossy().starts_with("id:"));
+        if !is_input || !path.is_file() {
+            continue;
+        }
+        let bytes = fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        let copy = dest.join(format!("{}{}", prefix, content_name(&bytes)));
+        if !copy.exists() {
+            fs::write(&copy, bytes).with_context(|| format!("failed to write {}", copy.display()))?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+impl Engine for Afl {
+    fn worker_bin(&self) -> &'static str {
+        "move-fuzzer-worker-afl"
+    }
+
+    fn worker_features(&self) -> &'static [&'static str] {
+        &["afl"]
+    }
+
+    fn worker_rustflags(&self) -> &'static str {
+        // The edge coverage `afl-compiler-rt.o` records, as `cargo afl` builds
+        "-Cpasses=sancov-module -Cllvm-args=-sanitizer-coverage-level=3 \
+         -Cllvm-args=-sanitizer-coverage-trace-pc-guard -Cllvm-args=-sanitizer-coverage-prune-blocks=0"
+    }
+
+    /// `afl-fuzz -i <corpus> -o <work dir> -- <worker>`, the worker reading
+    /// its inputs from AFL++ rather than taking libFuzzer arguments.
+    fn fuzz_command(&self, project: &FuzzProject, build: &BuildOptions, campaign: &Campaign) -> Result<Command> {
+        if campaign.jobs != 1 {
+            bail!("`--jobs` is not supported with AFL++, run secondary `afl-fuzz -S` instances on the same work directory instead");
+        }
+        let afl_fuzz = find_in_path("afl-fuzz").context("could not find `afl-fuzz` in PATH, install AFL++ first")?;
+        let worker = project.get_engine_worker_command(build, FuzzEngine::Afl)?;
+
+        // AFL++ needs at least one seed, the empty input decodes to default arguments
+        let seeds = campaign.corpus.first().context("a corpus directory is required")?;
+        if fs::read_dir(seeds).map_or(true, |mut entries| entries.next().is_none()) {
+            fs::write(seeds.join("empty"), [])
+                .with_context(|| format!("failed to write a seed in {}", seeds.display()))?;
+        }
+        fs::create_dir_all(&campaign.work_dir)
+            .with_context(|| format!("could not make the AFL++ directory {}", campaign.work_dir.display()))?;
+
+        let mut cmd = Command::new(afl_fuzz);
+        cmd.arg("-i").arg(seeds).arg("-o").arg(&campaign.work_dir);
+        if let Some(runs) = campaign.runs {
+            cmd.arg("-E").arg(runs.to_string());
+        }
+        if let Some(max_total_time) = campaign.max_total_time {
+            cmd.arg("-V").arg(max_total_time.to_string());
+        }
+        if let Some(seed) = campaign.seed {
+            cmd.arg("-s").arg(seed.to_string());
+        }
+        // The arguments are libFuzzer's: its dictionaries are given to AFL++,
+        // which takes several, and the worker flags to the worker
+        let (flags, input_dirs) = split_libfuzzer_args(&project.libfuzzer_args(&build.target, &campaign.args));
+        let mut worker_flags = vec![];
+        for flag in flags {
+            if let Some(dictionary) = flag.strip_prefix("-dict=") {
+                cmd.arg("-x").arg(dictionary);
+            } else if flag.starts_with("--") {
+                worker_flags.push(flag);
+            } else {
+                eprintln!("Warning: ignoring the libFuzzer flag `{}` with AFL++", flag);
+            }
+        }
+        for dir in input_dirs {
+            eprintln!("Warning: ignoring the input directory {} with AFL++, it reads the corpus only", dir.display());
+        }
+        if let Some(dictionary) = &campaign.dictionary {
+            cmd.arg("-x").arg(dictionary);
+        }
+        cmd.arg("--").arg(worker.get_program()).args(worker.get_args()).args(worker_flags);
+        for (key, value) in worker.get_envs() {
+            match value {
+                Some(value) => cmd.env(key, value),
+                None => cmd.env_remove(key),
+            };
+        }
+        // Pick up the previous campaign of the target instead of refusing to start
+        cmd.env("AFL_AUTORESUME", "1");
+        Ok(cmd)
+    }
+
+    fn collect(&self, campaign: &Campaign) -> Result<usize> {
+        let instance = campaign.work_dir.join("default");
+        if let Some(corpus) = campaign.corpus.first() {
+            copy_inputs(&instance.join("queue"), corpus, "")?;
+        }
+        copy_inputs(&instance.join("crashes"), &campaign.artifacts, "crash-")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_libfuzzer_args_keeps_input_dirs_apart() {
+        let args = ["-seed=1", "seeds", "--gas-limit=10", "-dict=move.dict", "/tmp/more"].map(String::from);
+        let (flags, dirs) = split_libfuzzer_args(&args);
+        assert_eq!(flags, vec!["-seed=1", "--gas-limit=10", "-dict=move.dict"]);
+        assert_eq!(dirs, vec![PathBuf::from("seeds"), PathBuf::from("/tmp/more")]);
+    }
+
+    #[test]
+    fn libfuzzer_dictionary_is_the_learned_one_without_dict_flag() {
+        let mut campaign = Campaign {
+            corpus: vec![],
+            artifacts: PathBuf::from("artifacts"),
+            work_dir: PathBuf::from("work"),
+            jobs: 1,
+            runs: None,
+            max_total_time: None,
+            timeouts: true,
+            seed: None,
+            args: vec![],
+            dictionary: None,
+        };
+        let flags = vec!["-seed=1".to_string()];
+        assert_eq!(libfuzzer_dictionary(&flags, &campaign).unwrap(), None);
+        campaign.dictionary = Some(PathBuf::from("learned.dict"));
+        assert_eq!(libfuzzer_dictionary(&flags, &campaign).unwrap(), Some(PathBuf::from("learned.dict")));
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::{Context, Result};
+use std::{fs, path::Path, time::Duration};
+
+/// The result of fuzzing a single target, reported as a JUnit test case.
+#[derive(Clone, Debug)]
+pub struct TestCase {
+    pub name: String,
+    pub time: Duration,
+    /// Summary and details of the failure, if the target failed
+    pub failure: Option<(String, String)>,
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Writes `cases` as a JUnit XML file with a single test suite.
+pub fn write_junit(path: &Path, suite: &str, cases: &[TestCase]) -> Result<()> {
+    let failures = cases.iter().filter(|c| c.failure.is_some()).count();
+    let time: f64 = cases.iter().map(|c| c.time.as_secs_f64()).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        cases.len(),
+        failures,
+        time
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        escape(suite),
+        cases.len(),
+        failures,
+        time
+    ));
+    for case in cases {
+        xml.push_str(&format!(
+            "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            escape(&case.name),
+            escape(suite),
+            case.time.as_secs_f64()
+        ));
+        match &case.failure {
+            None => xml.push_str("/>\n"),
+            Some((message, details)) => {
+                xml.push_str(">\n");
+                xml.push_str(&format!(
+                    "      <failure message=\"{}\">{}</failure>\n",
+                    escape(message),
+                    escape(details)
+                ));
+                xml.push_str("    </testcase>\n");
+            }
+        }
+    }
+    xml.push_str("  </testsuite>\n");
+    xml.push_str("</testsuites>\n");
+
+    fs::write(path, xml).with_context(|| format!("failed to write JUnit report to {}", path.display()))
+}
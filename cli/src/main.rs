@@ -7,8 +7,22 @@
 
 #[macro_use]
 mod templates;
+mod abi;
+mod chain;
+mod config;
+mod crash;
+mod dashboard;
+mod diagnostic;
+mod engine;
+mod junit;
+mod metrics;
+mod report;
+mod session;
+mod stats;
+mod trace;
 pub mod options;
 pub mod project;
+mod remote;
 mod utils;
 use anyhow::Result;
 use clap::{Args, Parser};
@@ -60,7 +74,8 @@ You can also get this by running `cargo fuzz run fuzz_target -- -help=1`
 Some useful options (to be used as `cargo fuzz run fuzz_target -- <options>`)
 include:
 
-  * `-max_len=<len>`: Will limit the length of the input string to `<len>`
+  * `-max_len=<len>`: Will limit the length of the input string to `<len>`,
+    by default the most bytes the target's arguments can consume
 
   * `-runs=<number>`: Will limit the number of tries (runs) before it gives up
 
@@ -73,7 +88,12 @@ include:
   * `-only_ascii`: Only provide ASCII input
 
   * `-dict=<file>`: Use a keyword dictionary from specified file. See
-    http://llvm.org/docs/LibFuzzer.html#dictionaries\
+    http://llvm.org/docs/LibFuzzer.html#dictionaries
+
+Options given there override the same ones of fuzz.toml and the defaults of
+`run`, but not `--jobs`, `--runs` and `--max-total-time`. Worker options may be
+given as `--option=value`. Other arguments are directories of inputs that are
+read, new inputs being written to the target's corpus.\
 ";
 
 const BUILD_BEFORE_HELP: &str = "\
@@ -134,6 +154,39 @@ pub enum Fuzz {
 
     /// Run program on the generated corpus and generate coverage information
     Coverage(options::Coverage),
+
+    /// Print how inputs are decoded into the arguments of a fuzz target
+    Describe(options::Describe),
+
+    /// Measure the executions per second of a target over its corpus
+    Bench(options::Bench),
+
+    /// Build and install the fuzzer worker binary
+    InstallWorker(options::InstallWorker),
+
+    /// Replay all the artifacts of a target and group them by failure
+    Triage(options::Triage),
+
+    /// Run a single input and print its arguments and Move VM status
+    Reproduce(options::Reproduce),
+
+    /// Export an input as a Move unit test calling the target function
+    ExportTest(options::ExportTest),
+
+    /// Fuzz every target for a fixed time, failing only on new unique crashes
+    Ci(options::Ci),
+
+    /// Replay every stored artifact and corpus entry once
+    Regress(options::Regress),
+
+    /// Remove or archive the corpus, artifacts and coverage of targets
+    Clean(options::Clean),
+
+    /// Maintain the corpus of a target
+    Corpus(options::Corpus),
+
+    /// Package targets in $OUT following the OSS-Fuzz build contract
+    OssFuzz(options::OssFuzz),
 }
 
 impl RunCommand for Fuzz {
@@ -148,6 +201,17 @@ impl RunCommand for Fuzz {
             Fuzz::Cmin(x) => x.run_command(),
             Fuzz::Tmin(x) => x.run_command(),
             Fuzz::Coverage(x) => x.run_command(),
+            Fuzz::Describe(x) => x.run_command(),
+            Fuzz::Bench(x) => x.run_command(),
+            Fuzz::InstallWorker(x) => x.run_command(),
+            Fuzz::Triage(x) => x.run_command(),
+            Fuzz::Reproduce(x) => x.run_command(),
+            Fuzz::ExportTest(x) => x.run_command(),
+            Fuzz::Ci(x) => x.run_command(),
+            Fuzz::Regress(x) => x.run_command(),
+            Fuzz::Clean(x) => x.run_command(),
+            Fuzz::Corpus(x) => x.run_command(),
+            Fuzz::OssFuzz(x) => x.run_command(),
         }
     }
 }
@@ -168,6 +232,17 @@ impl FromStr for Fuzz {
             "cmin" => Ok(Fuzz::Cmin(Cmin::parse())),
             "tmin" => Ok(Fuzz::Tmin(Tmin::parse())),
             "coverage" => Ok(Fuzz::Coverage(Coverage::parse())),
+            "describe" => Ok(Fuzz::Describe(Describe::parse())),
+            "bench" => Ok(Fuzz::Bench(Bench::parse())),
+            "install-worker" => Ok(Fuzz::InstallWorker(InstallWorker::parse())),
+            "triage" => Ok(Fuzz::Triage(Triage::parse())),
+            "reproduce" => Ok(Fuzz::Reproduce(Reproduce::parse())),
+            "export-test" => Ok(Fuzz::ExportTest(ExportTest::parse())),
+            "ci" => Ok(Fuzz::Ci(Ci::parse())),
+            "regress" => Ok(Fuzz::Regress(Regress::parse())),
+            "clean" => Ok(Fuzz::Clean(Clean::parse())),
+            "corpus" => Ok(Fuzz::Corpus(Corpus::parse())),
+            "oss-fuzz" => Ok(Fuzz::OssFuzz(OssFuzz::parse())),
             _ => Err(format!("Unknown command: {}", s)),
         }
     }
@@ -185,6 +260,17 @@ impl Args for Fuzz {
             "cmin" => Cmin::augment_args(cmd),
             "tmin" => Tmin::augment_args(cmd),
             "coverage" => Coverage::augment_args(cmd),
+            "describe" => Describe::augment_args(cmd),
+            "bench" => Bench::augment_args(cmd),
+            "install-worker" => InstallWorker::augment_args(cmd),
+            "triage" => Triage::augment_args(cmd),
+            "reproduce" => Reproduce::augment_args(cmd),
+            "export-test" => ExportTest::augment_args(cmd),
+            "ci" => Ci::augment_args(cmd),
+            "regress" => Regress::augment_args(cmd),
+            "clean" => Clean::augment_args(cmd),
+            "corpus" => Corpus::augment_args(cmd),
+            "oss-fuzz" => OssFuzz::augment_args(cmd),
             _ => cmd, // Return unchanged command if unknown
         }
     }
@@ -200,6 +286,17 @@ impl Args for Fuzz {
             "cmin" => Cmin::augment_args_for_update(cmd),
             "tmin" => Tmin::augment_args_for_update(cmd),
             "coverage" => Coverage::augment_args_for_update(cmd),
+            "describe" => Describe::augment_args_for_update(cmd),
+            "bench" => Bench::augment_args_for_update(cmd),
+            "install-worker" => InstallWorker::augment_args_for_update(cmd),
+            "triage" => Triage::augment_args_for_update(cmd),
+            "reproduce" => Reproduce::augment_args_for_update(cmd),
+            "export-test" => ExportTest::augment_args_for_update(cmd),
+            "ci" => Ci::augment_args_for_update(cmd),
+            "regress" => Regress::augment_args_for_update(cmd),
+            "clean" => Clean::augment_args_for_update(cmd),
+            "corpus" => Corpus::augment_args_for_update(cmd),
+            "oss-fuzz" => OssFuzz::augment_args_for_update(cmd),
             _ => cmd, // Return unchanged command if unknown
         }
     }
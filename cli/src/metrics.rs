@@ -0,0 +1,116 @@
+use std::{
+    io::{Read, Write}, net::{TcpListener, UdpSocket}, sync::{Arc, Mutex, PoisonError}, thread, time
+};
+
+use anyhow::{Context, Result};
+
+use crate::stats::CampaignStats;
+
+/// How often the metrics are pushed to StatsD.
+const STATSD_INTERVAL: time::Duration = time::Duration::from_secs(10);
+
+/// Exports the progress of a campaign for monitoring systems, served to
+/// Prometheus and/or pushed to StatsD.
+pub struct Metrics {
+    /// Latest Prometheus exposition, served by the endpoint thread
+    exposition: Option<Arc<Mutex<String>>>,
+    statsd: Option<UdpSocket>,
+    pushed: Option<time::Instant>,
+}
+
+/// A gauge or counter with its value, `None` until libFuzzer reports it.
+struct Sample {
+    name: &'static str,
+    help: &'static str,
+    kind: &'static str,
+    value: Option<f64>,
+}
+
+fn samples(stats: &CampaignStats) -> Vec<Sample> {
+    let sample = |name, help, kind, value| Sample { name, help, kind, value };
+    vec![
+        sample("executions_total", "Executions of the target", "counter", stats.runs.map(|n| n as f64)),
+        sample("executions_per_second", "Executions per second", "gauge", stats.exec_per_sec.map(|n| n as f64)),
+        sample("corpus_entries", "Inputs in the corpus", "gauge", stats.corpus_entries().map(|n| n as f64)),
+        sample("move_instructions_covered", "Instructions of the target module covered", "gauge", Some(stats.covered as f64)),
+        sample("move_coverage_percent", "Percentage of the target module covered", "gauge", stats.coverage_percent()),
+        sample("artifacts_total", "Failing inputs found", "counter", Some(stats.artifacts as f64)),
+    ]
+}
+
+/// The metrics in the Prometheus text format, labelled with the target.
+fn prometheus_text(stats: &CampaignStats) -> String {
+    let target = format!("{}::{}", stats.target.get_module_name(), stats.target.get_target_function());
+    let mut text = String::new();
+    for sample in samples(stats) {
+        if let Some(value) = sample.value {
+            text.push_str(&format!("# HELP move_fuzzer_{} {}\n", sample.name, sample.help));
+            text.push_str(&format!("# TYPE move_fuzzer_{} {}\n", sample.name, sample.kind));
+            text.push_str(&format!("move_fuzzer_{}{{target=\"{}\"}} {}\n", sample.name, target, value));
+        }
+    }
+    text
+}
+
+/// The metrics as StatsD gauges named `move_fuzzer.<module>.<function>.<metric>`.
+fn statsd_text(stats: &CampaignStats) -> String {
+    let prefix = format!("move_fuzzer.{}.{}", stats.target.get_module_name(), stats.target.get_target_function());
+    samples(stats)
+        .into_iter()
+        .filter_map(|sample| Some(format!("{}.{}:{}|g\n", prefix, sample.name, sample.value?)))
+        .collect()
+}
+
+/// Serves the exposition to every connection made to `addr`, whatever the request.
+fn serve_prometheus(addr: &str) -> Result<Arc<Mutex<String>>> {
+    let listener = TcpListener::bind(addr).with_context(|| format!("failed to listen for metrics on {}", addr))?;
+    eprintln!("Serving Prometheus metrics on http://{}/metrics", addr);
+    let exposition = Arc::new(Mutex::new(String::new()));
+    let served = exposition.clone();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().filter_map(|s| s.ok()) {
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request);
+            let body = served.lock().unwrap_or_else(PoisonError::into_inner).clone();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+    Ok(exposition)
+}
+
+impl Metrics {
+    /// Starts serving Prometheus metrics on `prometheus` and pushing them to
+    /// the StatsD server at `statsd`, whichever are given.
+    pub fn new(prometheus: Option<&str>, statsd: Option<&str>) -> Result<Self> {
+        let exposition = prometheus.map(serve_prometheus).transpose()?;
+        let statsd = statsd
+            .map(|addr| {
+                let socket = UdpSocket::bind("0.0.0.0:0").context("failed to open a StatsD socket")?;
+                socket
+                    .connect(addr)
+                    .with_context(|| format!("failed to reach StatsD at {}", addr))?;
+                Ok::<_, anyhow::Error>(socket)
+            })
+            .transpose()?;
+        Ok(Metrics { exposition, statsd, pushed: None })
+    }
+
+    /// Updates the served metrics, and pushes them if it's time to.
+    pub fn publish(&mut self, stats: &CampaignStats) {
+        if let Some(exposition) = &self.exposition {
+            *exposition.lock().unwrap_or_else(PoisonError::into_inner) = prometheus_text(stats);
+        }
+        if let Some(socket) = &self.statsd {
+            if self.pushed.map_or(true, |t| t.elapsed() >= STATSD_INTERVAL) {
+                self.pushed = Some(time::Instant::now());
+                // Metrics are best effort, a lost datagram is replaced by the next
+                let _ = socket.send(statsd_text(stats).as_bytes());
+            }
+        }
+    }
+}
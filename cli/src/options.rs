@@ -1,40 +1,329 @@
 pub mod add;
+pub mod bench;
 pub mod build;
+pub mod ci;
+pub mod clean;
 pub mod cmin;
+pub mod corpus;
 pub mod coverage;
+pub mod describe;
+pub mod export_test;
 pub mod fmt;
 pub mod init;
+pub mod install_worker;
 pub mod list;
+pub mod oss_fuzz;
+pub mod regress;
+pub mod reproduce;
 pub mod run;
 pub mod tmin;
+pub mod triage;
 
 pub use self::{
-    add::Add, build::Build, cmin::Cmin, coverage::Coverage, fmt::Fmt, init::Init,
-    list::List, run::Run, tmin::Tmin,
+    add::Add, bench::Bench, build::Build, ci::Ci, clean::Clean, cmin::Cmin, corpus::Corpus,
+    coverage::Coverage, describe::Describe, export_test::ExportTest, fmt::Fmt, init::Init,
+    install_worker::InstallWorker, list::List, oss_fuzz::OssFuzz, regress::Regress,
+    reproduce::Reproduce, run::Run, tmin::Tmin, triage::Triage,
 };
 
 use clap::*;
 use std::str::FromStr;
 use std::{fmt as stdfmt, path::PathBuf};
 use std::fmt::Debug;
+use move_compiler::command_line::LintFlag;
 use move_package::BuildConfig;
+use crate::project::WORKER_PATH_ENV;
 
 #[derive(Clone, Debug, Eq, PartialEq, Parser)]
 pub struct BuildOptions {
     /// Path to a package which the command should be run with respect to.
-    #[clap(long = "path", short = 'p', global = true)]
+    /// `run` and `build` fuzz its functions directly, without a fuzz package
+    #[clap(long = "path", short = 'p', visible_alias = "package-path", global = true)]
     pub package_path: Option<PathBuf>,
 
     /// Print additional diagnostics if available.
     #[clap(short = 'v', global = true)]
     pub verbose: bool,
 
+    /// Build the Move package even if it is unchanged since the last build.
+    #[clap(long, global = true)]
+    pub force_build: bool,
+
     #[clap(flatten)]
     pub target: Target,
 
     #[clap(flatten)] 
     /// move build options
     pub build_config: BuildConfig,
+
+    #[clap(flatten)]
+    /// options forwarded to the fuzzer worker
+    pub worker: WorkerOptions,
+}
+
+/// How the worker generates struct arguments.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum StructGeneration {
+    /// Fill the struct fields directly with arbitrary values
+    #[default]
+    Fields,
+    /// Call a public constructor of the struct, failing if none exists
+    Constructors,
+    /// Call a public constructor of the struct, filling the fields directly if none exists
+    ConstructorsOrFields,
+}
+
+impl stdfmt::Display for StructGeneration {
+    fn fmt(&self, f: &mut stdfmt::Formatter) -> stdfmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        write!(f, "{}", value.get_name())
+    }
+}
+
+/// Which byte vectors the worker generates as valid text.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum StringGeneration {
+    /// Generate every `vector<u8>` as arbitrary bytes
+    Bytes,
+    /// Generate valid text for `std::string::String` and `std::ascii::String` values
+    #[default]
+    Strings,
+    /// Also generate every other `vector<u8>` as valid UTF-8
+    All,
+}
+
+impl stdfmt::Display for StringGeneration {
+    fn fmt(&self, f: &mut stdfmt::Formatter) -> stdfmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        write!(f, "{}", value.get_name())
+    }
+}
+
+/// Which functions the worker accepts as the target, by visibility.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum TargetVisibility {
+    /// Only `public` functions, callable from other modules and transactions
+    Public,
+    /// Only `entry` functions, callable from transactions
+    Entry,
+    /// Any function, including private helpers
+    #[default]
+    Any,
+}
+
+impl stdfmt::Display for TargetVisibility {
+    fn fmt(&self, f: &mut stdfmt::Formatter) -> stdfmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        write!(f, "{}", value.get_name())
+    }
+}
+
+/// How the worker picks the instantiation of a generic target.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum TypeArgSelection {
+    /// Don't instantiate generic targets, their type parameters are unsupported
+    #[default]
+    None,
+    /// Select the instantiation with a hash of the input, which spreads them
+    /// evenly over the inputs, so that findings replay with the same one
+    Hash,
+    /// Select the instantiation with the first byte of the input, so that
+    /// findings replay with the same one
+    Input,
+}
+
+impl stdfmt::Display for TypeArgSelection {
+    fn fmt(&self, f: &mut stdfmt::Formatter) -> stdfmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        write!(f, "{}", value.get_name())
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Parser)]
+pub struct WorkerOptions {
+    /// How struct arguments are generated
+    #[clap(long, value_enum, default_value_t = StructGeneration::Fields)]
+    pub struct_generation: StructGeneration,
+
+    /// Which `vector<u8>` and string parameters are generated as valid text
+    #[clap(long, value_enum, default_value_t = StringGeneration::Strings)]
+    pub string_generation: StringGeneration,
+
+    /// Generate `Option`, `FixedPoint32` and Sui object values (`UID`, `Coin`,
+    /// `Clock`, `TxContext`) field by field instead of as values the
+    /// framework functions accept
+    #[clap(long)]
+    pub raw_std_types: bool,
+
+    /// Functions accepted as the target: executions bypass visibility, so
+    /// this restricts fuzzing to the externally reachable ones
+    #[clap(long, value_enum, default_value_t = TargetVisibility::Any)]
+    pub visibility: TargetVisibility,
+
+    /// Fuzz a generic target with the instantiations its type parameters
+    /// allow among the types declared in the package and the primitives
+    #[clap(long, value_enum, default_value_t = TypeArgSelection::None)]
+    pub instantiate: TypeArgSelection,
+
+    /// Gas available to each execution, unmetered if not set
+    #[clap(long)]
+    pub gas_limit: Option<u64>,
+
+    /// Abort code that is expected and not reported as a crash
+    #[clap(long = "allow-abort-code")]
+    pub allowed_abort_codes: Vec<u64>,
+
+    /// Action taken when an execution fails with a class of error, as
+    /// `class=action` (e.g. `out-of-gas=reject`, `memory-limit-exceeded=ignore`)
+    #[clap(long = "on-error")]
+    pub error_actions: Vec<String>,
+
+    /// Report executions of a target returning `bool` that complete with `false`
+    #[clap(long)]
+    pub oracle_bool: bool,
+
+    /// Fuzz publishing modules instead of calling the target: inputs are module
+    /// bytecode, seeded with the compiled modules, exercising the VM's
+    /// deserializer, verifier and linker
+    #[clap(long)]
+    pub publish: bool,
+
+    /// State machine model, in TOML or JSON, describing the calls inputs are
+    /// interpreted as: setup calls, allowed functions with their weights,
+    /// pinned arguments and the calls they must follow
+    #[clap(long, conflicts_with = "publish")]
+    pub model: Option<PathBuf>,
+
+    /// Function without parameters called after each execution, as `module::function`,
+    /// whose aborts are reported as crashes
+    #[clap(long)]
+    pub invariant: Option<String>,
+
+    /// Function called before the target on every input, as `module::function`
+    /// (e.g. `init_module`), so that the resources it creates exist; its signer
+    /// parameters are given the module's address
+    #[clap(long = "setup-function")]
+    pub setup_functions: Vec<String>,
+
+    /// Pin a parameter to a constant value instead of fuzzing it, as `argN=value`
+    /// (e.g. `--fix arg0=0xCAFE`)
+    #[clap(long = "fix")]
+    pub fixed_args: Vec<String>,
+
+    /// Address signer arguments are picked from, as a hex literal or `deployer`
+    /// for the address the target is published at
+    #[clap(long = "signer")]
+    pub signers: Vec<String>,
+
+    /// Address a named address of the package is published at, as `name=0x...`;
+    /// names left unassigned in Move.toml are compiled with it
+    #[clap(long = "address")]
+    pub addresses: Vec<String>,
+
+    /// Number of test accounts, numbered from `0x1000`, added to the signer pool
+    #[clap(long, default_value_t = 0)]
+    pub test_signers: u64,
+
+    /// Still generate random signers besides the pooled ones
+    #[clap(long)]
+    pub random_signers: bool,
+
+    /// Weights of the addresses generated for `address` parameters, as
+    /// `named=3,core=1,zero=1,random=3`
+    #[clap(long)]
+    pub address_weights: Option<String>,
+
+    /// Percentage of the integers picked among boundary values (0, 1, MAX,
+    /// powers of two...) instead of uniformly, 10 if not set
+    #[clap(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub special_int_percent: Option<u8>,
+
+    /// Have each worker sample its vector lengths, integer bias and address
+    /// weights at startup, so that `--jobs` workers explore differently
+    #[clap(long)]
+    pub swarm: bool,
+
+    /// Seed of the swarm profile to reproduce, as printed by a worker
+    #[clap(long)]
+    pub swarm_seed: Option<u64>,
+
+    /// Highest bytecode version the worker reads the modules with, the latest
+    /// it supports if not set
+    #[clap(long)]
+    pub bytecode_version: Option<u32>,
+
+    /// Elements per vector counted when bounding the input length passed to
+    /// libFuzzer as `-max_len` (16 if not set)
+    #[clap(long)]
+    pub vector_budget: Option<usize>,
+
+    /// Record and report the Move coverage of the dependencies too, not only
+    /// of the target module
+    #[clap(long)]
+    pub include_deps: bool,
+
+    /// Maximum nesting of the generated vectors, structs and enums, deeper
+    /// inputs are rejected from the corpus (32 if not set)
+    #[clap(long)]
+    pub max_input_depth: Option<usize>,
+
+    /// Maximum number of values generated from one input, larger inputs are
+    /// rejected from the corpus (100000 if not set)
+    #[clap(long)]
+    pub max_input_values: Option<usize>,
+
+    /// REST API of a node the resources read by the target are fetched from,
+    /// so that it runs against a fork of the on-chain state
+    #[clap(long)]
+    pub fork_url: Option<String>,
+
+    /// Ledger version the forked state is read at, the latest if not set
+    #[clap(long, requires = "fork_url")]
+    pub fork_version: Option<u64>,
+
+    /// Directory the fetched resources are cached in, so that later runs
+    /// don't fetch them again
+    #[clap(long, requires = "fork_url")]
+    pub fork_cache: Option<PathBuf>,
+
+    /// Most verbose messages the worker logs (error, warn, info, debug or
+    /// trace), info if not set; `MOVE_FUZZER_LOG` overrides it
+    #[clap(long)]
+    pub log_level: Option<String>,
+
+    /// Path to the `move-fuzzer-worker` binary, looked up in PATH if not set
+    #[clap(long, env = WORKER_PATH_ENV)]
+    pub worker_path: Option<PathBuf>,
+}
+
+/// How commands print their results.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable text
+    #[default]
+    Text,
+    /// A single JSON document on stdout, diagnostics stay on stderr
+    Json,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Parser)]
+pub struct OutputOptions {
+    /// Format of the command results
+    #[clap(long = "output", value_enum, default_value_t = OutputFormat::Text, global = true)]
+    pub format: OutputFormat,
+}
+
+impl OutputOptions {
+    pub fn is_json(&self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    /// Prints the JSON results of a command, if JSON output was requested.
+    pub fn emit(&self, value: serde_json::Value) {
+        if self.is_json() {
+            println!("{}", value);
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Parser)]
@@ -95,10 +384,159 @@ impl FromStr for BuildOptions {
 
 impl std::fmt::Display for BuildOptions {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(package_path) = &self.package_path {
+            write!(f, " --path {}", package_path.display())?;
+        }
         if self.verbose {
             write!(f, " -v")?;
         }
 
+        // The build flags, so that the printed commands compile the same modules
+        let config = &self.build_config;
+        if config.dev_mode {
+            write!(f, " --dev")?;
+        }
+        if config.test_mode {
+            write!(f, " --test")?;
+        }
+        if config.generate_docs {
+            write!(f, " --doc")?;
+        }
+        if let Some(install_dir) = &config.install_dir {
+            write!(f, " --install-dir {}", install_dir.display())?;
+        }
+        if config.force_recompilation {
+            write!(f, " --force")?;
+        }
+        if config.fetch_deps_only {
+            write!(f, " --fetch-deps-only")?;
+        }
+        if config.skip_fetch_latest_git_deps {
+            write!(f, " --skip-fetch-latest-git-deps")?;
+        }
+        if let Some(flavor) = &config.default_flavor {
+            write!(f, " --default-move-flavor {}", flavor)?;
+        }
+        if let Some(edition) = &config.default_edition {
+            write!(f, " --default-move-edition {}", edition)?;
+        }
+        if config.deps_as_root {
+            write!(f, " --dependencies-are-root")?;
+        }
+        if config.silence_warnings {
+            write!(f, " --silence-warnings")?;
+        }
+        if config.warnings_are_errors {
+            write!(f, " --warnings-are-errors")?;
+        }
+        if config.lint_flag == LintFlag::LEVEL_NONE {
+            write!(f, " --no-lint")?;
+        } else if config.lint_flag == LintFlag::LEVEL_ALL {
+            write!(f, " --lint")?;
+        }
+        if self.force_build {
+            write!(f, " --force-build")?;
+        }
+
+        // The worker flags, so that the printed commands decode inputs the same way
+        let worker = &self.worker;
+        if worker.struct_generation != StructGeneration::default() {
+            write!(f, " --struct-generation {}", worker.struct_generation)?;
+        }
+        if worker.string_generation != StringGeneration::default() {
+            write!(f, " --string-generation {}", worker.string_generation)?;
+        }
+        if worker.raw_std_types {
+            write!(f, " --raw-std-types")?;
+        }
+        if worker.visibility != TargetVisibility::default() {
+            write!(f, " --visibility {}", worker.visibility)?;
+        }
+        if worker.instantiate != TypeArgSelection::default() {
+            write!(f, " --instantiate {}", worker.instantiate)?;
+        }
+        if let Some(gas_limit) = worker.gas_limit {
+            write!(f, " --gas-limit {}", gas_limit)?;
+        }
+        for code in &worker.allowed_abort_codes {
+            write!(f, " --allow-abort-code {}", code)?;
+        }
+        for action in &worker.error_actions {
+            write!(f, " --on-error {}", action)?;
+        }
+        if worker.oracle_bool {
+            write!(f, " --oracle-bool")?;
+        }
+        if worker.publish {
+            write!(f, " --publish")?;
+        }
+        if let Some(model) = &worker.model {
+            write!(f, " --model {}", model.display())?;
+        }
+        if let Some(invariant) = &worker.invariant {
+            write!(f, " --invariant {}", invariant)?;
+        }
+        for setup in &worker.setup_functions {
+            write!(f, " --setup-function {}", setup)?;
+        }
+        for fix in &worker.fixed_args {
+            write!(f, " --fix {}", fix)?;
+        }
+        for signer in &worker.signers {
+            write!(f, " --signer {}", signer)?;
+        }
+        for address in &worker.addresses {
+            write!(f, " --address {}", address)?;
+        }
+        if worker.test_signers != 0 {
+            write!(f, " --test-signers {}", worker.test_signers)?;
+        }
+        if worker.random_signers {
+            write!(f, " --random-signers")?;
+        }
+        if let Some(weights) = &worker.address_weights {
+            write!(f, " --address-weights {}", weights)?;
+        }
+        if let Some(percent) = worker.special_int_percent {
+            write!(f, " --special-int-percent {}", percent)?;
+        }
+        if worker.swarm {
+            write!(f, " --swarm")?;
+        }
+        if let Some(seed) = worker.swarm_seed {
+            write!(f, " --swarm-seed {}", seed)?;
+        }
+        if let Some(version) = worker.bytecode_version {
+            write!(f, " --bytecode-version {}", version)?;
+        }
+        if let Some(budget) = worker.vector_budget {
+            write!(f, " --vector-budget {}", budget)?;
+        }
+        if worker.include_deps {
+            write!(f, " --include-deps")?;
+        }
+        if let Some(depth) = worker.max_input_depth {
+            write!(f, " --max-input-depth {}", depth)?;
+        }
+        if let Some(values) = worker.max_input_values {
+            write!(f, " --max-input-values {}", values)?;
+        }
+        if let Some(url) = &worker.fork_url {
+            write!(f, " --fork-url {}", url)?;
+        }
+        if let Some(version) = worker.fork_version {
+            write!(f, " --fork-version {}", version)?;
+        }
+        if let Some(cache) = &worker.fork_cache {
+            write!(f, " --fork-cache {}", cache.display())?;
+        }
+        if let Some(level) = &worker.log_level {
+            write!(f, " --log-level {}", level)?;
+        }
+        if let Some(worker_path) = &worker.worker_path {
+            write!(f, " --worker-path {}", worker_path.display())?;
+        }
+
         Ok(())
     }
 }
@@ -141,8 +579,7 @@ mod test {
     use clap::Parser;
     use std::path::PathBuf;
     use std::collections::BTreeMap;
-    use move_core_types::account_address::AccountAddress;
-    use move_compiler::command_line::LintFlag;
+    use move_compiler::editions::{Edition, Flavor};
 
     #[test]
     fn display_build_options() {
@@ -150,10 +587,11 @@ mod test {
         let default_build_options = BuildOptions {
             package_path: None,
             verbose: false,
+            force_build: false,
             target: Target {
                 target_module: None,
                 target_function: None,
-                target_name: None,
+                target_name: Some("target_name".to_string()),
             },
             build_config: BuildConfig {
                 dev_mode: false,
@@ -172,6 +610,7 @@ mod test {
                 additional_named_addresses: BTreeMap::new(),
                 lint_flag: LintFlag::default(),
             },
+            worker: WorkerOptions::default(),
         };
 
         let opts = vec![
@@ -185,18 +624,7 @@ mod test {
                 ..default_build_options.clone()
             },
             BuildOptions {
-                target: Target {
-                    target_module: Some(PathBuf::from("module_name")),
-                    target_function: Some("target_function".to_string()),
-                    ..default_build_options.target.clone()
-                },
-                ..default_build_options.clone()
-            },
-            BuildOptions {
-                target: Target {
-                    target_name: Some("target_name".to_string()),
-                    ..default_build_options.target.clone()
-                },
+                force_build: true,
                 ..default_build_options.clone()
             },
             BuildOptions {
@@ -283,75 +711,221 @@ mod test {
                 },
                 ..default_build_options.clone()
             },
+            BuildOptions {
+                build_config: BuildConfig {
+                    lint_flag: LintFlag::LEVEL_NONE,
+                    ..default_build_options.build_config.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                build_config: BuildConfig {
+                    lint_flag: LintFlag::LEVEL_ALL,
+                    ..default_build_options.build_config.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    struct_generation: StructGeneration::Constructors,
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    string_generation: StringGeneration::All,
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    visibility: TargetVisibility::Entry,
+                    raw_std_types: true,
+                    instantiate: TypeArgSelection::Input,
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    gas_limit: Some(1000),
+                    allowed_abort_codes: vec![1, 2],
+                    error_actions: vec!["out-of-gas=reject".to_string(), "memory-limit-exceeded=ignore".to_string()],
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    oracle_bool: true,
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    publish: true,
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    model: Some(PathBuf::from("models/vault.toml")),
+                    invariant: Some("vault::solvent".to_string()),
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    setup_functions: vec!["vault::init_module".to_string(), "vault::fuzz_setup".to_string()],
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    fixed_args: vec!["arg0=0xCAFE".to_string(), "arg2=1000".to_string()],
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    signers: vec!["0x1".to_string(), "deployer".to_string()],
+                    test_signers: 4,
+                    random_signers: true,
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    addresses: vec!["fuzz=0x42".to_string(), "coin=0xC0FFEE".to_string()],
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    address_weights: Some("named=4,random=1".to_string()),
+                    special_int_percent: Some(50),
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    swarm: true,
+                    swarm_seed: Some(42),
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    max_input_depth: Some(4),
+                    max_input_values: Some(1000),
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    bytecode_version: Some(6),
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    vector_budget: Some(64),
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    include_deps: true,
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    log_level: Some("debug".to_string()),
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    fork_url: Some("http://localhost:8080/v1".to_string()),
+                    fork_version: Some(42),
+                    fork_cache: Some(PathBuf::from("fork-cache")),
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
+            BuildOptions {
+                worker: WorkerOptions {
+                    worker_path: Some(PathBuf::from("bin/move-fuzzer-worker")),
+                    ..default_build_options.worker.clone()
+                },
+                ..default_build_options.clone()
+            },
         ];
 
-        for (i, case) in opts.iter().enumerate() {
-            println!("{i}");
-            println!("{:?}", case);
-            println!();
-            let case_str = format_build_options(case);
-            println!("{:?}", case_str);
-            println!();
-            let parsed_case = BuildOptions::parse_from(case_str.split_whitespace());
-            assert_eq!(case, &parsed_case);
+        for case in &opts {
+            let args = format!("cargo-fuzz{} --target-name target_name", case);
+            let parsed = BuildOptions::parse_from(args.split_whitespace());
+            assert_eq!(case, &parsed, "{}", args);
         }
     }
 
-    fn format_build_options(opts: &BuildOptions) -> String {
-        let mut args = vec![];
-        
-        if let Some(path) = &opts.package_path {
-            args.push(format!("--path {}", path.display()));
-        }
-        if opts.verbose {
-            args.push("-v".to_string());
-        }
-        if let Some(module_name) = &opts.target.target_module {
-            args.push(format!("--module_name {}", module_name.display()));
-        }
-        if let Some(target_function) = &opts.target.target_function {
-            args.push(format!("--target_function {}", target_function));
-        }
-        if let Some(target_name) = &opts.target.target_name {
-            args.push(format!("--target_name {}", target_name));
-        }
-        if opts.build_config.dev_mode {
-            args.push("--dev".to_string());
-        }
-        if opts.build_config.test_mode {
-            args.push("--test".to_string());
-        }
-        if opts.build_config.generate_docs {
-            args.push("--doc".to_string());
-        }
-        if let Some(install_dir) = &opts.build_config.install_dir {
-            args.push(format!("--install-dir {}", install_dir.display()));
-        }
-        if opts.build_config.force_recompilation {
-            args.push("--force".to_string());
-        }
-        if opts.build_config.fetch_deps_only {
-            args.push("--fetch-deps-only".to_string());
-        }
-        if opts.build_config.skip_fetch_latest_git_deps {
-            args.push("--skip-fetch-latest-git-deps".to_string());
-        }
-        if let Some(default_flavor) = &opts.build_config.default_flavor {
-            args.push(format!("--default-move-flavor {:?}", default_flavor));
-        }
-        if let Some(default_edition) = &opts.build_config.default_edition {
-            args.push(format!("--default-move-edition {:?}", default_edition));
-        }
-        if opts.build_config.deps_as_root {
-            args.push("--dependencies-are-root".to_string());
-        }
-        if opts.build_config.silence_warnings {
-            args.push("--silence-warnings".to_string());
-        }
-        if opts.build_config.warnings_are_errors {
-            args.push("--warnings-are-errors".to_string());
-        }
+    #[test]
+    fn display_round_trips_build_config() {
+        let build = BuildOptions::parse_from(["cargo-fuzz", "--target-name", "target_name"]);
+        let configs = vec![
+            BuildConfig {
+                dev_mode: true,
+                test_mode: true,
+                generate_docs: true,
+                ..build.build_config.clone()
+            },
+            BuildConfig {
+                install_dir: Some(PathBuf::from("install/dir")),
+                force_recompilation: true,
+                fetch_deps_only: true,
+                skip_fetch_latest_git_deps: true,
+                ..build.build_config.clone()
+            },
+            BuildConfig {
+                default_flavor: Some(Flavor::Sui),
+                default_edition: Some(Edition::E2024_BETA),
+                deps_as_root: true,
+                ..build.build_config.clone()
+            },
+            BuildConfig {
+                silence_warnings: true,
+                warnings_are_errors: true,
+                lint_flag: LintFlag::LEVEL_ALL,
+                ..build.build_config.clone()
+            },
+            BuildConfig {
+                lint_flag: LintFlag::LEVEL_NONE,
+                ..build.build_config.clone()
+            },
+        ];
 
-        args.join(" ")
+        for build_config in configs {
+            let case = BuildOptions { verbose: true, build_config, ..build.clone() };
+            let args = format!("cargo-fuzz{} --target-name target_name", case);
+            let parsed = BuildOptions::parse_from(args.split_whitespace());
+            assert_eq!(case, parsed, "{}", args);
+        }
     }
 }
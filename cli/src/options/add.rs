@@ -1,9 +1,16 @@
+use crate::abi::{find_function, format_qualified_type, function_name, load_module, public_functions};
+use crate::chain::{fetch_aptos_module, fetch_package, DEFAULT_APTOS_NODE_URL, DEFAULT_RPC_URL};
+use crate::config::TargetConfig;
 use crate::project::FuzzProject;
-use crate::templates::create_target_template;
+use crate::templates::{create_target_from, create_target_template};
 use crate::Target;
 use crate::{options::FuzzDirWrapper, RunCommand};
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::*;
+use move_binary_format::file_format::{SignatureToken, Visibility};
+use move_binary_format::CompiledModule;
+use std::collections::BTreeSet;
+use std::{fs, path::Path};
 
 
 
@@ -13,13 +20,51 @@ pub struct Add {
     #[clap(flatten)] 
     pub fuzz_dir_wrapper: FuzzDirWrapper,
 
-    /// Name of the new fuzz target
-    pub target: String,
+    #[clap(required_unless_present_any = ["from_chain", "from_aptos", "module"])]
+    /// Name of the new fuzz target, or of the harness with `--harness`
+    pub target: Option<String>,
+
+    #[clap(long, requires = "function", conflicts_with_all = ["from_chain", "from_aptos"])]
+    /// Compiled module of the fuzz package the function to fuzz is in
+    pub module: Option<String>,
+
+    #[clap(long, requires = "module")]
+    /// Function to fuzz, registered as a `<module>-<function>` target in fuzz.toml
+    pub function: Option<String>,
+
+    #[clap(long, requires = "module")]
+    /// Generate a Move harness calling the function with typed parameters
+    /// instead, to adapt the call by hand
+    pub harness: bool,
+
+    #[clap(long, conflicts_with_all = ["target", "from_aptos"])]
+    /// Download the Sui package with this id, and its dependencies, from a
+    /// fullnode and register a target for each of its public functions
+    pub from_chain: Option<String>,
+
+    #[clap(long, conflicts_with = "target")]
+    /// Download the Aptos module `address::module`, and the modules it depends
+    /// on, from a node and register a target for each of its public functions
+    pub from_aptos: Option<String>,
+
+    #[clap(long)]
+    /// URL of the node modules are downloaded from, Sui's or Aptos' mainnet
+    /// fullnode by default
+    pub rpc: Option<String>,
 }
 
 impl RunCommand for Add {
     fn run_command(&mut self)-> Result<()> {
         let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.to_owned())?;
+        if let Some(package) = &self.from_chain {
+            return self.add_from_chain(&project, package);
+        }
+        if let Some(module) = &self.from_aptos {
+            return self.add_from_aptos(&project, module);
+        }
+        if let (Some(module), Some(function)) = (&self.module, &self.function) {
+            return self.add_function(&project, module, function);
+        }
         self.add_target(&project)
     }
 }
@@ -27,17 +72,196 @@ impl RunCommand for Add {
 impl Add {
     /// Create a new fuzz target.
     pub fn add_target(&self, project: &FuzzProject) -> Result<()> {
-        let target = Target {
-            target_module: None,
-            target_function: None,
-            target_name: Some(self.target.clone()),
+        let name = self.target.clone().expect("a target or --from-chain is required");
+        create_target_template(project, &name)
+            .with_context(|| format!("could not add target {:?}", name))?;
+        register_targets(project, vec![(name.clone(), name, String::from("fuzz_target"))])
+    }
+
+    /// Fuzzes `module::function` of the compiled package, registering it as a
+    /// target or, with `--harness`, generating a target calling it.
+    pub fn add_function(&self, project: &FuzzProject, module: &str, function: &str) -> Result<()> {
+        let direct = Target {
+            target_module: Some(module.to_owned()),
+            target_function: Some(function.to_owned()),
+            target_name: None,
         };
+        project.validate_target(&direct)?;
+        if !self.harness {
+            let name = format!("{}-{}", module, function);
+            return register_targets(project, vec![(name, module.to_owned(), function.to_owned())]);
+        }
+
+        let compiled = load_module(&project.get_module_path(&direct))?;
+        let name = self.target.clone().unwrap_or_else(|| format!("{}_{}", module, function));
+        let source = harness_source(&compiled, function, &name)?;
+        create_target_from(project, &name, format_args!("{}", source))
+            .with_context(|| format!("could not add target {:?}", name))?;
+        eprintln!("Generated {}", project.get_target_path(&name).display());
+        register_targets(project, vec![(name.clone(), name, String::from("fuzz_target"))])
+    }
+
+    /// Stores the modules of an on-chain package with the compiled ones, its
+    /// dependencies under `dependencies/<package-id>`, and registers a target in
+    /// fuzz.toml for each public function, named `<module>-<function>`.
+    pub fn add_from_chain(&self, project: &FuzzProject, package_id: &str) -> Result<()> {
+        let rpc = self.rpc.as_deref().unwrap_or(DEFAULT_RPC_URL);
+        let modules_dir = project.get_bytecode_modules_dir();
+        let mut fetched = BTreeSet::new();
+        let mut pending = vec![package_id.to_owned()];
+        let mut targets = vec![];
+        while let Some(id) = pending.pop() {
+            if !fetched.insert(id.clone()) {
+                continue;
+            }
+            eprintln!("Downloading package {} from {}", id, rpc);
+            let package = fetch_package(rpc, &id)?;
+            let dir = if id == package_id {
+                modules_dir.clone()
+            } else {
+                modules_dir.join("dependencies").join(&id)
+            };
+            for (name, bytes) in &package.modules {
+                let module = write_module(&dir, name, bytes)?;
+                if id == package_id {
+                    targets.extend(public_targets(name, &module));
+                }
+            }
+            pending.extend(package.dependencies);
+        }
+        register_targets(project, targets)
+    }
 
-        // Create corpus and artifact directories for the newly added target
+    /// Same as `add_from_chain` for an Aptos module, given as `address::module`:
+    /// the modules it uses are stored under `dependencies/<address>`.
+    pub fn add_from_aptos(&self, project: &FuzzProject, module_id: &str) -> Result<()> {
+        let node = self.rpc.as_deref().unwrap_or(DEFAULT_APTOS_NODE_URL);
+        let (address, name) = match module_id.split_once("::") {
+            Some(id) => id,
+            None => bail!("invalid module `{}`, expected `address::module`", module_id),
+        };
+        let modules_dir = project.get_bytecode_modules_dir();
+        let mut fetched = BTreeSet::new();
+        let mut pending = vec![(address.to_owned(), name.to_owned())];
+        let mut targets = vec![];
+        while let Some((address, name)) = pending.pop() {
+            if !fetched.insert((address.clone(), name.clone())) {
+                continue;
+            }
+            eprintln!("Downloading module {}::{} from {}", address, name, node);
+            let bytes = fetch_aptos_module(node, &address, &name)?;
+            let root = fetched.len() == 1;
+            let dir = if root {
+                modules_dir.clone()
+            } else {
+                modules_dir.join("dependencies").join(&address)
+            };
+            let module = write_module(&dir, &name, &bytes)
+                .context("the module may use an Aptos bytecode version the fuzzer doesn't support")?;
+            if root {
+                targets.extend(public_targets(&name, &module));
+            }
+            let self_id = module.self_id();
+            for handle in module.module_handles() {
+                let id = module.module_id_for_handle(handle);
+                if id != self_id {
+                    pending.push((id.address().to_hex_literal(), id.name().to_string()));
+                }
+            }
+        }
+        register_targets(project, targets)
+    }
+}
+
+/// A target module `fuzz::<name>` whose `fuzz_target` takes the parameters of
+/// `function`, references by value, and calls it.
+fn harness_source(module: &CompiledModule, function: &str, name: &str) -> Result<String> {
+    let def = find_function(module, function).context("the function was validated")?;
+    let handle = module.function_handle_at(def.function);
+    if def.visibility != Visibility::Public {
+        bail!("`{}` is not public, the harness can't call it", function);
+    }
+    if !handle.type_parameters.is_empty() {
+        bail!("`{}` is generic, write a harness instantiating its type parameters by hand", function);
+    }
+
+    let mut params = vec![];
+    let mut args = vec![];
+    for (i, token) in module.signature_at(handle.parameters).0.iter().enumerate() {
+        let (param, arg) = match token {
+            SignatureToken::Reference(inner) => (inner.as_ref(), format!("&arg{}", i)),
+            SignatureToken::MutableReference(inner) => (inner.as_ref(), format!("&mut arg{}", i)),
+            _ => (token, format!("arg{}", i)),
+        };
+        params.push(format!("arg{}: {}", i, format_qualified_type(module, param)));
+        args.push(arg);
+    }
+    let call = format!(
+        "{}::{}::{}({})",
+        module.address().to_hex_literal(),
+        module.self_id().name(),
+        function,
+        args.join(", ")
+    );
+    let call = match module.signature_at(handle.return_).0.len() {
+        0 => format!("{};", call),
+        1 => format!("let _ = {};", call),
+        n => format!("let ({}) = {};", vec!["_"; n].join(", "), call),
+    };
+    Ok(format!(
+        r##"module fuzz::{name} {{
+    // Generated from {module}::{function}; returned values without `drop` must be consumed
+    public fun fuzz_target({params}) {{
+        {call}
+    }}
+}}
+"##,
+        name = name,
+        module = module.self_id().name(),
+        function = function,
+        params = params.join(", "),
+        call = call,
+    ))
+}
+
+/// Writes the bytecode of module `name` in `dir`, returning it deserialized.
+fn write_module(dir: &Path, name: &str, bytes: &[u8]) -> Result<CompiledModule> {
+    let module = CompiledModule::deserialize_with_defaults(bytes)
+        .with_context(|| format!("could not deserialize module {}", name))?;
+    fs::create_dir_all(dir)
+        .with_context(|| format!("could not make a modules directory at {:?}", dir))?;
+    let path = dir.join(format!("{}.mv", name));
+    fs::write(&path, bytes).with_context(|| format!("could not write module {}", path.display()))?;
+    Ok(module)
+}
+
+fn public_targets(name: &str, module: &CompiledModule) -> Vec<(String, String, String)> {
+    public_functions(module)
+        .map(|def| {
+            let function = function_name(module, def);
+            (format!("{}-{}", name, function), name.to_owned(), function)
+        })
+        .collect()
+}
+
+/// Registers each of `targets`, as `(name, module, function)`, in fuzz.toml
+/// and makes its directories, keeping the settings of the ones already there.
+fn register_targets(project: &FuzzProject, targets: Vec<(String, String, String)>) -> Result<()> {
+    let mut config = project.config.clone();
+    for (name, module, function) in targets {
+        let target = Target {
+            target_module: Some(module.clone()),
+            target_function: Some(function.clone()),
+            target_name: None,
+        };
         project.corpus_for(&target)?;
         project.artifacts_for(&target)?;
-        
-        create_target_template(project, &self.target)
-            .with_context(|| format!("could not add target {:?}", self.target))
+        eprintln!("Registered target {}", name);
+        config.targets.entry(name).or_insert_with(|| TargetConfig {
+            module: Some(module),
+            function: Some(function),
+            ..TargetConfig::default()
+        });
     }
-}
\ No newline at end of file
+    config.save(&project.get_config_path())
+}
@@ -0,0 +1,96 @@
+use crate::{
+    build::exec_build, options::{corpus::upgrade_encoding, BuildOptions, FuzzDirWrapper}, project::FuzzProject,
+    trace::MOVE_FUZZER_COVERAGE_DIR_ENV, utils::strip_current_dir_prefix, RunCommand
+};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Parser)]
+pub struct Bench {
+    #[clap(flatten)]
+    pub build: BuildOptions,
+
+    #[clap(flatten)]
+    pub fuzz_dir_wrapper: FuzzDirWrapper,
+
+    #[clap(long, default_value = "10000")]
+    /// Number of executions, the corpus inputs being taken in turn
+    pub runs: usize,
+
+    #[clap(long)]
+    /// Corpus to execute instead of the target's
+    pub corpus: Option<PathBuf>,
+}
+
+impl RunCommand for Bench {
+    fn run_command(&mut self) -> Result<()> {
+        let project = FuzzProject::with_package(
+            self.fuzz_dir_wrapper.fuzz_dir.to_owned(),
+            self.build.package_path.clone(),
+        )?;
+        project.apply_config(&mut self.build);
+        self.exec_bench(&project)
+    }
+}
+
+/// Executions per second in a report printed by the worker's `--bench`.
+fn execs_per_sec(report: &str) -> Option<f64> {
+    report
+        .lines()
+        .find_map(|line| line.strip_prefix("Exec/s: "))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+impl Bench {
+    /// Runs the worker over the corpus and returns its report, recording the
+    /// Move coverage in `coverage_dir` if given.
+    fn bench(&self, project: &FuzzProject, corpus: &Path, coverage_dir: Option<&Path>) -> Result<String> {
+        let mut cmd = project.get_worker_command(&self.build)?;
+        cmd.arg(format!("--bench={}", corpus.display()));
+        cmd.arg(format!("--bench-runs={}", self.runs));
+        if let Some(dir) = coverage_dir {
+            cmd.env(MOVE_FUZZER_COVERAGE_DIR_ENV, dir);
+        }
+        let output = cmd
+            .output()
+            .with_context(|| format!("failed to execute: {:?}", cmd))?;
+        if !output.status.success() {
+            eprint!("{}", String::from_utf8_lossy(&output.stderr));
+            bail!("failed to benchmark target: {:?}", cmd);
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Measures the executions per second over the corpus, with the Move
+    /// coverage off then on, and the time spent decoding the inputs,
+    /// serializing the arguments and executing them in the VM
+    pub fn exec_bench(&self, project: &FuzzProject) -> Result<()> {
+        exec_build(&self.build, project)?;
+        upgrade_encoding(project, &self.build)?;
+
+        let corpus = match &self.corpus {
+            Some(corpus) => corpus.clone(),
+            None => project.corpus_for(&self.build.target)?,
+        };
+        eprintln!(
+            "Benchmarking {} executions over {}",
+            self.runs,
+            strip_current_dir_prefix(&corpus).display()
+        );
+
+        // The VM reads the trace path once per process, so each setting runs in its own
+        let without_coverage = self.bench(project, &corpus, None)?;
+        let coverage_dir = tempfile::tempdir().context("failed to create temp dir")?;
+        let with_coverage = self.bench(project, &corpus, Some(coverage_dir.path()))?;
+
+        println!("Coverage off:\n{}", without_coverage);
+        println!("Coverage on:\n{}", with_coverage);
+        if let (Some(off), Some(on)) = (execs_per_sec(&without_coverage), execs_per_sec(&with_coverage)) {
+            if on > 0.0 {
+                println!("Coverage overhead: {:.1}x", off / on);
+            }
+        }
+        Ok(())
+    }
+}
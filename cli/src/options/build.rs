@@ -5,9 +5,15 @@ use crate::{
 };
 use anyhow::{bail, Context, Result};
 use clap::Parser;
+use move_compiler::command_line::LintFlag;
+use move_core_types::account_address::AccountAddress;
+use move_package::BuildConfig;
+use sha2::{Digest, Sha256};
 
+use std::{fs, io, path::Path};
 
-use std::{process::Command};
+/// File of the package's `build` directory holding the fingerprint of its last build.
+const FINGERPRINT_FILE: &str = ".fuzz-fingerprint";
 
 #[derive(Clone, Debug, Parser)]
 pub struct Build {
@@ -20,59 +26,160 @@ pub struct Build {
 
 impl RunCommand for Build {
     fn run_command(&mut self)-> Result<()> {
-        let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.to_owned())?;
-        exec_build(&self.build, &project, false)
+        let project = FuzzProject::with_package(
+            self.fuzz_dir_wrapper.fuzz_dir.to_owned(),
+            self.build.package_path.clone(),
+        )?;
+        project.apply_config(&mut self.build);
+        exec_build(&self.build, &project)
     }
 }
 
-fn move_build(build: &BuildOptions) -> Result<Command> {
-    let mut cmd = Command::new("move");
-    cmd.arg("build");
-    
-    if build.verbose {
-        cmd.arg("--verbose");
-    }
+/// Feeds `bytes` to `hasher` prefixed with their length, so that consecutive
+/// fields can't be confused with each other.
+fn hash_field(hasher: &mut Sha256, bytes: &[u8]) {
+    hasher.update((bytes.len() as u64).to_le_bytes());
+    hasher.update(bytes);
+}
 
-    if build.build_config.fetch_deps_only {
-        cmd.arg("--fetch-deps-only");
+/// Feeds the contents of `path` to `hasher`, or a marker when it can't be read.
+fn hash_file(hasher: &mut Sha256, path: &Path) {
+    match fs::read(path) {
+        Ok(contents) => {
+            hash_field(hasher, b"file");
+            hash_field(hasher, &contents);
+        }
+        Err(_) => hash_field(hasher, b"missing"),
     }
+}
 
-    if build.build_config.force_recompilation {
-        cmd.arg("--force");
+/// SHA-256 of what the build depends on: the manifest, the lock file, the
+/// sources and the build options. It is persisted, so it hashes explicit
+/// fields rather than `Debug` output. Dependencies fetched from elsewhere are
+/// left out, `--force-build` picks up their changes.
+fn fingerprint(package: &Path, config: &BuildConfig) -> String {
+    let mut hasher = Sha256::new();
+    let lint = if config.lint_flag == LintFlag::LEVEL_NONE {
+        "none"
+    } else if config.lint_flag == LintFlag::LEVEL_ALL {
+        "all"
+    } else {
+        "default"
+    };
+    let options = [
+        ("dev", config.dev_mode.to_string()),
+        ("test", config.test_mode.to_string()),
+        ("doc", config.generate_docs.to_string()),
+        (
+            "install-dir",
+            config.install_dir.as_ref().map(|dir| dir.display().to_string()).unwrap_or_default(),
+        ),
+        ("flavor", config.default_flavor.as_ref().map(|flavor| flavor.to_string()).unwrap_or_default()),
+        ("edition", config.default_edition.as_ref().map(|edition| edition.to_string()).unwrap_or_default()),
+        ("deps-as-root", config.deps_as_root.to_string()),
+        ("warnings-are-errors", config.warnings_are_errors.to_string()),
+        ("lint", lint.to_string()),
+    ];
+    for (name, value) in options {
+        hash_field(&mut hasher, name.as_bytes());
+        hash_field(&mut hasher, value.as_bytes());
     }
-
-    if build.build_config.skip_fetch_latest_git_deps {
-        cmd.arg("--skip-fetch-latest-git-deps");
+    for (name, address) in &config.additional_named_addresses {
+        hash_field(&mut hasher, name.as_bytes());
+        hash_field(&mut hasher, &address.into_bytes());
     }
-
-    Ok(cmd)
+    for manifest in ["Move.toml", "Move.lock"] {
+        hash_file(&mut hasher, &package.join(manifest));
+    }
+    let mut sources = walkdir::WalkDir::new(package.join("sources"))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect::<Vec<_>>();
+    sources.sort();
+    for source in sources {
+        let relative = source.strip_prefix(package).unwrap_or(&source);
+        hash_field(&mut hasher, relative.to_string_lossy().as_bytes());
+        hash_file(&mut hasher, &source);
+    }
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
 }
 
-pub fn exec_build(
-    build: &BuildOptions,
-    project: &FuzzProject,
-    coverage: bool
-) -> Result<()> {
-    let mut move_cmd = move_build(build)?;
-
-    if let Some(target_dir) = project.get_target_dir(&build.package_path, coverage)? {
-        move_cmd.arg("--path").arg(&target_dir);
+/// Assigns the addresses given as `name=0x...` to the named addresses the
+/// package's manifest leaves unassigned (`"_"`), which would not compile
+/// otherwise. Assigned ones are moved by the worker when it loads the modules.
+fn assign_unassigned_addresses(package: &Path, addresses: &[String], config: &mut BuildConfig) -> Result<()> {
+    if addresses.is_empty() {
+        return Ok(());
     }
-    else {
-        move_cmd.arg("--path").arg(&project.get_fuzz_dir());
+    let manifest_path = package.join("Move.toml");
+    let manifest: toml::Value = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("failed to read {}", manifest_path.display()))?
+        .parse()
+        .with_context(|| format!("could not decode the manifest file at {}", manifest_path.display()))?;
+    let unassigned = |name: &str| {
+        manifest
+            .get("addresses")
+            .and_then(|addresses| addresses.get(name))
+            .and_then(toml::Value::as_str)
+            == Some("_")
+    };
+    for assignment in addresses {
+        let (name, literal) = match assignment.split_once('=') {
+            Some(pair) => pair,
+            None => bail!("invalid address assignment `{}`, expected `name=0x...`", assignment),
+        };
+        if unassigned(name) {
+            let address = AccountAddress::from_hex_literal(literal)
+                .with_context(|| format!("invalid address `{}` for `{}`", literal, name))?;
+            config.additional_named_addresses.insert(name.to_string(), address);
+        }
     }
+    Ok(())
+}
 
-    let mut move_build = Command::new("move");
-    move_build.arg("build").current_dir("fuzz");
+/// Compiles the fuzz package, or the package fuzzed directly, with the Move
+/// build options given, writing its bytecode to its `build` directory. The
+/// build is skipped when neither the package nor the options changed since
+/// the last one, unless `--force-build` is given.
+pub fn exec_build(build: &BuildOptions, project: &FuzzProject) -> Result<()> {
+    let package = project
+        .package
+        .clone()
+        .unwrap_or_else(|| project.get_fuzz_dir().to_owned());
+    let mut config = build.build_config.clone();
+    assign_unassigned_addresses(&package, &build.worker.addresses, &mut config)?;
+
+    // The compiler reports its diagnostics on the writer, keep stdout for results
+    let mut diagnostics = io::stderr();
+    if config.fetch_deps_only {
+        return config
+            .download_deps_for_package(&package, &mut diagnostics)
+            .with_context(|| format!("failed to fetch the dependencies of {}", package.display()));
+    }
 
-    let move_status = move_build
-        .status()
-        .with_context(|| format!("failed to execute: {:?}", move_build))?;
-    if !move_status.success() {
-        //bail!("failed to build fuzz script: {:?}", move_build);
-        bail!("failed to build fuzz script: {:?}", move_build);
+    let fingerprint_path = package.join("build").join(FINGERPRINT_FILE);
+    let fingerprint = fingerprint(&package, &config);
+    let unchanged = !build.force_build
+        && !config.force_recompilation
+        && project.get_bytecode_modules_dir().is_dir()
+        && fs::read_to_string(&fingerprint_path).map_or(false, |last| last == fingerprint);
+    if unchanged {
+        if build.verbose {
+            eprintln!("{} is unchanged, skipping the build", package.display());
+        }
+        return project.validate_target(&build.target);
     }
 
-    Ok(())
-}
+    if build.verbose {
+        eprintln!("Building {}", package.display());
+    }
+    config
+        .compile_package(&package, &mut diagnostics)
+        .with_context(|| format!("failed to build the Move package at {}", package.display()))?;
+    // A missing fingerprint only costs a rebuild next time
+    let _ = fs::write(&fingerprint_path, fingerprint);
 
+    project.validate_target(&build.target)
+}
@@ -0,0 +1,177 @@
+use crate::{
+    build::exec_build, crash::Outcome, engine::FuzzEngine,
+    options::{corpus::upgrade_encoding, BuildOptions, FuzzDirWrapper, OutputOptions}, project::FuzzProject, report::ReportOptions,
+    run::{random_seed, replay_input, Run}, RunCommand, Target
+};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::{collections::BTreeSet, fs, path::{Path, PathBuf}};
+
+#[derive(Clone, Debug, Parser)]
+// Every target is fuzzed unless one is selected
+#[command(mut_group("target", |group| group.required(false)))]
+pub struct Ci {
+    #[clap(flatten)]
+    pub build: BuildOptions,
+
+    #[clap(flatten)]
+    pub fuzz_dir_wrapper: FuzzDirWrapper,
+
+    #[clap(long, default_value = "60")]
+    /// Fuzzing time in seconds for each target
+    pub budget: u64,
+
+    #[clap(short, long, default_value = "1")]
+    /// Number of concurrent jobs to run for each target
+    pub jobs: u16,
+
+    #[clap(last(true))]
+    /// Additional libFuzzer arguments passed through to the binary
+    pub args: Vec<String>,
+}
+
+impl RunCommand for Ci {
+    fn run_command(&mut self) -> Result<()> {
+        let project = FuzzProject::with_package(
+            self.fuzz_dir_wrapper.fuzz_dir.to_owned(),
+            self.build.package_path.clone(),
+        )?;
+        self.exec_ci(&project)
+    }
+}
+
+/// What fuzzing a single target found.
+struct Summary {
+    name: String,
+    crashes: usize,
+    new: Vec<(Outcome, PathBuf)>,
+}
+
+fn artifacts_in(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut artifacts = fs::read_dir(dir)
+        .with_context(|| format!("failed to read directory entries of {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect::<Vec<_>>();
+    artifacts.sort();
+    Ok(artifacts)
+}
+
+impl Ci {
+    fn selected_targets(&self, project: &FuzzProject) -> Vec<BuildOptions> {
+        let targets = if self.build.target.target_name.is_some() || self.build.target.target_function.is_some() {
+            vec![self.build.target.clone()]
+        } else {
+            project
+                .targets
+                .iter()
+                .map(|name| Target {
+                    target_module: None,
+                    target_function: None,
+                    target_name: Some(name.clone()),
+                })
+                .collect()
+        };
+        targets
+            .into_iter()
+            .map(|target| {
+                let mut build = self.build.clone();
+                build.target = target;
+                project.apply_config(&mut build);
+                build
+            })
+            .collect()
+    }
+
+    /// Fuzzes `build.target` for the time budget and classifies the new artifacts
+    /// against the failures already known from the stored ones.
+    fn fuzz_target(&self, project: &FuzzProject, build: &BuildOptions) -> Result<Summary> {
+        exec_build(build, project)?;
+        upgrade_encoding(project, build)?;
+
+        let artifacts_dir = project.artifacts_for(&build.target)?;
+        let mut known = BTreeSet::new();
+        for artifact in artifacts_in(&artifacts_dir)? {
+            let (outcome, _) = replay_input(project, build, &artifact, &self.args)?;
+            known.insert(outcome);
+        }
+
+        let run = Run {
+            build: build.clone(),
+            corpus: vec![],
+            fuzz_dir_wrapper: self.fuzz_dir_wrapper.clone(),
+            jobs: self.jobs,
+            runs: None,
+            max_total_time: Some(self.budget),
+            // Printed with the summary, so that a failing job can be reproduced
+            seed: Some(random_seed()),
+            all_targets: false,
+            crash_on: vec![],
+            watch: false,
+            accumulate: false,
+            learn_dict: false,
+            resume: false,
+            dashboard: false,
+            metrics_addr: None,
+            statsd: None,
+            engine: FuzzEngine::LibFuzzer,
+            junit: None,
+            report: ReportOptions::default(),
+            output: OutputOptions::default(),
+            args: self.args.clone(),
+        };
+        let artifacts_before = project.get_artifacts(&build.target)?;
+        // A failing run is expected when a crash is found, the artifacts tell what happened
+        let _ = run.exec_fuzz_target(project);
+
+        let mut crashes = project
+            .get_new_artifacts(&build.target, &artifacts_before)?
+            .into_iter()
+            .collect::<Vec<_>>();
+        crashes.sort();
+
+        let mut new = vec![];
+        for artifact in &crashes {
+            let (outcome, _) = replay_input(project, build, artifact, &self.args)?;
+            if outcome.is_crash() && known.insert(outcome.clone()) {
+                new.push((outcome, artifact.clone()));
+            }
+        }
+
+        let name = match &build.target.target_name {
+            Some(name) => name.clone(),
+            None => format!("{}::{}", build.target.get_module_name(), build.target.get_target_function()),
+        };
+        Ok(Summary { name, crashes: crashes.len(), new })
+    }
+
+    /// Fuzzes every target for a fixed time and fails only on new unique crashes
+    pub fn exec_ci(&self, project: &FuzzProject) -> Result<()> {
+        let mut summaries = vec![];
+        for build in self.selected_targets(project) {
+            summaries.push(self.fuzz_target(project, &build)?);
+        }
+
+        eprintln!("\n{:─<80}", "");
+        eprintln!("\nFuzzed {} targets for {}s each\n", summaries.len(), self.budget);
+        for summary in &summaries {
+            let status = match (summary.crashes, summary.new.len()) {
+                (0, _) => String::from("ok"),
+                (crashes, 0) => format!("ok ({} known crashes)", crashes),
+                (crashes, new) => format!("FAILED ({} crashes, {} new)", crashes, new),
+            };
+            eprintln!("\t{:<40} {}", summary.name, status);
+            for (outcome, artifact) in &summary.new {
+                eprintln!("\t    {}\n\t    {}", outcome, artifact.display());
+            }
+        }
+        eprintln!("\n{:─<80}\n", "");
+
+        let new: usize = summaries.iter().map(|s| s.new.len()).sum();
+        if new > 0 {
+            bail!("Found {} new unique crashes", new);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{options::FuzzDirWrapper, project::FuzzProject, RunCommand, Target};
+use anyhow::{Context, Result};
+use clap::Parser;
+use std::{fs, path::{Path, PathBuf}};
+
+#[derive(Clone, Debug, Parser)]
+// Every target is cleaned unless one is selected
+#[command(mut_group("target", |group| group.required(false)))]
+pub struct Clean {
+    #[clap(flatten)]
+    pub target: Target,
+
+    #[clap(flatten)]
+    pub fuzz_dir_wrapper: FuzzDirWrapper,
+
+    #[clap(long)]
+    /// Clean the corpus
+    pub corpus: bool,
+
+    #[clap(long)]
+    /// Clean the artifacts
+    pub artifacts: bool,
+
+    #[clap(long)]
+    /// Clean the coverage data
+    pub coverage: bool,
+
+    #[clap(long)]
+    /// Move the data into this directory instead of removing it
+    pub archive: Option<PathBuf>,
+}
+
+impl RunCommand for Clean {
+    fn run_command(&mut self) -> Result<()> {
+        let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.to_owned())?;
+        self.exec_clean(&project)
+    }
+}
+
+impl Clean {
+    fn selected_targets(&self, project: &FuzzProject) -> Vec<Target> {
+        if self.target.target_name.is_some() || self.target.target_function.is_some() {
+            return vec![self.target.clone()];
+        }
+        project
+            .targets
+            .iter()
+            .map(|name| Target {
+                target_module: None,
+                target_function: None,
+                target_name: Some(name.clone()),
+            })
+            .collect()
+    }
+
+    /// Removes or archives a generated directory, keeping its path relative
+    /// to the fuzz directory inside the archive.
+    fn clean_dir(&self, project: &FuzzProject, dir: &Path) -> Result<()> {
+        if !dir.exists() {
+            return Ok(());
+        }
+        if let Some(archive) = &self.archive {
+            let relative = dir.strip_prefix(project.get_fuzz_dir()).unwrap_or(dir);
+            let dest = archive.join(relative);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("could not make an archive directory at {:?}", parent))?;
+            }
+            fs::rename(dir, &dest)
+                .with_context(|| format!("failed to move {} to {}", dir.display(), dest.display()))?;
+            eprintln!("Archived {} to {}", dir.display(), dest.display());
+        } else {
+            fs::remove_dir_all(dir).with_context(|| format!("failed to remove {}", dir.display()))?;
+            eprintln!("Removed {}", dir.display());
+        }
+        Ok(())
+    }
+
+    /// Removes or archives the corpus, artifacts and coverage of the selected
+    /// targets, or of every target. All three are cleaned when none is given.
+    pub fn exec_clean(&self, project: &FuzzProject) -> Result<()> {
+        let all = !(self.corpus || self.artifacts || self.coverage);
+        for target in self.selected_targets(project) {
+            if all || self.corpus {
+                self.clean_dir(project, &project.corpus_for(&target)?)?;
+            }
+            if all || self.artifacts {
+                self.clean_dir(project, &project.artifacts_for(&target)?)?;
+            }
+            if all || self.coverage {
+                let (raw, _) = project.coverage_for(&target)?;
+                self.clean_dir(project, raw.parent().unwrap_or(&raw))?;
+            }
+        }
+        // The Move coverage is recorded for the whole project
+        if (all || self.coverage) && self.target.target_name.is_none() && self.target.target_function.is_none() {
+            self.clean_dir(project, &project.move_coverage_dir()?)?;
+        }
+        Ok(())
+    }
+}
@@ -1,9 +1,10 @@
 use crate::{
-    build::exec_build, options::{BuildOptions, FuzzDirWrapper}, project::FuzzProject, RunCommand
+    build::exec_build, options::{corpus::upgrade_encoding, BuildOptions, FuzzDirWrapper}, project::FuzzProject,
+    trace::{replay_with_coverage, MoveCoverage}, RunCommand
 };
-use anyhow::{anyhow, Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::Parser;
-use std::{fs, path::PathBuf};
+use std::{cmp::Reverse, collections::HashSet, fs, path::PathBuf};
 
 
 
@@ -16,7 +17,8 @@ pub struct Cmin {
     pub fuzz_dir_wrapper: FuzzDirWrapper,
 
     #[clap()]
-    /// The corpus directory to minify into
+    /// The corpus directory to minify into, the target's one by default,
+    /// merging in the `corpus-dirs` of its configuration
     pub corpus: Option<PathBuf>,
 
     #[clap(last(true))]
@@ -26,49 +28,93 @@ pub struct Cmin {
 
 impl RunCommand for Cmin {
     fn run_command(&mut self)-> Result<()> {
-        let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.to_owned())?;
+        let project = FuzzProject::with_package(
+            self.fuzz_dir_wrapper.fuzz_dir.to_owned(),
+            self.build.package_path.clone(),
+        )?;
+        project.apply_config(&mut self.build);
         self.exec_cmin(&project)
     }
 }
 
 impl Cmin {
+    /// Keeps the smallest set of corpus entries preserving the Move coverage of the whole corpus
     pub fn exec_cmin(&self, project: &FuzzProject) -> Result<()> {
-        exec_build(&self.build, project, false)?;
-        let mut cmd = project.get_run_fuzzer_command(&self.build.target)?;
-        // todo: trasformare cargo run nel comando che ritorna la chiamata al fuzzer installato
+        exec_build(&self.build, project)?;
+        upgrade_encoding(project, &self.build)?;
 
-        for arg in &self.args {
-            cmd.arg(arg);
-        }
-
-        let corpus = if let Some(corpus) = self.corpus.clone() {
-            corpus
+        let (corpus, extra_dirs) = if let Some(corpus) = self.corpus.clone() {
+            (corpus, vec![])
         } else {
-            project.corpus_for(&self.build.target)?
+            (project.corpus_for(&self.build.target)?, project.corpus_dirs_for(&self.build.target))
         };
-        let corpus = corpus
-            .to_str()
-            .ok_or_else(|| anyhow!("corpus must be valid unicode"))?
-            .to_owned();
+
+        let mut entries = vec![];
+        for dir in std::iter::once(&corpus).chain(&extra_dirs) {
+            for entry in fs::read_dir(dir)
+                .with_context(|| format!("failed to read directory entries of {}", dir.display()))?
+            {
+                let path = entry?.path();
+                if !path.is_file() {
+                    continue;
+                }
+                let size = path.metadata()?.len();
+                let (_, coverage) = replay_with_coverage(project, &self.build, &path, &self.args)?;
+                entries.push((path, size, coverage));
+            }
+        }
+
+        let total = entries
+            .iter()
+            .flat_map(|(_, _, coverage)| coverage.iter().cloned())
+            .collect::<MoveCoverage>();
+        if total.is_empty() && !entries.is_empty() {
+            bail!(
+                "no corpus entry reached the target, or the worker does not trace Move \
+                 executions: reinstall it with `install-worker` so that it is built with \
+                 debug assertions"
+            );
+        }
+
+        // Greedily keep the entries adding new coverage, looking at the ones covering
+        // the most first and preferring the smallest among equivalent ones
+        entries.sort_by_key(|(path, size, coverage)| (Reverse(coverage.len()), *size, path.clone()));
+        let mut covered = MoveCoverage::new();
+        let mut kept = vec![];
+        for (path, _, coverage) in &entries {
+            if !coverage.is_subset(&covered) {
+                covered.extend(coverage.iter().cloned());
+                kept.push(path);
+            }
+        }
 
         let tmp: tempfile::TempDir = tempfile::TempDir::new_in(project.get_fuzz_dir())?;
         let tmp_corpus = tmp.path().join("corpus");
         fs::create_dir(&tmp_corpus)?;
-
-        // cmd.arg("-merge=1").arg(&tmp_corpus).arg(&corpus); // todo: passare argomento a move-fuzzer
-
-        // Spawn cmd in child process instead of exec-ing it
-        let status = cmd
-            .status()
-            .with_context(|| format!("could not execute command: {:?}", cmd))?;
-        if status.success() {
-            // move corpus directory into tmp to auto delete it
-            fs::rename(&corpus, tmp.path().join("old"))?;
-            fs::rename(tmp.path().join("corpus"), corpus)?;
-        } else {
-            println!("Failed to minimize corpus: {}", status);
+        let mut names = HashSet::new();
+        for path in &kept {
+            let name = path.file_name().expect("corpus entries are files").to_string_lossy();
+            // Entries of different corpus directories may share a name
+            let mut unique = name.to_string();
+            let mut n = 1;
+            while !names.insert(unique.clone()) {
+                unique = format!("{}-{}", name, n);
+                n += 1;
+            }
+            fs::copy(path, tmp_corpus.join(unique))
+                .with_context(|| format!("failed to copy {}", path.display()))?;
         }
 
+        // move corpus directory into tmp to auto delete it
+        fs::rename(&corpus, tmp.path().join("old"))?;
+        fs::rename(&tmp_corpus, &corpus)?;
+
+        eprintln!(
+            "Kept {} of {} corpus entries, covering {} Move instructions",
+            kept.len(),
+            entries.len(),
+            covered.len()
+        );
         Ok(())
     }
-}
\ No newline at end of file
+}
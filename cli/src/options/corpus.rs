@@ -0,0 +1,218 @@
+use crate::{
+    build::exec_build, options::{BuildOptions, FuzzDirWrapper, Target}, project::FuzzProject,
+    remote::Remote, RunCommand
+};
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use std::{ffi, fs, io, path::{Path, PathBuf}};
+
+#[derive(Clone, Debug, Parser)]
+pub struct Corpus {
+    #[clap(subcommand)]
+    pub command: CorpusCommand,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum CorpusCommand {
+    /// Re-encode the corpus of a target whose parameters changed since an earlier build,
+    /// or bring it to the input encoding of the worker
+    Migrate(Migrate),
+
+    /// Upload the corpus entries of a target missing from the shared corpus
+    Push(CorpusSync),
+
+    /// Download the shared corpus entries of a target missing locally
+    Pull(CorpusSync),
+}
+
+impl RunCommand for Corpus {
+    fn run_command(&mut self) -> Result<()> {
+        match &mut self.command {
+            CorpusCommand::Migrate(x) => x.run_command(),
+            CorpusCommand::Push(x) => x.exec_sync(Remote::push),
+            CorpusCommand::Pull(x) => x.exec_sync(Remote::pull),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Parser)]
+pub struct Migrate {
+    #[clap(flatten)]
+    pub build: BuildOptions,
+
+    #[clap(flatten)]
+    pub fuzz_dir_wrapper: FuzzDirWrapper,
+
+    #[clap(long)]
+    /// Compiled modules of the earlier build the corpus was generated with,
+    /// e.g. a copy of `build/fuzz/bytecode_modules` made before the change.
+    /// Without it, the entries are only brought to the worker's input
+    /// encoding from the version recorded for the corpus, and stamped with it
+    pub old_build: Option<PathBuf>,
+
+    #[clap(long)]
+    /// Directory the migrated corpus is written to, instead of rewriting it in place
+    pub output: Option<PathBuf>,
+}
+
+impl RunCommand for Migrate {
+    fn run_command(&mut self) -> Result<()> {
+        let project = FuzzProject::with_package(
+            self.fuzz_dir_wrapper.fuzz_dir.to_owned(),
+            self.build.package_path.clone(),
+        )?;
+        project.apply_config(&mut self.build);
+        self.exec_migrate(&project)
+    }
+}
+
+impl Migrate {
+    /// Decodes each corpus entry with the parameters of the earlier build and
+    /// re-encodes the arguments for the current ones, so that the coverage the
+    /// corpus reached survives a signature change.
+    pub fn exec_migrate(&self, project: &FuzzProject) -> Result<()> {
+        let old_module = match &self.old_build {
+            Some(old_build) => {
+                let old_module = old_build.join(format!("{}.mv", self.build.target.get_module_name()));
+                if !old_module.is_file() {
+                    bail!("could not find module `{}` in {}", self.build.target.get_module_name(), old_build.display());
+                }
+                Some(old_module)
+            }
+            None => None,
+        };
+        exec_build(&self.build, project)?;
+
+        let target = &self.build.target;
+        let recorded = recorded_encoding(project, target)?;
+        let corpus = project.corpus_for(target)?;
+        migrate(project, &self.build, &corpus, old_module.as_deref(), self.output.as_deref(), recorded)?;
+        // Entries left in place without a header are now all in the worker's encoding
+        if self.output.is_none() {
+            if let Some(version) = project.encoding_version(&self.build)? {
+                record_encoding(project, target, version)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Version of the input encoding corpora were written with before the CLI
+/// recorded it.
+const UNRECORDED_ENCODING_VERSION: u8 = 2;
+
+/// Returns the version of the input encoding the entries without a header in
+/// the target's corpus and artifacts were written with.
+fn recorded_encoding(project: &FuzzProject, target: &Target) -> Result<u8> {
+    let path = project.encoding_file_for(target);
+    match fs::read_to_string(&path) {
+        Ok(version) => version
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid input encoding version in {}", path.display())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(UNRECORDED_ENCODING_VERSION),
+        Err(e) => Err(e).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+fn record_encoding(project: &FuzzProject, target: &Target, version: u8) -> Result<()> {
+    let path = project.encoding_file_for(target);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("could not make the directory {}", dir.display()))?;
+    }
+    fs::write(&path, format!("{}\n", version)).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Has the worker re-encode the entries of `dir` for the current build, from
+/// `old_module` if given, decoding the ones without a header as
+/// `encoding_version`.
+fn migrate(
+    project: &FuzzProject,
+    build: &BuildOptions,
+    dir: &Path,
+    old_module: Option<&Path>,
+    output: Option<&Path>,
+    encoding_version: u8,
+) -> Result<()> {
+    let mut cmd = project.get_worker_command(build)?;
+    if let Some(old_module) = old_module {
+        let mut migrate_from_arg = ffi::OsString::from("--migrate-from=");
+        migrate_from_arg.push(old_module);
+        cmd.arg(migrate_from_arg);
+    }
+    let mut migrate_corpus_arg = ffi::OsString::from("--migrate-corpus=");
+    migrate_corpus_arg.push(dir);
+    cmd.arg(migrate_corpus_arg);
+    if let Some(output) = output {
+        let mut output_arg = ffi::OsString::from("--migrate-output=");
+        output_arg.push(output);
+        cmd.arg(output_arg);
+    }
+    cmd.arg(format!("--encoding-version={}", encoding_version));
+
+    let status = cmd
+        .status()
+        .with_context(|| format!("failed to execute: {:?}", cmd))?;
+    if !status.success() {
+        bail!("failed to migrate {}: {:?}", dir.display(), cmd);
+    }
+    Ok(())
+}
+
+/// Brings the target's corpus and artifacts to the worker's input encoding
+/// when their entries without a header were written with another version,
+/// since the worker decodes those as its own. Called before the inputs are
+/// read, once the worker is built.
+pub fn upgrade_encoding(project: &FuzzProject, build: &BuildOptions) -> Result<()> {
+    let version = match project.encoding_version(build)? {
+        Some(version) => version,
+        // A worker predating the versions decodes every input the same way
+        None => return Ok(()),
+    };
+    let target = &build.target;
+    let recorded = recorded_encoding(project, target)?;
+    if recorded != version {
+        for dir in [project.corpus_for(target)?, project.artifacts_for(target)?] {
+            migrate(project, build, &dir, None, None, recorded)?;
+        }
+        eprintln!(
+            "Migrated the inputs of {}::{} from input encoding version {} to {}",
+            target.get_module_name(),
+            target.get_target_function(),
+            recorded,
+            version
+        );
+    }
+    if recorded != version || !project.encoding_file_for(target).is_file() {
+        record_encoding(project, target, version)?;
+    }
+    Ok(())
+}
+
+#[derive(Clone, Debug, Parser)]
+pub struct CorpusSync {
+    #[clap(flatten)]
+    pub target: Target,
+
+    #[clap(flatten)]
+    pub fuzz_dir_wrapper: FuzzDirWrapper,
+
+    #[clap(long)]
+    /// Shared corpus location, as an `s3://`, `gs://` or `file://` URL or a
+    /// directory; defaults to the `corpus-url` of fuzz.toml
+    pub url: Option<String>,
+}
+
+impl CorpusSync {
+    /// Copies entries between the target's corpus and its shared one, never
+    /// deleting any, so that machines fuzzing the same target share progress.
+    pub fn exec_sync(&self, sync: fn(&Remote, &Target, &Path) -> Result<()>) -> Result<()> {
+        let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.to_owned())?;
+        let url = match self.url.clone().or_else(|| project.config.corpus_url.clone()) {
+            Some(url) => url,
+            None => bail!("no shared corpus location, give one with `--url` or the `corpus-url` key of fuzz.toml"),
+        };
+        let remote = Remote::parse(&url)?;
+        sync(&remote, &self.target, &project.corpus_for(&self.target)?)
+    }
+}
@@ -1,10 +1,16 @@
-use std::{env, fs, path::{Path, PathBuf}, process::Command};
+use std::{env, fs, path::{Path, PathBuf}, process::Command, time};
 
 use crate::{
-    build::exec_build, options::{BuildOptions, FuzzDirWrapper}, project::FuzzProject, utils::rustlib, RunCommand
+    abi::load_module, build::exec_build, options::{corpus::upgrade_encoding, BuildOptions, FuzzDirWrapper, OutputOptions}, project::FuzzProject,
+    trace::{
+        annotated_disassembly, covered_source_lines, coverage_diff, function_coverage, merge_coverage_maps,
+        merge_hit_counts, read_move_coverage, write_move_coverage, MoveCoverage, MOVE_FUZZER_COVERAGE_DIR_ENV
+    },
+    utils::rustlib, RunCommand
 };
 use anyhow::{anyhow, bail, Context, Result};
 use clap::Parser;
+use serde_json::json;
 
 #[derive(Clone, Debug, Parser)]
 pub struct Coverage {
@@ -18,6 +24,25 @@ pub struct Coverage {
     #[clap(long)]
     pub llvm_path: Option<PathBuf>,
 
+    #[clap(flatten)]
+    pub output: OutputOptions,
+
+    #[clap(long, value_parser = parse_percent)]
+    /// Fail if the corpus covers less than this percentage of the Move
+    /// instructions of the target module
+    pub threshold: Option<f64>,
+
+    #[clap(long)]
+    /// Move coverage to compare with, saved by an earlier run of this command
+    /// (`move-coverage.json`) or a `.coverage_map` of a campaign; reports the
+    /// functions and source lines newly covered or no longer covered
+    pub baseline: Option<PathBuf>,
+
+    #[clap(long)]
+    /// Print the disassembled target module, each instruction with the
+    /// number of times the corpus ran it; for targets without sources
+    pub bytecode: bool,
+
     /// Custom corpus directories or artifact files
     pub corpus: Vec<String>,
 
@@ -28,20 +53,34 @@ pub struct Coverage {
 
 impl RunCommand for Coverage {
     fn run_command(&mut self)-> Result<()> {
-        let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.to_owned())?;
+        let project = FuzzProject::with_package(
+            self.fuzz_dir_wrapper.fuzz_dir.to_owned(),
+            self.build.package_path.clone(),
+        )?;
+        project.apply_config(&mut self.build);
         self.exec_coverage(&project)
     }
 }
 
+fn parse_percent(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(percent),
+        _ => Err(format!("`{}` is not a percentage between 0 and 100", value)),
+    }
+}
+
 impl Coverage {
     /// Produce self information for a given corpus
     pub fn exec_coverage(&self, project: &FuzzProject) -> Result<()> {
         // Build project with source-based self generation enabled.
-        exec_build(&self.build, project, true)?;
+        exec_build(&self.build, project)?;
+        upgrade_encoding(project, &self.build)?;
 
         // Retrieve corpus directories.
         let corpora = if self.corpus.is_empty() {
-            vec![project.corpus_for(&self.build.target)?]
+            let mut corpora = vec![project.corpus_for(&self.build.target)?];
+            corpora.extend(project.corpus_dirs_for(&self.build.target));
+            corpora
         } else {
             self
                 .corpus
@@ -66,13 +105,17 @@ impl Coverage {
                 or provide a nonempty corpus directory."
             )
         }
+        let inputs = readable_input_files.count();
 
         let (self_out_raw_dir, self_out_file) = project.coverage_for(&self.build.target)?;
+        // The workers record the Move coverage of the inputs they run there
+        let move_coverage_dir = tempfile::tempdir().context("failed to create temp dir")?;
 
         for corpus in corpora.iter() {
             // _tmp_dir is deleted when it goes of of scope.
             let (mut cmd, _tmp_dir) =
                 self.create_coverage_cmd(project, &self_out_raw_dir, &corpus.as_path())?;
+            cmd.env(MOVE_FUZZER_COVERAGE_DIR_ENV, move_coverage_dir.path());
             eprintln!("Generating self data for corpus {:?}", corpus);
             let status = cmd
                 .status()
@@ -95,9 +138,133 @@ impl Coverage {
             &self_out_file,
         )?;
 
+        let (move_coverage, _) = merge_coverage_maps(move_coverage_dir.path(), &self.build.target, &time::SystemTime::UNIX_EPOCH)?;
+        let module = load_module(&project.get_module_path(&self.build.target))?;
+        let functions = if self.build.worker.include_deps {
+            // Every module the corpus reached, functions named after their module
+            let mut functions = vec![];
+            for path in walkdir::WalkDir::new(project.get_bytecode_modules_dir())
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().extension().map_or(false, |ext| ext == "mv"))
+            {
+                let dependency = load_module(path.path())?;
+                let reached = function_coverage(&dependency, &move_coverage);
+                if reached.iter().any(|(_, covered, _)| *covered > 0) {
+                    let name = dependency.self_id().name().to_string();
+                    functions.extend(
+                        reached
+                            .into_iter()
+                            .map(|(function, covered, total)| (format!("{}::{}", name, function), covered, total)),
+                    );
+                }
+            }
+            functions
+        } else {
+            function_coverage(&module, &move_coverage)
+        };
+        let covered = functions.iter().map(|(_, covered, _)| covered).sum::<usize>();
+        let total = functions.iter().map(|(_, _, total)| total).sum::<usize>();
+        let percent = |covered: usize, total: usize| 100.0 * covered as f64 / total.max(1) as f64;
+
+        eprintln!("\n{:<40} {:>9} {:>9} {:>8}", "Function", "Covered", "Total", "%");
+        for (name, covered, total) in &functions {
+            eprintln!("{:<40} {:>9} {:>9} {:>7.1}%", name, covered, total, percent(*covered, *total));
+        }
+        eprintln!("{:<40} {:>9} {:>9} {:>7.1}%\n", "Total", covered, total, percent(covered, total));
+
+        // Saved for later runs to compare with
+        let saved = self_out_file.with_file_name("move-coverage.json");
+        write_move_coverage(&saved, &move_coverage)?;
+        eprintln!("Move coverage saved in {}", saved.display());
+
+        let bytecode = if self.bytecode {
+            let hits = merge_hit_counts(move_coverage_dir.path(), &self.build.target)?;
+            let disassembly = annotated_disassembly(&module, &hits)?;
+            if !self.output.is_json() {
+                println!("{}", disassembly);
+            }
+            Some(disassembly)
+        } else {
+            None
+        };
+
+        let baseline = match &self.baseline {
+            Some(path) => Some(self.compare(project, path, &move_coverage)?),
+            None => None,
+        };
+
+        self.output.emit(json!({
+            "target": self.build.target.get_command(),
+            "corpora": corpora.iter().map(|c| c.display().to_string()).collect::<Vec<_>>(),
+            "inputs": inputs,
+            "profdata": self_out_file.display().to_string(),
+            "baseline": baseline,
+            "bytecode": bytecode,
+            "move_coverage": {
+                "covered": covered,
+                "total": total,
+                "functions": functions
+                    .iter()
+                    .map(|(name, covered, total)| json!({ "name": name, "covered": covered, "total": total }))
+                    .collect::<Vec<_>>(),
+            },
+        }));
+        if let Some(threshold) = self.threshold {
+            if percent(covered, total) < threshold {
+                bail!(
+                    "Move coverage of {:.1}% is below the threshold of {}%",
+                    percent(covered, total),
+                    threshold
+                );
+            }
+        }
         Ok(())
     }
 
+    /// Prints the Move coverage gained and lost since the `baseline`, and
+    /// returns it for the JSON output.
+    fn compare(&self, project: &FuzzProject, baseline: &Path, coverage: &MoveCoverage) -> Result<serde_json::Value> {
+        let before = read_move_coverage(baseline)?;
+        let functions = coverage_diff(&before, coverage);
+        let lines_before = covered_source_lines(project, &before);
+        let lines_after = covered_source_lines(project, coverage);
+        let gained_lines = lines_after.difference(&lines_before).collect::<Vec<_>>();
+        let lost_lines = lines_before.difference(&lines_after).collect::<Vec<_>>();
+
+        eprintln!("Compared with {}:", baseline.display());
+        if functions.is_empty() {
+            eprintln!("  Same Move instructions covered\n");
+        } else {
+            eprintln!("\n{:<60} {:>9} {:>9}", "Function", "Gained", "Lost");
+            for (name, gained, lost) in &functions {
+                eprintln!("{:<60} {:>9} {:>9}", name, gained, lost);
+            }
+            eprintln!();
+        }
+        for (title, lines) in [("Lines newly covered", &gained_lines), ("Lines no longer covered", &lost_lines)] {
+            if !lines.is_empty() {
+                eprintln!("{}:", title);
+                for line in lines {
+                    eprintln!("\t{}", line);
+                }
+                eprintln!();
+            }
+        }
+
+        Ok(json!({
+            "path": baseline.display().to_string(),
+            "gained": functions.iter().map(|(_, gained, _)| gained).sum::<usize>(),
+            "lost": functions.iter().map(|(_, _, lost)| lost).sum::<usize>(),
+            "functions": functions
+                .iter()
+                .map(|(name, gained, lost)| json!({ "name": name, "gained": gained, "lost": lost }))
+                .collect::<Vec<_>>(),
+            "lines_gained": gained_lines,
+            "lines_lost": lost_lines,
+        }))
+    }
+
     fn create_coverage_cmd(
         &self,
         project: &FuzzProject,
@@ -137,7 +304,7 @@ impl Coverage {
         cmd.arg(dummy_corpus.path());
         cmd.arg(corpus_dir);
 
-        for arg in &self.args {
+        for arg in project.libfuzzer_args(&self.build.target, &self.args) {
             cmd.arg(arg);
         }
 
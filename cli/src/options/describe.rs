@@ -0,0 +1,43 @@
+use crate::{
+    build::exec_build, options::{BuildOptions, FuzzDirWrapper}, project::FuzzProject, RunCommand
+};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+
+#[derive(Clone, Debug, Parser)]
+pub struct Describe {
+    #[clap(flatten)]
+    pub build: BuildOptions,
+
+    #[clap(flatten)]
+    pub fuzz_dir_wrapper: FuzzDirWrapper,
+}
+
+impl RunCommand for Describe {
+    fn run_command(&mut self) -> Result<()> {
+        let project = FuzzProject::with_package(
+            self.fuzz_dir_wrapper.fuzz_dir.to_owned(),
+            self.build.package_path.clone(),
+        )?;
+        project.apply_config(&mut self.build);
+        self.exec_describe(&project)
+    }
+}
+
+impl Describe {
+    /// Prints the argument generation plan the worker uses for the target
+    pub fn exec_describe(&self, project: &FuzzProject) -> Result<()> {
+        exec_build(&self.build, project)?;
+        let mut cmd = project.get_worker_command(&self.build)?;
+        cmd.arg("--describe");
+
+        let status = cmd
+            .status()
+            .with_context(|| format!("failed to execute: {:?}", cmd))?;
+        if !status.success() {
+            bail!("failed to describe target: {:?}", cmd);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{
+    build::exec_build, options::{corpus::upgrade_encoding, BuildOptions, FuzzDirWrapper}, project::FuzzProject, RunCommand,
+};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::{fs, path::PathBuf, process::Stdio};
+
+#[derive(Clone, Debug, Parser)]
+pub struct ExportTest {
+    #[clap(flatten)]
+    pub build: BuildOptions,
+
+    #[clap(flatten)]
+    pub fuzz_dir_wrapper: FuzzDirWrapper,
+
+    /// Path to the input to export
+    pub artifact: PathBuf,
+
+    #[clap(long, short = 'o')]
+    /// Path of the generated Move file, defaults to `fuzz/tests/<name>.move`
+    pub output: Option<PathBuf>,
+
+    #[clap(long)]
+    /// Name of the generated test module and function
+    pub name: Option<String>,
+}
+
+impl RunCommand for ExportTest {
+    fn run_command(&mut self) -> Result<()> {
+        let project = FuzzProject::with_package(
+            self.fuzz_dir_wrapper.fuzz_dir.to_owned(),
+            self.build.package_path.clone(),
+        )?;
+        project.apply_config(&mut self.build);
+        self.exec_export_test(&project)
+    }
+}
+
+impl ExportTest {
+    /// Default test name, derived from the target function and the artifact file name
+    fn default_name(&self) -> String {
+        let artifact = self
+            .artifact
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let suffix = artifact
+            .chars()
+            .take(16)
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+            .collect::<String>();
+        format!("regression_{}_{}", self.build.target.get_target_function(), suffix)
+    }
+
+    /// Writes a Move unit test calling the target with the arguments decoded from the artifact
+    pub fn exec_export_test(&self, project: &FuzzProject) -> Result<()> {
+        if !self.artifact.exists() {
+            bail!("Input test case does not exist: {}", self.artifact.display());
+        }
+
+        let name = self.name.clone().unwrap_or_else(|| self.default_name());
+        let output = match &self.output {
+            Some(output) => output.clone(),
+            None => project.get_fuzz_dir().join("tests").join(format!("{}.move", name)),
+        };
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("could not make a tests directory at {:?}", parent))?;
+        }
+
+        exec_build(&self.build, project)?;
+        upgrade_encoding(project, &self.build)?;
+
+        let mut cmd = project.get_run_fuzzer_command(&self.build)?;
+        cmd.stdin(Stdio::null());
+        cmd.env("MOVE_FUZZER_EXPORT_TEST_PATH", &output);
+        cmd.env("MOVE_FUZZER_EXPORT_TEST_NAME", &name);
+        cmd.arg(&self.artifact);
+
+        let result = cmd
+            .output()
+            .with_context(|| format!("failed to run command: {:?}", cmd))?;
+        if !result.status.success() || !output.exists() {
+            bail!(
+                "failed to export {} as a Move test\n\n\
+                 Command: {:?}\n\n\
+                 === stderr ===\n\
+                 {}",
+                self.artifact.display(),
+                cmd,
+                String::from_utf8_lossy(&result.stderr)
+            );
+        }
+
+        eprintln!("Move test written to {}", output.display());
+        eprintln!("Parameters the generator couldn't express in Move are left as TODO comments.");
+        Ok(())
+    }
+}
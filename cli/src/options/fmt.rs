@@ -20,7 +20,11 @@ pub struct Fmt {
 
 impl RunCommand for Fmt {
     fn run_command(&mut self)-> Result<()> {
-        let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.to_owned())?;
+        let project = FuzzProject::with_package(
+            self.fuzz_dir_wrapper.fuzz_dir.to_owned(),
+            self.build.package_path.clone(),
+        )?;
+        project.apply_config(&mut self.build);
         self.debug_fmt_input(&project)
     }
 }
@@ -1,9 +1,26 @@
-use crate::{options::FuzzDirWrapper, project::FuzzProject, templates::create_target_template, utils::manage_initial_instance, RunCommand};
+use crate::{
+    config::{FuzzConfig, TargetConfig}, options::FuzzDirWrapper, project::FuzzProject, utils::manage_initial_instance,
+    RunCommand
+};
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 
 
-use std::{fs, io::Write, path::{PathBuf}};
+use std::{fs, path::{Path, PathBuf}};
+
+/// Kinds of Move packages a fuzz project can be created for.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum ProjectTemplate {
+    /// Sui framework, with a target round-tripping `sui::hex`
+    Sui,
+    /// Aptos framework, with a target calling `aptos_std::math64`
+    Aptos,
+    /// Move standard library, with a target calling `std::vector`
+    #[default]
+    Core,
+    /// No dependency, with an empty target
+    Minimal,
+}
 
 #[derive(Clone, Debug, Parser)]
 pub struct Init {
@@ -15,6 +32,16 @@ pub struct Init {
     /// Whether to create a separate workspace for fuzz targets crate
     pub fuzzing_workspace: Option<bool>,
 
+    #[clap(long, value_enum, default_value_t = ProjectTemplate::Core)]
+    /// Framework the fuzz package depends on, setting up its dependencies,
+    /// named addresses and an example target
+    pub template: ProjectTemplate,
+
+    #[clap(long)]
+    /// Overwrite the files of an existing project with the templates, only
+    /// the missing ones are added otherwise
+    pub force: bool,
+
     #[clap(flatten)] 
     pub fuzz_dir_wrapper: FuzzDirWrapper,
 }
@@ -26,42 +53,116 @@ impl RunCommand for Init {
     }
 }
 
+/// Files and directories written by `init`, so that a failure part way
+/// leaves the fuzz directory as it was.
+#[derive(Default)]
+struct Written {
+    dirs: Vec<PathBuf>,
+    /// Files written, with their contents before if they were overwritten
+    files: Vec<(PathBuf, Option<Vec<u8>>)>,
+}
+
+impl Written {
+    fn create_dir_all(&mut self, dir: &Path) -> Result<()> {
+        let mut missing = dir.ancestors().take_while(|d| !d.exists()).collect::<Vec<_>>();
+        missing.reverse();
+        for dir in missing {
+            fs::create_dir(dir).with_context(|| format!("failed to create directory {}", dir.display()))?;
+            self.dirs.push(dir.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Writes `contents` to `path`, unless it exists and `force` is not set.
+    /// Returns whether the file was written.
+    fn write(&mut self, path: &Path, contents: &str, force: bool) -> Result<bool> {
+        let previous = match fs::read(path) {
+            Ok(_) if !force => return Ok(false),
+            Ok(bytes) => Some(bytes),
+            Err(_) => None,
+        };
+        if let Some(parent) = path.parent() {
+            self.create_dir_all(parent)?;
+        }
+        // Recorded first, a failed write may still have truncated the file
+        self.files.push((path.to_path_buf(), previous));
+        fs::write(path, contents).with_context(|| format!("failed to write to {}", path.display()))?;
+        Ok(true)
+    }
+
+    /// Restores the overwritten files and removes what was created.
+    fn rollback(self) {
+        for (path, previous) in self.files.into_iter().rev() {
+            let _ = match previous {
+                Some(bytes) => fs::write(&path, bytes),
+                None => fs::remove_file(&path),
+            };
+        }
+        for dir in self.dirs.into_iter().rev() {
+            let _ = fs::remove_dir(&dir);
+        }
+    }
+}
+
 impl Init {
     /// Creates the fuzz project structure and returns a new instance.
     ///
     /// This will not clone move-fuzzer.
     /// Similar to `FuzzProject::new`, the fuzz directory will depend on `fuzz_dir_opt`.
+    /// An existing project only gets its missing files, or all of them again
+    /// with `--force`; nothing is left behind if a step fails.
     pub fn init(&self, fuzz_dir_opt: Option<PathBuf>) -> Result<FuzzProject> {
         let project = manage_initial_instance(fuzz_dir_opt)?;
-        let fuzz_project = project.get_fuzz_dir();
-
-        // TODO: check if the project is already initialized
-        fs::create_dir(fuzz_project)
-            .with_context(|| format!("failed to create directory {}", fuzz_project.display()))?;
+        let mut written = Written::default();
+        if let Err(e) = self.write_project(&project, &mut written) {
+            written.rollback();
+            return Err(e);
+        }
+        Ok(project)
+    }
 
+    fn write_project(&self, project: &FuzzProject, written: &mut Written) -> Result<()> {
+        let fuzz_project = project.get_fuzz_dir();
         let move_toml_path = fuzz_project.join("Move.toml");
+        if move_toml_path.is_file() && !self.force {
+            eprintln!("{} is already a fuzz project, adding the missing files", fuzz_project.display());
+        }
+        written.create_dir_all(fuzz_project)?;
 
-        let mut move_toml = fs::File::create(&move_toml_path)
-            .with_context(|| format!("failed to create {}", move_toml_path.display()))?;
-
-        move_toml
-            .write_fmt(move_toml_template!())
-            .with_context(|| format!("failed to write to {}", move_toml_path.display()))?;
-
-        let gitignore = fuzz_project.join(".gitignore");
-        let mut ignore = fs::File::create(&gitignore)
-            .with_context(|| format!("failed to create {}", gitignore.display()))?;
-        ignore
-            .write_fmt(gitignore_template!())
-            .with_context(|| format!("failed to write to {}", gitignore.display()))?;
-
-        create_target_template(&project, &self.target)
-            .with_context(|| {
-                format!(
-                    "could not create template file for target {:?}",
-                    self.target
-                )
-            })?;
-        Ok(project)
+        let manifest = match self.template {
+            ProjectTemplate::Sui => sui_move_toml_template!().to_string(),
+            ProjectTemplate::Aptos => aptos_move_toml_template!().to_string(),
+            ProjectTemplate::Core => move_toml_template!().to_string(),
+            ProjectTemplate::Minimal => minimal_move_toml_template!().to_string(),
+        };
+        let target = &self.target;
+        let source = match self.template {
+            ProjectTemplate::Sui => sui_target_template!(target).to_string(),
+            ProjectTemplate::Aptos => aptos_target_template!(target).to_string(),
+            ProjectTemplate::Core => core_target_template!(target).to_string(),
+            ProjectTemplate::Minimal => move_target_template!(target).to_string(),
+        };
+        let files = [
+            (move_toml_path, manifest),
+            (fuzz_project.join(".gitignore"), gitignore_template!().to_string()),
+            (project.get_target_path(target), source),
+        ];
+        for (path, contents) in &files {
+            if !written.write(path, contents, self.force)? {
+                eprintln!("Kept the existing {}, use `--force` to overwrite it", path.display());
+            }
+        }
+
+        // The target is added to the definitions of an existing fuzz.toml
+        let config_path = project.get_config_path();
+        let mut config = FuzzConfig::load(&config_path)?;
+        config.targets.entry(target.clone()).or_insert_with(|| TargetConfig {
+            module: Some(target.clone()),
+            function: Some(String::from("fuzz_target")),
+            ..TargetConfig::default()
+        });
+        let contents = toml::to_string_pretty(&config).context("could not encode the config")?;
+        written.write(&config_path, &contents, true)?;
+        Ok(())
     }
 }
@@ -0,0 +1,93 @@
+use crate::{engine::FuzzEngine, RunCommand};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+
+use std::{path::PathBuf, process::Command};
+
+const WORKER_GIT_URL: &str = "https://github.com/LucaPoda/move-fuzzer";
+
+#[derive(Clone, Debug, Parser)]
+pub struct InstallWorker {
+    #[clap(long)]
+    /// Path to a local checkout of the move-fuzzer worker crate
+    pub path: Option<PathBuf>,
+
+    #[clap(long, default_value = WORKER_GIT_URL, conflicts_with = "path")]
+    /// Git repository to install the worker from
+    pub git: String,
+
+    #[clap(long, conflicts_with = "path")]
+    /// Git revision to install, defaults to the tag of this CLI version
+    pub rev: Option<String>,
+
+    #[clap(long)]
+    /// Directory to install the worker into, see `cargo install --root`
+    pub root: Option<PathBuf>,
+
+    #[clap(long, default_value = "nightly")]
+    /// Rust toolchain used to build the worker
+    pub toolchain: String,
+
+    #[clap(long, value_enum, default_value_t = FuzzEngine::LibFuzzer)]
+    /// Fuzzing engine the worker is built for; the AFL++ worker is installed
+    /// next to the libFuzzer one, as `move-fuzzer-worker-afl`
+    pub engine: FuzzEngine,
+}
+
+impl RunCommand for InstallWorker {
+    fn run_command(&mut self) -> Result<()> {
+        self.install_worker()
+    }
+}
+
+impl InstallWorker {
+    /// Builds and installs the worker with the coverage instrumentation it needs
+    pub fn install_worker(&self) -> Result<()> {
+        let engine = self.engine.engine();
+        let mut cmd = Command::new("cargo");
+        cmd.arg(format!("+{}", self.toolchain))
+            .arg("install")
+            .arg("--force")
+            .arg("--bin")
+            .arg(engine.worker_bin())
+            .arg("--no-default-features")
+            .arg("--features")
+            .arg(engine.worker_features().join(","));
+
+        if let Some(path) = &self.path {
+            cmd.arg("--path").arg(path);
+        } else {
+            let rev = self
+                .rev
+                .clone()
+                .unwrap_or_else(|| format!("v{}", env!("CARGO_PKG_VERSION")));
+            cmd.arg("--git").arg(&self.git).arg("--rev").arg(rev).arg("move-fuzzer");
+        }
+
+        if let Some(root) = &self.root {
+            cmd.arg("--root").arg(root);
+        }
+
+        // Same flags as `setup.sh`: the worker must be built with the
+        // sanitizer coverage instrumentation libFuzzer relies on.
+        cmd.env(
+            "RUSTFLAGS",
+            format!(
+                "-Clink-dead-code -Cdebug-assertions -Ccodegen-units=1 --cfg fuzzing {}",
+                engine.worker_rustflags()
+            )
+            .trim_end(),
+        );
+        cmd.env("ASAN_OPTIONS", "detect_odr_violation=0");
+
+        eprintln!("Installing {}...", engine.worker_bin());
+        let status = cmd
+            .status()
+            .with_context(|| format!("failed to execute: {:?}", cmd))?;
+        if !status.success() {
+            bail!("failed to install the fuzzer worker: {:?}", cmd);
+        }
+
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{options::FuzzDirWrapper, project::FuzzProject, RunCommand};
+use crate::{options::{FuzzDirWrapper, OutputOptions}, project::FuzzProject, RunCommand};
 use anyhow::Result;
 use clap::Parser;
 
@@ -9,11 +9,14 @@ use clap::Parser;
 pub struct List {
     #[clap(flatten)] 
     pub fuzz_dir_wrapper: FuzzDirWrapper,
+
+    #[clap(flatten)]
+    pub output: OutputOptions,
 }
 
 impl RunCommand for List {
     fn run_command(&mut self)-> Result<()> {
         let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.to_owned())?;
-        project.list_targets()
+        project.list_targets(&self.output)
     }
 }
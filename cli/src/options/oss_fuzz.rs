@@ -0,0 +1,210 @@
+use crate::{
+    build::exec_build, options::{corpus::upgrade_encoding, BuildOptions, FuzzDirWrapper}, project::FuzzProject, utils::find_in_path,
+    RunCommand, Target
+};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::{fs, path::{Path, PathBuf}, process::Command};
+
+/// Directory of `$OUT` the compiled modules are copied to.
+const MODULES_DIR: &str = "move_modules";
+
+#[derive(Clone, Debug, Parser)]
+// Every target is packaged unless one is selected
+#[command(mut_group("target", |group| group.required(false)))]
+pub struct OssFuzz {
+    #[clap(flatten)]
+    pub build: BuildOptions,
+
+    #[clap(flatten)]
+    pub fuzz_dir_wrapper: FuzzDirWrapper,
+
+    #[clap(long, env = "OUT")]
+    /// Directory the fuzzers are written to, OSS-Fuzz's `$OUT`
+    pub out: PathBuf,
+}
+
+impl RunCommand for OssFuzz {
+    fn run_command(&mut self) -> Result<()> {
+        let project = FuzzProject::with_package(
+            self.fuzz_dir_wrapper.fuzz_dir.to_owned(),
+            self.build.package_path.clone(),
+        )?;
+        self.exec_oss_fuzz(&project)
+    }
+}
+
+/// Quotes an argument for a POSIX shell.
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+impl OssFuzz {
+    fn selected_targets(&self, project: &FuzzProject) -> Vec<BuildOptions> {
+        let targets = if self.build.target.target_name.is_some() || self.build.target.target_function.is_some() {
+            vec![self.build.target.clone()]
+        } else {
+            project
+                .targets
+                .iter()
+                .map(|name| Target {
+                    target_module: None,
+                    target_function: None,
+                    target_name: Some(name.clone()),
+                })
+                .collect()
+        };
+        targets
+            .into_iter()
+            .map(|target| {
+                let mut build = self.build.clone();
+                build.target = target;
+                project.apply_config(&mut build);
+                build
+            })
+            .collect()
+    }
+
+    /// Writes each target as OSS-Fuzz expects it in `$OUT`: an executable
+    /// named after the target, its `.options` file and `_seed_corpus.zip`.
+    /// The executables are scripts running the worker, copied next to them,
+    /// on the compiled modules, copied to `$OUT/move_modules`.
+    pub fn exec_oss_fuzz(&self, project: &FuzzProject) -> Result<()> {
+        fs::create_dir_all(&self.out)
+            .with_context(|| format!("could not make the output directory {}", self.out.display()))?;
+        let modules = self.out.join(MODULES_DIR);
+        let mut copied_worker = false;
+        for build in self.selected_targets(project) {
+            exec_build(&build, project)?;
+            upgrade_encoding(project, &build)?;
+            if !copied_worker {
+                let worker = project.worker_path(&build)?;
+                fs::copy(&worker, self.out.join("move-fuzzer-worker"))
+                    .with_context(|| format!("failed to copy {}", worker.display()))?;
+                copy_dir(&project.get_bytecode_modules_dir(), &modules)?;
+                // The worker finds the compiled named addresses there to apply `--address`
+                let modules_dir = project.get_bytecode_modules_dir();
+                if let Some(build_info) = modules_dir.parent().map(|dir| dir.join("BuildInfo.yaml")) {
+                    if build_info.is_file() {
+                        fs::copy(&build_info, self.out.join("BuildInfo.yaml"))
+                            .with_context(|| format!("failed to copy {}", build_info.display()))?;
+                    }
+                }
+                copied_worker = true;
+            }
+
+            let name = match &build.target.target_name {
+                Some(name) => name.clone(),
+                None => format!("{}-{}", build.target.get_module_name(), build.target.get_target_function()),
+            };
+            self.write_launcher(project, &build, &name)?;
+            self.write_options(project, &build.target, &name)?;
+            self.write_seed_corpus(project, &build.target, &name)?;
+            eprintln!("Packaged {}", name);
+        }
+        Ok(())
+    }
+
+    /// The target's worker command with paths relative to the script, as
+    /// `$OUT` is moved to the machines running the fuzzers.
+    fn write_launcher(&self, project: &FuzzProject, build: &BuildOptions, name: &str) -> Result<()> {
+        let cmd = project.get_worker_command(build)?;
+        let mut script = String::from("#!/bin/sh\nHERE=\"$(dirname \"$0\")\"\nexec \"$HERE/move-fuzzer-worker\"");
+        for arg in cmd.get_args() {
+            let arg = arg.to_string_lossy();
+            if arg.starts_with("--module-path=") {
+                let module = format!("{}/{}.mv", MODULES_DIR, build.target.get_module_name());
+                script.push_str(&format!(" \"--module-path=$HERE/\"{}", shell_quote(&module)));
+            } else {
+                script.push_str(&format!(" {}", shell_quote(&arg)));
+            }
+        }
+        script.push_str(" \"$@\"\n");
+
+        let path = self.out.join(name);
+        fs::write(&path, script).with_context(|| format!("could not write {}", path.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+                .with_context(|| format!("could not make {} executable", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// The libFuzzer arguments of fuzz.toml, as `flag = value` lines of the
+    /// `[libfuzzer]` section. The dictionaries are merged into `<name>.dict`,
+    /// next to the target, as the options are read on other machines.
+    fn write_options(&self, project: &FuzzProject, target: &Target, name: &str) -> Result<()> {
+        let mut flags: Vec<(String, String)> = vec![];
+        let mut dictionary = String::new();
+        for arg in project.libfuzzer_args(target, &[]) {
+            let (flag, value) = match arg.trim_start_matches('-').split_once('=') {
+                Some(pair) => pair,
+                None => continue,
+            };
+            if flag == "dict" {
+                dictionary += &fs::read_to_string(value).with_context(|| format!("failed to read the dictionary {}", value))?;
+                if !dictionary.is_empty() && !dictionary.ends_with('\n') {
+                    dictionary.push('\n');
+                }
+                continue;
+            }
+            // libFuzzer keeps the last value of a flag, and a key can only
+            // appear once in a section
+            match flags.iter_mut().find(|(f, _)| f == flag) {
+                Some((_, v)) => *v = value.to_string(),
+                None => flags.push((flag.to_string(), value.to_string())),
+            }
+        }
+        if !dictionary.is_empty() {
+            let dict_name = format!("{}.dict", name);
+            let path = self.out.join(&dict_name);
+            fs::write(&path, dictionary).with_context(|| format!("could not write {}", path.display()))?;
+            flags.push(("dict".to_string(), dict_name));
+        }
+        if flags.is_empty() {
+            return Ok(());
+        }
+        let mut options = String::from("[libfuzzer]\n");
+        for (flag, value) in flags {
+            options.push_str(&format!("{} = {}\n", flag, value));
+        }
+        let path = self.out.join(format!("{}.options", name));
+        fs::write(&path, options).with_context(|| format!("could not write {}", path.display()))
+    }
+
+    fn write_seed_corpus(&self, project: &FuzzProject, target: &Target, name: &str) -> Result<()> {
+        let corpus = project.corpus_for(target)?;
+        let empty = fs::read_dir(&corpus).map_or(true, |mut entries| entries.next().is_none());
+        if empty {
+            return Ok(());
+        }
+        let zip = find_in_path("zip").context("could not find `zip` in PATH, it is needed for the seed corpus")?;
+        let archive = self.out.join(format!("{}_seed_corpus.zip", name));
+        // zip adds to an existing archive, start from a fresh one
+        let _ = fs::remove_file(&archive);
+        let mut cmd = Command::new(zip);
+        cmd.arg("-q").arg("-j").arg("-r").arg(&archive).arg(&corpus);
+        let status = cmd
+            .status()
+            .with_context(|| format!("failed to execute: {:?}", cmd))?;
+        if !status.success() {
+            bail!("failed to archive the seed corpus: {:?}", cmd);
+        }
+        Ok(())
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    for entry in walkdir::WalkDir::new(from).into_iter().filter_map(|e| e.ok()) {
+        let dest = to.join(entry.path().strip_prefix(from)?);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&dest).with_context(|| format!("could not make {}", dest.display()))?;
+        } else {
+            fs::copy(entry.path(), &dest)
+                .with_context(|| format!("failed to copy {}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
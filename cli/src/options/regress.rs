@@ -0,0 +1,117 @@
+use crate::{
+    build::exec_build, crash::Outcome, options::{corpus::upgrade_encoding, BuildOptions, FuzzDirWrapper}, project::FuzzProject,
+    run::replay_input, utils::strip_current_dir_prefix, RunCommand
+};
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::{fs, path::{Path, PathBuf}};
+
+#[derive(Clone, Debug, Parser)]
+pub struct Regress {
+    #[clap(flatten)]
+    pub build: BuildOptions,
+
+    #[clap(flatten)]
+    pub fuzz_dir_wrapper: FuzzDirWrapper,
+
+    #[clap(long, default_value = "10")]
+    /// Seconds after which a replayed input is considered a timeout
+    pub timeout: u64,
+
+    #[clap(long)]
+    /// Don't fail when stored artifacts no longer crash, e.g. after fixing the bugs they found
+    pub allow_fixed: bool,
+
+    #[clap(last(true))]
+    /// Additional libFuzzer arguments passed through to the binary
+    pub args: Vec<String>,
+}
+
+impl RunCommand for Regress {
+    fn run_command(&mut self) -> Result<()> {
+        let project = FuzzProject::with_package(
+            self.fuzz_dir_wrapper.fuzz_dir.to_owned(),
+            self.build.package_path.clone(),
+        )?;
+        project.apply_config(&mut self.build);
+        self.exec_regress(&project)
+    }
+}
+
+fn inputs_in(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut inputs = fs::read_dir(dir)
+        .with_context(|| format!("failed to read directory entries of {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect::<Vec<_>>();
+    inputs.sort();
+    Ok(inputs)
+}
+
+impl Regress {
+    fn replay(&self, project: &FuzzProject, input: &Path) -> Result<Outcome> {
+        let mut args = self.args.clone();
+        args.push(format!("-timeout={}", self.timeout));
+        let (outcome, _) = replay_input(project, &self.build, input, &args)?;
+        Ok(outcome)
+    }
+
+    /// Replays every stored artifact and corpus entry once
+    pub fn exec_regress(&self, project: &FuzzProject) -> Result<()> {
+        exec_build(&self.build, project)?;
+        upgrade_encoding(project, &self.build)?;
+
+        let artifacts = inputs_in(&project.artifacts_for(&self.build.target)?)?;
+        let corpus = inputs_in(&project.corpus_for(&self.build.target)?)?;
+
+        // Replay the corpus first, replaying a crashing entry adds it to the artifacts
+        let mut crashing = vec![];
+        for input in &corpus {
+            let outcome = self.replay(project, input)?;
+            if outcome.is_crash() {
+                crashing.push((input.clone(), outcome));
+            }
+        }
+
+        let mut fixed = vec![];
+        for artifact in &artifacts {
+            if !self.replay(project, artifact)?.is_crash() {
+                fixed.push(artifact.clone());
+            }
+        }
+
+        eprintln!("\n{:─<80}", "");
+        eprintln!(
+            "\nReplayed {} artifacts and {} corpus entries\n",
+            artifacts.len(),
+            corpus.len()
+        );
+        if !crashing.is_empty() {
+            eprintln!("Corpus entries that crash:\n");
+            for (input, outcome) in &crashing {
+                eprintln!("\t{}\n\t    {}", strip_current_dir_prefix(input).display(), outcome);
+            }
+            eprintln!();
+        }
+        if !fixed.is_empty() {
+            eprintln!("Artifacts that no longer crash:\n");
+            for artifact in &fixed {
+                eprintln!("\t{}", strip_current_dir_prefix(artifact).display());
+            }
+            eprintln!();
+        }
+        eprintln!("{:─<80}\n", "");
+
+        if !crashing.is_empty() {
+            bail!("{} corpus entries crash", crashing.len());
+        }
+        if !fixed.is_empty() && !self.allow_fixed {
+            bail!(
+                "{} artifacts no longer crash, remove them or pass `--allow-fixed`",
+                fixed.len()
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{
+    build::exec_build, crash::vm_report, diagnostic::Diagnostic, options::{corpus::upgrade_encoding, BuildOptions, FuzzDirWrapper, OutputOptions}, project::FuzzProject,
+    run::{replay_input, run_fuzz_target_debug_formatter}, RunCommand
+};
+use anyhow::{bail, Result};
+use clap::Parser;
+use serde_json::json;
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, Parser)]
+pub struct Reproduce {
+    #[clap(flatten)]
+    pub build: BuildOptions,
+
+    #[clap(flatten)]
+    pub fuzz_dir_wrapper: FuzzDirWrapper,
+
+    /// Path to the input to replay
+    pub artifact: PathBuf,
+
+    #[clap(flatten)]
+    pub output: OutputOptions,
+
+    #[clap(last(true))]
+    /// Additional libFuzzer arguments passed through to the binary
+    pub args: Vec<String>,
+}
+
+impl RunCommand for Reproduce {
+    fn run_command(&mut self) -> Result<()> {
+        let project = FuzzProject::with_package(
+            self.fuzz_dir_wrapper.fuzz_dir.to_owned(),
+            self.build.package_path.clone(),
+        )?;
+        project.apply_config(&mut self.build);
+        self.exec_reproduce(&project)
+    }
+}
+
+impl Reproduce {
+    /// Runs a single input and prints its arguments and the resulting VM status
+    pub fn exec_reproduce(&self, project: &FuzzProject) -> Result<()> {
+        if !self.artifact.exists() {
+            bail!("Input test case does not exist: {}", self.artifact.display());
+        }
+
+        exec_build(&self.build, project)?;
+        upgrade_encoding(project, &self.build)?;
+
+        let debug = run_fuzz_target_debug_formatter(project, &self.build, &self.build.target, &self.artifact).ok();
+        let (outcome, output) = replay_input(project, &self.build, &self.artifact, &self.args)?;
+        let report = vm_report(&output);
+        self.output.emit(json!({
+            "artifact": self.artifact.display().to_string(),
+            "error": outcome.to_string(),
+            "crash": outcome.is_crash(),
+            "arguments": debug,
+            "report": report,
+        }));
+        if outcome.is_crash() {
+            eprintln!();
+            Diagnostic {
+                outcome: &outcome,
+                artifact: &self.artifact,
+                report: &report,
+                arguments: debug.as_deref(),
+                commands: vec![],
+            }
+            .print(&project.get_bytecode_modules_dir());
+        } else if let Some(debug) = &debug {
+            eprintln!("\nDecoded arguments:\n");
+            for l in debug.lines() {
+                eprintln!("\t{}", l);
+            }
+            eprintln!();
+        }
+
+        if outcome.is_crash() {
+            bail!("Input reproduces: {}", outcome);
+        }
+        eprintln!("Input does not reproduce a crash");
+        Ok(())
+    }
+}
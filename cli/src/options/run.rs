@@ -1,12 +1,80 @@
 use crate::{
-    build::exec_build, options::{BuildOptions, FuzzDirWrapper}, project::FuzzProject, utils::strip_current_dir_prefix, RunCommand, Target
+    abi::{instruction_count, load_module}, build::exec_build, crash::{vm_report, Outcome}, dashboard::Dashboard,
+    diagnostic::Diagnostic,
+    engine::{Campaign, FuzzEngine}, junit::{write_junit, TestCase}, metrics::Metrics,
+    options::{corpus::upgrade_encoding, BuildOptions, FuzzDirWrapper, OutputOptions}, project::FuzzProject, report::{Finding, ReportOptions},
+    session::Session, stats::{read_swarm_seeds, read_worker_stats, CampaignStats}, trace::{
+        accumulate_coverage, clear_coverage_maps, covered_source_lines, learn_dictionary, learned_dictionary,
+        merge_coverage_maps, MoveCoverage, MOVE_FUZZER_COVERAGE_DIR_ENV
+    },
+    utils::strip_current_dir_prefix, RunCommand, Target
 };
 use anyhow::{bail, Context, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use serde_json::json;
 
-use std::{fs, path::Path, process::Stdio, time};
+use std::{collections::{hash_map::RandomState, HashSet}, fs, hash::{BuildHasher, Hasher}, io::{self, BufRead, BufReader}, path::{Path, PathBuf}, process::{Child, Command, Output, Stdio}, sync::{atomic::{AtomicBool, Ordering}, Once}, thread, time};
+
+/// Whether the fuzzer is running, an interrupt then being left to it so that
+/// the session is saved and the summary printed once it exits.
+static FUZZING: AtomicBool = AtomicBool::new(false);
+
+/// Lets the fuzzer handle Ctrl-C while it runs, interrupting the CLI only
+/// outside of it.
+fn leave_interrupts_to_the_fuzzer() {
+    static HANDLER: Once = Once::new();
+    HANDLER.call_once(|| {
+        let handler = ctrlc::set_handler(|| {
+            if !FUZZING.load(Ordering::SeqCst) {
+                std::process::exit(130);
+            }
+        });
+        if let Err(e) = handler {
+            eprintln!("Warning: Ctrl-C will not save the session: {}", e);
+        }
+    });
+}
+
+/// Kinds of failures that can be selected as findings with `--crash-on`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum CrashClass {
+    /// The Move code aborted
+    Abort,
+    /// Arithmetic overflow, underflow or division by zero
+    Arithmetic,
+    /// The execution ran out of gas
+    OutOfGas,
+    /// The execution exceeded the VM memory limit
+    Memory,
+    /// The target returned `false` with `--oracle-bool`
+    Property,
+    /// The invariant function failed
+    Invariant,
+    /// Any other VM error
+    Unknown,
+    /// libFuzzer killed a slow execution
+    Timeout,
+}
+
+impl CrashClass {
+    /// Worker error classes, as used by `--on-error`, making up this class.
+    fn worker_classes(self) -> &'static [&'static str] {
+        match self {
+            CrashClass::Abort => &["abort"],
+            CrashClass::Arithmetic => &["arithmetic-error"],
+            CrashClass::OutOfGas => &["out-of-gas"],
+            CrashClass::Memory => &["memory-limit-exceeded"],
+            CrashClass::Property => &["property-violated"],
+            CrashClass::Invariant => &["invariant-violated"],
+            CrashClass::Unknown => &["unknown", "runtime", "out-of-bound"],
+            CrashClass::Timeout => &[],
+        }
+    }
+}
 
 #[derive(Clone, Debug, Parser)]
+// `--all-targets` replaces the target selection
+#[command(mut_group("target", |group| group.required(false)))]
 pub struct Run {
     #[clap(flatten)] 
     pub build: BuildOptions,
@@ -25,18 +93,131 @@ pub struct Run {
     /// Number of concurrent jobs to run
     pub jobs: u16,
 
+    #[clap(long)]
+    /// Number of individual test runs, fuzzing runs indefinitely if not set
+    pub runs: Option<u64>,
+
+    #[clap(long)]
+    /// Maximum fuzzing time in seconds, fuzzing runs indefinitely if not set
+    pub max_total_time: Option<u64>,
+
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..))]
+    /// Seed of libFuzzer's and the worker's random choices, printed in the
+    /// summary to reproduce the campaign; a random one if not set
+    pub seed: Option<u32>,
+
+    #[clap(long, conflicts_with_all = ["target_name", "target_function"])]
+    /// Fuzz every target of the project, one after the other
+    pub all_targets: bool,
+
+    #[clap(long, value_enum, value_delimiter = ',')]
+    /// Failures reported as findings, e.g. `arithmetic,abort,timeout`; the Move
+    /// failures not listed are ignored and timeouts are disabled. Every failure
+    /// is a finding if not set
+    pub crash_on: Vec<CrashClass>,
+
+    #[clap(long, conflicts_with_all = ["all_targets", "junit", "runs", "max_total_time"])]
+    /// Rebuild the target and restart fuzzing whenever the package sources
+    /// change, keeping the corpus; runs until interrupted
+    pub watch: bool,
+
+    #[clap(long)]
+    /// Merge the Move coverage of this campaign into the one of the earlier
+    /// campaigns on the target, kept until the target module changes
+    pub accumulate: bool,
+
+    #[clap(long)]
+    /// Learn the constants the target compares its values against where the
+    /// campaign got stuck, e.g. magic numbers, and give them to the next
+    /// campaigns on the target as a dictionary
+    pub learn_dict: bool,
+
+    #[clap(long, conflicts_with_all = ["all_targets", "watch", "runs", "max_total_time"])]
+    /// Continue the campaign last run on the target, with its seed and
+    /// engine flags, for the time and runs it had left
+    pub resume: bool,
+
+    #[clap(long, conflicts_with = "watch")]
+    /// Show a live summary of the campaign (executions, corpus, Move coverage,
+    /// artifacts) instead of the raw libFuzzer output
+    pub dashboard: bool,
+
+    #[clap(long, conflicts_with = "watch")]
+    /// Serve Prometheus metrics of the campaign (executions, corpus, Move
+    /// coverage, artifacts) at this address, e.g. `0.0.0.0:9184`
+    pub metrics_addr: Option<String>,
+
+    #[clap(long, conflicts_with = "watch")]
+    /// Push the same metrics as gauges to the StatsD server at this address
+    pub statsd: Option<String>,
+
+    #[clap(long, value_enum, default_value_t = FuzzEngine::LibFuzzer)]
+    /// Fuzzing engine driving the worker; findings are still replayed with
+    /// the libFuzzer worker
+    pub engine: FuzzEngine,
+
+    #[clap(long)]
+    /// Write a JUnit XML summary with one test case per fuzzed target
+    pub junit: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub report: ReportOptions,
+
+    #[clap(flatten)]
+    pub output: OutputOptions,
+
     #[clap(last(true))]
-    /// Additional libFuzzer arguments passed through to the binary
+    /// Additional arguments passed through to the engine, libFuzzer's binary
+    /// or `afl-fuzz`
     pub args: Vec<String>,
 }
 
 impl RunCommand for Run {
     fn run_command(&mut self) -> Result<()> {
-        let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.to_owned())?;
+        let project = FuzzProject::with_package(
+            self.fuzz_dir_wrapper.fuzz_dir.to_owned(),
+            self.build.package_path.clone(),
+        )?;
+        if !self.all_targets {
+            if self.build.target.target_name.is_none() && self.build.target.target_function.is_none() {
+                bail!("a target is required, use `--target-name`, `--target-function` or `--all-targets`");
+            }
+            project.apply_config(&mut self.build);
+        }
+        if self.resume {
+            let session = Session::load(&project.session_file_for(&self.build.target))?;
+            if session.is_complete() {
+                eprintln!(
+                    "The campaign on the target is complete after {} legs ({} seconds, {} runs), run it again without `--resume`",
+                    session.legs, session.elapsed, session.runs_done
+                );
+                return Ok(());
+            }
+            self.continue_session(&session);
+        }
+        // Chosen here rather than by libFuzzer, so that it can be printed
+        if self.seed.is_none() {
+            self.seed = Some(
+                self.args
+                    .iter()
+                    .rev()
+                    .find_map(|arg| arg.strip_prefix("-seed=")?.parse().ok())
+                    .filter(|seed| *seed != 0)
+                    .unwrap_or_else(random_seed),
+            );
+        }
+        if self.watch {
+            return self.exec_watch(&project);
+        }
         self.exec_fuzz(&project)
     }
 }
 
+/// A random non-zero seed, libFuzzer picking its own for 0.
+pub(crate) fn random_seed() -> u32 {
+    (RandomState::new().build_hasher().finish() as u32).max(1)
+}
+
 
 pub fn run_fuzz_target_debug_formatter(
     project: &FuzzProject,
@@ -46,7 +227,7 @@ pub fn run_fuzz_target_debug_formatter(
 ) -> Result<String> {
     let debug_output = tempfile::NamedTempFile::new().context("failed to create temp file")?;
 
-    let mut cmd = project.get_run_fuzzer_command(&build.target)?;
+    let mut cmd = project.get_run_fuzzer_command(build)?;
     cmd.stdin(Stdio::null());
     cmd.env("MOVE_LIBFUZZER_DEBUG_PATH", debug_output.path());
     cmd.arg(artifact);
@@ -88,68 +269,581 @@ pub fn run_fuzz_target_debug_formatter(
     Ok(debug)
 }
 
+/// Returns the command running the worker on a single input.
+pub fn replay_command(
+    project: &FuzzProject,
+    build: &BuildOptions,
+    input: &Path,
+    args: &[String],
+) -> Result<Command> {
+    let mut cmd = project.get_run_fuzzer_command(build)?;
+    for arg in project.libfuzzer_args(&build.target, args) {
+        cmd.arg(arg);
+    }
+    cmd.arg(input);
+    cmd.stdin(Stdio::null());
+    Ok(cmd)
+}
+
+/// Replays a single input through the worker and classifies the result.
+pub fn replay_input(
+    project: &FuzzProject,
+    build: &BuildOptions,
+    input: &Path,
+    args: &[String],
+) -> Result<(Outcome, Output)> {
+    let mut cmd = replay_command(project, build, input, args)?;
+    let output = cmd
+        .output()
+        .with_context(|| format!("failed to run command: {:?}", cmd))?;
+    Ok((Outcome::from_output(&output), output))
+}
+
+/// How often `--watch` checks the sources for changes.
+const WATCH_INTERVAL: time::Duration = time::Duration::from_millis(500);
+
+/// Paths and modification times of the files under `dir`, compared to tell
+/// when the sources changed.
+fn sources_snapshot(dir: &Path) -> Vec<(PathBuf, Option<time::SystemTime>)> {
+    let mut files = walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| {
+            let modified = e.metadata().ok().and_then(|m| m.modified().ok());
+            (e.into_path(), modified)
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+/// Seeds an empty corpus with the compiled modules of the package, the
+/// inputs of `--publish`.
+fn seed_modules(project: &FuzzProject, corpus: &Path) -> Result<()> {
+    let empty = fs::read_dir(corpus).map_or(true, |mut entries| entries.next().is_none());
+    if !empty {
+        return Ok(());
+    }
+    fs::create_dir_all(corpus).with_context(|| format!("could not make the corpus directory {}", corpus.display()))?;
+    for module in walkdir::WalkDir::new(project.get_bytecode_modules_dir())
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().map_or(false, |ext| ext == "mv"))
+    {
+        let name = module.path().file_stem().unwrap_or_default().to_string_lossy();
+        let seed = corpus.join(format!("module-{}", name));
+        fs::copy(module.path(), &seed).with_context(|| format!("failed to copy {}", module.path().display()))?;
+    }
+    Ok(())
+}
+
+/// Renames a libFuzzer artifact such as `crash-<sha1>` after the failure it
+/// triggers, e.g. `abort-3-coin-<sha1>`. Keeps the original name if the
+/// rename fails.
+fn rename_artifact(artifact: &Path, outcome: &Outcome) -> PathBuf {
+    let name = match artifact.file_name() {
+        Some(name) => name.to_string_lossy(),
+        None => return artifact.to_path_buf(),
+    };
+    let hash = name.split_once('-').map_or(name.as_ref(), |(_, hash)| hash);
+    let renamed = artifact.with_file_name(format!("{}-{}", outcome.artifact_prefix(), hash));
+    match fs::rename(artifact, &renamed) {
+        Ok(()) => renamed,
+        Err(e) => {
+            eprintln!("Failed to rename {}: {}", artifact.display(), e);
+            artifact.to_path_buf()
+        }
+    }
+}
+
+/// Where the libFuzzer output goes besides the returned statistics.
+struct Monitor {
+    stats: CampaignStats,
+    dashboard: Option<Dashboard>,
+    metrics: Option<Metrics>,
+}
+
+/// Forwards the libFuzzer output of `child` to stderr, or to the dashboard
+/// and metrics if given, returning the last reported number of runs and
+/// executions per second.
+fn forward_libfuzzer_stats(child: &mut Child, mut monitor: Option<Monitor>) -> (Option<u64>, Option<u64>) {
+    let mut runs = None;
+    let mut exec_per_sec = None;
+    if let Some(stderr) = child.stderr.take() {
+        for line in BufReader::new(stderr).lines().map_while(|l| l.ok()) {
+            match &mut monitor {
+                Some(monitor) => {
+                    let status = monitor.stats.update(&line);
+                    match &mut monitor.dashboard {
+                        Some(dashboard) => dashboard.update(&monitor.stats, &line, status),
+                        None => eprintln!("{}", line),
+                    }
+                    if let Some(metrics) = &mut monitor.metrics {
+                        metrics.publish(&monitor.stats);
+                    }
+                }
+                None => eprintln!("{}", line),
+            }
+            // Status lines look like `#4096 pulse cov: 12 ft: 15 corp: 3/9b exec/s: 2048 rss: 40Mb`
+            if let Some(n) = line.strip_prefix('#').and_then(|l| l.split_whitespace().next()) {
+                runs = n.parse().ok().or(runs);
+            }
+            if let Some(rest) = line.split("exec/s: ").nth(1) {
+                exec_per_sec = rest.split_whitespace().next().and_then(|n| n.parse().ok()).or(exec_per_sec);
+            }
+        }
+    }
+    (runs, exec_per_sec)
+}
 
 impl Run {
-    /// Fuzz a given fuzz target
+    /// Takes the settings of the campaign `session` continues, the ones given
+    /// on the command line winning.
+    fn continue_session(&mut self, session: &Session) {
+        self.max_total_time = session.remaining_time();
+        self.runs = session.remaining_runs();
+        self.seed = self.seed.or(session.seed);
+        if self.args.is_empty() {
+            self.args = session.args.clone();
+        }
+        if self.jobs == 1 {
+            self.jobs = session.jobs;
+        }
+        if self.engine == FuzzEngine::default() {
+            self.engine = session.engine;
+        }
+    }
+
+    /// Adds the leg that just ran to the campaign on the target, continuing
+    /// the saved one with `--resume`, and saves it.
+    fn save_session(&self, project: &FuzzProject, elapsed: time::Duration, runs: u64, coverage: &MoveCoverage) -> Result<Session> {
+        let path = project.session_file_for(&self.build.target);
+        let mut session = if self.resume {
+            Session::load(&path)?
+        } else {
+            Session {
+                max_total_time: self.max_total_time,
+                runs: self.runs,
+                seed: self.seed,
+                engine: self.engine,
+                jobs: self.jobs,
+                args: self.args.clone(),
+                ..Default::default()
+            }
+        };
+        session.legs += 1;
+        session.elapsed += elapsed.as_secs();
+        session.runs_done += runs;
+        session.coverage.extend(coverage.iter().cloned());
+        session.save(&path)?;
+        Ok(session)
+    }
+
+    /// Fuzz the selected target, or every target with `--all-targets`
     pub fn exec_fuzz(&self, project: &FuzzProject) -> Result<()> {
-        exec_build(&self.build, project, false)?;
-        let mut cmd = project.get_run_fuzzer_command(&self.build.target)?;
+        let runs = if self.all_targets {
+            if self.runs.is_none() && self.max_total_time.is_none() {
+                bail!("`--all-targets` requires `--runs` or `--max-total-time`, otherwise the first target is fuzzed forever");
+            }
+            project
+                .targets
+                .iter()
+                .map(|name| {
+                    let mut run = self.clone();
+                    run.build.target = Target {
+                        target_module: None,
+                        target_function: None,
+                        target_name: Some(name.clone()),
+                    };
+                    project.apply_config(&mut run.build);
+                    run
+                })
+                .collect()
+        } else {
+            vec![self.clone()]
+        };
+
+        let mut cases = vec![];
+        let mut errors = vec![];
+        for run in &runs {
+            let artifacts_before = project.get_artifacts(&run.build.target)?;
+            let timer = time::Instant::now();
+            let result = run.exec_fuzz_target(project);
+            if self.junit.is_some() {
+                cases.push(run.test_case(project, &artifacts_before, timer.elapsed(), &result));
+            }
+            if let Err(e) = result {
+                if self.all_targets {
+                    eprintln!("Error: {:#}", e);
+                }
+                errors.push(e);
+            }
+        }
 
-        for arg in &self.args {
-            cmd.arg(arg);
+        if let Some(junit) = &self.junit {
+            write_junit(junit, "move-fuzzer", &cases)?;
+            eprintln!("JUnit report written to {}", junit.display());
         }
 
-        if !self.corpus.is_empty() {
-            for corpus in &self.corpus {
-                cmd.arg(corpus);
+        match errors.len() {
+            0 => Ok(()),
+            1 if !self.all_targets => Err(errors.remove(0)),
+            n => bail!("{} of {} fuzz targets failed", n, runs.len()),
+        }
+    }
+
+    /// Describes the result of fuzzing this target as a JUnit test case.
+    fn test_case(&self, project: &FuzzProject, artifacts_before: &HashSet<PathBuf>, elapsed: time::Duration, result: &Result<()>) -> TestCase {
+        let failure = result.as_ref().err().map(|e| {
+            let mut details = format!("{:#}\n", e);
+            let mut artifacts = project
+                .get_new_artifacts(&self.build.target, artifacts_before)
+                .map(|a| a.into_iter().collect::<Vec<_>>())
+                .unwrap_or_default();
+            artifacts.sort();
+            for artifact in artifacts {
+                details.push_str(&format!("\nFailing input: {}\n", artifact.display()));
+                if let Ok(debug) = run_fuzz_target_debug_formatter(project, &self.build, &self.build.target, &artifact) {
+                    details.push_str(&debug);
+                }
             }
+            (e.to_string(), details)
+        });
+        let name = match &self.build.target.target_name {
+            Some(name) => name.clone(),
+            None => format!("{}::{}", self.build.target.get_module_name(), self.build.target.get_target_function()),
+        };
+        TestCase {
+            name,
+            time: elapsed,
+            failure,
+        }
+    }
+
+    /// The build options with the worker ignoring the failures not selected
+    /// with `--crash-on`. Rules given with `--on-error` come last and win.
+    fn crash_on_build(&self) -> BuildOptions {
+        let mut build = self.build.clone();
+        if self.crash_on.is_empty() {
+            return build;
+        }
+        let ignored = CrashClass::value_variants()
+            .iter()
+            .filter(|class| !self.crash_on.contains(class))
+            .flat_map(|class| class.worker_classes())
+            .map(|class| format!("{}=ignore", class));
+        build.worker.error_actions = ignored.chain(self.build.worker.error_actions.iter().cloned()).collect();
+        build
+    }
+
+    /// What the engine is asked to do for this target.
+    fn campaign(&self, project: &FuzzProject) -> Result<Campaign> {
+        let corpus = if self.corpus.is_empty() {
+            let mut corpus = vec![project.corpus_for(&self.build.target)?];
+            corpus.extend(project.corpus_dirs_for(&self.build.target));
+            corpus
         } else {
-            cmd.arg(project.corpus_for(&self.build.target)?);
+            self.corpus.iter().map(PathBuf::from).collect()
+        };
+        if self.build.worker.publish {
+            seed_modules(project, &corpus[0])?;
         }
+        Ok(Campaign {
+            corpus,
+            artifacts: project.artifacts_for(&self.build.target)?,
+            work_dir: project.engine_dir_for(&self.build.target, self.engine),
+            jobs: self.jobs,
+            runs: self.runs,
+            max_total_time: self.max_total_time,
+            timeouts: self.crash_on.is_empty() || self.crash_on.contains(&CrashClass::Timeout),
+            seed: self.seed,
+            args: self.args.clone(),
+            dictionary: self.learned_dictionary(project)?,
+        })
+    }
 
-        if self.jobs != 1 {
-            cmd.arg(format!("-fork={}", self.jobs));
+    /// The dictionary learned by the earlier campaigns on the target, with
+    /// `--learn-dict`.
+    fn learned_dictionary(&self, project: &FuzzProject) -> Result<Option<PathBuf>> {
+        if !self.learn_dict {
+            return Ok(None);
         }
+        let path = learned_dictionary(&project.move_coverage_dir()?, &self.build.target);
+        Ok(path.is_file().then_some(path))
+    }
+
+    /// The engine command fuzzing the target, recording the Move coverage
+    /// in `coverage_dir`.
+    fn fuzz_command(&self, project: &FuzzProject, coverage_dir: &Path) -> Result<Command> {
+        let campaign = self.campaign(project)?;
+        let mut cmd = self.engine.engine().fuzz_command(project, &self.crash_on_build(), &campaign)?;
+
+        // Every worker process, forks included, records its Move coverage in its own files there
+        cmd.env(MOVE_FUZZER_COVERAGE_DIR_ENV, coverage_dir);
+        Ok(cmd)
+    }
+
+    /// Fuzzes the target until interrupted, rebuilding it and restarting the
+    /// fuzzer whenever a file of the package's `sources/` changes. The corpus
+    /// is kept across restarts; findings are left in the artifacts directory.
+    fn exec_watch(&self, project: &FuzzProject) -> Result<()> {
+        let sources = project.get_targets_dir();
+        let coverage_dir = project.move_coverage_dir()?;
+        loop {
+            if self.learn_dict {
+                // The restarted fuzzer starts with what the previous runs learned
+                learn_dictionary(&coverage_dir, &self.build.target)?;
+            }
+            let snapshot = sources_snapshot(&sources);
+            let mut child = match exec_build(&self.build, project)
+                .and_then(|_| upgrade_encoding(project, &self.build))
+                .and_then(|_| self.fuzz_command(project, &coverage_dir))
+            {
+                Ok(mut cmd) => Some(cmd.spawn().with_context(|| format!("failed to spawn command: {:?}", cmd))?),
+                Err(e) => {
+                    eprintln!("Error: {:#}", e);
+                    None
+                }
+            };
+            eprintln!("Watching {} for changes", sources.display());
+            while sources_snapshot(&sources) == snapshot {
+                thread::sleep(WATCH_INTERVAL);
+                if let Some(status) = child.as_mut().map(|c| c.try_wait()).transpose()?.flatten() {
+                    eprintln!("\nThe fuzzer exited with {}, waiting for the sources to change\n", status);
+                    child = None;
+                }
+            }
+            if let Some(mut child) = child {
+                // The fuzzer may have exited meanwhile, only the wait matters
+                let _ = child.kill();
+                child.wait().context("failed to wait on the fuzzer")?;
+            }
+            eprintln!("\nSources changed, rebuilding and restarting the fuzzer\n");
+        }
+    }
+
+    /// Fuzz a given fuzz target
+    pub fn exec_fuzz_target(&self, project: &FuzzProject) -> Result<()> {
+        // Fail early on a malformed `--report` rather than after fuzzing
+        self.report.requested()?;
+        exec_build(&self.build, project)?;
+        upgrade_encoding(project, &self.build)?;
+        let coverage_dir = project.move_coverage_dir()?;
+        // The summary covers this campaign, earlier ones are kept with `--accumulate`
+        clear_coverage_maps(&coverage_dir, &self.build.target)?;
+        let mut cmd = self.fuzz_command(project, &coverage_dir)?;
 
         // When libfuzzer finds failing inputs, those inputs will end up in the
         // artifacts directory. To easily filter old artifacts from new ones,
-        // get the current time, and then later we only consider files modified
-        // after now.
+        // list the current ones, and then later we only consider the others.
+        let artifacts_before = project.get_artifacts(&self.build.target)?;
         let before_fuzzing = time::SystemTime::now();
 
+        // Keep stdout for the JSON results, the worker output goes to stderr
+        if self.output.is_json() {
+            cmd.stdout(io::stderr());
+            cmd.stderr(Stdio::piped());
+        }
+        let monitor = if self.dashboard || self.metrics_addr.is_some() || self.statsd.is_some() {
+            cmd.stderr(Stdio::piped());
+            let module = load_module(&project.get_module_path(&self.build.target)).ok();
+            // A share of the target module's instructions, unless dependencies are covered too
+            let instructions = module
+                .as_ref()
+                .filter(|_| !self.build.worker.include_deps)
+                .map(instruction_count);
+            Some(Monitor {
+                stats: CampaignStats::new(&self.build.target, coverage_dir.clone(), before_fuzzing, instructions),
+                dashboard: self.dashboard.then(Dashboard::default),
+                metrics: match (&self.metrics_addr, &self.statsd) {
+                    (None, None) => None,
+                    (prometheus, statsd) => Some(Metrics::new(prometheus.as_deref(), statsd.as_deref())?),
+                },
+            })
+        } else {
+            None
+        };
+
+        leave_interrupts_to_the_fuzzer();
+        FUZZING.store(true, Ordering::SeqCst);
         let mut child = cmd
             .spawn()
             .with_context(|| format!("failed to spawn command: {:?}", cmd))?;
+        let (runs, exec_per_sec) = forward_libfuzzer_stats(&mut child, monitor);
         let status = child
             .wait()
             .with_context(|| format!("failed to wait on child process for command: {:?}", cmd))?;
-        if status.success() {
+        FUZZING.store(false, Ordering::SeqCst);
+        let elapsed = before_fuzzing.elapsed().unwrap_or_default();
+        // Engines other than libFuzzer exit successfully whatever they found
+        let crashes = self.engine.engine().collect(&self.campaign(project)?)?;
+        let crashed = !status.success() || crashes > 0;
+
+        let (coverage, processes) = merge_coverage_maps(&coverage_dir, &self.build.target, &before_fuzzing)?;
+        let source_lines = covered_source_lines(project, &coverage);
+        if source_lines.is_empty() {
+            eprintln!(
+                "Move coverage: {} instructions, recorded by {} fuzzing processes",
+                coverage.len(),
+                processes
+            );
+        } else {
+            eprintln!(
+                "Move coverage: {} instructions on {} source lines, recorded by {} fuzzing processes",
+                coverage.len(),
+                source_lines.len(),
+                processes
+            );
+        }
+        let workers = read_worker_stats(&coverage_dir, &self.build.target, &before_fuzzing);
+        if workers.executions > 0 {
+            eprintln!(
+                "Move executions: {}, {:.1}% undecodable, {:.1}% rejected, {:.1} argument bytes each",
+                workers.executions,
+                workers.percent(workers.undecodable),
+                workers.percent(workers.rejected),
+                workers.argument_bytes_per_execution()
+            );
+        }
+        let histogram = workers.mutation_histogram();
+        if !histogram.is_empty() {
+            eprintln!("Mutations kept for new coverage:");
+            let width = histogram.iter().map(|(kind, _)| kind.len()).max().unwrap_or(0);
+            for (kind, counts) in histogram {
+                eprintln!(
+                    "  {:width$}  {:>8} of {:>10} ({:.3}%)",
+                    kind,
+                    counts.kept,
+                    counts.applied,
+                    counts.kept as f64 * 100.0 / counts.applied.max(1) as f64,
+                    width = width
+                );
+            }
+        }
+        // libFuzzer's count is only known when its output is read, the worker's always is
+        let session = self.save_session(project, elapsed, runs.unwrap_or(workers.executions), &coverage)?;
+        if session.legs > 1 {
+            eprintln!(
+                "Session: {} legs, {} seconds, {} runs, {} Move instructions covered",
+                session.legs,
+                session.elapsed,
+                session.runs_done,
+                session.coverage.len()
+            );
+        }
+        let accumulated = if self.accumulate {
+            let module = project.get_module_path(&self.build.target);
+            let accumulated = accumulate_coverage(&coverage_dir, &self.build.target, &module, &coverage)?;
+            eprintln!(
+                "Accumulated Move coverage: {} instructions over {} campaigns",
+                accumulated.coverage.len(),
+                accumulated.campaigns
+            );
+            Some(accumulated.coverage.len())
+        } else {
+            None
+        };
+        if self.learn_dict {
+            let entries = learn_dictionary(&coverage_dir, &self.build.target)?;
+            eprintln!(
+                "Learned dictionary: {} entries in {}",
+                entries,
+                learned_dictionary(&coverage_dir, &self.build.target).display()
+            );
+        }
+        if let Some(seed) = self.seed {
+            eprintln!("Seed: {} (reproduce with `--seed {}`)", seed, seed);
+        }
+
+        let mut results = json!({
+            "target": self.build.target.get_command(),
+            "exit_reason": if crashed { "crash" } else { "completed" },
+            "exit_code": status.code(),
+            "runs": runs,
+            "exec_per_sec": exec_per_sec,
+            "move_instructions_covered": coverage.len(),
+            "move_source_lines_covered": source_lines.len(),
+            "move_instructions_accumulated": accumulated,
+            "move_executions": workers.executions,
+            "undecodable_inputs": workers.undecodable,
+            "rejected_inputs": workers.rejected,
+            "seed": self.seed,
+            "new_artifacts": [],
+        });
+        if !crashed {
+            self.report.write(&self.build.target, &[])?;
+            self.output.emit(results);
             return Ok(());
         }
 
         // Get and print the `Debug` formatting of any new artifacts, along with
         // tips about how to reproduce failures and/or minimize test cases.
 
-        let new_artifacts = project.get_artifacts_since(&self.build.target, &before_fuzzing)?;
+        let new_artifacts = project.get_new_artifacts(&self.build.target, &artifacts_before)?;
+        let artifact_count = new_artifacts.len();
+        let mut flaky = 0;
+        let mut findings = vec![];
+        if self.jobs != 1 {
+            // Forks share the artifacts directory, so their findings are all collected here
+            eprintln!("Collected {} new artifacts from {} forks", new_artifacts.len(), self.jobs);
+        }
+
+        // A worker sampling a swarm profile decodes the same bytes into other
+        // arguments than a fresh one would, so findings are replayed with the
+        // profiles the workers recorded
+        let swarm_seeds = match self.build.worker.swarm_seed {
+            Some(_) => vec![],
+            None => read_swarm_seeds(&coverage_dir, &self.build.target, &before_fuzzing),
+        };
 
         for artifact in new_artifacts {
+            // Replaying in a fresh worker confirms the failure reproduces, and
+            // names the artifact after what it triggers, so that a listing of
+            // the artifacts directory tells what was found
+            let mut build = self.build.clone();
+            let (mut outcome, mut output) = replay_input(project, &build, &artifact, &self.args)?;
+            for &seed in &swarm_seeds {
+                if outcome.is_crash() {
+                    break;
+                }
+                build.worker.swarm_seed = Some(seed);
+                (outcome, output) = replay_input(project, &build, &artifact, &self.args)?;
+            }
+            if !outcome.is_crash() {
+                build.worker.swarm_seed = self.build.worker.swarm_seed;
+            }
+            let reproduced = outcome.is_crash();
+            let artifact = rename_artifact(&artifact, &outcome);
+
             // To make the artifact a little easier to read, strip the current
             // directory prefix when possible.
             let artifact = strip_current_dir_prefix(&artifact);
 
-            eprintln!("\n{:─<80}", "");
-            eprintln!("\nFailing input:\n\n\t{}\n", artifact.display());
-
             // Note: ignore errors when running the debug formatter. This most
             // likely just means that we're dealing with a fuzz target that uses
             // an older version of the libfuzzer crate, and doesn't support
             // `MOVE_LIBFUZZER_DEBUG_PATH`.
-            if let Ok(debug) = run_fuzz_target_debug_formatter(project, &self.build, &self.build.target, artifact) {
-                eprintln!("Output of `std::fmt::Debug`:\n");
-                for l in debug.lines() {
+            let debug = run_fuzz_target_debug_formatter(project, &build, &self.build.target, artifact).ok();
+
+            if self.output.is_json() {
+                results["new_artifacts"].as_array_mut().expect("initialized as an array").push(json!({
+                    "path": artifact.display().to_string(),
+                    "arguments": debug,
+                    "reproduced": reproduced,
+                }));
+            }
+
+            eprintln!("\n{:─<80}\n", "");
+            if !reproduced {
+                // The execution depended on more than the input, e.g. on state
+                // left by earlier executions in the same process
+                flaky += 1;
+                eprintln!("Flaky input, passes when replayed alone:\n\n\t{}\n", artifact.display());
+                for l in debug.iter().flat_map(|debug| debug.lines()) {
                     eprintln!("\t{}", l);
                 }
-                eprintln!();
+                continue;
             }
 
             let fuzz_dir = if project.fuzz_dir_is_default_path() {
@@ -157,24 +851,48 @@ impl Run {
             } else {
                 format!(" --fuzz-dir {}", project.get_fuzz_dir().display())
             };
+            let command = |subcommand: &str| {
+                format!(
+                    "cargo fuzz {}{}{} {} {}",
+                    subcommand,
+                    fuzz_dir,
+                    build,
+                    self.build.target.get_command(),
+                    artifact.display()
+                )
+            };
+            Diagnostic {
+                outcome: &outcome,
+                artifact,
+                report: &vm_report(&output),
+                arguments: debug.as_deref(),
+                commands: vec![("Reproduce with", command("run")), ("Minimize test case with", command("tmin"))],
+            }
+            .print(&project.get_bytecode_modules_dir());
 
-            eprintln!(
-                "Reproduce with:\n\n\tcargo fuzz run{fuzz_dir}{options} {target} {artifact} \n",
-                fuzz_dir = &fuzz_dir,
-                options = &self.build,
-                target = self.build.target.get_command(),
-                artifact = artifact.display()
-            );
-            eprintln!(
-                "Minimize test case with:\n\n\tcargo fuzz tmin{fuzz_dir}{options} {target} {artifact}\n",
-                fuzz_dir = &fuzz_dir,
-                options = &self.build,
-                target = self.build.target.get_command(),
-                artifact = artifact.display()
-            );
+            if self.report.requested()?.is_some() {
+                findings.push(Finding {
+                    outcome,
+                    artifact: artifact.to_path_buf(),
+                    arguments: debug,
+                    count: 1,
+                });
+            }
         }
 
         eprintln!("{:─<80}\n", "");
-        bail!("Fuzz target exited with {}", status)
+        if flaky > 0 {
+            eprintln!(
+                "{} of {} new artifacts did not reproduce when replayed, they are kept as `flaky-*`\n",
+                flaky, artifact_count
+            );
+        }
+        results["flaky_artifacts"] = json!(flaky);
+        self.report.write(&self.build.target, &findings)?;
+        self.output.emit(results);
+        if !status.success() {
+            bail!("Fuzz target exited with {}", status)
+        }
+        bail!("Fuzzing found {} failing inputs", crashes)
     }
 }
@@ -1,9 +1,21 @@
 use crate::{
-    build::exec_build, options::{BuildOptions, FuzzDirWrapper}, project::FuzzProject, run::run_fuzz_target_debug_formatter, utils::strip_current_dir_prefix, RunCommand
+    build::exec_build, options::{corpus::upgrade_encoding, BuildOptions, FuzzDirWrapper}, project::FuzzProject, run::run_fuzz_target_debug_formatter, utils::strip_current_dir_prefix, RunCommand
 };
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use clap::Parser;
-use std::{path::PathBuf, time};
+use std::{ffi, fs, path::{Path, PathBuf}, process::Stdio};
+
+/// Prefix of the artifacts written by `tmin`.
+const MINIMIZED_PREFIX: &str = "minimized-from-";
+
+/// What minimizing one artifact of `tmin --all` came to.
+enum BatchResult {
+    /// Shrunk from the first size to the second
+    Minimized(u64, u64),
+    /// A minimized version of the artifact already exists
+    Skipped,
+    Failed(anyhow::Error),
+}
 
 
 
@@ -23,9 +35,19 @@ pub struct Tmin {
     /// Number of minimization attempts to perform
     pub runs: u32,
 
-    #[clap()]
-    /// Path to the failing test case to be minimized
-    pub test_case: PathBuf,
+    #[clap(long)]
+    /// Use libFuzzer's byte-level minimization instead of shrinking the decoded arguments
+    pub byte_level: bool,
+
+    #[clap(long)]
+    /// Minimize every artifact of the target, or of the directory given in
+    /// place of the test case, skipping the ones minimized already
+    pub all: bool,
+
+    #[clap(required_unless_present = "all")]
+    /// Path to the failing test case to be minimized, or the directory of the
+    /// artifacts with `--all`
+    pub test_case: Option<PathBuf>,
 
     #[clap(last(true))]
     /// Additional libFuzzer arguments passed through to the binary
@@ -34,56 +56,25 @@ pub struct Tmin {
 
 impl RunCommand for Tmin {
     fn run_command(&mut self)-> Result<()> {
-        let project = FuzzProject::new(self.fuzz_dir_wrapper.fuzz_dir.to_owned())?;
+        let project = FuzzProject::with_package(
+            self.fuzz_dir_wrapper.fuzz_dir.to_owned(),
+            self.build.package_path.clone(),
+        )?;
+        project.apply_config(&mut self.build);
         self.exec_tmin(&project)
     }
 }
 
 impl Tmin {
     pub fn exec_tmin(&self, project: &FuzzProject) -> Result<()> {
-        exec_build(&self.build, project, false)?;
-        let mut cmd = project.get_run_fuzzer_command(&self.build.target)?;
-        cmd.arg("-minimize_crash=1")
-            .arg(format!("-runs={}", self.runs))
-            .arg(&self.test_case);
-
-        for arg in &self.args {
-            cmd.arg(arg);
-        }
-
-        let before_tmin = time::SystemTime::now();
-
-        let mut child = cmd
-            .spawn()
-            .with_context(|| format!("failed to spawn command: {:?}", cmd))?;
-        let status = child
-            .wait()
-            .with_context(|| format!("failed to wait on child process for command: {:?}", cmd))?;
-        if !status.success() {
-            eprintln!("\n{:─<80}\n", "");
-            return Err(anyhow!("Command `{:?}` exited with {}", cmd, status)).with_context(|| {
-                "Test case minimization failed.\n\
-                 \n\
-                 Usually this isn't a hard error, and just means that libfuzzer\n\
-                 doesn't know how to minimize the test case any further while\n\
-                 still reproducing the original crash.\n\
-                 \n\
-                 See the logs above for details."
-            });
+        exec_build(&self.build, project)?;
+        upgrade_encoding(project, &self.build)?;
+        if self.all {
+            return self.exec_batch_tmin(project);
         }
-
-        // Find and display the most recently modified artifact, which is
-        // presumably the result of minification. Yeah, this is a little hacky,
-        // but it seems to work. I don't want to parse libfuzzer's stderr output
-        // and hope it never changes.
-        let minimized_artifact = project
-            .get_artifacts_since(&self.build.target, &before_tmin)?
-            .into_iter()
-            .max_by_key(|a| {
-                a.metadata()
-                    .and_then(|m| m.modified())
-                    .unwrap_or(time::SystemTime::UNIX_EPOCH)
-            });
+        let test_case = self.test_case.as_deref().context("a test case is required")?;
+        let artifacts = project.artifacts_for(&self.build.target)?;
+        let minimized_artifact = self.minimize(project, test_case, &artifacts)?;
 
         if let Some(artifact) = minimized_artifact {
             let artifact = strip_current_dir_prefix(&artifact);
@@ -121,4 +112,165 @@ impl Tmin {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Minimizes every artifact of the target, or of the directory given, that
+    /// has no minimized version yet, writing them alongside, then prints how
+    /// much each shrunk.
+    fn exec_batch_tmin(&self, project: &FuzzProject) -> Result<()> {
+        let dir = match &self.test_case {
+            Some(dir) if dir.is_dir() => dir.clone(),
+            Some(dir) => bail!("{} is not a directory of artifacts", dir.display()),
+            None => project.artifacts_for(&self.build.target)?,
+        };
+        if self.byte_level
+            && project
+                .libfuzzer_args(&self.build.target, &self.args)
+                .iter()
+                .any(|arg| arg.starts_with("-exact_artifact_path="))
+        {
+            bail!("`-exact_artifact_path` would receive every minimized artifact, it can't be used with `--all`");
+        }
+        let mut artifacts: Vec<PathBuf> = fs::read_dir(&dir)
+            .with_context(|| format!("failed to read directory entries of {}", dir.display()))?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|path| path.is_file())
+            .collect();
+        artifacts.sort();
+        let is_minimized = |path: &Path| path.file_name().map_or(false, |n| n.to_string_lossy().starts_with(MINIMIZED_PREFIX));
+        artifacts.retain(|path| !is_minimized(path));
+
+        let mut results = vec![];
+        for (i, artifact) in artifacts.iter().enumerate() {
+            let result = if minimized_path(artifact, &dir).map_or(false, |path| path.exists()) {
+                BatchResult::Skipped
+            } else {
+                eprintln!("[{}/{}] Minimizing {}", i + 1, artifacts.len(), strip_current_dir_prefix(artifact).display());
+                match self.minimize(project, artifact, &dir) {
+                    Ok(Some(minimized)) => {
+                        let before = fs::metadata(artifact).map_or(0, |m| m.len());
+                        let after = fs::metadata(&minimized).map_or(0, |m| m.len());
+                        BatchResult::Minimized(before, after)
+                    }
+                    Ok(None) => BatchResult::Failed(anyhow!("no minimized input was written")),
+                    Err(e) => BatchResult::Failed(e),
+                }
+            };
+            results.push((artifact, result));
+        }
+
+        eprintln!("\n{:<50} {:>9} {:>9} {:>8}", "Artifact", "Before", "After", "Saved");
+        for (artifact, result) in &results {
+            let name = artifact.file_name().unwrap_or_default().to_string_lossy();
+            match *result {
+                BatchResult::Minimized(before, after) => {
+                    let saved = if before == 0 { 0.0 } else { 100.0 * before.saturating_sub(after) as f64 / before as f64 };
+                    eprintln!("{:<50} {:>9} {:>9} {:>7.1}%", name, before, after, saved);
+                }
+                BatchResult::Skipped => eprintln!("{:<50} minimized already", name),
+                BatchResult::Failed(ref e) => eprintln!("{:<50} failed: {:#}", name, e),
+            }
+        }
+        let failed = results.iter().filter(|(_, r)| matches!(r, BatchResult::Failed(_))).count();
+        let minimized = results.iter().filter(|(_, r)| matches!(r, BatchResult::Minimized(..))).count();
+        eprintln!("\n{} minimized, {} skipped, {} failed", minimized, results.len() - minimized - failed, failed);
+        if failed > 0 {
+            bail!("{} of {} artifacts could not be minimized", failed, results.len());
+        }
+        Ok(())
+    }
+
+    /// Minimizes `test_case`, writing the result in `dir`.
+    fn minimize(&self, project: &FuzzProject, test_case: &Path, dir: &Path) -> Result<Option<PathBuf>> {
+        if self.byte_level {
+            self.exec_libfuzzer_tmin(project, test_case, dir)
+        } else {
+            self.exec_typed_tmin(project, test_case, dir).map(Some)
+        }
+    }
+
+    /// Shrinks the decoded arguments in the worker, writing the result in `dir`
+    fn exec_typed_tmin(&self, project: &FuzzProject, test_case: &Path, dir: &Path) -> Result<PathBuf> {
+        let output = minimized_path(test_case, dir)
+            .with_context(|| format!("invalid test case path: {}", test_case.display()))?;
+
+        let mut cmd = project.get_run_fuzzer_command(&self.build)?;
+        cmd.stdin(Stdio::null());
+        cmd.env("MOVE_FUZZER_MINIMIZE_PATH", &output);
+        cmd.env("MOVE_FUZZER_MINIMIZE_RUNS", self.runs.to_string());
+        cmd.arg(test_case);
+
+        let result = cmd
+            .output()
+            .with_context(|| format!("failed to run command: {:?}", cmd))?;
+        if !result.status.success() || !output.exists() {
+            bail!(
+                "Test case minimization failed\n\n\
+                 Command: {:?}\n\n\
+                 === stderr ===\n\
+                 {}",
+                cmd,
+                String::from_utf8_lossy(&result.stderr)
+            );
+        }
+
+        let before = fs::metadata(test_case)?.len();
+        let after = fs::metadata(&output)?.len();
+        eprintln!("Shrunk the test case from {} to {} bytes", before, after);
+        Ok(output)
+    }
+
+    /// Runs libFuzzer's `-minimize_crash`, returning the artifact it wrote
+    fn exec_libfuzzer_tmin(&self, project: &FuzzProject, test_case: &Path, dir: &Path) -> Result<Option<PathBuf>> {
+        let libfuzzer_args = project.libfuzzer_args(&self.build.target, &self.args);
+        // libFuzzer writes each smaller input found there, the last one being the result
+        let minimized = match libfuzzer_args.iter().find_map(|arg| arg.strip_prefix("-exact_artifact_path=")) {
+            Some(path) => PathBuf::from(path),
+            None => minimized_path(test_case, dir)
+                .with_context(|| format!("invalid test case path: {}", test_case.display()))?,
+        };
+        if minimized.exists() {
+            fs::remove_file(&minimized).with_context(|| format!("failed to remove {}", minimized.display()))?;
+        }
+
+        let mut cmd = project.get_run_fuzzer_command(&self.build)?;
+        cmd.arg("-minimize_crash=1")
+            .arg(format!("-runs={}", self.runs))
+            .arg(test_case);
+
+        let mut exact_artifact_arg = ffi::OsString::from("-exact_artifact_path=");
+        exact_artifact_arg.push(&minimized);
+        cmd.arg(exact_artifact_arg);
+        for arg in libfuzzer_args.iter().filter(|arg| !arg.starts_with("-exact_artifact_path=")) {
+            cmd.arg(arg);
+        }
+
+        let mut child = cmd
+            .spawn()
+            .with_context(|| format!("failed to spawn command: {:?}", cmd))?;
+        let status = child
+            .wait()
+            .with_context(|| format!("failed to wait on child process for command: {:?}", cmd))?;
+        if !status.success() {
+            eprintln!("\n{:─<80}\n", "");
+            return Err(anyhow!("Command `{:?}` exited with {}", cmd, status)).with_context(|| {
+                "Test case minimization failed.\n\
+                 \n\
+                 Usually this isn't a hard error, and just means that libfuzzer\n\
+                 doesn't know how to minimize the test case any further while\n\
+                 still reproducing the original crash.\n\
+                 \n\
+                 See the logs above for details."
+            });
+        }
+
+        Ok(minimized.exists().then_some(minimized))
+    }
+}
+
+/// Where the minimized version of `test_case` is written in `dir`, named
+/// after the original artifact when minimizing a minimized one again.
+fn minimized_path(test_case: &Path, dir: &Path) -> Option<PathBuf> {
+    let name = test_case.file_name()?.to_string_lossy().into_owned();
+    let name = name.strip_prefix(MINIMIZED_PREFIX).unwrap_or(&name);
+    Some(dir.join(format!("{}{}", MINIMIZED_PREFIX, name)))
+}
@@ -0,0 +1,124 @@
+use crate::{
+    build::exec_build, crash::Outcome, options::{corpus::upgrade_encoding, BuildOptions, FuzzDirWrapper, OutputOptions}, project::FuzzProject,
+    report::{Finding, ReportOptions}, run::{replay_input, run_fuzz_target_debug_formatter},
+    utils::strip_current_dir_prefix, RunCommand
+};
+use anyhow::{Context, Result};
+use clap::Parser;
+use serde_json::json;
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+#[derive(Clone, Debug, Parser)]
+pub struct Triage {
+    #[clap(flatten)]
+    pub build: BuildOptions,
+
+    #[clap(flatten)]
+    pub fuzz_dir_wrapper: FuzzDirWrapper,
+
+    #[clap(long, default_value = "10")]
+    /// Seconds after which a replayed input is considered a timeout
+    pub timeout: u64,
+
+    #[clap()]
+    /// Directory containing the artifacts to triage, defaults to the target's artifacts
+    pub artifacts: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub report: ReportOptions,
+
+    #[clap(flatten)]
+    pub output: OutputOptions,
+
+    #[clap(last(true))]
+    /// Additional libFuzzer arguments passed through to the binary
+    pub args: Vec<String>,
+}
+
+impl RunCommand for Triage {
+    fn run_command(&mut self) -> Result<()> {
+        let project = FuzzProject::with_package(
+            self.fuzz_dir_wrapper.fuzz_dir.to_owned(),
+            self.build.package_path.clone(),
+        )?;
+        project.apply_config(&mut self.build);
+        self.exec_triage(&project)
+    }
+}
+
+impl Triage {
+    /// Groups the artifacts of a target by the failure they trigger
+    pub fn exec_triage(&self, project: &FuzzProject) -> Result<()> {
+        self.report.requested()?;
+        exec_build(&self.build, project)?;
+        upgrade_encoding(project, &self.build)?;
+
+        let artifacts_dir = match &self.artifacts {
+            Some(dir) => dir.clone(),
+            None => project.artifacts_for(&self.build.target)?,
+        };
+
+        let mut artifacts = fs::read_dir(&artifacts_dir)
+            .with_context(|| format!("failed to read directory entries of {}", artifacts_dir.display()))?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_file())
+            .collect::<Vec<_>>();
+        artifacts.sort();
+
+        let mut args = self.args.clone();
+        args.push(format!("-timeout={}", self.timeout));
+
+        let mut buckets: BTreeMap<Outcome, Vec<PathBuf>> = BTreeMap::new();
+        for artifact in artifacts {
+            let (outcome, _) = replay_input(project, &self.build, &artifact, &args)?;
+            buckets.entry(outcome).or_default().push(artifact);
+        }
+
+        let mut findings = vec![];
+        let mut json_buckets = vec![];
+        for (outcome, mut inputs) in buckets {
+            // The smallest input is usually the easiest to understand
+            inputs.sort_by_key(|p| p.metadata().map(|m| m.len()).unwrap_or(u64::MAX));
+            let representative = strip_current_dir_prefix(&inputs[0]);
+
+            eprintln!("\n{:─<80}", "");
+            eprintln!("\n{} ({} artifacts)\n", outcome, inputs.len());
+            eprintln!("Representative input:\n\n\t{}\n", representative.display());
+            let debug = run_fuzz_target_debug_formatter(project, &self.build, &self.build.target, representative).ok();
+            if let Some(debug) = &debug {
+                eprintln!("Decoded arguments:\n");
+                for l in debug.lines() {
+                    eprintln!("\t{}", l);
+                }
+                eprintln!();
+            }
+
+            json_buckets.push(json!({
+                "error": outcome.to_string(),
+                "class": outcome.class(),
+                "crash": outcome.is_crash(),
+                "count": inputs.len(),
+                "representative": representative.display().to_string(),
+                "arguments": debug,
+            }));
+
+            if outcome.is_crash() {
+                findings.push(Finding {
+                    outcome,
+                    artifact: representative.to_path_buf(),
+                    arguments: debug,
+                    count: inputs.len(),
+                });
+            }
+        }
+        eprintln!("{:─<80}\n", "");
+        self.report.write(&self.build.target, &findings)?;
+        self.output.emit(json!({
+            "target": self.build.target.get_command(),
+            "buckets": json_buckets,
+        }));
+
+        Ok(())
+    }
+}
@@ -1,8 +1,11 @@
 
 
 
-use crate::utils::{collect_targets, default_target, is_fuzz_manifest, manage_initial_instance};
-use crate::{Target};
+use crate::config::{FuzzConfig, TargetConfig, CONFIG_FILE};
+use crate::engine::FuzzEngine;
+use crate::abi::{find_function, format_function_signature, load_module, public_functions};
+use crate::utils::{collect_targets, default_target, find_in_path, is_fuzz_manifest, manage_initial_instance};
+use crate::{BuildOptions, OutputOptions, Target};
 use anyhow::{bail, Context, Result};
 
 
@@ -18,11 +21,19 @@ use std::{
 };
 
 pub(crate) const DEFAULT_FUZZ_DIR: &str = "fuzz";
+pub(crate) const WORKER_BIN: &str = "move-fuzzer-worker";
+pub(crate) const WORKER_PATH_ENV: &str = "MOVE_FUZZER_WORKER";
+/// `env_logger` directives of the worker, overriding `--log-level`.
+pub(crate) const WORKER_LOG_ENV: &str = "MOVE_FUZZER_LOG";
 
 pub(crate) struct FuzzProject {
     /// The project with fuzz targets
     pub(crate) fuzz_dir: PathBuf,
     pub(crate) targets: Vec<String>,
+    /// Settings read from `fuzz.toml`
+    pub(crate) config: FuzzConfig,
+    /// Package fuzzed directly from its build output, instead of the fuzz package
+    pub(crate) package: Option<PathBuf>,
 }
 
 impl FuzzProject {
@@ -43,10 +54,71 @@ impl FuzzProject {
                 project.get_manifest_path().display()
             );
         }
-        project.targets = collect_targets(&manifest);
+        project.config = FuzzConfig::load(&project.get_config_path())?;
+        // Targets are defined in fuzz.toml, `[[bin]]` tables of older projects still count
+        let mut targets = collect_targets(&manifest);
+        targets.extend(project.config.targets.keys().cloned());
+        targets.sort();
+        targets.dedup();
+        project.targets = targets;
         Ok(project)
     }
 
+    /// Same as `new`, except that with `package` the targets are functions of
+    /// that package, fuzzed from its build output without a harness. The fuzz
+    /// directory then only keeps the corpus, artifacts and `fuzz.toml`, and
+    /// doesn't need to be a Move package.
+    pub(crate) fn with_package(fuzz_dir_opt: Option<PathBuf>, package: Option<PathBuf>) -> Result<Self> {
+        let package = match package {
+            Some(package) => package,
+            None => return Self::new(fuzz_dir_opt),
+        };
+        let mut project = manage_initial_instance(fuzz_dir_opt)?;
+        project.config = FuzzConfig::load(&project.get_config_path())?;
+        project.targets = project.config.targets.keys().cloned().collect();
+        project.package = Some(package);
+        Ok(project)
+    }
+
+    pub(crate) fn get_config_path(&self) -> PathBuf {
+        self.get_fuzz_dir().join(CONFIG_FILE)
+    }
+
+    /// Completes the command line options with the settings stored in `fuzz.toml`.
+    pub(crate) fn apply_config(&self, build: &mut BuildOptions) {
+        if let Some(config) = self.config.target(&build.target) {
+            config.apply(build);
+            if build.worker.model.is_none() {
+                build.worker.model = config.model.as_ref().map(|model| self.get_fuzz_dir().join(model));
+            }
+        }
+    }
+
+    /// Returns the libFuzzer arguments stored for the target followed by `args`,
+    /// so that the ones given on the command line win.
+    pub(crate) fn libfuzzer_args(&self, target: &Target, args: &[String]) -> Vec<String> {
+        let mut res = self
+            .config
+            .target(target)
+            .map(|config| config.libfuzzer_flags(self.get_fuzz_dir()))
+            .unwrap_or_default();
+        // Dictionaries are merged rather than overridden by libFuzzer, so
+        // those given on the command line replace the configured ones
+        if args.iter().any(|arg| arg.starts_with("-dict=")) {
+            res.retain(|arg| !arg.starts_with("-dict="));
+        }
+        res.extend(args.iter().cloned());
+        res
+    }
+
+    /// Returns the input directories read along with the corpus of the target.
+    pub(crate) fn corpus_dirs_for(&self, target: &Target) -> Vec<PathBuf> {
+        self.config
+            .target(target)
+            .map(|config| config.corpus_dirs.iter().map(|dir| self.get_fuzz_dir().join(dir)).collect())
+            .unwrap_or_default()
+    }
+
     pub(crate) fn get_fuzz_dir(&self) -> &Path {
         &self.fuzz_dir
     }
@@ -59,9 +131,18 @@ impl FuzzProject {
         self.get_fuzz_dir().join("Move.toml")
     }
 
-    pub(crate) fn list_targets(&self) -> Result<()> {
-        for bin in &self.targets {
-            println!("{}", bin);
+    pub(crate) fn list_targets(&self, output: &OutputOptions) -> Result<()> {
+        if output.is_json() {
+            output.emit(serde_json::json!({ "targets": self.targets }));
+            return Ok(());
+        }
+        for name in &self.targets {
+            match self.config.targets.get(name) {
+                Some(TargetConfig { module: Some(module), function: Some(function), .. }) => {
+                    println!("{} ({}::{})", name, module, function)
+                }
+                _ => println!("{}", name),
+            }
         }
         Ok(())
     }
@@ -100,7 +181,9 @@ impl FuzzProject {
         }
     }
 
-    pub(crate) fn get_artifacts_since(&self, target: &Target, since: &time::SystemTime) -> Result<HashSet<PathBuf>> {
+    /// Returns the artifacts of the target, to tell the ones a run writes
+    /// with `get_new_artifacts`.
+    pub(crate) fn get_artifacts(&self, target: &Target) -> Result<HashSet<PathBuf>> {
         let mut artifacts = HashSet::new();
 
         let artifacts_dir = self.artifacts_for(target)?;
@@ -121,27 +204,180 @@ impl FuzzProject {
             let metadata = entry
                 .metadata()
                 .context("failed to read artifact metadata")?;
-            let modified = metadata
-                .modified()
-                .context("failed to get artifact modification time")?;
-            if !metadata.is_file() || modified <= *since {
-                continue;
+            if metadata.is_file() {
+                artifacts.insert(entry.path());
             }
-
-            artifacts.insert(entry.path());
         }
 
         Ok(artifacts)
     }
 
-    pub(crate) fn get_run_fuzzer_command(&self, target: &Target) -> Result<Command> {
-        let mut module_path = self.fuzz_dir.clone();
-        module_path.push("build");
-        module_path.push("fuzz");
-        module_path.push("bytecode_modules");
+    /// Returns the artifacts of the target not in `before`. libFuzzer names
+    /// artifacts after their contents, so a new input always gets a new name,
+    /// unlike modification times this holds on coarse-timestamp filesystems
+    /// and when other processes touch the directory.
+    pub(crate) fn get_new_artifacts(&self, target: &Target, before: &HashSet<PathBuf>) -> Result<HashSet<PathBuf>> {
+        let mut artifacts = self.get_artifacts(target)?;
+        artifacts.retain(|artifact| !before.contains(artifact));
+        Ok(artifacts)
+    }
+
+    /// Returns the directory holding the compiled modules of the fuzz package.
+    pub(crate) fn get_bytecode_modules_dir(&self) -> PathBuf {
+        if let Some(package) = &self.package {
+            return package.join("build").join(package_name(package)).join("bytecode_modules");
+        }
+        let mut modules_dir = self.fuzz_dir.clone();
+        modules_dir.push("build");
+        modules_dir.push("fuzz");
+        modules_dir.push("bytecode_modules");
+        modules_dir
+    }
+
+    pub(crate) fn get_module_path(&self, target: &Target) -> PathBuf {
+        let mut module_path = self.get_bytecode_modules_dir();
         module_path.push(format!("{}.mv", target.get_module_name()));
+        module_path
+    }
 
-        let mut cmd = Command::new("move-fuzzer-worker");
+    /// Checks that the target module and function exist in the build output,
+    /// listing what is available when they don't.
+    pub(crate) fn validate_target(&self, target: &Target) -> Result<()> {
+        let module_name = target.get_module_name();
+        let module_path = self.get_module_path(target);
+        if !module_path.is_file() {
+            let modules_dir = self.get_bytecode_modules_dir();
+            let script = modules_dir.with_file_name("bytecode_scripts").join(format!("{}.mv", module_name));
+            if script.is_file() {
+                // Script fuzzing is not supported, the worker only executes module functions
+                bail!(
+                    "`{}` is a script, and scripts are not supported as fuzz targets; \
+                     call its logic from a public module function and fuzz that instead",
+                    module_name
+                );
+            }
+            let mut available = fs::read_dir(self.get_bytecode_modules_dir())
+                .map(|entries| {
+                    entries
+                        .filter_map(|e| e.ok())
+                        .map(|e| e.path())
+                        .filter(|p| p.extension().map(|ext| ext == "mv") == Some(true))
+                        .filter_map(|p| p.file_stem().map(|s| s.to_string_lossy().into_owned()))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            available.sort();
+            bail!(
+                "could not find module `{}` in {}\n\nAvailable modules:\n\t{}",
+                module_name,
+                self.get_bytecode_modules_dir().display(),
+                available.join("\n\t")
+            );
+        }
+
+        let module = load_module(&module_path)?;
+        let function_name = target.get_target_function();
+        if find_function(&module, &function_name).is_none() {
+            let available = public_functions(&module)
+                .map(|def| format_function_signature(&module, def))
+                .collect::<Vec<_>>();
+            bail!(
+                "could not find function `{}` in module `{}`\n\nAvailable public functions:\n\t{}",
+                function_name,
+                module_name,
+                available.join("\n\t")
+            );
+        }
+        Ok(())
+    }
+
+    /// Resolves the worker binary from the command line (or `MOVE_FUZZER_WORKER`),
+    /// then `fuzz.toml`, then `PATH`.
+    pub(crate) fn worker_path(&self, build: &BuildOptions) -> Result<PathBuf> {
+        self.engine_worker_path(build, FuzzEngine::LibFuzzer)
+    }
+
+    /// Resolves the worker binary built for `engine`. The configured path is
+    /// the libFuzzer worker's, the other engines' are looked up next to it
+    /// before `PATH`.
+    pub(crate) fn engine_worker_path(&self, build: &BuildOptions, engine: FuzzEngine) -> Result<PathBuf> {
+        let bin = engine.engine().worker_bin();
+        let configured = build
+            .worker
+            .worker_path
+            .clone()
+            .or_else(|| self.config.worker_path.clone());
+        if let Some(path) = configured {
+            if !path.is_file() {
+                bail!(
+                    "the fuzzer worker `{}` does not exist.\n\n\
+                     Install it with `cargo move-fuzz install-worker` or fix the configured path.",
+                    path.display()
+                );
+            }
+            if engine == FuzzEngine::LibFuzzer {
+                return Ok(path);
+            }
+            let sibling = path.with_file_name(bin);
+            if sibling.is_file() {
+                return Ok(sibling);
+            }
+        }
+
+        find_in_path(bin).with_context(|| {
+            format!(
+                "could not find `{bin}` in PATH.\n\n\
+                 Install it with `cargo move-fuzz install-worker --engine {engine}`, or point to an existing \
+                 binary with `--worker-path`, the `{WORKER_PATH_ENV}` environment variable \
+                 or the `worker-path` key of fuzz.toml."
+            )
+        })
+    }
+
+    /// Returns the value of the line starting with `prefix` printed by the
+    /// worker's `--describe`, `None` if the worker doesn't print one.
+    fn describe_line(&self, build: &BuildOptions, prefix: &str) -> Result<Option<String>> {
+        let mut cmd = self.get_worker_command(build)?;
+        cmd.arg("--describe");
+        let output = cmd
+            .output()
+            .with_context(|| format!("failed to execute: {:?}", cmd))?;
+        if !output.status.success() {
+            bail!("failed to describe target: {:?}", cmd);
+        }
+        let line = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .find_map(|line| line.strip_prefix(prefix).map(str::to_owned));
+        Ok(line)
+    }
+
+    /// Returns the bound on the input length of the target printed by the
+    /// worker's `--describe`, `None` if the worker doesn't print one.
+    pub(crate) fn max_input_len(&self, build: &BuildOptions) -> Result<Option<usize>> {
+        let line = self.describe_line(build, "Max input size: ")?;
+        Ok(line.and_then(|size| size.trim_end_matches(" bytes").trim().parse().ok()))
+    }
+
+    /// Returns the version of the input encoding the worker decodes inputs
+    /// without a header with, `None` for a worker predating the versions.
+    pub(crate) fn encoding_version(&self, build: &BuildOptions) -> Result<Option<u8>> {
+        let line = self.describe_line(build, "Encoding version: ")?;
+        Ok(line.and_then(|version| version.trim().parse().ok()))
+    }
+
+    /// Returns the worker command configured for the target, without any
+    /// libFuzzer argument.
+    pub(crate) fn get_worker_command(&self, build: &BuildOptions) -> Result<Command> {
+        self.get_engine_worker_command(build, FuzzEngine::LibFuzzer)
+    }
+
+    /// Returns the command of the worker built for `engine`, configured for
+    /// the target.
+    pub(crate) fn get_engine_worker_command(&self, build: &BuildOptions, engine: FuzzEngine) -> Result<Command> {
+        let target = &build.target;
+        let module_path = self.get_module_path(target);
+
+        let mut cmd = Command::new(self.engine_worker_path(build, engine)?);
 
         let mut module_path_arg = ffi::OsString::from("--module-path=");    
         module_path_arg.push(module_path);
@@ -152,13 +388,117 @@ impl FuzzProject {
         let mut target_function_arg = ffi::OsString::from("--target-function=");    
         target_function_arg.push(target.get_target_function());
 
-        let mut artifact_arg = ffi::OsString::from("-artifact_prefix=");
-        artifact_arg.push(self.artifacts_for(target)?);
-        
+        let mut struct_generation_arg = ffi::OsString::from("--struct-generation=");
+        struct_generation_arg.push(build.worker.struct_generation.to_string());
+
+        cmd.arg(format!("--string-generation={}", build.worker.string_generation));
+        if build.worker.raw_std_types {
+            cmd.arg("--raw-std-types");
+        }
+        cmd.arg(format!("--visibility={}", build.worker.visibility));
+        cmd.arg(format!("--instantiate={}", build.worker.instantiate));
+        if let Some(gas_limit) = build.worker.gas_limit {
+            cmd.arg(format!("--gas-limit={}", gas_limit));
+        }
+        for code in &build.worker.allowed_abort_codes {
+            cmd.arg(format!("--allow-abort-code={}", code));
+        }
+        for action in &build.worker.error_actions {
+            cmd.arg(format!("--on-error={}", action));
+        }
+        if build.worker.oracle_bool {
+            cmd.arg("--oracle-bool");
+        }
+        if build.worker.publish {
+            cmd.arg("--publish");
+        }
+        if let Some(model) = &build.worker.model {
+            cmd.arg(format!("--model={}", model.display()));
+        }
+        if let Some(invariant) = &build.worker.invariant {
+            cmd.arg(format!("--invariant={}", invariant));
+        }
+        for setup in &build.worker.setup_functions {
+            cmd.arg(format!("--setup-function={}", setup));
+        }
+        for fix in &build.worker.fixed_args {
+            cmd.arg(format!("--fix={}", fix));
+        }
+        for signer in &build.worker.signers {
+            cmd.arg(format!("--signer={}", signer));
+        }
+        for address in &build.worker.addresses {
+            cmd.arg(format!("--address={}", address));
+        }
+        if build.worker.test_signers > 0 {
+            cmd.arg(format!("--test-signers={}", build.worker.test_signers));
+        }
+        if build.worker.random_signers {
+            cmd.arg("--random-signers");
+        }
+        if let Some(weights) = &build.worker.address_weights {
+            cmd.arg(format!("--address-weights={}", weights));
+        }
+        if let Some(percent) = build.worker.special_int_percent {
+            cmd.arg(format!("--special-int-percent={}", percent));
+        }
+        if build.worker.swarm {
+            cmd.arg("--swarm");
+        }
+        if let Some(seed) = build.worker.swarm_seed {
+            cmd.arg(format!("--swarm-seed={}", seed));
+        }
+        if let Some(version) = build.worker.bytecode_version {
+            cmd.arg(format!("--bytecode-version={}", version));
+        }
+        if let Some(budget) = build.worker.vector_budget {
+            cmd.arg(format!("--vector-budget={}", budget));
+        }
+        if build.worker.include_deps {
+            cmd.arg("--include-deps");
+        }
+        let mut abi_cache_arg = ffi::OsString::from("--abi-cache=");
+        abi_cache_arg.push(self.abi_cache_dir());
+        cmd.arg(abi_cache_arg);
+        if let Some(depth) = build.worker.max_input_depth {
+            cmd.arg(format!("--max-input-depth={}", depth));
+        }
+        if let Some(values) = build.worker.max_input_values {
+            cmd.arg(format!("--max-input-values={}", values));
+        }
+        if let Some(level) = &build.worker.log_level {
+            cmd.arg(format!("--log-level={}", level));
+        }
+        // Only the startup timings, `--log-level=debug` logs every execution
+        if build.verbose && env::var_os(WORKER_LOG_ENV).is_none() {
+            cmd.env(WORKER_LOG_ENV, "move_fuzzer::startup=debug");
+        }
+        if let Some(url) = &build.worker.fork_url {
+            cmd.arg(format!("--fork-url={}", url));
+        }
+        if let Some(version) = build.worker.fork_version {
+            cmd.arg(format!("--fork-version={}", version));
+        }
+        if let Some(cache) = &build.worker.fork_cache {
+            let mut cache_arg = ffi::OsString::from("--fork-cache=");
+            cache_arg.push(cache);
+            cmd.arg(cache_arg);
+        }
+
         cmd.arg(module_path_arg)
             .arg(target_module_arg)
             .arg(target_function_arg)
-            .arg(artifact_arg);
+            .arg(struct_generation_arg);
+
+        Ok(cmd)
+    }
+
+    pub(crate) fn get_run_fuzzer_command(&self, build: &BuildOptions) -> Result<Command> {
+        let mut cmd = self.get_worker_command(build)?;
+
+        let mut artifact_arg = ffi::OsString::from("-artifact_prefix=");
+        artifact_arg.push(self.artifacts_for(&build.target)?);
+        cmd.arg(artifact_arg);
 
         Ok(cmd)
     }
@@ -180,21 +520,75 @@ impl FuzzProject {
         Ok((coverage_raw, coverage_data))
     }
 
+    /// Returns the directory where the workers cache the parameter types of
+    /// the targets, created by the workers themselves.
+    pub(crate) fn abi_cache_dir(&self) -> PathBuf {
+        self.get_fuzz_dir().join("cache").join("abi")
+    }
+
+    /// Returns the directory where the worker processes record their Move coverage.
+    pub(crate) fn move_coverage_dir(&self) -> Result<PathBuf> {
+        let mut p = self.get_fuzz_dir().to_owned();
+        p.push("coverage");
+        p.push("move");
+        fs::create_dir_all(&p)
+            .with_context(|| format!("could not make a coverage directory at {:?}", p))?;
+        Ok(p)
+    }
+
     pub(crate) fn corpus_for(&self, target: &Target) -> Result<PathBuf> {
         let mut p = self.get_fuzz_dir().to_owned();
-        p.push("corpus");
-        p.push(target.get_module_name());
-        p.push(target.get_target_function());
+        if let Some(corpus) = self.config.target(target).and_then(|c| c.corpus.as_ref()) {
+            p.push(corpus);
+        } else {
+            p.push("corpus");
+            p.push(target.get_module_name());
+            p.push(target.get_target_function());
+        }
         fs::create_dir_all(&p)
             .with_context(|| format!("could not make a corpus directory at {:?}", p))?;
         Ok(p)
     }
 
-    pub(crate) fn artifacts_for(&self, target: &Target) -> Result<PathBuf> {
+    /// Returns the directory engines other than libFuzzer keep their state in,
+    /// e.g. the AFL++ queue.
+    pub(crate) fn engine_dir_for(&self, target: &Target, engine: FuzzEngine) -> PathBuf {
         let mut p = self.get_fuzz_dir().to_owned();
-        p.push("artifacts");
+        p.push(engine.to_string());
         p.push(target.get_module_name());
         p.push(target.get_target_function());
+        p
+    }
+
+    /// Returns the file the campaign last run on `target` is saved in, for
+    /// `run --resume`.
+    pub(crate) fn session_file_for(&self, target: &Target) -> PathBuf {
+        let mut p = self.get_fuzz_dir().to_owned();
+        p.push("sessions");
+        p.push(target.get_module_name());
+        p.push(format!("{}.json", target.get_target_function()));
+        p
+    }
+
+    /// Returns the file recording the input encoding version the inputs
+    /// without a header in the target's corpus and artifacts were written with.
+    pub(crate) fn encoding_file_for(&self, target: &Target) -> PathBuf {
+        let mut p = self.get_fuzz_dir().to_owned();
+        p.push("encoding");
+        p.push(target.get_module_name());
+        p.push(target.get_target_function());
+        p
+    }
+
+    pub(crate) fn artifacts_for(&self, target: &Target) -> Result<PathBuf> {
+        let mut p = self.get_fuzz_dir().to_owned();
+        if let Some(artifacts) = self.config.target(target).and_then(|c| c.artifacts.as_ref()) {
+            p.push(artifacts);
+        } else {
+            p.push("artifacts");
+            p.push(target.get_module_name());
+            p.push(target.get_target_function());
+        }
 
         // This adds a trailing slash, which is necessary for libFuzzer, because
         // it does simple string concatenation when joining paths.
@@ -219,4 +613,15 @@ impl FuzzProject {
             )
         })
     }
-}
\ No newline at end of file
+}
+
+/// Name of the Move package at `package`, which its build output is stored
+/// under. Falls back to the directory name if the manifest can't be read.
+fn package_name(package: &Path) -> String {
+    fs::read_to_string(package.join("Move.toml"))
+        .ok()
+        .and_then(|data| data.parse::<toml::Value>().ok())
+        .and_then(|manifest| manifest.get("package")?.get("name")?.as_str().map(String::from))
+        .or_else(|| package.canonicalize().ok()?.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_default()
+}
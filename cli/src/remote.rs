@@ -0,0 +1,111 @@
+use std::{fs, path::{Path, PathBuf}, process::Command};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{options::Target, utils::find_in_path};
+
+/// A location corpora are shared through, each target's under
+/// `<location>/<module>/<function>`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Remote {
+    /// An S3 bucket, synchronized with the `aws` CLI
+    S3(String),
+    /// A Google Cloud Storage bucket, synchronized with `gsutil`
+    Gcs(String),
+    /// A directory, e.g. a network mount
+    Dir(PathBuf),
+}
+
+impl Remote {
+    /// Parses an `s3://`, `gs://` or `file://` URL, or a plain directory path.
+    pub fn parse(url: &str) -> Result<Self> {
+        let url = url.trim_end_matches('/');
+        if url.starts_with("s3://") {
+            Ok(Remote::S3(url.to_owned()))
+        } else if url.starts_with("gs://") {
+            Ok(Remote::Gcs(url.to_owned()))
+        } else if let Some(path) = url.strip_prefix("file://") {
+            Ok(Remote::Dir(PathBuf::from(path)))
+        } else if url.contains("://") {
+            bail!("unsupported corpus URL `{}`, expected an s3://, gs:// or file:// URL or a directory", url)
+        } else {
+            Ok(Remote::Dir(PathBuf::from(url)))
+        }
+    }
+
+    fn for_target(&self, target: &Target) -> Remote {
+        let module = target.get_module_name();
+        let function = target.get_target_function();
+        match self {
+            Remote::S3(url) => Remote::S3(format!("{}/{}/{}", url, module, function)),
+            Remote::Gcs(url) => Remote::Gcs(format!("{}/{}/{}", url, module, function)),
+            Remote::Dir(dir) => Remote::Dir(dir.join(module).join(function)),
+        }
+    }
+
+    /// Uploads the entries of `corpus` missing from the target's remote corpus.
+    pub fn push(&self, target: &Target, corpus: &Path) -> Result<()> {
+        match self.for_target(target) {
+            Remote::Dir(dir) => copy_missing(corpus, &dir),
+            remote => remote.sync(&corpus.display().to_string(), &remote.url()),
+        }
+    }
+
+    /// Downloads the entries of the target's remote corpus missing from `corpus`.
+    pub fn pull(&self, target: &Target, corpus: &Path) -> Result<()> {
+        match self.for_target(target) {
+            Remote::Dir(dir) if !dir.exists() => Ok(()),
+            Remote::Dir(dir) => copy_missing(&dir, corpus),
+            remote => remote.sync(&remote.url(), &corpus.display().to_string()),
+        }
+    }
+
+    fn url(&self) -> String {
+        match self {
+            Remote::S3(url) | Remote::Gcs(url) => url.clone(),
+            Remote::Dir(dir) => dir.display().to_string(),
+        }
+    }
+
+    /// Copies the files of `from` missing in `to`; nothing is ever deleted,
+    /// corpus entries being named after their contents.
+    fn sync(&self, from: &str, to: &str) -> Result<()> {
+        let (tool, args): (&str, &[&str]) = match self {
+            Remote::S3(_) => ("aws", &["s3", "sync", "--no-progress"]),
+            Remote::Gcs(_) => ("gsutil", &["-m", "-q", "rsync"]),
+            Remote::Dir(_) => unreachable!("directories are copied directly"),
+        };
+        let tool_path = find_in_path(tool)
+            .with_context(|| format!("could not find `{}` in PATH, it is needed to sync with {}", tool, self.url()))?;
+        let mut cmd = Command::new(tool_path);
+        cmd.args(args).arg(from).arg(to);
+        let status = cmd
+            .status()
+            .with_context(|| format!("failed to execute: {:?}", cmd))?;
+        if !status.success() {
+            bail!("failed to sync the corpus: {:?}", cmd);
+        }
+        Ok(())
+    }
+}
+
+fn copy_missing(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to).with_context(|| format!("could not make a corpus directory at {:?}", to))?;
+    let mut copied = 0;
+    for entry in fs::read_dir(from)
+        .with_context(|| format!("failed to read directory entries of {}", from.display()))?
+    {
+        let path = entry?.path();
+        let dest = match path.file_name() {
+            Some(name) if path.is_file() => to.join(name),
+            _ => continue,
+        };
+        if !dest.exists() {
+            fs::copy(&path, &dest)
+                .with_context(|| format!("failed to copy {} to {}", path.display(), dest.display()))?;
+            copied += 1;
+        }
+    }
+    eprintln!("Copied {} entries from {} to {}", copied, from.display(), to.display());
+    Ok(())
+}
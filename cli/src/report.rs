@@ -0,0 +1,127 @@
+use crate::{crash::Outcome, options::Target};
+use anyhow::{bail, Context, Result};
+use clap::{Parser, ValueEnum};
+use serde_json::{json, Value};
+use std::{collections::BTreeSet, fs, path::{Path, PathBuf}};
+
+/// Formats the findings of a campaign can be written in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum ReportFormat {
+    /// Static Analysis Results Interchange Format, understood by code scanning tools
+    Sarif,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Parser)]
+pub struct ReportOptions {
+    /// Write the findings to a report file, e.g. `--report sarif findings.sarif`
+    #[clap(long, num_args = 2, value_names = ["FORMAT", "PATH"])]
+    pub report: Vec<String>,
+}
+
+impl ReportOptions {
+    /// Returns the requested report format and path, if any.
+    pub fn requested(&self) -> Result<Option<(ReportFormat, PathBuf)>> {
+        match self.report.as_slice() {
+            [] => Ok(None),
+            [format, path] => match ReportFormat::from_str(format, true) {
+                Ok(format) => Ok(Some((format, PathBuf::from(path)))),
+                Err(_) => bail!("unknown report format `{}`, expected `sarif`", format),
+            },
+            _ => bail!("`--report` expects a format and a path"),
+        }
+    }
+
+    /// Writes `findings` to the requested report, if any.
+    pub fn write(&self, target: &Target, findings: &[Finding]) -> Result<()> {
+        if let Some((format, path)) = self.requested()? {
+            match format {
+                ReportFormat::Sarif => write_sarif(&path, target, findings)?,
+            }
+            eprintln!("Report written to {}", path.display());
+        }
+        Ok(())
+    }
+}
+
+/// A failing input found by the fuzzer.
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub outcome: Outcome,
+    pub artifact: PathBuf,
+    /// Arguments the input decodes to, one per line
+    pub arguments: Option<String>,
+    /// Number of artifacts triggering the same failure
+    pub count: usize,
+}
+
+fn rule_id(outcome: &Outcome) -> String {
+    format!("move-fuzzer/{}", outcome.class())
+}
+
+fn function_name(target: &Target) -> String {
+    format!("{}::{}", target.get_module_name(), target.get_target_function())
+}
+
+/// Writes the findings as a SARIF 2.1.0 log with one result per finding.
+pub fn write_sarif(path: &Path, target: &Target, findings: &[Finding]) -> Result<()> {
+    let function = function_name(target);
+    let rules = findings
+        .iter()
+        .map(|f| rule_id(&f.outcome))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|id| {
+            json!({
+                "id": id,
+                "shortDescription": { "text": format!("{} found by fuzzing", id.trim_start_matches("move-fuzzer/")) },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let results = findings
+        .iter()
+        .map(|f| {
+            let mut text = format!("`{}` fails with {}", function, f.outcome);
+            if let Some(arguments) = &f.arguments {
+                text.push_str("\n\nArguments:\n");
+                text.push_str(arguments.trim_end());
+            }
+            json!({
+                "ruleId": rule_id(&f.outcome),
+                "level": "error",
+                "message": { "text": text },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": f.artifact.display().to_string() },
+                    },
+                    "logicalLocations": [{
+                        "fullyQualifiedName": function,
+                        "kind": "function",
+                    }],
+                }],
+                "properties": {
+                    "error": f.outcome.to_string(),
+                    "occurrences": f.count,
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let log: Value = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "move-fuzzer",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    });
+
+    let contents = serde_json::to_string_pretty(&log).context("failed to serialize the SARIF report")?;
+    fs::write(path, contents).with_context(|| format!("failed to write report to {}", path.display()))
+}
@@ -0,0 +1,82 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{engine::FuzzEngine, trace::MoveCoverage};
+
+/// State of the campaign last run on a target, saved after each of its legs
+/// so that `run --resume` continues it where it left off: with the same seed
+/// and engine flags, for the time and runs it had left.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Session {
+    /// Time box of the whole campaign, in seconds
+    pub max_total_time: Option<u64>,
+    /// Runs of the whole campaign
+    pub runs: Option<u64>,
+    pub seed: Option<u32>,
+    pub engine: FuzzEngine,
+    pub jobs: u16,
+    /// Arguments passed through to the engine
+    pub args: Vec<String>,
+    /// Times the campaign was run, the first one included
+    pub legs: usize,
+    /// Seconds fuzzed over every leg
+    pub elapsed: u64,
+    /// Executions over every leg, as the engine counted them
+    pub runs_done: u64,
+    /// Move coverage over every leg
+    pub coverage: MoveCoverage,
+}
+
+impl Session {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read(path).with_context(|| format!("no session to resume in {}", path.display()))?;
+        serde_json::from_slice(&data).with_context(|| format!("failed to parse the session {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("could not make the directory {}", dir.display()))?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(self)?).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Seconds left of the time box, if any.
+    pub fn remaining_time(&self) -> Option<u64> {
+        self.max_total_time.map(|time| time.saturating_sub(self.elapsed))
+    }
+
+    /// Runs left, if limited.
+    pub fn remaining_runs(&self) -> Option<u64> {
+        self.runs.map(|runs| runs.saturating_sub(self.runs_done))
+    }
+
+    /// Whether the time box or the runs are used up.
+    pub fn is_complete(&self) -> bool {
+        self.remaining_time() == Some(0) || self.remaining_runs() == Some(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn session_completes_when_time_or_runs_are_used_up() {
+        let mut session = Session {
+            max_total_time: Some(600),
+            elapsed: 450,
+            ..Default::default()
+        };
+        assert_eq!(session.remaining_time(), Some(150));
+        assert_eq!(session.remaining_runs(), None);
+        assert!(!session.is_complete());
+        session.elapsed = 610;
+        assert_eq!(session.remaining_time(), Some(0));
+        assert!(session.is_complete());
+
+        let session = Session { runs: Some(1000), runs_done: 1000, ..Default::default() };
+        assert!(session.is_complete());
+    }
+}
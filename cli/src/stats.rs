@@ -0,0 +1,227 @@
+use std::{collections::BTreeMap, fs, path::{Path, PathBuf}, time};
+
+use serde::Deserialize;
+
+use crate::{options::Target, trace::merge_coverage_maps};
+
+/// How often the Move coverage maps are merged again.
+const COVERAGE_REFRESH: time::Duration = time::Duration::from_secs(2);
+
+/// Move-level statistics of a worker process, written by the worker next to
+/// its coverage map as `<module>-<function>-<pid>.stats.json`.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct WorkerStats {
+    pub executions: u64,
+    /// Inputs no arguments could be built from
+    pub undecodable: u64,
+    /// Inputs rejected from the corpus by the generation limits or the crash policy
+    pub rejected: u64,
+    pub crashes: u64,
+    pub arguments: u64,
+    pub argument_bytes: u64,
+    /// When the process last found new Move instructions, in seconds since the epoch
+    pub last_new_coverage: Option<u64>,
+    /// Seed of the swarm profile the process sampled
+    pub swarm_seed: Option<u64>,
+    /// Mutations made by the worker's mutator, by kind
+    pub mutations: BTreeMap<String, MutationCounts>,
+}
+
+/// How often a kind of mutation was applied, and produced an input libFuzzer
+/// kept for its new coverage.
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct MutationCounts {
+    pub applied: u64,
+    pub kept: u64,
+}
+
+impl WorkerStats {
+    fn add(&mut self, other: WorkerStats) {
+        self.executions += other.executions;
+        self.undecodable += other.undecodable;
+        self.rejected += other.rejected;
+        self.crashes += other.crashes;
+        self.arguments += other.arguments;
+        self.argument_bytes += other.argument_bytes;
+        self.last_new_coverage = self.last_new_coverage.max(other.last_new_coverage);
+        for (kind, counts) in other.mutations {
+            let total = self.mutations.entry(kind).or_default();
+            total.applied += counts.applied;
+            total.kept += counts.kept;
+        }
+    }
+
+    /// The kinds of mutations with the inputs they produced that libFuzzer
+    /// kept, most effective first.
+    pub fn mutation_histogram(&self) -> Vec<(&str, MutationCounts)> {
+        let mut kinds = self
+            .mutations
+            .iter()
+            .map(|(kind, counts)| (kind.as_str(), *counts))
+            .collect::<Vec<_>>();
+        let rate = |counts: &MutationCounts| counts.kept as f64 / counts.applied.max(1) as f64;
+        kinds.sort_by(|(_, a), (_, b)| rate(b).partial_cmp(&rate(a)).unwrap_or(std::cmp::Ordering::Equal));
+        kinds
+    }
+
+    /// Share of the executions, in percent, `count` stands for.
+    pub fn percent(&self, count: u64) -> f64 {
+        count as f64 * 100.0 / self.executions.max(1) as f64
+    }
+
+    /// Mean serialized size of the arguments generated per execution.
+    pub fn argument_bytes_per_execution(&self) -> f64 {
+        self.argument_bytes as f64 / self.executions.max(1) as f64
+    }
+
+    /// Time since a worker last found new Move instructions.
+    pub fn since_new_coverage(&self) -> Option<time::Duration> {
+        let at = time::UNIX_EPOCH + time::Duration::from_secs(self.last_new_coverage?);
+        time::SystemTime::now().duration_since(at).ok()
+    }
+}
+
+/// Progress of a fuzzing campaign, followed from the libFuzzer output and the
+/// Move coverage maps the workers write.
+pub struct CampaignStats {
+    pub target: Target,
+    coverage_dir: PathBuf,
+    started: time::SystemTime,
+    pub timer: time::Instant,
+    /// Instructions of the target module, `None` if it couldn't be loaded
+    pub module_instructions: Option<usize>,
+    pub runs: Option<u64>,
+    pub exec_per_sec: Option<u64>,
+    /// Corpus size as libFuzzer reports it, e.g. `12/340b`
+    pub corpus: Option<String>,
+    /// Instructions of the target module covered so far
+    pub covered: usize,
+    pub last_new_coverage: Option<time::Instant>,
+    pub artifacts: usize,
+    /// Statistics of the worker processes of this campaign, summed
+    pub workers: WorkerStats,
+    coverage_refreshed: Option<time::Instant>,
+}
+
+impl CampaignStats {
+    pub fn new(target: &Target, coverage_dir: PathBuf, started: time::SystemTime, module_instructions: Option<usize>) -> Self {
+        CampaignStats {
+            target: target.clone(),
+            coverage_dir,
+            started,
+            timer: time::Instant::now(),
+            module_instructions,
+            runs: None,
+            exec_per_sec: None,
+            corpus: None,
+            covered: 0,
+            last_new_coverage: None,
+            artifacts: 0,
+            workers: WorkerStats::default(),
+            coverage_refreshed: None,
+        }
+    }
+
+    /// Takes a line of libFuzzer output into account, returning whether it
+    /// was a status line.
+    pub fn update(&mut self, line: &str) -> bool {
+        if self.coverage_refreshed.map_or(true, |t| t.elapsed() >= COVERAGE_REFRESH) {
+            self.refresh_coverage();
+        }
+        // Status lines look like `#4096 NEW cov: 12 ft: 15 corp: 3/9b exec/s: 2048 rss: 40Mb`
+        let status = match line.strip_prefix('#') {
+            Some(status) => status,
+            None => {
+                if line.contains("Test unit written to") {
+                    self.artifacts += 1;
+                }
+                return false;
+            }
+        };
+        let mut words = status.split_whitespace();
+        self.runs = words.next().and_then(|n| n.parse().ok()).or(self.runs);
+        if words.next() == Some("NEW") {
+            self.last_new_coverage = Some(time::Instant::now());
+        }
+        self.exec_per_sec = field(line, "exec/s:").and_then(|n| n.parse().ok()).or(self.exec_per_sec);
+        self.corpus = field(line, "corp:").map(str::to_owned).or(self.corpus.take());
+        true
+    }
+
+    /// Number of corpus entries, the part of `corpus` before the slash.
+    pub fn corpus_entries(&self) -> Option<u64> {
+        self.corpus.as_deref()?.split('/').next()?.parse().ok()
+    }
+
+    /// Percentage of the target module's instructions covered.
+    pub fn coverage_percent(&self) -> Option<f64> {
+        match self.module_instructions {
+            Some(total) if total > 0 => Some(self.covered as f64 * 100.0 / total as f64),
+            _ => None,
+        }
+    }
+
+    fn refresh_coverage(&mut self) {
+        self.coverage_refreshed = Some(time::Instant::now());
+        // The maps are rewritten while being read, a failed merge is retried later
+        if let Ok((coverage, _)) = merge_coverage_maps(&self.coverage_dir, &self.target, &self.started) {
+            // Functions are named `address::module::function`, dependencies included
+            let module = self.target.get_module_name();
+            self.covered = coverage
+                .iter()
+                .filter(|(function, _)| function.split("::").nth(1) == Some(module.as_str()))
+                .count();
+        }
+        self.workers = read_worker_stats(&self.coverage_dir, &self.target, &self.started);
+    }
+}
+
+/// Statistics written in `dir` since `since` by each worker process fuzzing
+/// `target`.
+fn worker_stats(dir: &Path, target: &Target, since: &time::SystemTime) -> Vec<WorkerStats> {
+    let prefix = format!("{}-{}-", target.get_module_name(), target.get_target_function());
+    let mut workers = vec![];
+    for entry in fs::read_dir(dir).into_iter().flatten().filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.starts_with(&prefix) || !name.ends_with(".stats.json") {
+            continue;
+        }
+        let updated = entry.metadata().and_then(|m| m.modified()).map_or(false, |m| m >= *since);
+        let stats = fs::read(entry.path())
+            .ok()
+            .and_then(|data| serde_json::from_slice::<WorkerStats>(&data).ok());
+        if let (true, Some(stats)) = (updated, stats) {
+            workers.push(stats);
+        }
+    }
+    workers
+}
+
+/// Sums the statistics written in `dir` since `since` by the worker processes
+/// fuzzing `target`.
+pub fn read_worker_stats(dir: &Path, target: &Target, since: &time::SystemTime) -> WorkerStats {
+    let mut total = WorkerStats::default();
+    for stats in worker_stats(dir, target, since) {
+        total.add(stats);
+    }
+    total
+}
+
+/// The distinct swarm profiles the worker processes fuzzing `target` sampled
+/// since `since`, which their findings only reproduce with.
+pub fn read_swarm_seeds(dir: &Path, target: &Target, since: &time::SystemTime) -> Vec<u64> {
+    let mut seeds = worker_stats(dir, target, since)
+        .into_iter()
+        .filter_map(|stats| stats.swarm_seed)
+        .collect::<Vec<_>>();
+    seeds.sort_unstable();
+    seeds.dedup();
+    seeds
+}
+
+/// The word following `name` in a libFuzzer status line.
+fn field<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    line.split(name).nth(1)?.split_whitespace().next()
+}
@@ -1,4 +1,4 @@
-use std::{fs, io::Write};
+use std::{fmt, fs, io::Write};
 
 use crate::project::FuzzProject;
 use anyhow::{Context, Result};
@@ -23,6 +23,56 @@ fuzz = "0x0"
     };
 }
 
+macro_rules! minimal_move_toml_template {
+    () => {
+        format_args!(
+            r##"[package]
+name = "fuzz"
+version = "0.0.0"
+edition = "legacy"
+
+[addresses]
+fuzz = "0x0"
+"##
+        )
+    };
+}
+
+macro_rules! sui_move_toml_template {
+    () => {
+        format_args!(
+            r##"[package]
+name = "fuzz"
+version = "0.0.0"
+edition = "legacy"
+
+[dependencies]
+Sui = {{ git = "https://github.com/MystenLabs/sui.git", subdir = "crates/sui-framework/packages/sui-framework", rev = "framework/mainnet" }}
+
+[addresses]
+fuzz = "0x0"
+"##
+        )
+    };
+}
+
+macro_rules! aptos_move_toml_template {
+    () => {
+        format_args!(
+            r##"[package]
+name = "fuzz"
+version = "0.0.0"
+
+[dependencies]
+AptosFramework = {{ git = "https://github.com/aptos-labs/aptos-core.git", subdir = "aptos-move/framework/aptos-framework", rev = "mainnet" }}
+
+[addresses]
+fuzz = "0xcafe"
+"##
+        )
+    };
+}
+
 macro_rules! gitignore_template {
     () => {
         format_args!(
@@ -30,6 +80,7 @@ macro_rules! gitignore_template {
 corpus
 artifacts
 coverage
+cache
 "##
         )
     };
@@ -49,8 +100,68 @@ target_name = $target_name
     };
 }
 
+macro_rules! core_target_template {
+    ($target_name:expr) => {
+        format_args!(
+            r##"module fuzz::{target_name} {{
+    use std::vector;
+
+    /// Reversing twice gives the input back
+    public fun fuzz_target(bytes: vector<u8>) {{
+        let reversed = copy bytes;
+        vector::reverse(&mut reversed);
+        vector::reverse(&mut reversed);
+        assert!(reversed == bytes, 0);
+    }}
+}}
+"##,
+target_name = $target_name
+        )
+    };
+}
+
+macro_rules! sui_target_template {
+    ($target_name:expr) => {
+        format_args!(
+            r##"module fuzz::{target_name} {{
+    use sui::hex;
+
+    /// Hex encoding round-trips
+    public fun fuzz_target(bytes: vector<u8>) {{
+        let encoded = hex::encode(copy bytes);
+        assert!(hex::decode(encoded) == bytes, 0);
+    }}
+}}
+"##,
+target_name = $target_name
+        )
+    };
+}
+
+macro_rules! aptos_target_template {
+    ($target_name:expr) => {
+        format_args!(
+            r##"module fuzz::{target_name} {{
+    use aptos_std::math64;
+
+    /// The larger of two numbers is never below the smaller one
+    public fun fuzz_target(a: u64, b: u64) {{
+        assert!(math64::max(a, b) >= math64::min(a, b), 0);
+    }}
+}}
+"##,
+target_name = $target_name
+        )
+    };
+}
+
 /// Add a new fuzz target script with a given name
 pub fn create_target_template(project: &FuzzProject, target: &str) -> Result<()> {
+    create_target_from(project, target, move_target_template!(target))
+}
+
+/// Add a new fuzz target script with a given name and contents
+pub fn create_target_from(project: &FuzzProject, target: &str, contents: fmt::Arguments) -> Result<()> {
     let move_target_path = project.get_target_path(target);
 
     // If the user manually created a fuzz project, but hasn't created any
@@ -65,7 +176,7 @@ pub fn create_target_template(project: &FuzzProject, target: &str) -> Result<()>
         .create_new(true)
         .open(&move_target_path)
         .with_context(|| format!("could not create target script file at {:?}", move_target_path))?;
-    move_script.write_fmt(move_target_template!(target))?;
+    move_script.write_fmt(contents)?;
 
     Ok(())
 }
\ No newline at end of file
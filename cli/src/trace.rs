@@ -0,0 +1,361 @@
+use crate::{abi::{function_name, load_module}, crash::Outcome, options::{BuildOptions, Target}, project::FuzzProject, run::replay_command};
+use anyhow::{Context, Result};
+use move_binary_format::{file_format::FunctionDefinitionIndex, CompiledModule};
+use move_bytecode_source_map::utils::source_map_from_file;
+use move_command_line_common::files::FileHash;
+use move_coverage::coverage_map::CoverageMap;
+use move_disassembler::disassembler::Disassembler;
+use move_ir_types::location::Spanned;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashMap}, fs, hash::{Hash, Hasher}, path::{Path, PathBuf}, time
+};
+
+/// Environment variable telling the Move VM where to write its execution trace.
+/// The VM only traces when built with debug assertions, as `install-worker` does.
+pub const MOVE_VM_TRACE_ENV: &str = "MOVE_VM_TRACE";
+
+/// Environment variable telling the worker where to record the Move coverage of
+/// a fuzzing campaign, each process writing its own trace and coverage map named
+/// `<module>-<function>-<pid>`.
+pub const MOVE_FUZZER_COVERAGE_DIR_ENV: &str = "MOVE_FUZZER_COVERAGE_DIR";
+
+/// Move instructions executed by an input, as fully qualified function name and
+/// bytecode offset.
+pub type MoveCoverage = BTreeSet<(String, u64)>;
+
+/// Reads the instructions executed according to a Move VM trace file.
+pub fn coverage_from_trace(path: &Path) -> MoveCoverage {
+    coverage_from_map(&CoverageMap::from_trace_file(path))
+}
+
+fn coverage_from_map(map: &CoverageMap) -> MoveCoverage {
+    let exec_map = map.to_unified_exec_map();
+    let mut coverage = MoveCoverage::new();
+    for ((address, module), module_map) in exec_map.module_maps {
+        for (function, offsets) in module_map.function_maps {
+            let name = format!("{}::{}::{}", address.to_hex_literal(), module, function);
+            coverage.extend(offsets.keys().map(|offset| (name.clone(), *offset)));
+        }
+    }
+    coverage
+}
+
+/// Reads a Move coverage saved by the `coverage` command, or a coverage map
+/// written by the workers of a campaign (`.coverage_map`).
+pub fn read_move_coverage(path: &Path) -> Result<MoveCoverage> {
+    if path.extension().map_or(false, |e| e == "coverage_map") {
+        let map = CoverageMap::from_binary_file(path)
+            .with_context(|| format!("failed to read coverage map {}", path.display()))?;
+        return Ok(coverage_from_map(&map));
+    }
+    let data = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_slice(&data).with_context(|| format!("failed to parse Move coverage {}", path.display()))
+}
+
+pub fn write_move_coverage(path: &Path, coverage: &MoveCoverage) -> Result<()> {
+    let data = serde_json::to_vec(coverage)?;
+    fs::write(path, data).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Instructions newly covered and no longer covered by `coverage` compared
+/// to `baseline`, counted by function, for the functions that changed.
+pub fn coverage_diff(baseline: &MoveCoverage, coverage: &MoveCoverage) -> Vec<(String, usize, usize)> {
+    let mut diff: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for (function, _) in coverage.difference(baseline) {
+        diff.entry(function).or_default().0 += 1;
+    }
+    for (function, _) in baseline.difference(coverage) {
+        diff.entry(function).or_default().1 += 1;
+    }
+    diff.into_iter()
+        .map(|(function, (gained, lost))| (function.to_owned(), gained, lost))
+        .collect()
+}
+
+/// Replays a single input with Move VM tracing enabled, returning its outcome and
+/// the Move instructions it executed.
+pub fn replay_with_coverage(
+    project: &FuzzProject,
+    build: &BuildOptions,
+    input: &Path,
+    args: &[String],
+) -> Result<(Outcome, MoveCoverage)> {
+    let trace_dir = tempfile::tempdir().context("failed to create temp dir")?;
+    let trace = trace_dir.path().join(".trace");
+
+    let mut cmd = replay_command(project, build, input, args)?;
+    cmd.env(MOVE_VM_TRACE_ENV, &trace);
+    let output = cmd
+        .output()
+        .with_context(|| format!("failed to run command: {:?}", cmd))?;
+    let outcome = Outcome::from_output(&output);
+
+    // The trace is only created once an instruction runs, inputs rejected before
+    // calling the target don't have one
+    if !trace.exists() {
+        return Ok((outcome, MoveCoverage::new()));
+    }
+    Ok((outcome, coverage_from_trace(&trace)))
+}
+
+/// Merges the coverage maps written in `dir` by the worker processes fuzzing
+/// `target`, returning the instructions covered and the number of maps updated
+/// since `since`.
+pub fn merge_coverage_maps(dir: &Path, target: &Target, since: &time::SystemTime) -> Result<(MoveCoverage, usize)> {
+    let mut coverage = MoveCoverage::new();
+    let mut updated = 0;
+    for (path, map) in target_coverage_maps(dir, target)? {
+        coverage.extend(coverage_from_map(&map));
+        if path.metadata().and_then(|m| m.modified()).map_or(false, |m| m >= *since) {
+            updated += 1;
+        }
+    }
+    Ok((coverage, updated))
+}
+
+/// Removes the coverage maps and traces left in `dir` by the worker
+/// processes of earlier campaigns on `target`.
+pub fn clear_coverage_maps(dir: &Path, target: &Target) -> Result<()> {
+    let prefix = format!("{}-{}-", target.get_module_name(), target.get_target_function());
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read directory entries of {}", dir.display()))? {
+        let path = entry?.path();
+        if path.file_name().map_or(false, |n| n.to_string_lossy().starts_with(&prefix)) {
+            fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Move coverage of the campaigns on a target since its module last changed,
+/// kept by `run --accumulate` in `<module>-<function>.accumulated.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AccumulatedCoverage {
+    /// Hash of the target module the coverage was recorded on
+    pub module_hash: String,
+    pub campaigns: usize,
+    pub coverage: MoveCoverage,
+}
+
+/// Merges the coverage of a campaign into the one accumulated in `dir` for
+/// `target`, starting over if `module` was rebuilt differently since.
+pub fn accumulate_coverage(dir: &Path, target: &Target, module: &Path, coverage: &MoveCoverage) -> Result<AccumulatedCoverage> {
+    let path = dir.join(format!("{}-{}.accumulated.json", target.get_module_name(), target.get_target_function()));
+    let bytes = fs::read(module).with_context(|| format!("failed to read {}", module.display()))?;
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    let module_hash = format!("{:016x}", hasher.finish());
+
+    let mut accumulated = fs::read(&path)
+        .ok()
+        .and_then(|data| serde_json::from_slice::<AccumulatedCoverage>(&data).ok())
+        .unwrap_or_default();
+    if accumulated.module_hash != module_hash {
+        if accumulated.campaigns > 0 {
+            eprintln!("The target module changed, the accumulated Move coverage starts over");
+        }
+        accumulated = AccumulatedCoverage {
+            module_hash,
+            ..Default::default()
+        };
+    }
+    accumulated.campaigns += 1;
+    accumulated.coverage.extend(coverage.iter().cloned());
+    fs::write(&path, serde_json::to_vec(&accumulated)?).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(accumulated)
+}
+
+/// Dictionary of the constants compared in `target` that the campaigns got
+/// stuck on, kept by `run --learn-dict` in `<module>-<function>.learned.dict`.
+pub fn learned_dictionary(dir: &Path, target: &Target) -> PathBuf {
+    dir.join(format!("{}-{}.learned.dict", target.get_module_name(), target.get_target_function()))
+}
+
+/// Adds the entries of the dictionaries written in `dir` by the worker
+/// processes fuzzing `target` to its learned dictionary, returning the number
+/// of entries it holds.
+pub fn learn_dictionary(dir: &Path, target: &Target) -> Result<usize> {
+    let path = learned_dictionary(dir, target);
+    let prefix = format!("{}-{}-", target.get_module_name(), target.get_target_function());
+    let mut entries: BTreeSet<String> = fs::read_to_string(&path)
+        .map(|text| text.lines().map(String::from).collect())
+        .unwrap_or_default();
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read directory entries of {}", dir.display()))? {
+        let worker_path = entry?.path();
+        let name = worker_path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        if name.starts_with(&prefix) && name.ends_with(".dict") {
+            // Written aside then renamed by the worker, never partial
+            let text = fs::read_to_string(&worker_path).with_context(|| format!("failed to read {}", worker_path.display()))?;
+            entries.extend(text.lines().filter(|line| !line.is_empty()).map(String::from));
+        }
+    }
+    if !entries.is_empty() {
+        let text: String = entries.iter().map(|entry| format!("{}\n", entry)).collect();
+        fs::write(&path, text).with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(entries.len())
+}
+
+/// Number of times each Move instruction ran, by fully qualified function
+/// name and bytecode offset.
+pub type HitCounts = BTreeMap<(String, u64), u64>;
+
+/// Sums the hit counts of the coverage maps written in `dir` by the worker
+/// processes fuzzing `target`.
+pub fn merge_hit_counts(dir: &Path, target: &Target) -> Result<HitCounts> {
+    let mut hits = HitCounts::new();
+    for (_, map) in target_coverage_maps(dir, target)? {
+        for ((address, module), module_map) in map.to_unified_exec_map().module_maps {
+            for (function, offsets) in module_map.function_maps {
+                let name = format!("{}::{}::{}", address.to_hex_literal(), module, function);
+                for (offset, count) in offsets {
+                    *hits.entry((name.clone(), offset)).or_default() += count;
+                }
+            }
+        }
+    }
+    Ok(hits)
+}
+
+/// The coverage maps written in `dir` by the worker processes fuzzing
+/// `target`, named `<module>-<function>-<pid>.coverage_map`.
+fn target_coverage_maps(dir: &Path, target: &Target) -> Result<Vec<(PathBuf, CoverageMap)>> {
+    let prefix = format!("{}-{}-", target.get_module_name(), target.get_target_function());
+    let mut maps = vec![];
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read directory entries of {}", dir.display()))? {
+        let path = entry?.path();
+        let is_target_map = path.extension().map_or(false, |e| e == "coverage_map")
+            && path
+                .file_name()
+                .map_or(false, |n| n.to_string_lossy().starts_with(&prefix));
+        if !is_target_map {
+            continue;
+        }
+        let map = CoverageMap::from_binary_file(&path)
+            .with_context(|| format!("failed to read coverage map {}", path.display()))?;
+        maps.push((path, map));
+    }
+    Ok(maps)
+}
+
+/// Disassembles `module`, each instruction preceded by the number of times it
+/// ran according to `hits`, `-` for the ones that never did.
+pub fn annotated_disassembly(module: &CompiledModule, hits: &HitCounts) -> Result<String> {
+    let disassembler = Disassembler::from_module(module, Spanned::unsafe_no_loc(()).loc)?;
+    let text = disassembler.disassemble()?;
+    let id = module.self_id();
+    let prefix = format!("{}::{}", id.address().to_hex_literal(), id.name());
+
+    // Code lines look like `\t3: LdU64(5)`, in the body of the function
+    // declared on the last unindented line ending with `{`
+    let mut function = None;
+    let mut annotated = String::new();
+    for line in text.lines() {
+        let indented = line.starts_with(char::is_whitespace);
+        if !indented && line.ends_with('{') {
+            function = line
+                .split_once('(')
+                .and_then(|(head, _)| head.split_whitespace().last())
+                .map(|name| format!("{}::{}", prefix, name));
+        }
+        let offset = line
+            .trim_start()
+            .split_once(':')
+            .and_then(|(offset, _)| offset.parse::<u64>().ok())
+            .filter(|_| indented);
+        let column = match (&function, offset) {
+            (Some(function), Some(offset)) => match hits.get(&(function.clone(), offset)) {
+                Some(count) => count.to_string(),
+                None => String::from("-"),
+            },
+            _ => String::new(),
+        };
+        annotated.push_str(&format!("{:>10}  {}\n", column, line));
+    }
+    Ok(annotated)
+}
+
+/// Covered and total instructions of each function of `module` defined
+/// with code, in definition order.
+pub fn function_coverage(module: &CompiledModule, coverage: &MoveCoverage) -> Vec<(String, usize, usize)> {
+    let id = module.self_id();
+    module
+        .function_defs()
+        .iter()
+        .filter_map(|def| {
+            let code = def.code.as_ref()?;
+            let name = function_name(module, def);
+            let qualified = format!("{}::{}::{}", id.address().to_hex_literal(), id.name(), name);
+            let covered = coverage
+                .range((qualified.clone(), 0)..=(qualified, u64::MAX))
+                .count();
+            Some((name, covered, code.code.len()))
+        })
+        .collect()
+}
+
+/// Source lines of the covered instructions, as `file.move:line`, read from
+/// the source maps of the package build. Instructions of modules built
+/// without source maps are left out.
+pub fn covered_source_lines(project: &FuzzProject, coverage: &MoveCoverage) -> BTreeSet<String> {
+    let modules_dir = project.get_bytecode_modules_dir();
+    let build_dir = match modules_dir.parent() {
+        Some(dir) => dir.to_path_buf(),
+        None => return BTreeSet::new(),
+    };
+    let files_with = |dir: &Path, ext: &str| {
+        walkdir::WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(move |e| e.path().extension().map_or(false, |e| e == ext))
+            .map(|e| e.into_path())
+            .collect::<Vec<_>>()
+    };
+
+    // Byte offset of the start of each line of the sources, by content hash
+    let sources: HashMap<FileHash, (String, Vec<u32>)> = files_with(&build_dir.join("sources"), "move")
+        .into_iter()
+        .filter_map(|path| {
+            let contents = fs::read_to_string(&path).ok()?;
+            let line_starts = std::iter::once(0)
+                .chain(contents.match_indices('\n').map(|(i, _)| i as u32 + 1))
+                .collect();
+            let name = path.file_name()?.to_string_lossy().into_owned();
+            Some((FileHash::new(&contents), (name, line_starts)))
+        })
+        .collect();
+    // Functions and source map of each module, named as in the coverage
+    let mut modules = HashMap::new();
+    for path in files_with(&modules_dir, "mv") {
+        let module = match load_module(&path) {
+            Ok(module) => module,
+            Err(_) => continue,
+        };
+        let id = module.self_id();
+        let map_path = build_dir
+            .join("source_maps")
+            .join(path.strip_prefix(&modules_dir).unwrap_or(&path))
+            .with_extension("mvsm");
+        if let Ok(map) = source_map_from_file(&map_path) {
+            let functions: HashMap<String, FunctionDefinitionIndex> = module
+                .function_defs()
+                .iter()
+                .enumerate()
+                .map(|(i, def)| (function_name(&module, def), FunctionDefinitionIndex(i as u16)))
+                .collect();
+            modules.insert(format!("{}::{}", id.address().to_hex_literal(), id.name()), (functions, map));
+        }
+    }
+
+    let mut lines = BTreeSet::new();
+    for (name, offset) in coverage {
+        let line = name.rsplit_once("::").and_then(|(module, function)| {
+            let (functions, map) = modules.get(module)?;
+            let loc = map.get_code_location(*functions.get(function)?, *offset as u16).ok()?;
+            let (file, line_starts) = sources.get(&loc.file_hash())?;
+            Some(format!("{}:{}", file, line_starts.partition_point(|&start| start <= loc.start())))
+        });
+        lines.extend(line);
+    }
+    lines
+}
@@ -2,6 +2,7 @@ use std::{env, fs, io::Read, path::{Path, PathBuf}, process::Command};
 
 use anyhow::{bail, Context, Result};
 
+use crate::config::FuzzConfig;
 use crate::project::{FuzzProject, DEFAULT_FUZZ_DIR};
 
 /// The default target to pass to cargo, to workaround issue #11.
@@ -63,6 +64,8 @@ pub fn manage_initial_instance(fuzz_dir_opt: Option<PathBuf>) -> Result<FuzzProj
     Ok(FuzzProject {
         fuzz_dir,
         targets: Vec::new(),
+        config: FuzzConfig::default(),
+        package: None,
     })
 }
 
@@ -89,6 +92,14 @@ pub fn collect_targets(value: &toml::Value) -> Vec<String> {
 }
 
 
+/// Looks for an executable in the directories listed in `PATH`.
+pub fn find_in_path(name: &str) -> Option<PathBuf> {
+    let paths = env::var_os("PATH")?;
+    env::split_paths(&paths)
+        .map(|dir| dir.join(format!("{}{}", name, env::consts::EXE_SUFFIX)))
+        .find(|candidate| candidate.is_file())
+}
+
 pub fn strip_current_dir_prefix(path: &Path) -> &Path {
     env::current_dir()
         .ok()
@@ -39,8 +39,29 @@ fn build_and_link_libfuzzer() {
     }
 }
 
+/// Links the AFL++ driver, calling `LLVMFuzzerInitialize` and
+/// `LLVMFuzzerTestOneInput` from its forkserver, and the runtime recording the
+/// sanitizer coverage for AFL++. Both are looked up in `AFL_PATH`, where
+/// AFL++ installs them.
+fn link_afl_driver() {
+    println!("cargo:rerun-if-env-changed=AFL_PATH");
+    let afl_path = ::std::env::var("AFL_PATH").unwrap_or_else(|_| String::from("/usr/local/lib/afl"));
+    let afl_dir = ::std::path::Path::new(&afl_path);
+    for file in ["libAFLDriver.a", "afl-compiler-rt.o"] {
+        let path = afl_dir.join(file);
+        if !path.is_file() {
+            panic!("could not find {}, set AFL_PATH to the directory AFL++ installed it in", path.display());
+        }
+        println!("cargo:rerun-if-changed={}", path.display());
+        println!("cargo:rustc-link-arg={}", path.display());
+    }
+    println!("cargo:rustc-link-lib=stdc++");
+}
+
 fn main() {
-    if cfg!(feature = "link_libfuzzer") {
+    if cfg!(feature = "afl") {
+        link_afl_driver();
+    } else if cfg!(feature = "link_libfuzzer") {
         build_and_link_libfuzzer();
     }
 }
\ No newline at end of file
@@ -14,10 +14,17 @@
 
 mod move_runner;
 
-use std::sync::Mutex;
-use clap::{ArgGroup, Parser};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
+use clap::{CommandFactory, Parser};
 use once_cell::sync::OnceCell;
-use crate::move_runner::MoveRunner;
+pub use crate::move_runner::{ExecutionHook, ExecutionOutcome, ExecutionStatus, MoveEvent, ReturnValue};
+use crate::move_runner::{
+    random_swarm_seed, AddressWeights, MoveRunner, RunnerConfig, StringGeneration, StructGeneration, TargetVisibility,
+    TypeArgSelection,
+};
 
 /// Indicates whether the input should be kept in the corpus or rejected. This
 /// should be returned by your fuzz target. If your fuzz target does not return
@@ -55,6 +62,62 @@ extern "C" {
     #[allow(improper_ctypes)]
     fn rust_fuzzer_test_input(input: &[u8]) -> i32;
     fn LLVMFuzzerMutate(data: *mut u8, size: usize, max_size: usize) -> usize;
+    fn atexit(callback: extern "C" fn()) -> i32;
+    fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    fn _exit(status: i32) -> !;
+}
+
+const SIGINT: i32 = 2;
+const SIGTERM: i32 = 15;
+
+/// Exit code of libFuzzer when interrupted.
+const INTERRUPT_EXIT_CODE: i32 = 72;
+
+/// Set by the first SIGINT or SIGTERM, the process exits before the next input.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_interrupt(_signum: i32) {
+    // A second signal means the current execution doesn't return
+    if INTERRUPTED.swap(true, Ordering::SeqCst) {
+        unsafe { _exit(INTERRUPT_EXIT_CODE) };
+    }
+}
+
+#[doc(hidden)]
+/// Exits through `exit`, so that the coverage is written, if the process was
+/// interrupted since the last input.
+pub fn exit_if_interrupted() {
+    if INTERRUPTED.load(Ordering::Relaxed) {
+        std::process::exit(INTERRUPT_EXIT_CODE);
+    }
+}
+
+/// Whether this process runs the inputs itself, rather than supervising the
+/// processes that do in libFuzzer's fork and merge modes.
+fn runs_inputs() -> bool {
+    !std::env::args().any(|arg| {
+        ["-fork=", "-jobs=", "-merge=", "-minimize_crash="]
+            .iter()
+            .any(|option| arg.starts_with(option))
+    })
+}
+
+/// Seed given to libFuzzer as `-seed=N`, the last one winning as in
+/// libFuzzer; 0 asks libFuzzer for a random seed and is ignored.
+fn libfuzzer_seed() -> Option<u64> {
+    std::env::args()
+        .filter_map(|arg| arg.strip_prefix("-seed=").and_then(|seed| seed.parse().ok()))
+        .last()
+        .filter(|&seed| seed != 0)
+}
+
+/// The AFL++ driver doesn't provide libFuzzer's mutator, so `fuzzer_mutate`
+/// leaves the input as is and AFL++ mutates it on its own.
+#[cfg(feature = "afl")]
+#[doc(hidden)]
+#[export_name = "LLVMFuzzerMutate"]
+pub extern "C" fn afl_fuzzer_mutate(_data: *mut u8, size: usize, _max_size: usize) -> usize {
+    size
 }
 
 /// Do not use; only for LibFuzzer's consumption.
@@ -80,11 +143,104 @@ pub unsafe fn test_input_wrap(data: *const u8, size: usize) -> i32 {
 pub static MOVE_LIBFUZZER_DEBUG_PATH: OnceCell<String> = OnceCell::new();
 
 #[doc(hidden)]
+/// Output path and test name of the Move unit test to export the input as.
+pub static MOVE_FUZZER_EXPORT_TEST: OnceCell<(String, String)> = OnceCell::new();
+
+#[doc(hidden)]
+/// Output path of the minimized input and number of candidates to try.
+pub static MOVE_FUZZER_MINIMIZE: OnceCell<(String, u32)> = OnceCell::new();
+
+#[doc(hidden)]
+/// Runner set up in initialization, that the runner of each thread is forked from.
 pub static MOVE_RUNNER : OnceCell<Mutex<MoveRunner>> = OnceCell::new();
 
+thread_local! {
+    static THREAD_RUNNER: RefCell<Option<MoveRunner>> = RefCell::new(None);
+}
+
+#[doc(hidden)]
+/// Calls `f` with the runner of the current thread, forking it from
+/// `MOVE_RUNNER` the first time, so that threads don't wait for each other.
+/// Returns `None` if the runner is not initialized.
+pub fn with_runner<R>(f: impl FnOnce(&mut MoveRunner) -> R) -> Option<R> {
+    THREAD_RUNNER.with(|runner| {
+        let mut runner = runner.borrow_mut();
+        if runner.is_none() {
+            let setup = MOVE_RUNNER.get()?.lock().unwrap_or_else(PoisonError::into_inner);
+            *runner = Some(setup.fork());
+        }
+        runner.as_mut().map(f)
+    })
+}
+
+/// Prefix of the line the worker prints when an execution fails, followed by
+/// the error class and its details. The CLI relies on it to classify crashes.
+pub const MOVE_ERROR_PREFIX: &str = "Move error: ";
+
+/// Prefix of the lines the worker prints to detail a failed execution (VM
+/// status, location and stack trace).
+pub const MOVE_REPORT_PREFIX: &str = "Move report: ";
+
+/// Environment variable overriding `--log-level` with `env_logger` directives.
+pub const LOG_ENV: &str = "MOVE_FUZZER_LOG";
+
+/// Target of the startup timings logged at `debug`, enabled alone by the
+/// CLI's `-v` without logging every execution.
+pub const STARTUP_LOG_TARGET: &str = "move_fuzzer::startup";
+
+/// Registers a hook invoked around every execution of the target. Runners
+/// are forked for each thread on its first execution, so hooks must be added
+/// before fuzzing starts to run on every thread.
+///
+/// Returns `false` if the runner is not initialized yet.
+pub fn add_execution_hook(hook: impl ExecutionHook + 'static) -> bool {
+    match MOVE_RUNNER.get() {
+        Some(runner) => {
+            runner.lock().unwrap_or_else(PoisonError::into_inner).add_hook(hook);
+            true
+        }
+        None => false,
+    }
+}
+
+#[doc(hidden)]
+/// Debug formatting of an input: the arguments it decodes to and what the
+/// target produced from them, or the raw bytes if the runner is not initialized.
+pub fn debug_format_input(bytes: &[u8]) -> String {
+    with_runner(|runner| runner.format_arguments(bytes) + &runner.format_outcome(bytes))
+        .unwrap_or_else(|| format!("{:?}\n", bytes))
+}
+
+#[doc(hidden)]
+/// Renders an input as a Move unit test calling the target function.
+pub fn export_move_test(bytes: &[u8], test_name: &str) -> String {
+    with_runner(|runner| runner.move_test(bytes, test_name)).unwrap_or_default()
+}
+
+#[doc(hidden)]
+/// Shrinks the arguments of a crashing input, see `MoveRunner::minimize`.
+pub fn minimize_input(bytes: &[u8], attempts: u32) -> Vec<u8> {
+    with_runner(|runner| runner.minimize(bytes, attempts)).unwrap_or_else(|| bytes.to_vec())
+}
+
+#[doc(hidden)]
+/// Recombines the arguments of two inputs, see `MoveRunner::crossover`.
+pub fn crossover_inputs(data1: &[u8], data2: &[u8], out: &mut [u8], seed: u32) -> usize {
+    let child = with_runner(|runner| runner.crossover(data1, data2, out.len(), seed))
+        .unwrap_or_else(|| data1[..data1.len().min(out.len())].to_vec());
+    out[..child.len()].copy_from_slice(&child);
+    child.len()
+}
+
+#[doc(hidden)]
+/// Mutates an input, its arguments or its raw bytes, see `MoveRunner::mutate`.
+pub fn mutate_input(data: &mut [u8], size: usize, max_size: usize, seed: u32) -> usize {
+    with_runner(|runner| runner.mutate(data, size, max_size, seed, fuzzer_mutate))
+        .unwrap_or_else(|| fuzzer_mutate(data, size, max_size))
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Parser)]
-#[command(allow_hyphen_values = true)]
-/// todo
+/// Options of the worker, given among libFuzzer's own arguments
 pub struct Cli {
     #[clap(long)]
     /// todo
@@ -98,15 +254,218 @@ pub struct Cli {
     /// todo
     pub target_function: String,
 
-    #[clap(allow_hyphen_values = true)]
-    /// todo
-    pub extra: Option<Vec<String>>
+    #[clap(long, value_enum, default_value_t = StructGeneration::Fields)]
+    /// How struct arguments are generated
+    pub struct_generation: StructGeneration,
+
+    #[clap(long, value_enum, default_value_t = StringGeneration::Strings)]
+    /// Which `vector<u8>` and string parameters are generated as valid text
+    pub string_generation: StringGeneration,
+
+    #[clap(long)]
+    /// Generate `Option`, `FixedPoint32` and Sui object values field by field
+    pub raw_std_types: bool,
+
+    #[clap(long, value_enum, default_value_t = TargetVisibility::Any)]
+    /// Functions accepted as the target
+    pub visibility: TargetVisibility,
+
+    #[clap(long, value_enum, default_value_t = TypeArgSelection::None)]
+    /// How the instantiation of a generic target is picked, among those
+    /// with the types of its package and the primitives
+    pub instantiate: TypeArgSelection,
+
+    #[clap(long)]
+    /// Gas available to each execution, unmetered if not set
+    pub gas_limit: Option<u64>,
+
+    #[clap(long = "allow-abort-code")]
+    /// Abort code that is expected and not reported as a crash
+    pub allowed_abort_codes: Vec<u64>,
+
+    #[clap(long = "on-error")]
+    /// Action taken when an execution fails with a class of error, as
+    /// `class=action` (e.g. `out-of-gas=reject`); actions are crash, reject
+    /// and ignore, classes not given are crashes
+    pub error_actions: Vec<String>,
+
+    #[clap(long)]
+    /// Report executions completing with `false`, the target must return `bool`
+    pub oracle_bool: bool,
+
+    #[clap(long)]
+    /// Function without parameters called after each execution, as `module::function`
+    pub invariant: Option<String>,
+
+    #[clap(long = "setup-function")]
+    /// Function called before the target on every input, as `module::function`,
+    /// e.g. `init_module`; its signer parameters are the module's address
+    pub setup_functions: Vec<String>,
+
+    #[clap(long = "fix")]
+    /// Pin a parameter to a constant value instead of fuzzing it, as `argN=value`
+    pub fixed_args: Vec<String>,
+
+    #[clap(long = "signer")]
+    /// Address signers are picked from, as a hex literal or `deployer`
+    pub signers: Vec<String>,
+
+    #[clap(long = "address")]
+    /// Address a named address of the package is published at, as
+    /// `name=0x...`, the modules being moved there from the compiled one
+    pub addresses: Vec<String>,
+
+    #[clap(long, default_value_t = 0)]
+    /// Number of test accounts, numbered from `0x1000`, added to the signer pool
+    pub test_signers: u64,
+
+    #[clap(long)]
+    /// Still generate random signers, besides the ones in the pool
+    pub random_signers: bool,
+
+    #[clap(long, default_value = "")]
+    /// Weights of the addresses generated for `address` parameters, as
+    /// `named=3,core=1,zero=1,random=3`; kinds not given keep these defaults
+    pub address_weights: AddressWeights,
+
+    #[clap(long, default_value_t = 10, value_parser = clap::value_parser!(u8).range(0..=100))]
+    /// Percentage of the integers picked among boundary values (0, 1, MAX,
+    /// powers of two...) instead of uniformly
+    pub special_int_percent: u8,
+
+    #[clap(long)]
+    /// Sample the vector lengths, integer bias and address weights at
+    /// startup, overriding the options above, so that each job differs;
+    /// the profile follows libFuzzer's `-seed=` when given
+    pub swarm: bool,
+
+    #[clap(long)]
+    /// Seed of the swarm profile to use, implies `--swarm`; the seed of a
+    /// sampled profile is printed at startup and with each crash, and
+    /// written to the statistics of the process
+    pub swarm_seed: Option<u64>,
+
+    #[clap(long, conflicts_with = "publish")]
+    /// State machine model, in TOML or JSON, inputs are interpreted with as
+    /// sequences of the calls it allows instead of arguments of the target
+    pub model: Option<PathBuf>,
+
+    #[clap(long)]
+    /// Treat inputs as module bytecode and publish them instead of calling the
+    /// target, fuzzing the VM's deserializer, verifier and linker; seed the
+    /// corpus with compiled modules
+    pub publish: bool,
+
+    #[clap(long)]
+    /// Highest bytecode version the modules are read with, the latest the
+    /// worker supports if not set
+    pub bytecode_version: Option<u32>,
+
+    #[clap(long)]
+    /// Directory the parameter types of the target are cached in, keyed by
+    /// the modules, so that later workers skip building the package model
+    pub abi_cache: Option<PathBuf>,
+
+    #[clap(long, default_value_t = 16)]
+    /// Elements per vector counted in the bound on the input size printed by
+    /// `--describe`, which the CLI passes to libFuzzer as `-max_len`
+    pub vector_budget: usize,
+
+    #[clap(long)]
+    /// Record the Move coverage of the dependencies too, not only of the
+    /// target module
+    pub include_deps: bool,
+
+    #[clap(long, default_value_t = 32)]
+    /// Maximum nesting of the generated vectors, structs and enums, deeper inputs are rejected
+    pub max_input_depth: usize,
+
+    #[clap(long, default_value_t = 100_000)]
+    /// Maximum number of values generated from one input, larger inputs are rejected
+    pub max_input_values: usize,
+
+    #[clap(long)]
+    /// REST API of a node, e.g. `https://fullnode.mainnet.aptoslabs.com/v1`,
+    /// the resources the target reads are fetched from, forking its state
+    pub fork_url: Option<String>,
+
+    #[clap(long, requires = "fork_url")]
+    /// Ledger version the forked state is read at, the latest if not set
+    pub fork_version: Option<u64>,
+
+    #[clap(long, requires = "fork_url")]
+    /// Directory the fetched resources are cached in across runs
+    pub fork_cache: Option<PathBuf>,
+
+    #[clap(long, default_value_t = log::LevelFilter::Info)]
+    /// Most verbose messages logged, `debug` and `trace` log every execution
+    pub log_level: log::LevelFilter,
+
+    #[clap(long)]
+    /// Print how inputs are decoded into the target arguments and exit
+    pub describe: bool,
+
+    #[clap(long)]
+    /// Corpus whose inputs are executed `--bench-runs` times in turn, then
+    /// print the executions per second and the time spent in each phase and exit
+    pub bench: Option<PathBuf>,
+
+    #[clap(long, default_value_t = 10_000, requires = "bench")]
+    /// Number of executions made by `--bench`
+    pub bench_runs: usize,
+
+    #[clap(long, requires = "migrate_corpus")]
+    /// Module of an earlier build of the target, whose inputs are re-encoded
+    /// for the current parameters by `--migrate-corpus`
+    pub migrate_from: Option<String>,
+
+    #[clap(long)]
+    /// Corpus of the earlier build to migrate, then exit; entries are
+    /// rewritten in place unless `--migrate-output` is given. Without
+    /// `--migrate-from`, they are only brought to the current encoding and
+    /// stamped with its version
+    pub migrate_corpus: Option<PathBuf>,
+
+    #[clap(long)]
+    /// Version of the encoding the inputs without a header were written
+    /// with, as recorded for their corpus by the CLI, the current one if not set
+    pub encoding_version: Option<u8>,
+
+    #[clap(long, requires = "migrate_corpus")]
+    /// Directory the migrated entries are written to
+    pub migrate_output: Option<PathBuf>,
+}
+
+/// Keeps the program name and the worker's own `--flag`s among the process
+/// arguments, leaving out libFuzzer's `-flag=value` options and corpus paths.
+/// Flags given as `--flag value` keep their value; unknown ones are kept too,
+/// so that they are reported with the usage.
+fn worker_args(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    let command = Cli::command();
+    let takes_value = |name: &str| {
+        command
+            .get_arguments()
+            .any(|arg| arg.get_long() == Some(name) && arg.get_action().takes_values())
+    };
+    let mut args = args.into_iter();
+    let mut kept: Vec<String> = args.next().into_iter().collect();
+    while let Some(arg) = args.next() {
+        let flag = match arg.strip_prefix("--") {
+            Some(flag) if !flag.is_empty() => flag,
+            _ => continue,
+        };
+        let separate_value = !flag.contains('=') && takes_value(flag);
+        kept.push(arg);
+        if separate_value {
+            kept.extend(args.next());
+        }
+    }
+    kept
 }
 
 #[doc(hidden)]
 #[export_name = "LLVMFuzzerInitialize"]
 pub extern "C" fn initialize(_argc: *const isize, _argv: *const *const *const u8) -> isize {
-    println!("RUST: Initialize {:?} {:?}", _argc, _argv);
     // Registers a panic hook that aborts the process before unwinding.
     // It is useful to abort before unwinding so that the fuzzer will then be
     // able to analyse the process stack frames to tell different bugs appart.
@@ -130,20 +489,184 @@ pub extern "C" fn initialize(_argc: *const isize, _argv: *const *const *const u8
             .expect("Since this is initialize it is only called once so can never fail");
     }
 
-    let cli = Cli::parse();
-    println!("{:?}", cli);
-    MOVE_RUNNER.set(
-        Mutex::new(
-            MoveRunner::new(
-                &cli.module_path.as_str(), 
-                &cli.target_module.as_str(),
-                &cli.target_function.as_str()
-            )
-        )
-    ).expect("Failed to initialize move runner");
+    // Same for `MOVE_FUZZER_EXPORT_TEST_PATH`, used to export the input as a
+    // Move unit test named `MOVE_FUZZER_EXPORT_TEST_NAME`.
+    if let Ok(path) = std::env::var("MOVE_FUZZER_EXPORT_TEST_PATH") {
+        let name = std::env::var("MOVE_FUZZER_EXPORT_TEST_NAME").unwrap_or_else(|_| String::from("regression"));
+        MOVE_FUZZER_EXPORT_TEST
+            .set((path, name))
+            .expect("Since this is initialize it is only called once so can never fail");
+    }
+
+    // And `MOVE_FUZZER_MINIMIZE_PATH`, where the input shrunk in at most
+    // `MOVE_FUZZER_MINIMIZE_RUNS` attempts is written.
+    if let Ok(path) = std::env::var("MOVE_FUZZER_MINIMIZE_PATH") {
+        let runs = std::env::var("MOVE_FUZZER_MINIMIZE_RUNS")
+            .ok()
+            .and_then(|runs| runs.parse().ok())
+            .unwrap_or(255);
+        MOVE_FUZZER_MINIMIZE
+            .set((path, runs))
+            .expect("Since this is initialize it is only called once so can never fail");
+    }
+
+    let coverage_dir = std::env::var("MOVE_FUZZER_COVERAGE_DIR").ok().map(PathBuf::from);
+
+    // Prints the usage and exits on invalid options, `--help` included
+    let cli = Cli::try_parse_from(worker_args(std::env::args())).unwrap_or_else(|e| e.exit());
+    // Logs go to stderr, next to libFuzzer's own output; `MOVE_FUZZER_LOG`
+    // takes `env_logger` directives such as `move_fuzzer=trace`
+    env_logger::Builder::new()
+        .filter_level(cli.log_level)
+        .parse_env(LOG_ENV)
+        .init();
+    log::debug!("{:?}", cli);
+    let config = RunnerConfig {
+        struct_generation: cli.struct_generation,
+        string_generation: cli.string_generation,
+        raw_std_types: cli.raw_std_types,
+        visibility: cli.visibility,
+        instantiate: cli.instantiate,
+        gas_limit: cli.gas_limit,
+        allowed_abort_codes: cli.allowed_abort_codes.clone(),
+        oracle_bool: cli.oracle_bool,
+        invariant: cli.invariant.clone(),
+        setup_functions: cli.setup_functions.clone(),
+        error_actions: cli.error_actions.clone(),
+        fixed_args: cli.fixed_args.clone(),
+        signers: cli.signers.clone(),
+        addresses: cli.addresses.clone(),
+        test_signers: cli.test_signers,
+        random_signers: cli.random_signers,
+        address_weights: cli.address_weights,
+        special_int_percent: cli.special_int_percent,
+        swarm_seed: cli.swarm_seed.or_else(|| cli.swarm.then(|| libfuzzer_seed().unwrap_or_else(random_swarm_seed))),
+        publish: cli.publish,
+        model: cli.model.clone(),
+        bytecode_version: cli.bytecode_version,
+        vector_budget: cli.vector_budget,
+        abi_cache: cli.abi_cache.clone(),
+        max_input_depth: cli.max_input_depth,
+        max_input_values: cli.max_input_values,
+        coverage_dir,
+        include_deps: cli.include_deps,
+        fork_url: cli.fork_url.clone(),
+        fork_version: cli.fork_version,
+        fork_cache: cli.fork_cache.clone(),
+        encoding_version: cli.encoding_version,
+    };
+    let mut runner = MoveRunner::new(
+        &cli.module_path.as_str(), 
+        &cli.target_module.as_str(),
+        &cli.target_function.as_str(),
+        config.clone(),
+    ).unwrap_or_else(|e| exit_on_startup_error(e));
+
+    if cli.describe {
+        print!("{}", runner.describe());
+        std::process::exit(0);
+    }
+
+    if let Some(corpus) = &cli.migrate_corpus {
+        let old = cli.migrate_from.as_ref().map(|old_path| {
+            MoveRunner::new(old_path, &cli.target_module, &cli.target_function, config.clone())
+                .unwrap_or_else(|e| exit_on_startup_error(format!("earlier build: {}", e)))
+        });
+        let output = cli.migrate_output.as_deref().unwrap_or(corpus);
+        let migrated = migrate_corpus(&runner, old.as_ref().unwrap_or(&runner), corpus, output)
+            .unwrap_or_else(|e| exit_on_startup_error(e));
+        log::info!("Migrated {} inputs to {}", migrated, output.display());
+        std::process::exit(0);
+    }
+
+    if let Err(e) = runner.snapshot_setup() {
+        exit_on_startup_error(e);
+    }
+
+    if let Some(corpus) = &cli.bench {
+        let inputs = read_corpus(corpus).unwrap_or_else(|e| exit_on_startup_error(e));
+        if inputs.is_empty() {
+            exit_on_startup_error(format!("The corpus {} is empty", corpus.display()));
+        }
+        print!("{}", runner.bench(&inputs, cli.bench_runs));
+        std::process::exit(0);
+    }
+
+    if cli.oracle_bool && !runner.returns_bool() {
+        exit_on_startup_error(format!("--oracle-bool requires {}::{} to return a single bool", cli.target_module, cli.target_function));
+    }
+
+    // The target's parameters are not generated when publishing modules or following a model
+    if let Some((i, param)) = runner.unsupported_parameter().filter(|_| !cli.publish && cli.model.is_none()) {
+        exit_on_startup_error(format!("Unsupported type for parameter arg{}: {}", i, param));
+    }
+
+    MOVE_RUNNER.set(Mutex::new(runner)).expect("Failed to initialize move runner");
+    // libFuzzer leaves through `exit`, write the coverage of the last executions then
+    unsafe { atexit(flush_coverage_at_exit) };
+    // libFuzzer's own handler leaves through `_Exit` when interrupted, skipping
+    // the above; it isn't installed over this one
+    if config.coverage_dir.is_some() && runs_inputs() {
+        unsafe {
+            signal(SIGINT, on_interrupt);
+            signal(SIGTERM, on_interrupt);
+        }
+    }
     0
 }
 
+/// Reads the inputs of `corpus`, in file name order.
+fn read_corpus(corpus: &Path) -> Result<Vec<Vec<u8>>, String> {
+    let entries = std::fs::read_dir(corpus).map_err(|e| format!("Can't read {}: {}", corpus.display(), e))?;
+    let mut paths = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Can't read {}: {}", corpus.display(), e))?;
+    paths.retain(|path| path.is_file());
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| std::fs::read(path).map_err(|e| format!("Can't read {}: {}", path.display(), e)))
+        .collect()
+}
+
+/// Writes each input of `corpus`, decoded with `old`, re-encoded by `runner`
+/// and stamped with the encoding version under the same file name in `output`. Returns the number of inputs migrated.
+fn migrate_corpus(runner: &MoveRunner, old: &MoveRunner, corpus: &Path, output: &Path) -> Result<usize, String> {
+    std::fs::create_dir_all(output).map_err(|e| format!("Can't create {}: {}", output.display(), e))?;
+    let entries = std::fs::read_dir(corpus).map_err(|e| format!("Can't read {}: {}", corpus.display(), e))?;
+    let mut migrated = 0;
+    for entry in entries {
+        let path = entry.map_err(|e| format!("Can't read {}: {}", corpus.display(), e))?.path();
+        let name = match path.file_name() {
+            Some(name) if path.is_file() => name,
+            _ => continue,
+        };
+        let bytes = std::fs::read(&path).map_err(|e| format!("Can't read {}: {}", path.display(), e))?;
+        let dest = output.join(name);
+        std::fs::write(&dest, runner.migrate(old, &bytes))
+            .map_err(|e| format!("Can't write {}: {}", dest.display(), e))?;
+        migrated += 1;
+    }
+    Ok(migrated)
+}
+
+/// Reports an error found before fuzzing begins and exits, instead of panicking
+/// into an abort that libFuzzer would report as a crash.
+fn exit_on_startup_error(message: String) -> ! {
+    eprintln!("move-fuzzer-worker: error: {}", message);
+    std::process::exit(1)
+}
+
+extern "C" fn flush_coverage_at_exit() {
+    // The coverage is shared by every thread's runner, flushing the first one's is enough
+    if let Some(runner) = MOVE_RUNNER.get() {
+        if let Ok(runner) = runner.try_lock() {
+            runner.flush_coverage();
+        }
+    }
+}
+
 /// Define a fuzz target.
 ///
 /// ## Example
@@ -259,13 +782,27 @@ macro_rules! fuzz_target {
                     use std::io::Write;
                     let mut file = std::fs::File::create(path)
                         .expect("failed to create `MOVE_LIBFUZZER_DEBUG_PATH` file");
-                    writeln!(&mut file, "{:?}", bytes)
+                    write!(&mut file, "{}", $crate::debug_format_input(bytes))
                         .expect("failed to write to `MOVE_LIBFUZZER_DEBUG_PATH` file");
                     return 0;
                 }
 
-                __libfuzzer_sys_run(bytes);
-                0
+                // `MOVE_FUZZER_EXPORT_TEST` is set in initialization too.
+                if let Some((path, name)) = $crate::MOVE_FUZZER_EXPORT_TEST.get() {
+                    std::fs::write(path, $crate::export_move_test(bytes, name))
+                        .expect("failed to write to `MOVE_FUZZER_EXPORT_TEST_PATH` file");
+                    return 0;
+                }
+
+                // `MOVE_FUZZER_MINIMIZE` is set in initialization as well.
+                if let Some((path, runs)) = $crate::MOVE_FUZZER_MINIMIZE.get() {
+                    std::fs::write(path, $crate::minimize_input(bytes, *runs))
+                        .expect("failed to write to `MOVE_FUZZER_MINIMIZE_PATH` file");
+                    return 0;
+                }
+
+                $crate::exit_if_interrupted();
+                __libfuzzer_sys_run(bytes).to_libfuzzer_code()
             }
 
             // Split out the actual fuzzer into a separate function which is
@@ -281,8 +818,8 @@ macro_rules! fuzz_target {
             // ideally help prevent oss-fuzz from deduplicate fuzz bugs across
             // distinct targets accidentally.
             #[inline(never)] 
-            fn __libfuzzer_sys_run($bytes: &[u8]) {
-                $body
+            fn __libfuzzer_sys_run($bytes: &[u8]) -> $crate::Corpus {
+                $crate::Corpus::from($body)
             }
         };
     };
@@ -662,8 +1199,10 @@ macro_rules! fuzz_crossover {
             ) -> usize {
                 $body
             }
+            let new_size = custom_crossover($data1, $data2, $out, $seed);
 
-            custom_crossover($data1, $data2, $out, $seed)
+            // Truncate the new size if it is larger than the max.
+            std::cmp::min(new_size, max_out_size)
         }
     };
 }
\ No newline at end of file
@@ -1,14 +1,28 @@
 #![no_main]
 
-use move_fuzzer::MOVE_RUNNER;
-use move_fuzzer::fuzz_target;
+use move_fuzzer::{fuzz_crossover, fuzz_mutator, fuzz_target, with_runner, Corpus};
 
 fuzz_target!(|bytes: &[u8]| {
     // data generation logic goes here
-    let mut runner = MOVE_RUNNER.get().unwrap().lock().unwrap();
-    let res = (*runner).execute(bytes);
-    if let Err(e) = res {
-        println!("{:?}", e.1);
-        std::process::abort();
+    let res = with_runner(|runner| runner.execute(bytes)).expect("Move runner not initialized");
+    match res {
+        // The input exceeds the generation limits, or the crash policy rejects its error
+        Err((None, _)) => Corpus::Reject,
+        Err(e) => {
+            eprintln!("{}{}", move_fuzzer::MOVE_ERROR_PREFIX, e.1);
+            std::process::abort();
+        }
+        Ok(_) => Corpus::Keep,
     }
 });
+
+// Mutate single arguments as well as bytes, counting which kinds of
+// mutations find new coverage
+fuzz_mutator!(|data: &mut [u8], size: usize, max_size: usize, seed: u32| {
+    move_fuzzer::mutate_input(data, size, max_size, seed)
+});
+
+// Recombine whole arguments rather than raw bytes, so that offspring stay valid
+fuzz_crossover!(|data1: &[u8], data2: &[u8], out: &mut [u8], seed: u32| {
+    move_fuzzer::crossover_inputs(data1, data2, out, seed)
+});
@@ -0,0 +1,67 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use move_binary_format::CompiledModule;
+
+use super::types::{FuzzerType, StringGeneration, StructGeneration};
+use super::utils::generate_abi_from_bin;
+
+/// Parameter types, reference kinds and bytecode length of a function, as
+/// returned by `generate_abi_from_bin`.
+pub type Abi = (Vec<FuzzerType>, Vec<Option<bool>>, usize);
+
+/// File the ABI of the function is cached in, named after every module it
+/// was derived from and the generation settings, so that a rebuild or another
+/// setting never reads a stale one.
+fn cache_path(
+    dir: &Path,
+    modules: &[CompiledModule],
+    module_name: &str,
+    function_name: &str,
+    struct_generation: StructGeneration,
+    string_generation: StringGeneration,
+    raw_std_types: bool,
+) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    modules.hash(&mut hasher);
+    format!("{:?} {:?} {}", struct_generation, string_generation, raw_std_types).hash(&mut hasher);
+    dir.join(format!("{}-{}-{:016x}.json", module_name, function_name, hasher.finish()))
+}
+
+/// `generate_abi_from_bin`, reading the result from `cache_dir` when an
+/// earlier worker stored it there and storing it otherwise. Building the
+/// model of the package is the slowest part of the startup, repeated by each
+/// `-fork` child; the cache is best effort and never fails the startup.
+pub fn cached_abi(
+    cache_dir: Option<&Path>,
+    modules: Vec<CompiledModule>,
+    module_name: &str,
+    function_name: &str,
+    struct_generation: StructGeneration,
+    string_generation: StringGeneration,
+    raw_std_types: bool,
+) -> Result<Abi, String> {
+    let dir = match cache_dir {
+        Some(dir) => dir,
+        None => return generate_abi_from_bin(modules, module_name, function_name, struct_generation, string_generation, raw_std_types),
+    };
+    let path = cache_path(dir, &modules, module_name, function_name, struct_generation, string_generation, raw_std_types);
+    if let Some(abi) = fs::read(&path).ok().and_then(|data| serde_json::from_slice::<Abi>(&data).ok()) {
+        log::debug!("ABI read from {}", path.display());
+        return Ok(abi);
+    }
+
+    let abi = generate_abi_from_bin(modules, module_name, function_name, struct_generation, string_generation, raw_std_types)?;
+    // Written aside then renamed, parallel workers never read a partial file
+    let partial = path.with_extension(format!("{}.tmp", std::process::id()));
+    let stored = fs::create_dir_all(dir)
+        .and_then(|_| fs::write(&partial, serde_json::to_vec(&abi).unwrap_or_default()))
+        .and_then(|_| fs::rename(&partial, &path));
+    if let Err(e) = stored {
+        log::warn!("Failed to cache the ABI in {}: {}", path.display(), e);
+        let _ = fs::remove_file(&partial);
+    }
+    Ok(abi)
+}
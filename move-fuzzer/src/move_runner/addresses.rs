@@ -0,0 +1,225 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use move_binary_format::CompiledModule;
+use move_core_types::account_address::AccountAddress;
+use serde::Deserialize;
+
+/// First address of the test accounts added with `--test-signers`.
+const TEST_ACCOUNTS_START: u64 = 0x1000;
+
+/// Framework addresses, such as the standard library's.
+const CORE_ADDRESSES: [AccountAddress; 2] = [AccountAddress::ONE, AccountAddress::TWO];
+
+/// Addresses a generated value is picked from, each with a weight. Random
+/// addresses are unlikely to own anything or to be checked by the code under
+/// test, so known ones find deeper paths.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddressPool {
+    /// `None` stands for an address read from the input
+    entries: Vec<(Option<AccountAddress>, usize)>,
+}
+
+impl AddressPool {
+    fn push(&mut self, address: Option<AccountAddress>, weight: usize) {
+        if weight > 0 && !self.entries.iter().any(|(a, _)| *a == address) {
+            self.entries.push((address, weight));
+        }
+    }
+
+    /// Number of choices read from the input to pick an address, zero when
+    /// every address is random.
+    pub fn choices(&self) -> usize {
+        if self.entries.iter().all(|(address, _)| address.is_none()) {
+            0
+        } else {
+            self.entries.iter().map(|(_, weight)| weight).sum()
+        }
+    }
+
+    /// Number of distinct addresses picked from, `None` when random ones are
+    /// generated too.
+    pub fn distinct(&self) -> Option<usize> {
+        if self.entries.iter().any(|(address, _)| address.is_none()) {
+            None
+        } else {
+            Some(self.entries.len())
+        }
+    }
+
+    /// The address picked by `choice`, `None` when a random one is generated.
+    pub fn get(&self, choice: usize) -> Option<AccountAddress> {
+        let mut start = 0;
+        for (address, weight) in &self.entries {
+            start += weight;
+            if choice < start {
+                return *address;
+            }
+        }
+        None
+    }
+}
+
+/// Weights of the kinds of addresses generated for `address` parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressWeights {
+    /// Each address named by the loaded packages
+    pub named: usize,
+    /// Each framework address, `0x1` and `0x2`
+    pub core: usize,
+    /// The zero address
+    pub zero: usize,
+    /// Random addresses
+    pub random: usize,
+}
+
+impl Default for AddressWeights {
+    fn default() -> Self {
+        AddressWeights { named: 3, core: 1, zero: 1, random: 3 }
+    }
+}
+
+impl FromStr for AddressWeights {
+    type Err = String;
+
+    /// Parses comma separated `kind=weight` pairs, e.g. `named=4,random=1`.
+    /// Kinds not given keep their default weight.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weights = AddressWeights::default();
+        for pair in s.split(',').filter(|pair| !pair.is_empty()) {
+            let (kind, weight) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid address weight `{}`, expected `kind=weight`", pair))?;
+            let weight = weight
+                .parse()
+                .map_err(|_| format!("Invalid weight `{}` for {} addresses", weight, kind))?;
+            match kind {
+                "named" => weights.named = weight,
+                "core" => weights.core = weight,
+                "zero" => weights.zero = weight,
+                "random" => weights.random = weight,
+                _ => return Err(format!("Unknown address kind `{}`, expected named, core, zero or random", kind)),
+            }
+        }
+        Ok(weights)
+    }
+}
+
+/// Builds the signer pool from addresses given as hex literals, `deployer`
+/// standing for the address the target is published at, followed by
+/// `test_accounts` accounts numbered from `0x1000`.
+pub fn signer_pool(
+    signers: &[String],
+    deployer: AccountAddress,
+    test_accounts: u64,
+    random: bool,
+) -> Result<AddressPool, String> {
+    let mut pool = AddressPool::default();
+    for signer in signers {
+        let address = match signer.as_str() {
+            "deployer" => deployer,
+            literal => AccountAddress::from_hex_literal(literal)
+                .map_err(|_| format!("Invalid signer address `{}`, expected a hex literal such as `0x1`", literal))?,
+        };
+        pool.push(Some(address), 1);
+    }
+    for i in 0..test_accounts {
+        let mut bytes = [0; AccountAddress::LENGTH];
+        bytes[AccountAddress::LENGTH - 8..].copy_from_slice(&(TEST_ACCOUNTS_START + i).to_be_bytes());
+        pool.push(Some(AccountAddress::new(bytes)), 1);
+    }
+    if random {
+        pool.push(None, 1);
+    }
+    Ok(pool)
+}
+
+/// Builds the address book from the addresses the loaded modules refer to,
+/// which include the named addresses of their packages.
+pub fn address_book(modules: &[CompiledModule], weights: AddressWeights) -> AddressPool {
+    let mut pool = AddressPool::default();
+    pool.push(Some(AccountAddress::ZERO), weights.zero);
+    for address in CORE_ADDRESSES {
+        pool.push(Some(address), weights.core);
+    }
+    let named = modules
+        .iter()
+        .flat_map(|module| module.address_identifiers().iter().copied())
+        .filter(|address| *address != AccountAddress::ZERO && !CORE_ADDRESSES.contains(address))
+        .collect::<BTreeSet<_>>();
+    for address in named {
+        pool.push(Some(address), weights.named);
+    }
+    pool.push(None, weights.random);
+    pool
+}
+
+/// The part of a package's `BuildInfo.yaml` telling which address each of
+/// its named addresses was compiled with.
+#[derive(Deserialize)]
+struct BuildInfo {
+    compiled_package_info: CompiledPackageInfo,
+}
+
+#[derive(Deserialize)]
+struct CompiledPackageInfo {
+    address_alias_instantiation: BTreeMap<String, String>,
+}
+
+/// Reads the named addresses of the package whose bytecode is in
+/// `modules_dir`, from the `BuildInfo.yaml` next to it.
+fn compiled_addresses(modules_dir: &Path) -> Result<BTreeMap<String, AccountAddress>, String> {
+    let path = modules_dir
+        .parent()
+        .map(|package| package.join("BuildInfo.yaml"))
+        .ok_or_else(|| format!("No build information for the modules of {}", modules_dir.display()))?;
+    let data = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let info: BuildInfo =
+        serde_yaml::from_slice(&data).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    info.compiled_package_info
+        .address_alias_instantiation
+        .into_iter()
+        .map(|(name, address)| {
+            AccountAddress::from_hex(&address)
+                .map(|address| (name, address))
+                .map_err(|_| format!("Invalid address `{}` in {}", address, path.display()))
+        })
+        .collect()
+}
+
+/// Publishes the modules at the addresses given as `name=0x...`, as the
+/// package would be once deployed: every reference to the address `name`
+/// was compiled with, the modules' own included, is rewritten to the given
+/// one. Names sharing their compiled address, such as several `0x0`
+/// placeholders, are moved together.
+pub fn relocate(modules: &mut [CompiledModule], modules_dir: &Path, assignments: &[String]) -> Result<(), String> {
+    if assignments.is_empty() {
+        return Ok(());
+    }
+    let compiled = compiled_addresses(modules_dir)?;
+    let mut moves = BTreeMap::new();
+    for assignment in assignments {
+        let (name, literal) = assignment
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid address assignment `{}`, expected `name=0x...`", assignment))?;
+        let from = *compiled.get(name).ok_or_else(|| {
+            let names = compiled.keys().cloned().collect::<Vec<_>>().join(", ");
+            format!("Unknown named address `{}`, the package names {}", name, names)
+        })?;
+        let to = AccountAddress::from_hex_literal(literal)
+            .map_err(|_| format!("Invalid address `{}` for `{}`, expected a hex literal such as `0x42`", literal, name))?;
+        if let Some(other) = moves.insert(from, to).filter(|other| *other != to) {
+            return Err(format!("`{}` is compiled at {}, which is already moved to {}", name, from.to_hex_literal(), other.to_hex_literal()));
+        }
+    }
+    for module in modules {
+        for address in module.address_identifiers.iter_mut() {
+            if let Some(to) = moves.get(address) {
+                *address = *to;
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,42 +1,147 @@
+use std::collections::BTreeMap;
 use std::mem;
 
 use arbitrary::{Unstructured, Arbitrary, Result as ArbitraryResult};
 
 use move_core_types::account_address::{AccountAddress, AccountAddressParseError};
-use move_core_types::runtime_value::{MoveStruct, MoveValue};
+use move_core_types::runtime_value::{MoveStruct, MoveValue, MoveVariant};
 use move_core_types::u256::U256 as MoveU256;
 
-use super::types::{FuzzerType, Error};
+use super::addresses::AddressPool;
+use super::buffers::ArgBuffers;
+use super::integers::SpecialInts;
+use super::sui_objects::fresh_uid;
+use super::types::{Constructor, Error, FuzzerType, TextEncoding};
 
-struct ArbitraryIter<'a, 'b> {
+/// Builds struct values by calling one of their public constructors.
+pub trait ValueConstructor {
+    /// Calls `constructor` with the given arguments and returns the built value
+    fn construct(&mut self, constructor: &Constructor, args: Vec<MoveValue>) -> Result<MoveValue, Error>;
+}
+
+/// Settings shared by the generation of every value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputConfig {
+    /// Addresses signer arguments are picked from
+    pub signers: AddressPool,
+    /// Addresses address arguments are picked from
+    pub addresses: AddressPool,
+    /// Boundary values integers are biased toward
+    pub special_ints: SpecialInts,
+    /// Bounds on the values generated from one input
+    pub limits: InputLimits,
+    /// Even continuation bytes whose upper bits are below this end vectors and
+    /// texts, from 1 to 128: the default of 128 ends them on every even byte
+    pub vector_end: u8,
+}
+
+/// Reads whether a vector or text gets one more element. A byte of 1 always
+/// continues and a byte of 0 always stops, whatever `vector_end` is.
+pub fn keep_going(u: &mut Unstructured, config: &InputConfig) -> bool {
+    let byte: u8 = u.arbitrary().unwrap_or(0);
+    byte & 1 == 1 || (byte >> 1) >= config.vector_end
+}
+
+/// Bounds on the values generated from one input, so that deeply nested or
+/// huge arguments reject the input instead of exhausting the worker's memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputLimits {
+    /// Nesting of vectors, structs and enums
+    pub max_depth: usize,
+    /// Values generated in total, counting every vector element and field
+    pub max_values: usize,
+}
+
+/// What the input generated so far consumed of the limits.
+#[derive(Default)]
+struct Budget {
+    depth: usize,
+    values: usize,
+    /// Fresh object ids handed out
+    uids: u64,
+}
+
+struct ArbitraryIter<'a, 'b, 'c> {
     u: &'b mut Unstructured<'a>,
-    t: FuzzerType
+    t: FuzzerType,
+    config: &'c InputConfig,
+    budget: &'c mut Budget,
+    constructor: &'c mut dyn ValueConstructor,
 }
 
-impl<'a, 'b> Iterator for ArbitraryIter<'a, 'b> {
+impl<'a, 'b, 'c> Iterator for ArbitraryIter<'a, 'b, 'c> {
     type Item = ArbitraryResult<Result<MoveValue, Error>>;
     fn next(&mut self) -> Option<ArbitraryResult<Result<MoveValue, Error>>> {
-        let keep_going = self.u.arbitrary().unwrap_or(false);
-        if keep_going {
-            Some(arbitrary_input(self.t.clone(), self.u))
+        if keep_going(self.u, self.config) {
+            Some(arbitrary_input(self.t.clone(), self.u, self.config, self.budget, self.constructor))
         } else {
             None
         }
     }
 }
 
-fn arbitrary_iter<'a, 'b>(u: &'b mut Unstructured<'a>, fuzzer_type: FuzzerType) -> ArbitraryResult<ArbitraryIter<'a, 'b>> {
+fn arbitrary_iter<'a, 'b, 'c>(u: &'b mut Unstructured<'a>, fuzzer_type: FuzzerType, config: &'c InputConfig, budget: &'c mut Budget, constructor: &'c mut dyn ValueConstructor) -> ArbitraryResult<ArbitraryIter<'a, 'b, 'c>> {
     Ok(ArbitraryIter {
         u,
         t: fuzzer_type,
+        config,
+        budget,
+        constructor,
     })
 }
 
-fn arbitrary_vec<'a, 'b>(u: &'b mut Unstructured<'a>, fuzzer_type: FuzzerType) -> ArbitraryResult<Result<MoveValue, Error>> {
-    Ok(Ok(MoveValue::Vector(arbitrary_iter(u, fuzzer_type)?.map(|x| x.unwrap().unwrap()).collect()))) // todo: capire se si possono levare gli unwrap
+fn arbitrary_vec<'a, 'b>(u: &'b mut Unstructured<'a>, fuzzer_type: FuzzerType, config: &InputConfig, budget: &mut Budget, constructor: &mut dyn ValueConstructor) -> ArbitraryResult<Result<MoveValue, Error>> {
+    let mut values = vec![];
+    for value in arbitrary_iter(u, fuzzer_type, config, budget, constructor)? {
+        match value? {
+            Ok(v) => values.push(v),
+            Err(e) => return Ok(Err(e)),
+        }
+    }
+    Ok(Ok(MoveValue::Vector(values)))
+}
+
+fn arbitrary_struct(u: &mut Unstructured, fields: Vec<FuzzerType>, config: &InputConfig, budget: &mut Budget, constructor: &mut dyn ValueConstructor) -> ArbitraryResult<Result<MoveValue, Error>> {
+    Ok(generate_all(fields, u, config, budget, constructor).map(|values| MoveValue::Struct(MoveStruct(values))))
+}
+
+fn arbitrary_variant(u: &mut Unstructured, variants: Vec<Vec<FuzzerType>>, config: &InputConfig, budget: &mut Budget, constructor: &mut dyn ValueConstructor) -> ArbitraryResult<Result<MoveValue, Error>> {
+    let tag = u.choose_index(variants.len())?;
+    let fields = variants[tag].clone();
+    Ok(generate_all(fields, u, config, budget, constructor).map(|fields| MoveValue::Variant(MoveVariant { tag: tag as u16, fields })))
 }
 
-fn arbitrary_u256(u: &mut Unstructured) -> ArbitraryResult<MoveU256> {
+fn arbitrary_constructed(u: &mut Unstructured, target: Constructor, config: &InputConfig, budget: &mut Budget, constructor: &mut dyn ValueConstructor) -> ArbitraryResult<Result<MoveValue, Error>> {
+    let args = match generate_all(target.args.clone(), u, config, budget, constructor) {
+        Ok(args) => args,
+        Err(e) => return Ok(Err(e)),
+    };
+    Ok(constructor.construct(&target, args))
+}
+
+/// Generates a vector of bytes holding valid text, one character at a time.
+fn arbitrary_text(u: &mut Unstructured, encoding: TextEncoding, config: &InputConfig) -> ArbitraryResult<Result<MoveValue, Error>> {
+    let mut text = String::new();
+    while keep_going(u, config) {
+        text.push(encoding.decode(u)?);
+    }
+    Ok(Ok(MoveValue::vector_u8(text.into_bytes())))
+}
+
+/// Generates an unsigned integer, either uniformly or among the special values of its type.
+fn arbitrary_int<T: for<'a> Arbitrary<'a> + Into<u128>>(u: &mut Unstructured, config: &InputConfig) -> ArbitraryResult<u128> {
+    let values = config.special_ints.values(mem::size_of::<T>() as u32 * 8);
+    match config.special_ints.pick(u, values.len())? {
+        Some(i) => Ok(values[i]),
+        None => Ok(T::arbitrary(u)?.into()),
+    }
+}
+
+fn arbitrary_u256(u: &mut Unstructured, config: &InputConfig) -> ArbitraryResult<MoveU256> {
+    let values = config.special_ints.u256_values();
+    if let Some(i) = config.special_ints.pick(u, values.len())? {
+        return Ok(values[i]);
+    }
     let mut buf = [0; mem::size_of::<MoveU256>()];
     u.fill_buffer(&mut buf)?;
     Ok(MoveU256::from_le_bytes(&buf))
@@ -48,54 +153,231 @@ fn arbitrary_account(u: &mut Unstructured) -> ArbitraryResult<Result<AccountAddr
     Ok(AccountAddress::from_bytes(&buf))
 }
 
-fn arbitrary_address(u: &mut Unstructured) -> ArbitraryResult<Result<MoveValue, Error>> {
-    let res = match arbitrary_account(u)? {
+/// Picks an address from `pool`, or generates a random one when the pool is
+/// empty or one of its random entries is chosen.
+fn arbitrary_pooled(u: &mut Unstructured, pool: &AddressPool) -> ArbitraryResult<Result<AccountAddress, AccountAddressParseError>> {
+    if pool.choices() > 0 {
+        if let Some(account) = pool.get(u.choose_index(pool.choices())?) {
+            return Ok(Ok(account));
+        }
+    }
+    arbitrary_account(u)
+}
+
+fn arbitrary_address(u: &mut Unstructured, config: &InputConfig) -> ArbitraryResult<Result<MoveValue, Error>> {
+    let res = match arbitrary_pooled(u, &config.addresses)? {
         Ok(account) => Ok(MoveValue::Address(account)),
         Err(e) => Err(Error::AccountAddressParseError { message: e.to_string() }),
     };
     Ok(res)
 }
 
-fn arbitrary_signer(u: &mut Unstructured) -> ArbitraryResult<Result<MoveValue, Error>> {
-    let res = match arbitrary_account(u)? {
+fn arbitrary_signer(u: &mut Unstructured, config: &InputConfig) -> ArbitraryResult<Result<MoveValue, Error>> {
+    let res = match arbitrary_pooled(u, &config.signers)? {
         Ok(account) => Ok(MoveValue::Signer(account)),
         Err(e) => Err(Error::AccountAddressParseError { message: e.to_string() }),
     };
     Ok(res)
 }
 
-fn arbitrary_input(input: FuzzerType, data: &mut arbitrary::Unstructured) -> ArbitraryResult<Result<MoveValue, Error>> {
+/// Picks the accounts of `count` signer parameters from the signer pool,
+/// reading them from the input the way other signer values are.
+pub fn arbitrary_signers(data: &mut Unstructured, config: &InputConfig, count: usize) -> Result<Vec<MoveValue>, Error> {
+    (0..count)
+        .map(|_| match arbitrary_signer(data, config) {
+            Ok(signer) => signer,
+            Err(e) => Err(Error::Unknown { message: format!("Input exhausted: {}", e) }),
+        })
+        .collect()
+}
+
+fn arbitrary_input(input: FuzzerType, data: &mut arbitrary::Unstructured, config: &InputConfig, budget: &mut Budget, constructor: &mut dyn ValueConstructor) -> ArbitraryResult<Result<MoveValue, Error>> {
+    budget.values += 1;
+    if budget.values > config.limits.max_values {
+        let message = format!("more than {} values generated", config.limits.max_values);
+        return Ok(Err(Error::InputLimitExceeded { message }));
+    }
+    let nested = matches!(
+        input,
+        FuzzerType::Vector(_) | FuzzerType::Struct(_) | FuzzerType::Constructor(_) | FuzzerType::Enum(_) | FuzzerType::Option(_)
+    );
+    if nested {
+        if budget.depth == config.limits.max_depth {
+            let message = format!("values nested more than {} levels deep", config.limits.max_depth);
+            return Ok(Err(Error::InputLimitExceeded { message }));
+        }
+        budget.depth += 1;
+    }
+    let value = generate(input, data, config, budget, constructor);
+    if nested {
+        budget.depth -= 1;
+    }
+    value
+}
+
+fn generate(input: FuzzerType, data: &mut arbitrary::Unstructured, config: &InputConfig, budget: &mut Budget, constructor: &mut dyn ValueConstructor) -> ArbitraryResult<Result<MoveValue, Error>> {
     match input {
         FuzzerType::Bool => Ok(Ok(MoveValue::Bool(<bool as Arbitrary>::arbitrary(data)?))),
-        FuzzerType::U8 => Ok(Ok(MoveValue::U8(<u8 as Arbitrary>::arbitrary(data)?))),
-        FuzzerType::U16 => Ok(Ok(MoveValue::U16(<u16 as Arbitrary>::arbitrary(data)?))),
-        FuzzerType::U32 => Ok(Ok(MoveValue::U32(<u32 as Arbitrary>::arbitrary(data)?))),
-        FuzzerType::U64 => Ok(Ok(MoveValue::U64(<u64 as Arbitrary>::arbitrary(data)?))),
-        FuzzerType::U128 => Ok(Ok(MoveValue::U128(<u128 as Arbitrary>::arbitrary(data)?))),
-        FuzzerType::U256 => Ok(Ok(MoveValue::U256(arbitrary_u256(data)?))),
-        FuzzerType::Vector(t) => Ok(arbitrary_vec(data, *t)?),
-        FuzzerType::Struct(values) => Ok(Ok(MoveValue::Struct(MoveStruct(arbitrary_inputs(values, data))))),
-        FuzzerType::Address => Ok(arbitrary_address(data)?),
-        FuzzerType::Signer => Ok(arbitrary_signer(data)?),
+        FuzzerType::U8 => Ok(Ok(MoveValue::U8(arbitrary_int::<u8>(data, config)? as u8))),
+        FuzzerType::U16 => Ok(Ok(MoveValue::U16(arbitrary_int::<u16>(data, config)? as u16))),
+        FuzzerType::U32 => Ok(Ok(MoveValue::U32(arbitrary_int::<u32>(data, config)? as u32))),
+        FuzzerType::U64 => Ok(Ok(MoveValue::U64(arbitrary_int::<u64>(data, config)? as u64))),
+        FuzzerType::U128 => Ok(Ok(MoveValue::U128(arbitrary_int::<u128>(data, config)?))),
+        FuzzerType::U256 => Ok(Ok(MoveValue::U256(arbitrary_u256(data, config)?))),
+        FuzzerType::Vector(t) => Ok(arbitrary_vec(data, *t, config, budget, constructor)?),
+        FuzzerType::Text(encoding) => Ok(arbitrary_text(data, encoding, config)?),
+        FuzzerType::Struct(values) => Ok(arbitrary_struct(data, values, config, budget, constructor)?),
+        FuzzerType::Constructor(target) => Ok(arbitrary_constructed(data, target, config, budget, constructor)?),
+        FuzzerType::Enum(variants) => Ok(arbitrary_variant(data, variants, config, budget, constructor)?),
+        FuzzerType::Unsupported(t) => Ok(Err(Error::Unknown { message: format!("Unsupported parameter type {}", t) })),
+        FuzzerType::Address => Ok(arbitrary_address(data, config)?),
+        FuzzerType::Signer => Ok(arbitrary_signer(data, config)?),
+        FuzzerType::Uid => {
+            budget.uids += 1;
+            Ok(Ok(fresh_uid(budget.uids - 1)))
+        }
+        std_type @ (FuzzerType::Option(_) | FuzzerType::FixedPoint32 | FuzzerType::TxContext | FuzzerType::Clock) => {
+            let value = generate(std_type.decoded_as(), data, config, budget, constructor)?;
+            Ok(value.map(|value| std_type.std_value(value)))
+        }
     }
 }
 
-/// todo
-pub fn arbitrary_inputs(inputs: Vec<FuzzerType>, data: &mut arbitrary::Unstructured) -> Vec<MoveValue> {
+/// Generates one value per input type. Values that can't be built (e.g. a
+/// constructor aborted) or that exceed the limits make the whole input invalid.
+pub fn arbitrary_inputs(inputs: Vec<FuzzerType>, data: &mut arbitrary::Unstructured, config: &InputConfig, constructor: &mut dyn ValueConstructor) -> Result<Vec<MoveValue>, Error> {
+    generate_all(inputs, data, config, &mut Budget::default(), constructor)
+}
+
+fn generate_all(inputs: Vec<FuzzerType>, data: &mut arbitrary::Unstructured, config: &InputConfig, budget: &mut Budget, constructor: &mut dyn ValueConstructor) -> Result<Vec<MoveValue>, Error> {
     let mut res = vec![];
     for input in inputs {
-        let arbitrary_result = arbitrary_input(input, data);
+        let arbitrary_result = arbitrary_input(input, data, config, budget, constructor);
         match arbitrary_result {
             Ok(parse_result) => {
                 match parse_result {
                     Ok(value) => res.push(value),
-                    Err(e) => eprintln!("{}", e), // todo: abort or not?
+                    Err(e) => {
+                        log::debug!("Invalid input: {}", e);
+                        return Err(e);
+                    }
                 }
             }
-            Err(e) => eprintln!("{}", e),
+            Err(e) => log::debug!("Input exhausted: {}", e),
         }
     }
-    println!("{:?}", res);
-    res
+    log::trace!("Generated arguments: {:?}", res);
+    Ok(res)
+}
+
+
+/// A parameter of a signature handled by `PrimitivePlan`.
+#[derive(Debug, Clone)]
+enum PrimitiveSlot {
+    /// Pinned with `--fix`, serialized once
+    Fixed(MoveValue, Vec<u8>),
+    Generated(FuzzerType),
+}
+
+/// Generation of the arguments of a signature made only of booleans,
+/// integers, addresses and signers, once fixed parameters are set aside. Their
+/// BCS encoding is written as they are generated, skipping the generic
+/// generation and `serialize_values`.
+#[derive(Debug, Clone)]
+pub struct PrimitivePlan {
+    slots: Vec<PrimitiveSlot>,
 }
 
+impl PrimitivePlan {
+    /// `None` unless every parameter not in `fixed` is a fixed-size primitive.
+    pub fn new(params: &[FuzzerType], fixed: &BTreeMap<usize, MoveValue>) -> Option<Self> {
+        let slots = params
+            .iter()
+            .enumerate()
+            .map(|(i, param)| match (fixed.get(&i), param) {
+                (Some(value), _) => Some(PrimitiveSlot::Fixed(value.clone(), value.simple_serialize()?)),
+                (
+                    None,
+                    FuzzerType::Bool
+                    | FuzzerType::U8
+                    | FuzzerType::U16
+                    | FuzzerType::U32
+                    | FuzzerType::U64
+                    | FuzzerType::U128
+                    | FuzzerType::U256
+                    | FuzzerType::Address
+                    | FuzzerType::Signer,
+                ) => Some(PrimitiveSlot::Generated(param.clone())),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(PrimitivePlan { slots })
+    }
+
+    /// Generates the arguments, fixed ones included, as `arbitrary_inputs`
+    /// and `splice_fixed_args` would, serializing them in `buffers`.
+    /// `None` when the input runs out, for the generic generation to handle.
+    pub fn generate(&self, u: &mut Unstructured, config: &InputConfig, buffers: &mut ArgBuffers) -> Option<Vec<MoveValue>> {
+        let mut values = Vec::with_capacity(self.slots.len());
+        for slot in &self.slots {
+            let t = match slot {
+                PrimitiveSlot::Fixed(value, bytes) => {
+                    values.push(value.clone());
+                    buffers.next().extend_from_slice(bytes);
+                    continue;
+                }
+                PrimitiveSlot::Generated(t) => t,
+            };
+            let value = match t {
+                FuzzerType::Bool => {
+                    let b = <bool as Arbitrary>::arbitrary(u).ok()?;
+                    buffers.next().push(b as u8);
+                    MoveValue::Bool(b)
+                }
+                FuzzerType::U8 => {
+                    let n = arbitrary_int::<u8>(u, config).ok()? as u8;
+                    buffers.next().extend_from_slice(&n.to_le_bytes());
+                    MoveValue::U8(n)
+                }
+                FuzzerType::U16 => {
+                    let n = arbitrary_int::<u16>(u, config).ok()? as u16;
+                    buffers.next().extend_from_slice(&n.to_le_bytes());
+                    MoveValue::U16(n)
+                }
+                FuzzerType::U32 => {
+                    let n = arbitrary_int::<u32>(u, config).ok()? as u32;
+                    buffers.next().extend_from_slice(&n.to_le_bytes());
+                    MoveValue::U32(n)
+                }
+                FuzzerType::U64 => {
+                    let n = arbitrary_int::<u64>(u, config).ok()? as u64;
+                    buffers.next().extend_from_slice(&n.to_le_bytes());
+                    MoveValue::U64(n)
+                }
+                FuzzerType::U128 => {
+                    let n = arbitrary_int::<u128>(u, config).ok()?;
+                    buffers.next().extend_from_slice(&n.to_le_bytes());
+                    MoveValue::U128(n)
+                }
+                FuzzerType::U256 => {
+                    let n = arbitrary_u256(u, config).ok()?;
+                    buffers.next().extend_from_slice(&n.to_le_bytes());
+                    MoveValue::U256(n)
+                }
+                FuzzerType::Address => {
+                    let account = arbitrary_pooled(u, &config.addresses).ok()?.ok()?;
+                    buffers.next().extend_from_slice(account.as_ref());
+                    MoveValue::Address(account)
+                }
+                FuzzerType::Signer => {
+                    let account = arbitrary_pooled(u, &config.signers).ok()?.ok()?;
+                    buffers.next().extend_from_slice(account.as_ref());
+                    MoveValue::Signer(account)
+                }
+                _ => return None,
+            };
+            values.push(value);
+        }
+        Some(values)
+    }
+}
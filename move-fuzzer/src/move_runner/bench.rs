@@ -0,0 +1,56 @@
+use std::fmt::{Display, Formatter};
+use std::ops::AddAssign;
+use std::time::Duration;
+
+/// Time spent in each phase of the executions of the target.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PhaseTimings {
+    /// Generating the arguments from the input, struct constructors included
+    pub decode: Duration,
+    /// Serializing the arguments for the VM
+    pub serialize: Duration,
+    /// Executing the target in the VM
+    pub vm: Duration,
+}
+
+impl AddAssign for PhaseTimings {
+    fn add_assign(&mut self, other: Self) {
+        self.decode += other.decode;
+        self.serialize += other.serialize;
+        self.vm += other.vm;
+    }
+}
+
+/// Result of `MoveRunner::bench`, printed by the worker's `--bench`.
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    pub executions: usize,
+    pub elapsed: Duration,
+    /// Phases of the executions that called the target; sequences and
+    /// published modules are only counted in `elapsed`
+    pub timings: PhaseTimings,
+}
+
+impl BenchReport {
+    pub fn execs_per_sec(&self) -> f64 {
+        self.executions as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+impl Display for BenchReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let per_exec = |d: Duration| d.as_secs_f64() * 1e6 / self.executions.max(1) as f64;
+        let share = |d: Duration| 100.0 * d.as_secs_f64() / self.elapsed.as_secs_f64().max(f64::EPSILON);
+        writeln!(f, "Executions: {}", self.executions)?;
+        writeln!(f, "Elapsed: {:.3}s", self.elapsed.as_secs_f64())?;
+        writeln!(f, "Exec/s: {:.1}", self.execs_per_sec())?;
+        for (name, d) in [
+            ("Decode", self.timings.decode),
+            ("Serialize", self.timings.serialize),
+            ("VM", self.timings.vm),
+        ] {
+            writeln!(f, "{}: {:.2}us/exec ({:.1}%)", name, per_exec(d), share(d))?;
+        }
+        Ok(())
+    }
+}
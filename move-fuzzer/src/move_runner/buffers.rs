@@ -0,0 +1,50 @@
+use move_core_types::runtime_value::MoveValue;
+
+/// Serialized arguments of an execution, kept by the runner so that the next
+/// executions write into the same allocations instead of fresh ones.
+#[derive(Debug, Default)]
+pub struct ArgBuffers {
+    buffers: Vec<Vec<u8>>,
+    /// Buffers holding arguments of the current execution
+    len: usize,
+}
+
+impl ArgBuffers {
+    /// Forgets the arguments of the previous execution, keeping the buffers.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// An empty buffer for the next argument.
+    pub fn next(&mut self) -> &mut Vec<u8> {
+        if self.len == self.buffers.len() {
+            self.buffers.push(vec![]);
+        }
+        let buffer = &mut self.buffers[self.len];
+        buffer.clear();
+        self.len += 1;
+        buffer
+    }
+
+    /// Appends the BCS encoding of each of `values`.
+    pub fn serialize_all(&mut self, values: &[MoveValue]) {
+        for value in values {
+            bcs::serialize_into(self.next(), value).expect("MoveValue should serialize");
+        }
+    }
+
+    /// Number of arguments of the current execution.
+    pub fn count(&self) -> usize {
+        self.len
+    }
+
+    /// Serialized size of the arguments of the current execution.
+    pub fn total_bytes(&self) -> usize {
+        self.buffers[..self.len].iter().map(Vec::len).sum()
+    }
+
+    /// The arguments of the current execution, as the VM takes them.
+    pub fn args(&self) -> Vec<&[u8]> {
+        self.buffers[..self.len].iter().map(Vec::as_slice).collect()
+    }
+}
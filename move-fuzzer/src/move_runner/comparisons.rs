@@ -0,0 +1,157 @@
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+
+use move_binary_format::file_format::{Bytecode, CodeOffset, SignatureToken};
+use move_binary_format::CompiledModule;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::identifier::Identifier;
+use move_coverage::coverage_map::ExecCoverageMap;
+
+use super::arbitrary_inputs::InputConfig;
+
+/// Selector byte `SpecialInts::pick` always reads as a uniform integer.
+const UNIFORM_SELECTOR: u8 = u8::MAX;
+
+/// A comparison of a value against a constant deciding a branch, such as the
+/// `x == MAGIC` of an `assert!`.
+#[derive(Debug)]
+struct ComparisonSite {
+    address: AccountAddress,
+    module: Identifier,
+    function: Identifier,
+    /// Offset of the comparison
+    offset: CodeOffset,
+    /// Offsets the branch following it goes to
+    successors: [CodeOffset; 2],
+    /// Encodings of the constant in an input
+    words: Vec<Vec<u8>>,
+}
+
+/// Bytes of the constant `instruction` loads, in the input encoding: integers
+/// and addresses little-endian, `vector<u8>` as its elements each after a
+/// continuation byte.
+fn constant_bytes(module: &CompiledModule, instruction: &Bytecode) -> Option<(Vec<u8>, bool)> {
+    let int = |bytes: &[u8]| Some((bytes.to_vec(), true));
+    match instruction {
+        Bytecode::LdU8(n) => int(&n.to_le_bytes()),
+        Bytecode::LdU16(n) => int(&n.to_le_bytes()),
+        Bytecode::LdU32(n) => int(&n.to_le_bytes()),
+        Bytecode::LdU64(n) => int(&n.to_le_bytes()),
+        Bytecode::LdU128(n) => int(&n.to_le_bytes()),
+        Bytecode::LdU256(n) => int(&n.to_le_bytes()),
+        Bytecode::LdConst(idx) => {
+            let constant = module.constant_pool().get(idx.0 as usize)?;
+            match &constant.type_ {
+                // BCS writes integers little-endian
+                SignatureToken::U8
+                | SignatureToken::U16
+                | SignatureToken::U32
+                | SignatureToken::U64
+                | SignatureToken::U128
+                | SignatureToken::U256 => int(&constant.data),
+                SignatureToken::Address => Some((constant.data.clone(), false)),
+                SignatureToken::Vector(inner) if **inner == SignatureToken::U8 => {
+                    let bytes: Vec<u8> = bcs::from_bytes(&constant.data).ok()?;
+                    let mut encoded: Vec<u8> = bytes.iter().flat_map(|b| [1, *b]).collect();
+                    encoded.push(0);
+                    Some((encoded, false))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Comparisons against constants in `modules` whose constants are worth
+/// trying in inputs once the fuzzer reaches them without getting past them,
+/// the way libFuzzer learns the operands of failing comparisons in native
+/// code. The Move VM doesn't expose the operands, so only the constant side
+/// of a comparison, known from the bytecode, is learned.
+#[derive(Debug, Default)]
+pub struct ComparisonSites {
+    sites: Vec<ComparisonSite>,
+}
+
+impl ComparisonSites {
+    /// Finds the comparisons of `modules` followed by a branch, one of their
+    /// operands being loaded right before them from a constant. Integers are
+    /// also encoded after the selector byte picking a uniform value when
+    /// `config` biases them toward special values.
+    pub fn load(modules: &[CompiledModule], config: &InputConfig) -> Self {
+        let mut sites = vec![];
+        for module in modules {
+            for def in module.function_defs() {
+                let code = match &def.code {
+                    Some(code) => &code.code,
+                    None => continue,
+                };
+                let function = module.identifier_at(module.function_handle_at(def.function).name);
+                for (i, window) in code.windows(4).enumerate() {
+                    let (offset, target) = match (&window[2], &window[3]) {
+                        (
+                            Bytecode::Eq | Bytecode::Neq | Bytecode::Lt | Bytecode::Gt | Bytecode::Le | Bytecode::Ge,
+                            Bytecode::BrTrue(target) | Bytecode::BrFalse(target),
+                        ) => ((i + 2) as CodeOffset, *target),
+                        _ => continue,
+                    };
+                    // A constant compared with a constant decides nothing
+                    let (bytes, is_int) = match (constant_bytes(module, &window[0]), constant_bytes(module, &window[1])) {
+                        (Some(constant), None) | (None, Some(constant)) => constant,
+                        _ => continue,
+                    };
+                    let mut words = vec![];
+                    if is_int && config.special_ints.enabled() {
+                        words.push([&[UNIFORM_SELECTOR][..], &bytes].concat());
+                    }
+                    words.push(bytes);
+                    sites.push(ComparisonSite {
+                        address: *module.address(),
+                        module: module.name().to_owned(),
+                        function: function.to_owned(),
+                        offset,
+                        successors: [target, offset + 2],
+                        words,
+                    });
+                }
+            }
+        }
+        ComparisonSites { sites }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sites.is_empty()
+    }
+
+    /// The constants of the comparisons `coverage` reached while missing one
+    /// of the branches they decide, as a libFuzzer dictionary.
+    pub fn dictionary(&self, coverage: &ExecCoverageMap) -> String {
+        let covered = |site: &ComparisonSite, offset: CodeOffset| {
+            coverage
+                .module_maps
+                .get(&(site.address, site.module.clone()))
+                .and_then(|module| module.function_maps.get(&site.function))
+                .map_or(false, |offsets| offsets.contains_key(&(offset as u64)))
+        };
+        let words: BTreeSet<&Vec<u8>> = self
+            .sites
+            .iter()
+            .filter(|site| covered(site, site.offset) && !site.successors.iter().all(|s| covered(site, *s)))
+            .flat_map(|site| &site.words)
+            .collect();
+        let mut dictionary = String::new();
+        for word in words {
+            dictionary.push('"');
+            for byte in word {
+                match byte {
+                    b'"' | b'\\' => write!(dictionary, "\\{}", *byte as char),
+                    0x20..=0x7e => write!(dictionary, "{}", *byte as char),
+                    _ => write!(dictionary, "\\x{:02X}", byte),
+                }
+                .expect("writing to a string can't fail");
+            }
+            dictionary.push_str("\"\n");
+        }
+        dictionary
+    }
+}
@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+
+use crate::move_runner::addresses::AddressWeights;
+use crate::move_runner::types::{StringGeneration, StructGeneration, TargetVisibility, TypeArgSelection};
+
+/// Options controlling how the runner generates inputs and judges executions.
+#[derive(Debug, Clone, Default)]
+pub struct RunnerConfig {
+    /// How struct arguments are generated
+    pub struct_generation: StructGeneration,
+    /// Which `vector<u8>` and string parameters are generated as valid text
+    pub string_generation: StringGeneration,
+    /// Whether `Option`, `FixedPoint32` and Sui object values (`UID`, `Coin`,
+    /// `Clock`, `TxContext`) are generated field by field instead of as
+    /// values the framework functions accept
+    pub raw_std_types: bool,
+    /// Functions accepted as the target
+    pub visibility: TargetVisibility,
+    /// How the instantiation of a generic target is picked
+    pub instantiate: TypeArgSelection,
+    /// Gas available to each execution, unmetered when `None`
+    pub gas_limit: Option<u64>,
+    /// Abort codes that are expected and not reported as crashes
+    pub allowed_abort_codes: Vec<u64>,
+    /// Whether a target returning `false` is reported as a violated property
+    pub oracle_bool: bool,
+    /// Functions called before the target on every input, as `module::function`
+    pub setup_functions: Vec<String>,
+    /// Function called after each successful execution, as `module::function`,
+    /// whose failures are reported as crashes
+    pub invariant: Option<String>,
+    /// Actions taken on failed executions, as `class=action`
+    pub error_actions: Vec<String>,
+    /// Parameters pinned to a constant value, as `argN=value`
+    pub fixed_args: Vec<String>,
+    /// Addresses signers are picked from, as hex literals or `deployer`
+    pub signers: Vec<String>,
+    /// Addresses the named addresses of the package are published at, as `name=0x...`
+    pub addresses: Vec<String>,
+    /// Number of test accounts added to the signer pool
+    pub test_signers: u64,
+    /// Whether random signers are still generated when the pool isn't empty
+    pub random_signers: bool,
+    /// How often each kind of address is generated for `address` parameters
+    pub address_weights: AddressWeights,
+    /// Percentage of the integers picked among boundary values instead of uniformly
+    pub special_int_percent: u8,
+    /// Seed of the swarm profile overriding the integer bias, address
+    /// weights and vector lengths, `None` to keep the configured ones
+    pub swarm_seed: Option<u64>,
+    /// Whether inputs are module bytecode published in the session instead
+    /// of arguments of the target
+    pub publish: bool,
+    /// State machine model the inputs are interpreted with as call sequences
+    pub model: Option<PathBuf>,
+    /// Directory the parameter plan of the target is cached in across runs
+    pub abi_cache: Option<PathBuf>,
+    /// Elements per vector counted in the bound on the input size
+    pub vector_budget: usize,
+    /// Highest bytecode version the modules are read with, the latest supported when `None`
+    pub bytecode_version: Option<u32>,
+    /// Maximum nesting of the generated vectors, structs and enums
+    pub max_input_depth: usize,
+    /// Maximum number of values generated from one input
+    pub max_input_values: usize,
+    /// Directory where the Move coverage of the executions is recorded
+    pub coverage_dir: Option<PathBuf>,
+    /// Whether the coverage of the dependencies is recorded too, not only
+    /// the target module's
+    pub include_deps: bool,
+    /// REST API of the node the resources missing locally are read from
+    pub fork_url: Option<String>,
+    /// Ledger version the forked state is read at, the latest when `None`
+    pub fork_version: Option<u64>,
+    /// Directory the resources read from the node are cached in
+    pub fork_cache: Option<PathBuf>,
+    /// Version of the encoding inputs without a header were written with,
+    /// the current one when `None`
+    pub encoding_version: Option<u8>,
+}
@@ -0,0 +1,232 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use move_core_types::language_storage::ModuleId;
+use move_coverage::coverage_map::{output_map_to_file, CoverageMap};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+
+use super::comparisons::ComparisonSites;
+use super::mutation::MutationKind;
+
+/// Environment variable read by the Move VM to find where to write its trace.
+const MOVE_VM_TRACE_ENV: &str = "MOVE_VM_TRACE";
+
+/// Number of executions between two merges of the trace into the coverage map.
+const MERGE_INTERVAL: u64 = 1000;
+
+/// Time between two writes of the coverage map file, read by the CLI.
+const WRITE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Mutated inputs remembered until libFuzzer picks them from its corpus.
+const MAX_PENDING_MUTATIONS: usize = 1 << 16;
+
+/// Trace of the process, set in the environment by the first recorder only:
+/// the VM reads it once, and changing the environment is unsound once the
+/// fuzzer runs other threads.
+static TRACE: OnceCell<PathBuf> = OnceCell::new();
+
+/// Move-level statistics of the executions of the process, which libFuzzer
+/// knows nothing about, written next to the coverage map for the CLI.
+#[derive(Debug, Default, Serialize)]
+pub struct ExecutionStats {
+    pub executions: u64,
+    /// Inputs no arguments could be built from, e.g. a struct constructor aborted
+    pub undecodable: u64,
+    /// Inputs rejected from the corpus, over the generation limits or by the
+    /// crash policy
+    pub rejected: u64,
+    pub crashes: u64,
+    /// Arguments generated for the target, and their serialized size
+    pub arguments: u64,
+    pub argument_bytes: u64,
+    /// Instructions in the coverage map as of the last merge
+    pub covered_instructions: usize,
+    /// When a merge last found new instructions, in seconds since the epoch
+    pub last_new_coverage: Option<u64>,
+    /// Seed of the swarm profile of the process, which its findings only
+    /// reproduce with
+    pub swarm_seed: Option<u64>,
+    /// Mutations made by the worker's mutator, by kind
+    pub mutations: BTreeMap<&'static str, MutationCounts>,
+}
+
+/// How often a kind of mutation was applied, and produced an input libFuzzer
+/// kept in its corpus for the new coverage it found.
+#[derive(Debug, Default, Serialize)]
+pub struct MutationCounts {
+    pub applied: u64,
+    pub kept: u64,
+}
+
+/// Records the Move coverage of the process in its own files, named after the
+/// target and the PID (`<module>-<function>-<pid>`), so that parallel workers
+/// sharing a coverage directory don't write to the same ones.
+///
+/// The VM appends every executed instruction to the trace, which is folded into
+/// the in-memory coverage map and truncated periodically to keep it small; the
+/// map is written every few seconds and when the process exits, along with
+/// the statistics of the executions (`<module>-<function>-<pid>.stats.json`)
+/// and the dictionary of the comparisons they are stuck on (`.dict`).
+pub struct CoverageRecorder {
+    trace: PathBuf,
+    stats_path: PathBuf,
+    stats: ExecutionStats,
+    /// Module the map is restricted to, every traced module if `None`
+    module: Option<ModuleId>,
+    map_path: PathBuf,
+    map: Option<CoverageMap>,
+    dict_path: PathBuf,
+    comparisons: ComparisonSites,
+    /// Kinds of the mutations that produced the inputs not mutated since, by
+    /// hash of the input
+    pending_mutations: HashMap<u64, MutationKind>,
+    executions: u64,
+    /// Whether the map has changed since it was last written
+    dirty: bool,
+    written: Instant,
+}
+
+impl CoverageRecorder {
+    /// Must be called before the VM executes any instruction, as it reads the
+    /// trace path only once, and before the process starts other threads.
+    pub fn new(dir: &Path, target_module: &str, target_function: &str, module: Option<ModuleId>) -> Self {
+        // Move identifiers can't contain `-`, so names can't be ambiguous
+        let name = format!("{}-{}-{}", target_module, target_function, std::process::id());
+        let trace = TRACE
+            .get_or_init(|| {
+                let trace = dir.join(format!("{}.trace", name));
+                std::env::set_var(MOVE_VM_TRACE_ENV, &trace);
+                trace
+            })
+            .clone();
+        CoverageRecorder {
+            trace,
+            stats_path: dir.join(format!("{}.stats.json", name)),
+            stats: ExecutionStats::default(),
+            module,
+            map_path: dir.join(format!("{}.coverage_map", name)),
+            map: None,
+            dict_path: dir.join(format!("{}.dict", name)),
+            comparisons: ComparisonSites::default(),
+            pending_mutations: HashMap::new(),
+            executions: 0,
+            dirty: false,
+            written: Instant::now(),
+        }
+    }
+
+    /// Statistics of the executions, updated by the runner before
+    /// `record_execution`.
+    pub fn stats_mut(&mut self) -> &mut ExecutionStats {
+        &mut self.stats
+    }
+
+    /// Sets the comparisons whose constants are written to the dictionary.
+    pub fn learn_comparisons(&mut self, comparisons: ComparisonSites) {
+        self.comparisons = comparisons;
+    }
+
+    /// Counts a mutation of `kind` from the input hashed `parent` to the one
+    /// hashed `child`. libFuzzer only picks the inputs it mutates from its
+    /// corpus, besides chaining mutations, so a parent produced by an earlier
+    /// mutation was kept for its new coverage, which the kind of that
+    /// mutation is credited with.
+    pub fn record_mutation(&mut self, parent: Option<u64>, child: u64, kind: MutationKind) {
+        if let Some(kept) = parent.and_then(|parent| self.pending_mutations.remove(&parent)) {
+            self.stats.mutations.entry(kept.name()).or_default().kept += 1;
+        }
+        self.stats.mutations.entry(kind.name()).or_default().applied += 1;
+        if self.pending_mutations.len() >= MAX_PENDING_MUTATIONS {
+            self.pending_mutations.clear();
+        }
+        self.pending_mutations.insert(child, kind);
+    }
+
+    pub fn record_execution(&mut self) {
+        self.executions += 1;
+        self.stats.executions += 1;
+        if self.executions % MERGE_INTERVAL == 0 {
+            self.merge();
+            if self.written.elapsed() >= WRITE_INTERVAL {
+                self.write();
+            }
+        }
+    }
+
+    /// Folds the trace into the coverage map and empties it, if any
+    /// instruction was traced since the last merge.
+    fn merge(&mut self) {
+        if fs::metadata(&self.trace).map_or(true, |metadata| metadata.len() == 0) {
+            return;
+        }
+        let mut map = match self.map.take() {
+            Some(map) => map.update_coverage_from_trace_file(&self.trace),
+            None => CoverageMap::from_trace_file(&self.trace),
+        };
+        // The VM traces every module, the dependencies being left out here
+        if let Some(module) = &self.module {
+            for exec_map in map.exec_maps.values_mut() {
+                exec_map
+                    .module_maps
+                    .retain(|(address, name), _| address == module.address() && name.as_ident_str() == module.name());
+            }
+        }
+        // The VM appends to the trace, so it keeps writing at the start of the file
+        if let Ok(file) = OpenOptions::new().write(true).open(&self.trace) {
+            let _ = file.set_len(0);
+        }
+        let covered = map
+            .to_unified_exec_map()
+            .module_maps
+            .values()
+            .flat_map(|module_map| module_map.function_maps.values())
+            .map(|offsets| offsets.len())
+            .sum();
+        if covered > self.stats.covered_instructions {
+            self.stats.covered_instructions = covered;
+            self.stats.last_new_coverage = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .ok()
+                .map(|elapsed| elapsed.as_secs());
+        }
+        self.map = Some(map);
+        self.dirty = true;
+    }
+
+    /// Writes the statistics, and the coverage map file if it changed, aside
+    /// then renamed so that the CLI never reads a partial one.
+    fn write(&mut self) {
+        self.written = Instant::now();
+        let partial = self.stats_path.with_extension("json.tmp");
+        let stats = serde_json::to_vec(&self.stats).unwrap_or_default();
+        if let Err(e) = fs::write(&partial, stats).and_then(|_| fs::rename(&partial, &self.stats_path)) {
+            log::warn!("Failed to write statistics {}: {}", self.stats_path.display(), e);
+        }
+        let map = match &self.map {
+            Some(map) if self.dirty => map,
+            _ => return,
+        };
+        let partial = self.map_path.with_extension("coverage_map.tmp");
+        if let Err(e) = output_map_to_file(&partial, map).and_then(|_| fs::rename(&partial, &self.map_path).map_err(Into::into)) {
+            log::warn!("Failed to write coverage map {}: {}", self.map_path.display(), e);
+            return;
+        }
+        if !self.comparisons.is_empty() {
+            let dictionary = self.comparisons.dictionary(&map.to_unified_exec_map());
+            let partial = self.dict_path.with_extension("dict.tmp");
+            if let Err(e) = fs::write(&partial, dictionary).and_then(|_| fs::rename(&partial, &self.dict_path)) {
+                log::warn!("Failed to write dictionary {}: {}", self.dict_path.display(), e);
+            }
+        }
+        self.dirty = false;
+    }
+
+    /// Merges the trace and writes the coverage map file.
+    pub fn flush(&mut self) {
+        self.merge();
+        self.write();
+    }
+}
@@ -0,0 +1,49 @@
+use std::collections::BTreeMap;
+
+use super::types::Error;
+
+/// What to do with an execution that failed with a given class of error.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorAction {
+    /// Report the input as a crash
+    #[default]
+    Crash,
+    /// Drop the input from the corpus
+    Reject,
+    /// Treat the execution as a successful one
+    Ignore,
+}
+
+/// The action taken for each class of error, given as `class=action` with
+/// `--on-error`. Classes not given are crashes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrashPolicy {
+    actions: BTreeMap<&'static str, ErrorAction>,
+}
+
+impl CrashPolicy {
+    /// Parses rules such as `out-of-gas=reject` or `memory-limit-exceeded=ignore`.
+    pub fn parse(rules: &[String]) -> Result<Self, String> {
+        let mut actions = BTreeMap::new();
+        for rule in rules {
+            let (class, action) = rule
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid error rule `{}`, expected `class=action`", rule))?;
+            let class = Error::CLASSES.iter().find(|c| **c == class).ok_or_else(|| {
+                format!("Unknown error class `{}`, expected one of {}", class, Error::CLASSES.join(", "))
+            })?;
+            let action = match action {
+                "crash" => ErrorAction::Crash,
+                "reject" => ErrorAction::Reject,
+                "ignore" => ErrorAction::Ignore,
+                _ => return Err(format!("Unknown action `{}` for {}, expected crash, reject or ignore", action, class)),
+            };
+            actions.insert(*class, action);
+        }
+        Ok(CrashPolicy { actions })
+    }
+
+    pub fn action(&self, error: &Error) -> ErrorAction {
+        self.actions.get(error.class()).copied().unwrap_or_default()
+    }
+}
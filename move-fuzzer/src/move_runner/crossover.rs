@@ -0,0 +1,54 @@
+use super::shrink::Sample;
+use super::swarm::SplitMix64;
+use super::types::FuzzerType;
+
+/// Recombines two values of type `ty`: fields and variants are mixed one by
+/// one, vectors and texts are spliced, other values are taken whole.
+fn mix(ty: &FuzzerType, a: &Sample, b: &Sample, rng: &mut SplitMix64) -> Sample {
+    match (ty, a, b) {
+        (FuzzerType::Struct(types), Sample::Fields(a), Sample::Fields(b)) => Sample::Fields(mix_all(types, a, b, rng)),
+        (FuzzerType::Constructor(c), Sample::Fields(a), Sample::Fields(b)) => Sample::Fields(mix_all(&c.args, a, b, rng)),
+        (FuzzerType::Enum(variants), Sample::Variant(tag, a), Sample::Variant(other, b)) if tag == other => {
+            Sample::Variant(*tag, mix_all(&variants[*tag], a, b, rng))
+        }
+        (FuzzerType::Vector(_), Sample::Vector(a), Sample::Vector(b)) => Sample::Vector(splice(a, b, rng)),
+        (FuzzerType::Text(_), Sample::Text(a), Sample::Text(b)) => Sample::Text(splice(a, b, rng)),
+        (FuzzerType::Option(_) | FuzzerType::FixedPoint32 | FuzzerType::TxContext | FuzzerType::Clock, a, b) => mix(&ty.decoded_as(), a, b, rng),
+        _ => pick(a, b, rng).clone(),
+    }
+}
+
+/// Takes each value from either parent, or recombines both.
+fn mix_all(types: &[FuzzerType], a: &[Sample], b: &[Sample], rng: &mut SplitMix64) -> Vec<Sample> {
+    types
+        .iter()
+        .zip(a.iter().zip(b))
+        .map(|(ty, (a, b))| match rng.below(3) {
+            0 => a.clone(),
+            1 => b.clone(),
+            _ => mix(ty, a, b, rng),
+        })
+        .collect()
+}
+
+fn pick<'a, T>(a: &'a T, b: &'a T, rng: &mut SplitMix64) -> &'a T {
+    if rng.below(2) == 0 {
+        a
+    } else {
+        b
+    }
+}
+
+/// The head of one sequence followed by the tail of the other.
+fn splice<T: Clone>(a: &[T], b: &[T], rng: &mut SplitMix64) -> Vec<T> {
+    let (head, tail) = if rng.below(2) == 0 { (a, b) } else { (b, a) };
+    let mut res = head[..rng.below(head.len() + 1)].to_vec();
+    res.extend_from_slice(&tail[rng.below(tail.len() + 1)..]);
+    res
+}
+
+/// Recombines the arguments decoded from two inputs, keeping the offspring a
+/// valid argument list instead of splicing the raw bytes.
+pub fn crossover(types: &[FuzzerType], a: &[Sample], b: &[Sample], seed: u32) -> Vec<Sample> {
+    mix_all(types, a, b, &mut SplitMix64(seed as u64))
+}
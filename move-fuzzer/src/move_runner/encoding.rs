@@ -0,0 +1,112 @@
+use std::borrow::Cow;
+
+use arbitrary::Unstructured;
+
+use super::arbitrary_inputs::InputConfig;
+use super::shrink;
+use super::types::FuzzerType;
+
+/// Version of the encoding arguments are decoded from, bumped whenever the
+/// same bytes would decode to different arguments:
+///
+/// 1. every vector and text element follows a byte continuing it when odd
+/// 2. even continuation bytes above `InputConfig::vector_end` also continue
+pub const ENCODING_VERSION: u8 = 2;
+
+/// Prefix of the `--describe` line giving `ENCODING_VERSION`, read by the CLI
+/// to tell whether a corpus was written with another version.
+pub const ENCODING_VERSION_PREFIX: &str = "Encoding version: ";
+
+/// Marks an input whose first bytes record the encoding it was written with.
+/// libFuzzer writes the inputs it finds without it, and those are decoded as
+/// the version recorded for their corpus, the current one by default.
+const MAGIC: [u8; 4] = *b"\xffMFZ";
+
+/// Length of the header of a stamped input: the magic and the version byte.
+const HEADER_LENGTH: usize = MAGIC.len() + 1;
+
+/// Version of the encoding `bytes` was written with and the encoded
+/// arguments that follow the header, `headerless` for an input without a
+/// header.
+pub fn split_header(bytes: &[u8], headerless: u8) -> (u8, &[u8]) {
+    match bytes.strip_prefix(&MAGIC) {
+        Some([version, payload @ ..]) => (*version, payload),
+        _ => (headerless, bytes),
+    }
+}
+
+/// Prefixes arguments encoded with the current version with their header,
+/// so that they still decode once a later release changes the encoding.
+pub fn stamp(payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LENGTH + payload.len());
+    bytes.extend(MAGIC);
+    bytes.push(ENCODING_VERSION);
+    bytes.extend(payload);
+    bytes
+}
+
+/// Settings decoding arguments written with an earlier `version` of the
+/// encoding, for a runner generating them with `config`. `None` when the
+/// version is unknown, e.g. written by a later release.
+pub fn legacy_config(version: u8, config: &InputConfig) -> Option<InputConfig> {
+    match version {
+        // Continuation bytes were read as booleans
+        1 => Some(InputConfig { vector_end: 128, ..config.clone() }),
+        _ => None,
+    }
+}
+
+/// The arguments of types `params` that `bytes` encodes, in the current
+/// encoding: without the header of a stamped input, and re-encoded when it
+/// was written with an earlier version, `headerless` for an input without a
+/// header. `None` when the version is unknown.
+pub fn upgrade<'b>(
+    params: &[FuzzerType],
+    bytes: &'b [u8],
+    headerless: u8,
+    config: &InputConfig,
+) -> Option<Cow<'b, [u8]>> {
+    let (version, payload) = split_header(bytes, headerless);
+    if version == ENCODING_VERSION {
+        return Some(Cow::Borrowed(payload));
+    }
+    let legacy = legacy_config(version, config)?;
+    let samples = shrink::parse_all(params, &mut Unstructured::new(payload), &legacy);
+    Some(Cow::Owned(shrink::encode_all(params, &samples, config)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::move_runner::addresses::AddressPool;
+    use crate::move_runner::arbitrary_inputs::InputLimits;
+    use crate::move_runner::integers::SpecialInts;
+
+    fn config(vector_end: u8) -> InputConfig {
+        InputConfig {
+            signers: AddressPool::default(),
+            addresses: AddressPool::default(),
+            special_ints: SpecialInts::new(0),
+            limits: InputLimits { max_depth: 32, max_values: 100_000 },
+            vector_end,
+        }
+    }
+
+    #[test]
+    fn headerless_input_decodes_as_the_version_of_its_corpus() {
+        let params = [FuzzerType::Vector(Box::new(FuzzerType::U8))];
+        let current = config(16);
+        // Version 1 stopped the vector on the even byte, the current one continues
+        let written = [0x40, 7, 1, 9];
+        let decode = |bytes: &[u8], config: &InputConfig| shrink::parse_all(&params, &mut Unstructured::new(bytes), config);
+        let expected = decode(&written, &legacy_config(1, &current).unwrap());
+        assert_ne!(decode(&written, &current), expected);
+
+        let upgraded = upgrade(&params, &written, 1, &current).unwrap();
+        assert_eq!(decode(&upgraded, &current), expected);
+        // The header wins over the version of the corpus
+        let stamped = stamp(&upgraded);
+        assert_eq!(upgrade(&params, &stamped, 1, &current).unwrap(), upgraded);
+        assert_eq!(upgrade(&params, &written, 3, &current), None);
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use move_binary_format::file_format::SignatureToken;
+use move_binary_format::CompiledModule;
+use move_core_types::language_storage::ModuleId;
+
+use super::source_maps::SourceMaps;
+
+/// Bit set in the abort codes built from Move 2024 error constants, the
+/// "clever errors" carrying the index of the constant's name.
+const CLEVER_ERROR_TAG: u64 = 1 << 63;
+
+/// Identifier index of a clever error aborting without a constant.
+const NO_IDENTIFIER: u16 = u16::MAX;
+
+/// Categories of the `std::error` convention, where codes are built as
+/// `category << 16 | reason`; the first one is category 1.
+const ERROR_CATEGORIES: [&str; 13] = [
+    "INVALID_ARGUMENT",
+    "OUT_OF_RANGE",
+    "INVALID_STATE",
+    "UNAUTHENTICATED",
+    "PERMISSION_DENIED",
+    "NOT_FOUND",
+    "ABORTED",
+    "ALREADY_EXISTS",
+    "RESOURCE_EXHAUSTED",
+    "CANCELLED",
+    "INTERNAL",
+    "NOT_IMPLEMENTED",
+    "UNAVAILABLE",
+];
+
+/// Error constants are named `E...` by convention, e.g. `EInsufficientBalance`
+/// or `E_INSUFFICIENT_BALANCE`.
+fn is_error_name(name: &str) -> bool {
+    name.starts_with('E') && name.chars().nth(1).map_or(false, |c| c.is_ascii_uppercase() || c == '_')
+}
+
+/// Names of the `u64` constants of the loaded modules, to tell which error
+/// an abort code stands for. Bytecode doesn't keep the names of constants,
+/// they are read from the source maps.
+#[derive(Debug, Default)]
+pub struct ErrorConstants {
+    /// Constant names by module and value
+    names: HashMap<(ModuleId, u64), String>,
+}
+
+impl ErrorConstants {
+    /// Reads the names from the source maps of `modules`. Modules without a
+    /// source map are left out, their abort codes are shown as numbers.
+    pub fn load(source_maps: &SourceMaps, modules: &[CompiledModule]) -> Self {
+        let mut constants = ErrorConstants::default();
+        for module in modules {
+            let id = module.self_id();
+            let source_map = match source_maps.get(&id) {
+                Some(source_map) => source_map,
+                None => continue,
+            };
+            for (name, &idx) in &source_map.constant_map {
+                let constant = &module.constant_pool()[idx as usize];
+                if constant.type_ != SignatureToken::U64 {
+                    continue;
+                }
+                let value = match bcs::from_bytes::<u64>(&constant.data) {
+                    Ok(value) => value,
+                    Err(_) => continue,
+                };
+                // Of the constants sharing a value, an error one names the code
+                let name = name.to_string();
+                let known = constants.names.entry((id.clone(), value)).or_insert_with(|| name.clone());
+                if !is_error_name(known) && is_error_name(&name) {
+                    *known = name;
+                }
+            }
+        }
+        constants
+    }
+
+    /// Name of the error `module` aborted with `code` for: the constant of a
+    /// clever error, a constant of that value, or a `std::error` category
+    /// with the constant of its reason, e.g. `INVALID_ARGUMENT(E_ZERO_AMOUNT)`.
+    pub fn name(&self, module: &CompiledModule, code: u64) -> Option<String> {
+        if code & CLEVER_ERROR_TAG != 0 {
+            let identifier = (code >> 16) as u16;
+            if identifier == NO_IDENTIFIER {
+                return None;
+            }
+            return module.identifiers().get(identifier as usize).map(ToString::to_string);
+        }
+        let id = module.self_id();
+        if let Some(name) = self.names.get(&(id.clone(), code)) {
+            return Some(name.clone());
+        }
+        let (category, reason) = (code >> 16, code & 0xffff);
+        let category = ERROR_CATEGORIES.get((category as usize).checked_sub(1)?)?;
+        self.names
+            .get(&(id, reason))
+            .map(|name| format!("{}({})", category, name))
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
+
+use move_core_types::account_address::AccountAddress;
+use move_core_types::gas_algebra::InternalGas;
+use move_core_types::identifier::Identifier;
+use move_core_types::language_storage::TypeTag;
+use move_core_types::runtime_value::MoveValue;
+use move_vm_runtime::native_functions::{NativeContext, NativeFunction, NativeFunctionTable};
+use move_vm_types::loaded_data::runtime_types::Type;
+use move_vm_types::natives::function::NativeResult;
+use move_vm_types::values::Value;
+use smallvec::smallvec;
+
+use move_binary_format::errors::PartialVMResult;
+
+/// An event emitted by the executed Move code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveEvent {
+    /// Type of the emitted value
+    pub type_tag: TypeTag,
+    /// The event value, `None` if it couldn't be decoded
+    pub value: Option<MoveValue>,
+}
+
+impl Display for MoveEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{}: {}", self.type_tag, value),
+            None => write!(f, "{}", self.type_tag),
+        }
+    }
+}
+
+/// Events emitted during the current execution, filled by the event natives.
+pub type EventStore = Arc<Mutex<Vec<MoveEvent>>>;
+
+/// `sui::event::emit<T>(event: T)`, recording the event in `store`.
+fn emit(store: EventStore) -> NativeFunction {
+    Arc::new(move |context: &mut NativeContext, ty_args: Vec<Type>, mut args: VecDeque<Value>| -> PartialVMResult<NativeResult> {
+        let ty = &ty_args[0];
+        let event = args.pop_back().expect("emit takes the event as its only argument");
+        let type_tag = context.type_to_type_tag(ty)?;
+        let value = context
+            .type_to_type_layout(ty)?
+            .and_then(|layout| event.simple_serialize(&layout).and_then(|bytes| MoveValue::simple_deserialize(&bytes, &layout).ok()));
+        store.lock().unwrap().push(MoveEvent { type_tag, value });
+        Ok(NativeResult::ok(InternalGas::zero(), smallvec![]))
+    })
+}
+
+/// Natives recording the events emitted by the executed code in `store`.
+pub fn event_natives(store: &EventStore) -> NativeFunctionTable {
+    vec![(
+        AccountAddress::TWO,
+        Identifier::new("event").unwrap(),
+        Identifier::new("emit").unwrap(),
+        emit(store.clone()),
+    )]
+}
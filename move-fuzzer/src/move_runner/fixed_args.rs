@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+
+use move_core_types::account_address::AccountAddress;
+use move_core_types::runtime_value::MoveValue;
+use move_core_types::u256::U256 as MoveU256;
+
+use super::types::FuzzerType;
+
+/// Splits a number literal into its digits and radix, `0x` prefixed numbers being hexadecimal.
+fn digits(value: &str) -> (&str, u32) {
+    match value.strip_prefix("0x") {
+        Some(hex) => (hex, 16),
+        None => (value, 10),
+    }
+}
+
+fn parse_int<T: TryFrom<u128>>(value: &str) -> Option<T> {
+    let (digits, radix) = digits(value);
+    u128::from_str_radix(digits, radix).ok()?.try_into().ok()
+}
+
+/// Parses `value` as a constant of type `ty`.
+fn parse_value(ty: &FuzzerType, value: &str) -> Result<MoveValue, String> {
+    let parsed = match ty {
+        FuzzerType::Bool => value.parse().ok().map(MoveValue::Bool),
+        FuzzerType::U8 => parse_int(value).map(MoveValue::U8),
+        FuzzerType::U16 => parse_int(value).map(MoveValue::U16),
+        FuzzerType::U32 => parse_int(value).map(MoveValue::U32),
+        FuzzerType::U64 => parse_int(value).map(MoveValue::U64),
+        FuzzerType::U128 => parse_int(value).map(MoveValue::U128),
+        FuzzerType::U256 => {
+            let (digits, radix) = digits(value);
+            MoveU256::from_str_radix(digits, radix).ok().map(MoveValue::U256)
+        }
+        FuzzerType::Address => value.parse::<AccountAddress>().ok().map(MoveValue::Address),
+        FuzzerType::Signer => value.parse::<AccountAddress>().ok().map(MoveValue::Signer),
+        FuzzerType::Text(_) => Some(MoveValue::vector_u8(value.as_bytes().to_vec())),
+        _ => return Err(format!("parameters of type {} can't be fixed", ty)),
+    };
+    parsed.ok_or_else(|| format!("`{}` is not a valid {}", value, ty))
+}
+
+/// Parses the `argN=value` pins given with `--fix` against the target parameters.
+pub fn parse_fixed_args(params: &[FuzzerType], fixes: &[String]) -> Result<BTreeMap<usize, MoveValue>, String> {
+    let mut fixed = BTreeMap::new();
+    for fix in fixes {
+        let (name, value) = fix
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid fixed argument `{}`, expected `argN=value`", fix))?;
+        let index = name
+            .strip_prefix("arg")
+            .and_then(|index| index.parse::<usize>().ok())
+            .ok_or_else(|| format!("Invalid parameter `{}`, expected `argN`", name))?;
+        let ty = params
+            .get(index)
+            .ok_or_else(|| format!("The target has no parameter {}", name))?;
+        let value = parse_value(ty, value).map_err(|e| format!("Can't fix {}: {}", name, e))?;
+        if fixed.insert(index, value).is_some() {
+            return Err(format!("{} is fixed more than once", name));
+        }
+    }
+    Ok(fixed)
+}
+
+/// Inserts the fixed values among the generated ones, in parameter order.
+pub fn splice_fixed_args(fixed: &BTreeMap<usize, MoveValue>, generated: Vec<MoveValue>) -> Vec<MoveValue> {
+    let mut generated = generated.into_iter();
+    let mut args = vec![];
+    for index in 0..fixed.len() + generated.len() {
+        match fixed.get(&index) {
+            Some(value) => args.push(value.clone()),
+            None => args.extend(generated.next()),
+        }
+    }
+    args
+}
@@ -0,0 +1,22 @@
+use std::sync::{Arc, Mutex};
+
+use move_core_types::runtime_value::MoveValue;
+
+use super::outcome::ExecutionOutcome;
+
+/// Callbacks invoked around each execution of the target, to add custom
+/// oracles, collect statistics or reset state without changing the runner.
+pub trait ExecutionHook: Send {
+    /// Called with the arguments decoded from the input, fixed ones included,
+    /// right before the target runs.
+    fn before_execution(&mut self, _args: &[MoveValue]) {}
+
+    /// Called once the target ran. An error turns an execution that succeeded
+    /// into a violated property, reported as a crash.
+    fn after_execution(&mut self, _outcome: &ExecutionOutcome) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// A hook shared by the runners of every thread.
+pub type SharedHook = Arc<Mutex<dyn ExecutionHook>>;
@@ -0,0 +1,98 @@
+use arbitrary::{Result as ArbitraryResult, Unstructured};
+
+use move_core_types::u256::U256 as MoveU256;
+
+/// Widths of the integer types up to `u128`.
+const WIDTHS: [u32; 5] = [8, 16, 32, 64, 128];
+
+/// Amounts code commonly compares against: percents, basis points, token decimals.
+const ROUND_AMOUNTS: [u128; 6] = [100, 1_000, 10_000, 1_000_000, 1_000_000_000, 1_000_000_000_000_000_000];
+
+/// Values at the edges of the integer ranges, where overflows and off-by-one
+/// bugs hide, picked instead of uniform values with a configurable probability.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpecialInts {
+    /// Selector bytes below this pick a special value, 0 disables them
+    threshold: u8,
+    /// Special values of the integer types up to `u128`, in `WIDTHS` order
+    values: Vec<Vec<u128>>,
+    u256: Vec<MoveU256>,
+}
+
+fn special_values(bits: u32) -> Vec<u128> {
+    let max = u128::MAX >> (128 - bits);
+    let mut values = vec![0, 1, 2, max, max - 1, max / 2, max / 2 + 1];
+    for shift in (8..bits).step_by(8) {
+        values.push(1 << shift);
+        values.push((1 << shift) - 1);
+    }
+    values.extend(ROUND_AMOUNTS.iter().filter(|amount| **amount <= max));
+    values.sort_unstable();
+    values.dedup();
+    values
+}
+
+fn special_u256() -> Vec<MoveU256> {
+    let mut values: Vec<MoveU256> = special_values(128).into_iter().map(MoveU256::from).collect();
+    let from_bytes = |f: fn(&mut [u8; 32])| {
+        let mut bytes = [0; 32];
+        f(&mut bytes);
+        MoveU256::from_le_bytes(&bytes)
+    };
+    values.extend([
+        // 2^128, 2^255 - 1, 2^255, MAX - 1, MAX
+        from_bytes(|b| b[16] = 1),
+        from_bytes(|b| {
+            b.fill(0xff);
+            b[31] = 0x7f;
+        }),
+        from_bytes(|b| b[31] = 0x80),
+        from_bytes(|b| {
+            b.fill(0xff);
+            b[0] = 0xfe;
+        }),
+        from_bytes(|b| b.fill(0xff)),
+    ]);
+    values
+}
+
+impl SpecialInts {
+    /// Picks special values in about `percent` percent of the integers.
+    pub fn new(percent: u8) -> Self {
+        SpecialInts {
+            threshold: (percent.min(100) as u32 * 256 / 100).min(u8::MAX as u32) as u8,
+            values: WIDTHS.iter().map(|bits| special_values(*bits)).collect(),
+            u256: special_u256(),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.threshold > 0
+    }
+
+    /// The special values of the `bits` wide integer type, up to `u128`.
+    pub fn values(&self, bits: u32) -> &[u128] {
+        WIDTHS
+            .iter()
+            .position(|b| *b == bits)
+            .map_or(&[], |i| &self.values[i])
+    }
+
+    pub fn u256_values(&self) -> &[MoveU256] {
+        &self.u256
+    }
+
+    /// Reads whether a special value is picked among `count`, and which one.
+    /// Selector bytes of 255 always read a uniform value.
+    pub fn pick(&self, u: &mut Unstructured, count: usize) -> ArbitraryResult<Option<usize>> {
+        if !self.enabled() {
+            return Ok(None);
+        }
+        let selector: u8 = u.arbitrary()?;
+        if selector < self.threshold {
+            Ok(Some(u.choose_index(count)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
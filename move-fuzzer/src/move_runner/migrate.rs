@@ -0,0 +1,80 @@
+use arbitrary::Unstructured;
+
+use move_core_types::account_address::AccountAddress;
+
+use super::addresses::AddressPool;
+use super::arbitrary_inputs::InputConfig;
+use super::shrink::{parse_all, Sample};
+use super::types::FuzzerType;
+
+/// Picks `address` again in `pool`, as a pooled entry when the pool holds it
+/// and as a random address otherwise.
+fn repick(address: AccountAddress, pool: &AddressPool) -> Sample {
+    let choices = pool.choices();
+    let pooled = (0..choices).find(|i| pool.get(*i) == Some(address));
+    let random = (0..choices).find(|i| pool.get(*i).is_none());
+    match (pooled, random) {
+        (Some(choice), _) => Sample::Address { choice, choices, address: None },
+        (None, Some(choice)) => Sample::Address { choice, choices, address: Some(address) },
+        // The pool can't produce the address, keep its first entry
+        (None, None) if choices > 0 => Sample::Address { choice: 0, choices, address: None },
+        (None, None) => Sample::Address { choice: 0, choices, address: Some(address) },
+    }
+}
+
+/// Re-encodes the choices of a value decoded with the `old` settings so that
+/// the `new` ones pick the same addresses, the pools depending on the modules.
+fn convert(ty: &FuzzerType, sample: Sample, old: &InputConfig, new: &InputConfig) -> Sample {
+    let convert_all = |types: &[FuzzerType], values: Vec<Sample>| {
+        types.iter().zip(values).map(|(t, v)| convert(t, v, old, new)).collect()
+    };
+    match (ty, sample) {
+        (FuzzerType::Address | FuzzerType::Signer, Sample::Address { choice, address, .. }) => {
+            let pool = |config: &InputConfig| match ty {
+                FuzzerType::Signer => config.signers.clone(),
+                _ => config.addresses.clone(),
+            };
+            match address.or_else(|| pool(old).get(choice)) {
+                Some(address) => repick(address, &pool(new)),
+                None => Sample::Address { choice, choices: pool(new).choices(), address },
+            }
+        }
+        (FuzzerType::Vector(t), Sample::Vector(values)) => {
+            Sample::Vector(values.into_iter().map(|v| convert(t, v, old, new)).collect())
+        }
+        (FuzzerType::Struct(types), Sample::Fields(values)) => Sample::Fields(convert_all(types, values)),
+        (FuzzerType::Constructor(c), Sample::Fields(values)) => Sample::Fields(convert_all(&c.args, values)),
+        (FuzzerType::Enum(variants), Sample::Variant(tag, values)) => {
+            Sample::Variant(tag, convert_all(&variants[tag], values))
+        }
+        (FuzzerType::Option(_) | FuzzerType::FixedPoint32 | FuzzerType::TxContext | FuzzerType::Clock, sample) => convert(&ty.decoded_as(), sample, old, new),
+        (_, sample) => sample,
+    }
+}
+
+/// Maps the arguments decoded for the `old` parameters onto the `new` ones.
+/// Each new parameter takes the old argument at the same position when their
+/// types match, else the first unused old argument of the same type, else the
+/// value an empty input decodes to.
+pub fn migrate(
+    old_types: &[FuzzerType],
+    old_samples: Vec<Sample>,
+    old: &InputConfig,
+    new_types: &[FuzzerType],
+    new: &InputConfig,
+) -> Vec<Sample> {
+    let mut unused: Vec<Option<Sample>> = old_samples.into_iter().map(Some).collect();
+    new_types
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| {
+            let same_position = (old_types.get(i) == Some(ty) && unused[i].is_some()).then_some(i);
+            let found = same_position
+                .or_else(|| (0..old_types.len()).find(|j| old_types[*j] == *ty && unused[*j].is_some()));
+            match found.and_then(|j| unused[j].take()) {
+                Some(sample) => convert(ty, sample, old, new),
+                None => parse_all(std::slice::from_ref(ty), &mut Unstructured::new(&[]), new).remove(0),
+            }
+        })
+        .collect()
+}
@@ -1,31 +1,137 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 
 use arbitrary::Unstructured;
 
-use move_binary_format::errors::VMResult;
+use move_binary_format::errors::{Location, VMError, VMResult};
+use move_binary_format::file_format::{CodeOffset, FunctionDefinitionIndex, SignatureToken, Visibility};
 use move_binary_format::CompiledModule;
 use move_core_types::account_address::AccountAddress;
 use move_core_types::identifier::IdentStr;
+use move_core_types::language_storage::{ModuleId, TypeTag};
 use move_core_types::runtime_value::serialize_values;
-use move_core_types::runtime_value::MoveValue;
-use move_core_types::vm_status::StatusCode;
+use move_core_types::runtime_value::{MoveTypeLayout, MoveValue};
+use move_core_types::vm_status::{StatusCode, StatusType};
 use move_vm_config::runtime::VMConfig;
 use move_vm_runtime::move_vm::MoveVM;
+use move_vm_runtime::session::{SerializedReturnValues, Session};
+use move_vm_test_utils::gas_schedule::{Gas, GasStatus, INITIAL_COST_SCHEDULE};
 use move_vm_types::gas::UnmeteredGasMeter;
 
+mod config;
+pub use crate::move_runner::config::RunnerConfig;
+
+mod coverage;
+use crate::move_runner::coverage::CoverageRecorder;
+
+mod comparisons;
+use crate::move_runner::comparisons::ComparisonSites;
+
+mod events;
+use crate::move_runner::events::{event_natives, EventStore};
+pub use crate::move_runner::events::MoveEvent;
+
+mod outcome;
+pub use crate::move_runner::outcome::{ExecutionOutcome, ExecutionStatus, ReturnValue};
+
+mod crash_policy;
+use crate::move_runner::crash_policy::{CrashPolicy, ErrorAction};
+
+mod hooks;
+use crate::move_runner::hooks::SharedHook;
+pub use crate::move_runner::hooks::ExecutionHook;
+
 mod utils;
-use crate::move_runner::utils::generate_abi_from_bin;
+use crate::move_runner::utils::{enumerate_instantiations, verify_modules};
+
+mod abi_cache;
+use crate::move_runner::abi_cache::cached_abi;
+
+mod source_maps;
+use crate::move_runner::source_maps::SourceMaps;
+
+mod error_constants;
+use crate::move_runner::error_constants::ErrorConstants;
 
 mod types;
 use crate::move_runner::types::FuzzerType as FuzzerType;
-use crate::move_runner::types::Error;
+use crate::move_runner::types::{Constructor, Error};
+pub use crate::move_runner::types::{StringGeneration, StructGeneration, TargetVisibility, TypeArgSelection};
+use crate::move_runner::types::Instantiation;
 
 mod arbitrary_inputs;
-use crate::move_runner::arbitrary_inputs::arbitrary_inputs;
+use crate::move_runner::arbitrary_inputs::{arbitrary_inputs, arbitrary_signers, InputConfig, InputLimits, PrimitivePlan, ValueConstructor};
+
+mod addresses;
+use crate::move_runner::addresses::{address_book, signer_pool};
+pub use crate::move_runner::addresses::AddressWeights;
+
+mod integers;
+use crate::move_runner::integers::SpecialInts;
+
+mod sui_objects;
+
+mod swarm;
+use crate::move_runner::swarm::SwarmProfile;
+pub use crate::move_runner::swarm::random_seed as random_swarm_seed;
+
+mod fixed_args;
+use crate::move_runner::fixed_args::{parse_fixed_args, splice_fixed_args};
+
+mod move_test;
+use crate::move_runner::move_test::{render_test, RecordingConstructor};
+
+mod shrink;
+
+mod encoding;
+use crate::move_runner::encoding::{legacy_config, split_header, stamp, ENCODING_VERSION, ENCODING_VERSION_PREFIX};
+
+mod crossover;
+
+mod mutation;
+use crate::move_runner::mutation::MutationKind;
+
+mod migrate;
+
+mod sequence;
+use crate::move_runner::sequence::{ModelCall, SequenceModel};
+
+mod buffers;
+use crate::move_runner::buffers::ArgBuffers;
+
+mod bench;
+use crate::move_runner::bench::PhaseTimings;
+pub use crate::move_runner::bench::BenchReport;
 
 mod module_manager;
 use self::module_manager::module_loader::ModuleLoader;
+use self::module_manager::fork::ForkedState;
 use self::module_manager::module_store::ModuleStore;
+use self::module_manager::snapshot::StorageSnapshot;
+
+/// Prefix of the `--describe` line giving `MoveRunner::max_input_size`, read
+/// by the CLI to set libFuzzer's `-max_len`.
+pub const MAX_INPUT_SIZE_PREFIX: &str = "Max input size: ";
+
+/// FNV-1a hash of an input, the same across builds so that an input replays
+/// with the instantiation it ran with in `hash` mode.
+fn input_hash(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3))
+}
+
+/// Most instantiations of a generic target fuzzed, the first byte of the
+/// input selecting one in `input` mode.
+const MAX_INSTANTIATIONS: usize = 256;
+
+/// libFuzzer's own largest `-max_len` default.
+const MAX_INPUT_SIZE_CAP: usize = 1 << 20;
 
 fn combine_signers_and_args(
     signers: Vec<AccountAddress>,
@@ -38,12 +144,189 @@ fn combine_signers_and_args(
         .collect()
 }
 
+/// Finds the module of a function given as `module::function` or
+/// `address::module::function` among the loaded modules, `what` naming the
+/// function in errors.
+fn find_module<'a>(modules: &'a [CompiledModule], path: &str, what: &str) -> Result<(&'a CompiledModule, String), String> {
+    let (module_path, function) = path
+        .rsplit_once("::")
+        .ok_or_else(|| format!("Invalid {} `{}`, expected `module::function`", what, path))?;
+    let (address, module_name) = match module_path.rsplit_once("::") {
+        Some((address, name)) => (Some(address), name),
+        None => (None, module_path),
+    };
+    let module = modules
+        .iter()
+        .find(|m| {
+            let id = m.self_id();
+            id.name().as_str() == module_name
+                && address.map_or(true, |a| AccountAddress::from_hex_literal(a).ok() == Some(*id.address()))
+        })
+        .ok_or_else(|| format!("Could not find the module of {} `{}`", what, path))?;
+    Ok((module, function.to_string()))
+}
+
+/// Resolves an invariant given as `module::function` or `address::module::function`
+/// among the loaded modules. The function must not take any parameter.
+fn find_invariant(modules: &[CompiledModule], invariant: &str) -> Result<(ModuleId, String), String> {
+    let (module, function) = find_module(modules, invariant, "invariant")?;
+    let takes_no_parameter = module.function_defs().iter().any(|def| {
+        let handle = module.function_handle_at(def.function);
+        module.identifier_at(handle.name).as_str() == function
+            && module.signature_at(handle.parameters).is_empty()
+    });
+    if !takes_no_parameter {
+        return Err(format!("Invariant `{}` must be a function without parameters", invariant));
+    }
+    Ok((module.self_id(), function))
+}
+
+/// Checks that the target function is accepted by the `visibility` policy.
+/// Executions bypass visibility, the policy keeps private helpers out of
+/// campaigns meant for the externally reachable functions.
+fn check_visibility(module: &CompiledModule, function: &str, visibility: TargetVisibility) -> Result<(), String> {
+    let def = module
+        .function_defs()
+        .iter()
+        .find(|def| module.identifier_at(module.function_handle_at(def.function).name).as_str() == function);
+    let def = match def {
+        Some(def) => def,
+        // Reported when resolving the target
+        None => return Ok(()),
+    };
+    let accepted = match visibility {
+        TargetVisibility::Public => def.visibility == Visibility::Public,
+        TargetVisibility::Entry => def.is_entry,
+        TargetVisibility::Any => true,
+    };
+    if !accepted {
+        let kind = if visibility == TargetVisibility::Public { "public" } else { "entry" };
+        return Err(format!(
+            "Target function `{}` is not {}, use --visibility=any to fuzz it anyway",
+            function, kind
+        ));
+    }
+    Ok(())
+}
+
+/// A function called before the target on every input, such as `init_module`.
+#[derive(Debug, Clone)]
+struct SetupCall {
+    module: ModuleId,
+    function: String,
+    /// Number of signer parameters, all given the address the module is published at
+    signers: usize,
+}
+
+/// Resolves a setup function given as `module::function` or
+/// `address::module::function` among the loaded modules. Its parameters can
+/// only be signers (or references to one).
+fn find_setup(modules: &[CompiledModule], setup: &str) -> Result<SetupCall, String> {
+    let (module, function) = find_module(modules, setup, "setup function")?;
+    let params = module
+        .function_defs()
+        .iter()
+        .map(|def| module.function_handle_at(def.function))
+        .find(|handle| module.identifier_at(handle.name).as_str() == function)
+        .map(|handle| module.signature_at(handle.parameters).0.clone())
+        .ok_or_else(|| format!("Could not find setup function `{}`", setup))?;
+    let is_signer = |token: &SignatureToken| match token {
+        SignatureToken::Signer => true,
+        SignatureToken::Reference(inner) => **inner == SignatureToken::Signer,
+        _ => false,
+    };
+    if !params.iter().all(is_signer) {
+        return Err(format!("Setup function `{}` can only take signers as parameters", setup));
+    }
+    Ok(SetupCall { module: module.self_id(), function, signers: params.len() })
+}
+
+/// Number of leading signer parameters of a target taking `params`, which
+/// the VM expects before the other arguments.
+fn leading_signers(params: &[FuzzerType]) -> usize {
+    params.iter().take_while(|param| **param == FuzzerType::Signer).count()
+}
+
+/// Status of a call that failed with `err`.
+fn failed_status(err: &VMError) -> ExecutionStatus {
+    match err.major_status() {
+        StatusCode::ABORTED => ExecutionStatus::Aborted(err.sub_status().unwrap_or_default()),
+        status => ExecutionStatus::Failed(status),
+    }
+}
+
+/// Decodes a value the VM serialized, reporting the ones the runner can't
+/// read instead of dropping them, so that positions keep matching the
+/// signature.
+fn decode_value(bytes: &[u8], layout: &MoveTypeLayout) -> ReturnValue {
+    match MoveValue::simple_deserialize(bytes, layout) {
+        Ok(value) => ReturnValue::Value(value),
+        Err(err) => {
+            let reason = format!("{}: {}", layout, err);
+            warn_unsupported_returns(&reason);
+            ReturnValue::Unsupported(reason)
+        }
+    }
+}
+
+/// Return values of a call and the values of its `&mut` parameters after it.
+fn decode_returns(values: &SerializedReturnValues) -> (Vec<ReturnValue>, Vec<(usize, ReturnValue)>) {
+    let returns = values
+        .return_values
+        .iter()
+        .map(|(bytes, layout)| decode_value(bytes, layout))
+        .collect();
+    let mutated = values
+        .mutable_reference_outputs
+        .iter()
+        .map(|(idx, bytes, layout)| (*idx as usize, decode_value(bytes, layout)))
+        .collect();
+    (returns, mutated)
+}
+
+/// Warns, once per process, that the target returns values the runner
+/// can't decode; the executions still count as returned.
+fn warn_unsupported_returns(reason: &str) {
+    static WARNED: AtomicBool = AtomicBool::new(false);
+    if !WARNED.swap(true, Ordering::Relaxed) {
+        log::warn!("The target returns values that can't be decoded, they are reported as unsupported: {}", reason);
+    }
+}
+
+/// Builds struct arguments by calling their constructors in the current session.
+struct SessionConstructor<'s, 'r, 'l> {
+    session: &'s mut Session<'r, 'l, ModuleStore>,
+}
+
+impl ValueConstructor for SessionConstructor<'_, '_, '_> {
+    fn construct(&mut self, constructor: &Constructor, args: Vec<MoveValue>) -> Result<MoveValue, Error> {
+        let result = self.session.execute_function_bypass_visibility(
+            &constructor.module,
+            IdentStr::new(&constructor.function).unwrap(),
+            vec![],
+            serialize_values(&args),
+            &mut UnmeteredGasMeter
+        ).map_err(|err| Error::ConstructorFailed {
+            message: format!("{}::{} - {:?}", constructor.module, constructor.function, err.major_status())
+        })?;
+
+        let (bytes, layout) = result.return_values.into_iter().next().ok_or_else(|| Error::ConstructorFailed {
+            message: format!("{}::{} returned no value", constructor.module, constructor.function)
+        })?;
+        MoveValue::simple_deserialize(&bytes, &layout).map_err(|err| Error::ConstructorFailed { message: err.to_string() })
+    }
+}
 
 /// todo
 #[derive(Debug, Clone)]
 pub struct TargetFunction {
     name: String,
     args: Vec<FuzzerType>,
+    /// `Some(mutable)` for the parameters taken by reference
+    references: Vec<Option<bool>>,
+    /// Number of leading `signer` and `&signer` parameters, the accounts
+    /// signing the call, picked from the signer pool
+    signers: usize,
     // type_args: Option<Vec<FuzzerType>> // todo: capire se si possono implementare i type arguments
 }
 
@@ -55,6 +338,49 @@ pub struct MoveRunner {
     target_module: String,
     target_function: TargetFunction,
     max_coverage: usize,
+    config: RunnerConfig,
+    /// Shared by the runners forked for other threads, as the VM traces the
+    /// whole process in a single file
+    coverage: Option<Arc<Mutex<CoverageRecorder>>>,
+    /// Function called after every successful execution of the target
+    invariant: Option<(ModuleId, String)>,
+    /// Values of the parameters pinned with `--fix`, by position
+    fixed_args: BTreeMap<usize, MoveValue>,
+    /// Address pools and integer biases used to generate the arguments
+    input_config: InputConfig,
+    events: EventStore,
+    /// Callbacks invoked around each execution, shared with the forked runners
+    hooks: Vec<SharedHook>,
+    /// Which failed executions are crashes, rejected or ignored
+    crash_policy: CrashPolicy,
+    /// On-chain state read by the executions, shared with the forked runners
+    fork: Option<Arc<ForkedState>>,
+    /// Model the inputs are interpreted with as call sequences, instead of
+    /// arguments of the target
+    model: Option<Arc<SequenceModel>>,
+    /// Functions called in order at the start of every execution
+    setup: Vec<SetupCall>,
+    /// State the setup functions left, once taken executions start from it
+    /// instead of calling them
+    snapshot: Option<Arc<StorageSnapshot>>,
+    /// Time spent in each phase of the executions, measured while benchmarking
+    timings: Option<PhaseTimings>,
+    /// Serializes the arguments as they are generated, when the target only
+    /// takes primitives
+    primitive_plan: Option<PrimitivePlan>,
+    /// Serialized arguments of the target, reused across executions
+    arg_buffers: ArgBuffers,
+    /// Source lines of the loaded modules, when the build output has them
+    source_maps: Arc<SourceMaps>,
+    /// Names of the abort codes of the loaded modules
+    error_constants: Arc<ErrorConstants>,
+    /// Instantiations of a generic target, empty unless `--instantiate` is set
+    instantiations: Arc<Vec<Instantiation>>,
+    /// Index of the instantiation `target_function` holds the parameters of
+    instantiation: usize,
+    /// Hash of the last input the mutator of this thread produced, which
+    /// libFuzzer mutates again when chaining mutations
+    last_mutated: Option<u64>,
 }
 
 impl Debug for MoveRunner {
@@ -63,16 +389,136 @@ impl Debug for MoveRunner {
     }
 }
 
+/// A VM recording the emitted events in the returned store.
+fn new_vm() -> (MoveVM, EventStore) {
+    let events = EventStore::default();
+    let move_vm = MoveVM::new_with_config(event_natives(&events), VMConfig::default()).unwrap();
+    (move_vm, events)
+}
+
 impl MoveRunner {
-    /// todo
-    pub fn new(module_path: &str, target_module: &str, target_function: &str) -> Self {
-        let move_vm = MoveVM::new_with_config(vec![], VMConfig::default()).unwrap();
+    /// Loads the target module and its dependencies and resolves the target
+    /// function, failing with a message fit for the user if any is missing or
+    /// an option doesn't apply to the target.
+    pub fn new(module_path: &str, target_module: &str, target_function: &str, config: RunnerConfig) -> Result<Self, String> {
+        let (move_vm, events) = new_vm();
         // Loading compiled module
-        let mut module_loader = ModuleLoader::new(String::from(module_path));
-        module_loader.load_depencencies();
+        let mut module_loader = ModuleLoader::new(String::from(module_path), config.bytecode_version)?;
+        module_loader.load_depencencies()?;
+        module_loader.relocate(&config.addresses)?;
+        verify_modules(&module_loader.get_all(), &VMConfig::default())?;
+        check_visibility(&module_loader.get_module(), target_function, config.visibility)?;
 
-        let params = generate_abi_from_bin(module_loader.get_all(), target_module, target_function);
-        MoveRunner {
+        let mut params = cached_abi(
+            config.abi_cache.as_deref(),
+            module_loader.get_all(),
+            target_module,
+            target_function,
+            config.struct_generation,
+            config.string_generation,
+            config.raw_std_types,
+        )?;
+        let instantiations = match config.instantiate {
+            TypeArgSelection::None => vec![],
+            _ => enumerate_instantiations(
+                module_loader.get_all(),
+                target_module,
+                target_function,
+                config.struct_generation,
+                config.string_generation,
+                config.raw_std_types,
+                MAX_INSTANTIATIONS,
+            )?,
+        };
+        if let Some(first) = instantiations.first() {
+            if !config.fixed_args.is_empty() {
+                return Err(String::from("Parameters of a generic target can't be fixed with --instantiate"));
+            }
+            log::info!("Fuzzing {} instantiations of `{}`", instantiations.len(), target_function);
+            params.0 = first.params.clone();
+            params.1 = first.references.clone();
+        }
+        let invariant = config
+            .invariant
+            .as_deref()
+            .map(|invariant| find_invariant(&module_loader.get_all(), invariant))
+            .transpose()?;
+        let fixed_args = parse_fixed_args(&params.0, &config.fixed_args)?;
+        let crash_policy = CrashPolicy::parse(&config.error_actions)?;
+        // Targets taking more values than the limit are rejected by the generic generation
+        let primitive_plan = PrimitivePlan::new(&params.0, &fixed_args)
+            .filter(|_| params.0.len() - fixed_args.len() <= config.max_input_values);
+        let setup = config
+            .setup_functions
+            .iter()
+            .map(|setup| find_setup(&module_loader.get_all(), setup))
+            .collect::<Result<Vec<_>, _>>()?;
+        let model = config
+            .model
+            .as_deref()
+            .map(|path| {
+                SequenceModel::load(
+                    path,
+                    &module_loader.get_all(),
+                    config.struct_generation,
+                    config.string_generation,
+                    config.raw_std_types,
+                )
+                    .map(Arc::new)
+            })
+            .transpose()?;
+        let fork = config
+            .fork_url
+            .as_deref()
+            .map(|url| ForkedState::new(url, config.fork_version, config.fork_cache.clone()).map(Arc::new))
+            .transpose()?;
+        let profile = match config.swarm_seed {
+            Some(seed) => {
+                let profile = SwarmProfile::sample(seed);
+                log::info!("Swarm profile (--swarm-seed={}): {}", seed, profile);
+                profile
+            }
+            None => SwarmProfile {
+                vector_end: 128,
+                special_int_percent: config.special_int_percent,
+                address_weights: config.address_weights,
+            },
+        };
+        let input_config = InputConfig {
+            signers: signer_pool(
+                &config.signers,
+                *module_loader.get_module().self_id().address(),
+                config.test_signers,
+                config.random_signers,
+            )?,
+            addresses: address_book(&module_loader.get_all(), profile.address_weights),
+            special_ints: SpecialInts::new(profile.special_int_percent),
+            limits: InputLimits {
+                max_depth: config.max_input_depth,
+                max_values: config.max_input_values,
+            },
+            vector_end: profile.vector_end,
+        };
+        let source_maps = Arc::new(SourceMaps::load(Path::new(module_path), &module_loader.get_all()));
+        let error_constants = Arc::new(ErrorConstants::load(&source_maps, &module_loader.get_all()));
+        let covered_module = (!config.include_deps).then(|| module_loader.get_module().self_id());
+        let coverage = config.coverage_dir.as_deref().map(|dir| {
+            let mut recorder = CoverageRecorder::new(dir, target_module, target_function, covered_module);
+            // Only the comparisons of the modules the map covers can be seen stuck
+            let compared = if config.include_deps { module_loader.get_all() } else { vec![module_loader.get_module()] };
+            recorder.learn_comparisons(ComparisonSites::load(&compared, &input_config));
+            recorder.stats_mut().swarm_seed = config.swarm_seed;
+            Arc::new(Mutex::new(recorder))
+        });
+        let signers = leading_signers(&params.0);
+        if let Some(accounts) = input_config.signers.distinct().filter(|&accounts| accounts < signers) {
+            log::warn!(
+                "The target takes {} signers but the signer pool has {} accounts, some will sign twice; add accounts with --signer or --test-signers",
+                signers,
+                accounts
+            );
+        }
+        Ok(MoveRunner {
             move_vm,
             module: module_loader.get_module(),
             dependencies: module_loader.get_dependencies(),
@@ -80,10 +526,130 @@ impl MoveRunner {
             target_function: TargetFunction {
                 name: String::from(target_function),
                 args: params.0,
+                references: params.1,
+                signers,
                 //type_args: None,
             },
-            max_coverage: params.1,
+            max_coverage: params.2,
+            config,
+            coverage,
+            invariant,
+            fixed_args,
+            input_config,
+            events,
+            hooks: vec![],
+            crash_policy,
+            fork,
+            model,
+            setup,
+            snapshot: None,
+            timings: None,
+            primitive_plan,
+            arg_buffers: ArgBuffers::default(),
+            source_maps,
+            error_constants,
+            instantiations: Arc::new(instantiations),
+            instantiation: 0,
+            last_mutated: None,
+        })
+    }
+
+    /// Registers a hook invoked around every execution of this runner and of
+    /// the runners forked from it afterwards.
+    pub fn add_hook(&mut self, hook: impl ExecutionHook + 'static) {
+        self.hooks.push(Arc::new(Mutex::new(hook)));
+    }
+
+    /// A runner for another thread, sharing the loaded modules and settings
+    /// of this one but executing in its own VM.
+    pub fn fork(&self) -> Self {
+        let (move_vm, events) = new_vm();
+        MoveRunner {
+            move_vm,
+            module: self.module.clone(),
+            dependencies: self.dependencies.clone(),
+            target_module: self.target_module.clone(),
+            target_function: self.target_function.clone(),
+            max_coverage: self.max_coverage,
+            config: self.config.clone(),
+            coverage: self.coverage.clone(),
+            invariant: self.invariant.clone(),
+            fixed_args: self.fixed_args.clone(),
+            input_config: self.input_config.clone(),
+            events,
+            hooks: self.hooks.clone(),
+            crash_policy: self.crash_policy.clone(),
+            fork: self.fork.clone(),
+            model: self.model.clone(),
+            setup: self.setup.clone(),
+            snapshot: self.snapshot.clone(),
+            timings: None,
+            primitive_plan: self.primitive_plan.clone(),
+            arg_buffers: ArgBuffers::default(),
+            source_maps: self.source_maps.clone(),
+            error_constants: self.error_constants.clone(),
+            instantiations: self.instantiations.clone(),
+            instantiation: self.instantiation,
+            last_mutated: None,
+        }
+    }
+
+    /// Instantiation of a generic target the execution of `bytes` uses, and
+    /// the length of the selector byte preceding the arguments in `input` mode.
+    fn selected_instantiation(&self, bytes: &[u8]) -> (usize, usize) {
+        match (self.config.instantiate, bytes.first()) {
+            _ if self.instantiations.is_empty() => (0, 0),
+            (TypeArgSelection::Input, Some(selector)) => (*selector as usize % self.instantiations.len(), 1),
+            (TypeArgSelection::Hash, _) => ((input_hash(bytes) % self.instantiations.len() as u64) as usize, 0),
+            _ => (self.instantiation, 0),
+        }
+    }
+
+    /// Switches the target parameters to those of instantiation `index`.
+    fn use_instantiation(&mut self, index: usize) {
+        if index == self.instantiation || index >= self.instantiations.len() {
+            return;
         }
+        let instantiations = self.instantiations.clone();
+        let instantiation = &instantiations[index];
+        self.target_function.args = instantiation.params.clone();
+        self.target_function.references = instantiation.references.clone();
+        self.target_function.signers = leading_signers(&instantiation.params);
+        self.primitive_plan = PrimitivePlan::new(&instantiation.params, &self.fixed_args)
+            .filter(|_| instantiation.params.len() <= self.config.max_input_values);
+        self.instantiation = index;
+    }
+
+    /// Switches to the instantiation `bytes` selects, returning the bytes the
+    /// arguments are decoded from.
+    fn instantiate<'b>(&mut self, bytes: &'b [u8]) -> &'b [u8] {
+        let (index, selector) = self.selected_instantiation(bytes);
+        self.use_instantiation(index);
+        &bytes[selector..]
+    }
+
+    /// The arguments `bytes` encodes, in the current encoding, see
+    /// `encoding::upgrade`; inputs without a header were written with
+    /// `--encoding-version`. `None` when the version is unknown. Published
+    /// modules, call sequences and the arguments of generic targets postdate
+    /// the earlier versions and are passed through.
+    fn upgrade<'b>(&self, bytes: &'b [u8]) -> Option<Cow<'b, [u8]>> {
+        let headerless = self.config.encoding_version.unwrap_or(ENCODING_VERSION);
+        if self.config.publish || self.model.is_some() || !self.instantiations.is_empty() {
+            let (version, payload) = split_header(bytes, headerless);
+            let known = version == ENCODING_VERSION || legacy_config(version, &self.input_config).is_some();
+            return known.then_some(Cow::Borrowed(payload));
+        }
+        encoding::upgrade(&self.get_target_parameters(), bytes, headerless, &self.input_config)
+    }
+
+    /// Type arguments of the current instantiation, empty for a target that
+    /// isn't generic.
+    fn type_args(&self) -> Vec<TypeTag> {
+        self.instantiations
+            .get(self.instantiation)
+            .map(|instantiation| instantiation.type_args.clone())
+            .unwrap_or_default()
     }
 
     // todo: capire se il coverage che c'è adesso funziona uguale
@@ -98,51 +664,918 @@ impl MoveRunner {
     //     }
     // }
 
+    fn new_gas_meter(&self) -> GasStatus<'static> {
+        match self.config.gas_limit {
+            Some(limit) => GasStatus::new(&INITIAL_COST_SCHEDULE, Gas::new(limit)),
+            None => GasStatus::new_unmetered(),
+        }
+    }
+
+    fn new_store(&self) -> ModuleStore {
+        let mut remote_view = ModuleStore::new(self.module.clone());
+        remote_view.add_dependencies(&self.dependencies);
+        remote_view.set_fork(self.fork.clone());
+        remote_view.set_snapshot(self.snapshot.clone());
+        remote_view
+    }
+
+    /// Formats a code location as `address::module::function+offset`,
+    /// followed by its source line as ` (file.move:line)` when the build
+    /// output has the source map of the module.
+    fn format_code_location(&self, id: &ModuleId, fdef_idx: FunctionDefinitionIndex, offset: CodeOffset) -> String {
+        let module = std::iter::once(&self.module)
+            .chain(self.dependencies.iter())
+            .find(|m| &m.self_id() == id);
+        let location = match module {
+            Some(module) if (fdef_idx.0 as usize) < module.function_defs().len() => {
+                let fdef = module.function_def_at(fdef_idx);
+                let name = module.identifier_at(module.function_handle_at(fdef.function).name);
+                format!("{}::{}+{}", id, name, offset)
+            }
+            _ => return format!("{}::<function {}>+{}", id, fdef_idx.0, offset),
+        };
+        match self.source_maps.line(id, fdef_idx, offset) {
+            Some(line) => format!("{} ({})", location, line),
+            None => location,
+        }
+    }
+
+    /// Formats where an error happened as `address::module::function+offset`.
+    fn error_location(&self, err: &VMError) -> String {
+        match err.location() {
+            Location::Module(id) => match err.offsets().first() {
+                Some((fdef_idx, offset)) => self.format_code_location(id, *fdef_idx, *offset),
+                None => id.to_string(),
+            },
+            Location::Undefined => String::from("undefined"),
+        }
+    }
+
+    /// Describes a VM error over multiple lines: status, location and, when the
+    /// VM recorded it, the call stack.
+    fn format_vm_error(&self, err: &VMError) -> Vec<String> {
+        let mut lines = vec![];
+        match err.sub_status() {
+            Some(sub_status) => lines.push(format!("VM status: {:?} (sub status {})", err.major_status(), sub_status)),
+            None => lines.push(format!("VM status: {:?}", err.major_status())),
+        }
+        if let Some(message) = err.message() {
+            lines.push(format!("Message: {}", message));
+        }
+        lines.push(format!("Location: {}", self.error_location(err)));
+        if let Some(state) = err.exec_state() {
+            lines.push(String::from("Stack trace:"));
+            for (id, fdef_idx, offset) in state.stack_trace().iter().rev() {
+                lines.push(format!("    {}", self.format_code_location(id, *fdef_idx, *offset)));
+            }
+        }
+        lines
+    }
+
+    /// Name of the error constant an abort code raised in `id` stands for.
+    fn error_constant(&self, id: &ModuleId, code: u64) -> Option<String> {
+        std::iter::once(&self.module)
+            .chain(self.dependencies.iter())
+            .find(|m| &m.self_id() == id)
+            .and_then(|module| self.error_constants.name(module, code))
+    }
+
+    fn error_message(&self, err: &VMError) -> String {
+        let location = self.error_location(err);
+        let constant = match (err.major_status(), err.sub_status(), err.location()) {
+            (StatusCode::ABORTED, Some(code), Location::Module(id)) => self.error_constant(id, code),
+            _ => None,
+        };
+        match (err.major_status(), err.sub_status(), err.message()) {
+            // Named as `code E_INSUFFICIENT_BALANCE (5) in ...` when the constant is known
+            (StatusCode::ABORTED, Some(code), _) => match constant {
+                Some(constant) => format!("code {} ({}) in {}", constant, code, location),
+                None => format!("code {} in {}", code, location),
+            },
+            (StatusCode::ABORTED, None, _)
+            | (StatusCode::ARITHMETIC_ERROR, _, None)
+            | (StatusCode::MEMORY_LIMIT_EXCEEDED, _, None)
+            | (StatusCode::OUT_OF_GAS, _, None) => format!("in {}", location),
+            (StatusCode::ARITHMETIC_ERROR, _, Some(m))
+            | (StatusCode::MEMORY_LIMIT_EXCEEDED, _, Some(m))
+            | (StatusCode::OUT_OF_GAS, _, Some(m)) => format!("{} in {}", m, location),
+            (status, _, Some(m)) => format!("{:?} - {} in {}", status, m, location),
+            (status, _, None) => format!("{:?} in {}", status, location),
+        }
+    }
+
+    /// Decodes `bytes` into the target arguments, formatted one per line.
+    pub fn format_arguments(&mut self, bytes: &[u8]) -> String {
+        let bytes = match self.upgrade(bytes) {
+            Some(bytes) => bytes,
+            None => return "Invalid input: unknown encoding version\n".to_string(),
+        };
+        let bytes = self.instantiate(&bytes);
+        let remote_view = self.new_store();
+        let mut session = self.move_vm.new_session(&remote_view);
+        let mut data = Unstructured::new(bytes);
+        match self.target_arguments(&mut data, &mut SessionConstructor { session: &mut session }) {
+            Ok(args) => args
+                .iter()
+                .enumerate()
+                .map(|(i, arg)| format!("arg{}: {}\n", i, arg))
+                .collect(),
+            Err(e) => format!("Invalid input: {}\n", e),
+        }
+    }
+
+    /// Runs the target on the arguments decoded from `bytes` and formats what
+    /// the execution produced, for the debug output.
+    pub fn format_outcome(&mut self, bytes: &[u8]) -> String {
+        let bytes = match self.upgrade(bytes) {
+            Some(bytes) => bytes,
+            None => return String::new(),
+        };
+        match self.run_target(&bytes, false) {
+            Ok(Some(outcome)) => outcome.to_string(),
+            Err((Some(outcome), error)) => format!("{}Error: {}\n", outcome, error),
+            Ok(None) | Err((None, _)) => String::new(),
+        }
+    }
+
+    /// Generates a Move unit test calling the target with the arguments decoded from `bytes`.
+    pub fn move_test(&mut self, bytes: &[u8], test_name: &str) -> String {
+        let bytes = match self.upgrade(bytes) {
+            Some(bytes) => bytes,
+            None => return "// Invalid input: unknown encoding version\n".to_string(),
+        };
+        let bytes = self.instantiate(&bytes);
+        let remote_view = self.new_store();
+        let mut session = self.move_vm.new_session(&remote_view);
+        if let Err(e) = self.run_setup(&mut session) {
+            return format!("// {}\n", e);
+        }
+        let mut data = Unstructured::new(bytes);
+        let mut session_constructor = SessionConstructor { session: &mut session };
+        let mut recorder = RecordingConstructor::new(&mut session_constructor);
+
+        let args = match self.target_arguments(&mut data, &mut recorder) {
+            Ok(args) => args,
+            Err(e) => return format!("// Invalid input: {}\n", e),
+        };
+        let rendered = recorder.render_all(&self.target_function.args, &args);
+        render_test(
+            &self.module.self_id(),
+            &self.target_function.name,
+            test_name,
+            &self.target_function.references,
+            &args,
+            rendered,
+        )
+    }
+
+    /// Whether the target function returns a single `bool`, as `--oracle-bool` requires.
+    pub fn returns_bool(&self) -> bool {
+        self.module.function_defs().iter().any(|def| {
+            let handle = self.module.function_handle_at(def.function);
+            self.module.identifier_at(handle.name).as_str() == self.target_function.name
+                && self.module.signature_at(handle.return_).0 == vec![SignatureToken::Bool]
+        })
+    }
+
+    /// Types of the parameters generated from the input, i.e. the ones not fixed.
     fn get_target_parameters(&self) -> Vec<FuzzerType> {
-        self.target_function.args.clone()
+        self.target_function
+            .args
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.fixed_args.contains_key(i))
+            .map(|(_, t)| t.clone())
+            .collect()
+    }
+
+    /// Generates the parameters not fixed from `data` and splices in the fixed
+    /// ones. The signers come first, picked from the signer pool.
+    fn target_arguments(&self, data: &mut Unstructured, constructor: &mut dyn ValueConstructor) -> Result<Vec<MoveValue>, Error> {
+        let params = self.get_target_parameters();
+        let signers = (0..self.target_function.signers)
+            .filter(|i| !self.fixed_args.contains_key(i))
+            .count();
+        let mut generated = arbitrary_signers(data, &self.input_config, signers)?;
+        generated.extend(arbitrary_inputs(params[signers..].to_vec(), data, &self.input_config, constructor)?);
+        Ok(splice_fixed_args(&self.fixed_args, generated))
+    }
+
+    /// Returns the first parameter whose values can't be generated, if any.
+    pub fn unsupported_parameter(&self) -> Option<(usize, &FuzzerType)> {
+        self.target_function
+            .args
+            .iter()
+            .enumerate()
+            .find(|(i, t)| !self.fixed_args.contains_key(i) && t.is_unsupported())
+    }
+
+    /// Bound on the input bytes the target arguments consume, counting
+    /// `--vector-budget` elements per vector, capped at 1 MiB. Published
+    /// modules and call sequences aren't bound by the target's parameters,
+    /// they get the cap.
+    pub fn max_input_size(&self) -> usize {
+        if self.config.publish || self.model.is_some() {
+            return MAX_INPUT_SIZE_CAP;
+        }
+        let size = |params: &[FuzzerType]| {
+            params
+                .iter()
+                .map(|t| t.max_input_size(self.config.vector_budget))
+                .fold(0, usize::saturating_add)
+        };
+        let size = match self.config.instantiate {
+            _ if self.instantiations.is_empty() => size(&self.get_target_parameters()),
+            // The selector byte, then the arguments of the largest instantiation
+            TypeArgSelection::Input => 1 + self.instantiations.iter().map(|i| size(&i.params)).max().unwrap_or(0),
+            _ => self.instantiations.iter().map(|i| size(&i.params)).max().unwrap_or(0),
+        };
+        size.clamp(1, MAX_INPUT_SIZE_CAP)
     }
 
-    /// todo
+    /// Describes how inputs are decoded into the target arguments.
+    pub fn describe(&self) -> String {
+        let mut description = format!("Target: {}::{}\n", self.target_module, self.target_function.name);
+        if !self.instantiations.is_empty() {
+            let selection = match self.config.instantiate {
+                TypeArgSelection::Input => "selected by the first input byte",
+                _ => "selected by a hash of the input",
+            };
+            description.push_str(&format!("Instantiations ({}):\n", selection));
+            for (i, instantiation) in self.instantiations.iter().enumerate() {
+                let type_args = instantiation.type_args.iter().map(ToString::to_string).collect::<Vec<_>>();
+                description.push_str(&format!("    {}: <{}>\n", i, type_args.join(", ")));
+            }
+        }
+        description.push_str("Parameters:\n");
+        for (i, param) in self.target_function.args.iter().enumerate() {
+            if let Some(value) = self.fixed_args.get(&i) {
+                description.push_str(&format!("    arg{}: {} (fixed to {})\n", i, param, value));
+                continue;
+            }
+            let size = if param.is_unsupported() {
+                String::from("unsupported")
+            } else if param.is_fixed_size() {
+                format!("{} bytes", param.min_input_size())
+            } else {
+                format!("at least {} bytes", param.min_input_size())
+            };
+            description.push_str(&format!("    arg{}: {} ({})\n", i, param, size));
+        }
+        if self.target_function.signers > 0 {
+            description.push_str(&format!(
+                "Signers: {} (arg0..arg{}, picked from the signer pool)\n",
+                self.target_function.signers,
+                self.target_function.signers - 1
+            ));
+        }
+        let params = self.get_target_parameters();
+        let min_size: usize = params.iter().map(|t| t.min_input_size()).sum();
+        let fixed = params.iter().all(|t| t.is_fixed_size());
+        description.push_str(&format!(
+            "Input size: {}{} bytes\n",
+            if fixed { "" } else { "at least " },
+            min_size
+        ));
+        description.push_str(&format!("{}{}\n", MAX_INPUT_SIZE_PREFIX, self.max_input_size()));
+        description.push_str(&format!("{}{}\n", ENCODING_VERSION_PREFIX, ENCODING_VERSION));
+        description
+    }
+
+    /// Runs the target function on the arguments decoded from `bytes`.
+    ///
+    /// Returns `Ok(None)` when no valid arguments could be built from the input
+    /// (e.g. a struct constructor aborted, or its encoding version is unknown),
+    /// so the target was not executed, and `Err((None, _))` when the input
+    /// should be rejected from the corpus: it exceeds the generation limits,
+    /// or the crash policy rejects its error.
+    /// Errors the crash policy ignores are returned as successful executions.
     pub fn execute(
         &mut self,
         bytes: &[u8]
-    ) -> Result<Option<()>, (Option<()>, Error)> {
-        let inputs = self.get_target_parameters();
-        let mut remote_view = ModuleStore::new(self.module.clone());
-        remote_view.add_dependencies(&self.dependencies);
+    ) -> Result<Option<ExecutionOutcome>, (Option<ExecutionOutcome>, Error)> {
+        let result = match self.upgrade(bytes).map(|bytes| self.run_target(&bytes, true)) {
+            Some(Err((Some(outcome), error))) => match self.crash_policy.action(&error) {
+                ErrorAction::Crash => Err((Some(outcome), error)),
+                ErrorAction::Reject => Err((None, error)),
+                ErrorAction::Ignore => Ok(Some(outcome)),
+            },
+            Some(result) => result,
+            // Written by a later release
+            None => Ok(None),
+        };
+        if let Some(coverage) = &self.coverage {
+            let mut coverage = coverage.lock().unwrap_or_else(PoisonError::into_inner);
+            let stats = coverage.stats_mut();
+            match &result {
+                Ok(None) => stats.undecodable += 1,
+                Err((None, _)) => stats.rejected += 1,
+                Err((Some(_), _)) => stats.crashes += 1,
+                Ok(Some(_)) => {}
+            }
+            stats.arguments += self.arg_buffers.count() as u64;
+            stats.argument_bytes += self.arg_buffers.total_bytes() as u64;
+            coverage.record_execution();
+        }
+        result
+    }
+
+    /// Executes `runs` inputs taken in turn from `inputs`, timing each phase
+    /// of the executions.
+    pub fn bench(&mut self, inputs: &[Vec<u8>], runs: usize) -> BenchReport {
+        self.timings = Some(PhaseTimings::default());
+        let start = Instant::now();
+        for bytes in inputs.iter().cycle().take(runs) {
+            let _ = self.execute(bytes);
+        }
+        // The coverage is written as it would be over a campaign
+        self.flush_coverage();
+        let elapsed = start.elapsed();
+        BenchReport {
+            executions: if inputs.is_empty() { 0 } else { runs },
+            elapsed,
+            timings: self.timings.take().unwrap_or_default(),
+        }
+    }
+
+    /// Whether the details of `error` are printed: only crashes are reported.
+    fn reports(&self, report: bool, error: &Error) -> bool {
+        report && self.crash_policy.action(error) == ErrorAction::Crash
+    }
+
+    /// Writes the Move coverage recorded since the last periodic flush.
+    pub fn flush_coverage(&self) {
+        if let Some(coverage) = &self.coverage {
+            coverage.lock().unwrap_or_else(PoisonError::into_inner).flush();
+        }
+    }
+
+    /// Shrinks the arguments decoded from a crashing input (halving integers,
+    /// dropping vector elements, zeroing addresses...) as long as the same error
+    /// is raised, trying at most `attempts` candidates. Returns the encoding of
+    /// the smallest arguments found in the current encoding, or `bytes` if the
+    /// input doesn't crash.
+    pub fn minimize(&mut self, bytes: &[u8], attempts: u32) -> Vec<u8> {
+        let input = bytes;
+        let bytes = match self.upgrade(input) {
+            Some(bytes) => bytes,
+            None => return input.to_vec(),
+        };
+        let bytes: &[u8] = &bytes;
+        let original = match self.run_target(bytes, false) {
+            Err((Some(_), error)) => error,
+            _ => return bytes.to_vec(),
+        };
+        // The selector byte of the instantiation is kept as is
+        let (instantiation, _) = self.selected_instantiation(bytes);
+        let arguments = self.instantiate(bytes);
+        let selector = bytes[..bytes.len() - arguments.len()].to_vec();
+        let params = self.get_target_parameters();
+        let input_config = self.input_config.clone();
+        let encode = |samples: &[shrink::Sample]| {
+            [selector.clone(), shrink::encode_all(&params, samples, &input_config)].concat()
+        };
+        let mut current = shrink::parse_all(&params, &mut Unstructured::new(arguments), &self.input_config);
+        let mut best = encode(&current);
+        if self.selected_instantiation(&best).0 != instantiation
+            || !matches!(self.run_target(&best, false), Err((_, ref e)) if *e == original)
+        {
+            best = bytes.to_vec();
+        }
+
+        let mut attempts_left = attempts;
+        'shrink: while attempts_left > 0 {
+            for candidate in shrink::shrink_all(&params, &current, &self.input_config) {
+                if shrink::compare(&params, &candidate, &current, &self.input_config) != std::cmp::Ordering::Less {
+                    continue;
+                }
+                // In `hash` mode the bytes select the instantiation, which must stay the same
+                let encoded = encode(&candidate);
+                if self.selected_instantiation(&encoded).0 != instantiation {
+                    continue;
+                }
+                if attempts_left == 0 {
+                    break 'shrink;
+                }
+                attempts_left -= 1;
+
+                if matches!(self.run_target(&encoded, false), Err((_, ref e)) if *e == original) {
+                    current = candidate;
+                    best = encoded;
+                    continue 'shrink;
+                }
+            }
+            break;
+        }
+        best
+    }
+
+    /// Recombines the arguments decoded from two inputs into a new input of at
+    /// most `max_size` bytes, see `crossover::crossover`.
+    pub fn crossover(&self, a: &[u8], b: &[u8], max_size: usize, seed: u32) -> Vec<u8> {
+        let (a, b) = match (self.upgrade(a), self.upgrade(b)) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return a[..a.len().min(max_size)].to_vec(),
+        };
+        let (a, b): (&[u8], &[u8]) = (&a, &b);
+        let ((index, selector), (other, _)) = (self.selected_instantiation(a), self.selected_instantiation(b));
+        if index != other {
+            // Arguments of different instantiations don't mix
+            return a[..a.len().min(max_size)].to_vec();
+        }
+        let params = match self.instantiations.get(index) {
+            Some(instantiation) => instantiation.params.clone(),
+            None => self.get_target_parameters(),
+        };
+        let (prefix, a) = a.split_at(selector);
+        let a = shrink::parse_all(&params, &mut Unstructured::new(a), &self.input_config);
+        let b = shrink::parse_all(&params, &mut Unstructured::new(&b[selector..]), &self.input_config);
+        let child = crossover::crossover(&params, &a, &b, seed);
+        let mut bytes = [prefix.to_vec(), shrink::encode_all(&params, &child, &self.input_config)].concat();
+        bytes.truncate(max_size);
+        bytes
+    }
+
+    /// Mutates `data[..size]` into at most `max_size` bytes, returning the new
+    /// size: half of the time one of its decoded arguments is mutated, see
+    /// `mutation::mutate`, otherwise and when that fails `bytes`, libFuzzer's
+    /// own mutator, mutates the raw bytes so that its dictionaries and
+    /// comparison tracing keep being used. The kinds of the mutations are
+    /// counted in the statistics, see `CoverageRecorder::record_mutation`.
+    pub fn mutate(
+        &mut self,
+        data: &mut [u8],
+        size: usize,
+        max_size: usize,
+        seed: u32,
+        bytes: impl FnOnce(&mut [u8], usize, usize) -> usize,
+    ) -> usize {
+        let parent = input_hash(&data[..size]);
+        let mutated = match seed % 2 {
+            0 => self.mutate_arguments(&data[..size], max_size, seed / 2),
+            _ => None,
+        };
+        let (size, kind) = match mutated {
+            Some((child, kind)) => {
+                data[..child.len()].copy_from_slice(&child);
+                (child.len(), kind)
+            }
+            None => (bytes(data, size, max_size), MutationKind::Bytes),
+        };
+        let child = input_hash(&data[..size]);
+        if let Some(coverage) = &self.coverage {
+            // A chained mutation starts from the last input, not from the corpus
+            let parent = Some(parent).filter(|parent| self.last_mutated != Some(*parent));
+            coverage.lock().unwrap_or_else(PoisonError::into_inner).record_mutation(parent, child, kind);
+        }
+        self.last_mutated = Some(child);
+        size
+    }
+
+    /// The arguments decoded from `bytes` with one value mutated, encoded in
+    /// the current encoding. `None` if no value can be mutated, the result is
+    /// longer than `max_size` or would select another instantiation, and for
+    /// inputs which aren't arguments of the target.
+    fn mutate_arguments(&self, bytes: &[u8], max_size: usize, seed: u32) -> Option<(Vec<u8>, MutationKind)> {
+        if self.config.publish || self.model.is_some() {
+            return None;
+        }
+        let bytes = self.upgrade(bytes)?;
+        let (index, selector) = self.selected_instantiation(&bytes);
+        let params = match self.instantiations.get(index) {
+            Some(instantiation) => instantiation.params.clone(),
+            None => self.get_target_parameters(),
+        };
+        let (prefix, arguments) = bytes.split_at(selector);
+        let mut samples = shrink::parse_all(&params, &mut Unstructured::new(arguments), &self.input_config);
+        let kind = mutation::mutate(&params, &mut samples, seed, &self.input_config)?;
+        let child = [prefix.to_vec(), shrink::encode_all(&params, &samples, &self.input_config)].concat();
+        let same_instantiation = self.selected_instantiation(&child).0 == index;
+        (child.len() <= max_size && same_instantiation).then(|| (child, kind))
+    }
+
+    /// Re-encodes an input of the target as `old`, a runner loaded from an
+    /// earlier build, for the current parameters, see `migrate::migrate`. The
+    /// result is stamped with the current encoding version. The arguments of
+    /// generic targets are only stamped, inputs of unknown versions kept as is.
+    pub fn migrate(&self, old: &MoveRunner, bytes: &[u8]) -> Vec<u8> {
+        let input = bytes;
+        let bytes = match old.upgrade(input) {
+            Some(bytes) => bytes,
+            None => return input.to_vec(),
+        };
+        if !self.instantiations.is_empty() || !old.instantiations.is_empty() {
+            return stamp(&bytes);
+        }
+        let old_params = old.get_target_parameters();
+        let params = self.get_target_parameters();
+        let samples = shrink::parse_all(&old_params, &mut Unstructured::new(&bytes), &old.input_config);
+        let migrated = migrate::migrate(&old_params, samples, &old.input_config, &params, &self.input_config);
+        stamp(&shrink::encode_all(&params, &migrated, &self.input_config))
+    }
+
+    /// Calls the invariant function in the session the target just ran in, so
+    /// that it observes the state the target left.
+    fn check_invariant(
+        &self,
+        session: &mut Session<'_, '_, ModuleStore>,
+        (module, function): &(ModuleId, String),
+        report: bool,
+    ) -> Result<(), Error> {
+        let result = session.execute_function_bypass_visibility(
+            module,
+            IdentStr::new(function).unwrap(),
+            vec![],
+            combine_signers_and_args(vec![], vec![]),
+            &mut self.new_gas_meter()
+        );
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                let message = format!("{}::{}: {}", module, function, self.error_message(&err));
+                let error = Error::InvariantViolated { message };
+                if self.reports(report, &error) {
+                    eprintln!("{}Invariant {}::{} failed", crate::MOVE_REPORT_PREFIX, module, function);
+                    for line in self.format_vm_error(&err) {
+                        eprintln!("{}{}", crate::MOVE_REPORT_PREFIX, line);
+                    }
+                }
+                Err(error)
+            }
+        }
+    }
+
+    /// Runs the target and collects what it produced, printing the details of
+    /// failures if `report` is set.
+    fn run_target(
+        &mut self,
+        bytes: &[u8],
+        report: bool,
+    ) -> Result<Option<ExecutionOutcome>, (Option<ExecutionOutcome>, Error)> {
+        self.events.lock().unwrap().clear();
+        let result = self.call_target(bytes, report);
+        let with_events = |mut outcome: ExecutionOutcome| {
+            outcome.events = std::mem::take(&mut *self.events.lock().unwrap());
+            outcome
+        };
+        let result = match result {
+            Ok(executed) => Ok(executed.map(with_events)),
+            Err((executed, error)) => Err((executed.map(with_events), error)),
+        };
+        let result = self.run_after_hooks(result);
+        if let Err((Some(outcome), error)) = &result {
+            if self.reports(report, error) && !outcome.events.is_empty() {
+                eprintln!("{}Events emitted:", crate::MOVE_REPORT_PREFIX);
+                for event in &outcome.events {
+                    eprintln!("{}  {}", crate::MOVE_REPORT_PREFIX, event);
+                }
+            }
+            // The same bytes decode differently under another profile
+            if let Some(seed) = self.config.swarm_seed.filter(|_| self.reports(report, error)) {
+                eprintln!("{}Swarm profile: --swarm-seed={}", crate::MOVE_REPORT_PREFIX, seed);
+            }
+        }
+        result
+    }
+
+    /// Lets the hooks inspect the outcome of an execution, the first error a
+    /// hook returns turning a successful execution into a violated property.
+    fn run_after_hooks(
+        &self,
+        result: Result<Option<ExecutionOutcome>, (Option<ExecutionOutcome>, Error)>,
+    ) -> Result<Option<ExecutionOutcome>, (Option<ExecutionOutcome>, Error)> {
+        let outcome = match &result {
+            Ok(Some(outcome)) | Err((Some(outcome), _)) => outcome,
+            _ => return result,
+        };
+        let mut failure = None;
+        for hook in &self.hooks {
+            if let Err(message) = hook.lock().unwrap_or_else(PoisonError::into_inner).after_execution(outcome) {
+                failure.get_or_insert(message);
+            }
+        }
+        match (result, failure) {
+            (Ok(Some(outcome)), Some(message)) => Err((Some(outcome), Error::PropertyViolated { message })),
+            (result, _) => result,
+        }
+    }
+
+    /// Publishes `bytes` as a module, with the dependencies of the target
+    /// but not the target itself, so that mutations of it can be published.
+    /// Inputs the deserializer rejects are not executed; modules the VM
+    /// refuses to publish are expected, only its invariant violations (and
+    /// panics) are crashes.
+    fn publish_input(
+        &mut self,
+        bytes: &[u8],
+        report: bool,
+    ) -> Result<Option<ExecutionOutcome>, (Option<ExecutionOutcome>, Error)> {
+        let module = match CompiledModule::deserialize_with_defaults(bytes) {
+            Ok(module) => module,
+            Err(_) => return Ok(None),
+        };
+        let remote_view = ModuleStore::from_dependencies(&self.dependencies);
         let mut session = self.move_vm.new_session(&remote_view);
+        let mut gas_meter = self.new_gas_meter();
+        let result = session.publish_module(bytes.to_vec(), *module.self_id().address(), &mut gas_meter);
+        let gas_used = self
+            .config
+            .gas_limit
+            .map(|limit| limit.saturating_sub(u64::from(gas_meter.remaining_gas())));
+        match result {
+            Ok(()) => Ok(Some(ExecutionOutcome::new(vec![], ExecutionStatus::Returned, gas_used))),
+            Err(err) => {
+                let outcome = ExecutionOutcome::new(vec![], ExecutionStatus::Failed(err.major_status()), gas_used);
+                if err.major_status().status_type() != StatusType::InvariantViolation {
+                    return Ok(Some(outcome));
+                }
+                let error = Error::Runtime {
+                    message: format!("publishing {} violated a VM invariant: {}", module.self_id(), self.error_message(&err)),
+                };
+                if self.reports(report, &error) {
+                    for line in self.format_vm_error(&err) {
+                        eprintln!("{}{}", crate::MOVE_REPORT_PREFIX, line);
+                    }
+                }
+                Err((Some(outcome), error))
+            }
+        }
+    }
+
+    /// Calls the setup functions in `session`, their signers being the
+    /// address their module is published at. Nothing is called once the
+    /// snapshot is taken, the store holding their effects already.
+    fn run_setup(&self, session: &mut Session<'_, '_, ModuleStore>) -> Result<(), String> {
+        if self.snapshot.is_some() {
+            return Ok(());
+        }
+        for setup in &self.setup {
+            let signers = vec![*setup.module.address(); setup.signers];
+            session
+                .execute_function_bypass_visibility(
+                    &setup.module,
+                    IdentStr::new(&setup.function).unwrap(),
+                    vec![],
+                    combine_signers_and_args(signers, vec![]),
+                    &mut self.new_gas_meter()
+                )
+                .map_err(|err| format!("Setup function {}::{} failed: {}", setup.module, setup.function, self.error_message(&err)))?;
+        }
+        Ok(())
+    }
+
+    /// Runs the setup functions once and keeps the state they leave as the
+    /// one every execution starts from, so that they aren't called again for
+    /// each input. A failing setup is reported at startup rather than
+    /// rejecting every input.
+    pub fn snapshot_setup(&mut self) -> Result<(), String> {
+        if self.setup.is_empty() {
+            return Ok(());
+        }
+        let remote_view = self.new_store();
+        let mut session = self.move_vm.new_session(&remote_view);
+        self.run_setup(&mut session)?;
+        let (changes, _) = session.finish();
+        let changes = changes.map_err(|err| format!("Could not record the state of the setup functions: {}", self.error_message(&err)))?;
+        let snapshot = StorageSnapshot::from_changes(changes);
+        log::info!("Setup snapshot taken, {} resources", snapshot.resource_count());
+        self.snapshot = Some(Arc::new(snapshot));
+        Ok(())
+    }
 
-        let ty_args = vec![]
-            .into_iter()
-            .map(|tag| session.load_type(&tag))
-            .collect::<VMResult<_>>()
-            .unwrap();
+    /// The error class of a failed call, printing its details if `report` is
+    /// set and it is a crash.
+    fn classify_error(&self, err: &VMError, report: bool) -> Error {
+        let message = self.error_message(err);
+        let error = match err.major_status() {
+            StatusCode::ABORTED => Error::Abort { message },
+            StatusCode::ARITHMETIC_ERROR => Error::ArithmeticError { message },
+            StatusCode::MEMORY_LIMIT_EXCEEDED => Error::MemoryLimitExceeded { message },
+            StatusCode::OUT_OF_GAS => Error::OutOfGas { message },
+            _ => Error::Unknown { message },
+        };
+        if self.reports(report, &error) {
+            for line in self.format_vm_error(err) {
+                eprintln!("{}{}", crate::MOVE_REPORT_PREFIX, line);
+            }
+        }
+        error
+    }
 
+    /// Runs the calls of the model the input decodes to in a single session,
+    /// so that each one sees the state the previous ones left. Calls failing
+    /// with an error the crash policy doesn't report are skipped, the first
+    /// reported one ends the sequence with the outcome of that call.
+    fn call_sequence(
+        &mut self,
+        model: &SequenceModel,
+        bytes: &[u8],
+        report: bool,
+    ) -> Result<Option<ExecutionOutcome>, (Option<ExecutionOutcome>, Error)> {
+        let remote_view = self.new_store();
+        let mut session = self.move_vm.new_session(&remote_view);
+        if self.run_setup(&mut session).is_err() {
+            return Ok(None);
+        }
         let mut data = Unstructured::new(bytes);
+        let mut made = vec![];
+        let mut returned = vec![false; model.calls.len()];
+        let mut gas_used = self.config.gas_limit.map(|_| 0);
+
+        let mut last = None;
+        for step in 0..model.setup.len() + model.max_calls {
+            // `None` for the setup calls, the index of the picked call otherwise
+            let (index, call): (Option<usize>, &ModelCall) = match model.setup.get(step) {
+                Some(call) => (None, call),
+                None => match model.next_call(&mut data, &returned) {
+                    Some(i) => (Some(i), &model.calls[i]),
+                    None => break,
+                },
+            };
+
+            let args = match arbitrary_inputs(call.generated_params(), &mut data, &self.input_config, &mut SessionConstructor { session: &mut session }) {
+                Ok(generated) => splice_fixed_args(&call.fixed_args, generated),
+                Err(error @ Error::InputLimitExceeded { .. }) => return Err((None, error)),
+                // A setup call that can't be made makes the whole sequence invalid
+                Err(_) if index.is_none() => return Ok(None),
+                Err(_) => continue,
+            };
+            for hook in &self.hooks {
+                hook.lock().unwrap_or_else(PoisonError::into_inner).before_execution(&args);
+            }
+            made.push(format!("{}({})", call, args.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ")));
+            self.arg_buffers.clear();
+            self.arg_buffers.serialize_all(&args);
+            let mut gas_meter = self.new_gas_meter();
+            let result = session.execute_function_bypass_visibility(
+                &call.module,
+                IdentStr::new(&call.function).unwrap(),
+                vec![],
+                self.arg_buffers.args(),
+                &mut gas_meter
+            );
+            if let (Some(used), Some(limit)) = (gas_used.as_mut(), self.config.gas_limit) {
+                *used += limit.saturating_sub(u64::from(gas_meter.remaining_gas()));
+            }
+            match result {
+                Ok(values) => {
+                    let mut outcome = ExecutionOutcome::new(args, ExecutionStatus::Returned, gas_used);
+                    (outcome.returns, outcome.mutated) = decode_returns(&values);
+                    if let Some(i) = index {
+                        returned[i] = true;
+                    }
+                    if let Some(invariant) = &self.invariant {
+                        if let Err(error) = self.check_invariant(&mut session, invariant, report) {
+                            self.report_sequence(&made, report, &error);
+                            return Err((Some(outcome), error));
+                        }
+                    }
+                    last = Some(outcome);
+                }
+                Err(err) => {
+                    let outcome = ExecutionOutcome::new(args, failed_status(&err), gas_used);
+                    let allowed = err.major_status() == StatusCode::ABORTED
+                        && err.sub_status().map_or(false, |code| self.config.allowed_abort_codes.contains(&code));
+                    let error = (!allowed).then(|| self.classify_error(&err, report));
+                    match error {
+                        Some(error) if self.crash_policy.action(&error) == ErrorAction::Crash => {
+                            self.report_sequence(&made, report, &error);
+                            return Err((Some(outcome), error));
+                        }
+                        // A failed setup call makes the whole sequence invalid
+                        _ if index.is_none() => return Ok(None),
+                        // Expected failures, such as a withdrawal beyond the balance, leave the state as is
+                        _ => last = Some(outcome),
+                    }
+                }
+            }
+        }
+        Ok(last)
+    }
+
+    /// Prints the calls made by a sequence ending with a reported `error`.
+    fn report_sequence(&self, made: &[String], report: bool, error: &Error) {
+        if self.reports(report, error) {
+            eprintln!("{}Call sequence:", crate::MOVE_REPORT_PREFIX);
+            for call in made {
+                eprintln!("{}  {}", crate::MOVE_REPORT_PREFIX, call);
+            }
+        }
+    }
+
+    /// Runs the target, printing the details of VM errors if `report` is set.
+    /// The outcome doesn't hold the emitted events yet.
+    fn call_target(
+        &mut self,
+        bytes: &[u8],
+        report: bool,
+    ) -> Result<Option<ExecutionOutcome>, (Option<ExecutionOutcome>, Error)> {
+        if self.config.publish {
+            return self.publish_input(bytes, report);
+        }
+        if let Some(model) = self.model.clone() {
+            return self.call_sequence(&model, bytes, report);
+        }
+        let bytes = self.instantiate(bytes);
+        let type_args = self.type_args();
+        let remote_view = self.new_store();
+        let mut session = self.move_vm.new_session(&remote_view);
+        if self.run_setup(&mut session).is_err() {
+            return Ok(None);
+        }
+
+        let ty_args = match type_args.iter().map(|tag| session.load_type(tag)).collect::<VMResult<_>>() {
+            Ok(ty_args) => ty_args,
+            Err(_) => return Ok(None),
+        };
+
+        let decode_start = Instant::now();
+        self.arg_buffers.clear();
+        let primitives = match &self.primitive_plan {
+            Some(plan) => plan.generate(&mut Unstructured::new(bytes), &self.input_config, &mut self.arg_buffers),
+            None => None,
+        };
+        let (args, decode, serialize) = match primitives {
+            // Serialized along the way
+            Some(args) => (args, decode_start.elapsed(), Duration::ZERO),
+            None => {
+                self.arg_buffers.clear();
+                let mut data = Unstructured::new(bytes);
+                let args = match self.target_arguments(&mut data, &mut SessionConstructor { session: &mut session }) {
+                    Ok(args) => args,
+                    Err(error @ Error::InputLimitExceeded { .. }) => return Err((None, error)),
+                    Err(_) => return Ok(None),
+                };
+                let decode = decode_start.elapsed();
+                let serialize_start = Instant::now();
+                self.arg_buffers.serialize_all(&args);
+                (args, decode, serialize_start.elapsed())
+            }
+        };
+        for hook in &self.hooks {
+            hook.lock().unwrap_or_else(PoisonError::into_inner).before_execution(&args);
+        }
+        let mut gas_meter = self.new_gas_meter();
+        let vm_start = Instant::now();
         let result = session.execute_function_bypass_visibility(
             &self.module.self_id(),
             IdentStr::new(&self.target_function.name).unwrap(),
             ty_args,
-            combine_signers_and_args(vec![], serialize_values(&arbitrary_inputs(inputs.clone(), &mut data))),
-            &mut UnmeteredGasMeter
+            self.arg_buffers.args(),
+            &mut gas_meter
         );
+        if let Some(timings) = &mut self.timings {
+            *timings += PhaseTimings { decode, serialize, vm: vm_start.elapsed() };
+        }
+        let gas_used = self
+            .config
+            .gas_limit
+            .map(|limit| limit.saturating_sub(u64::from(gas_meter.remaining_gas())));
+        let new_outcome = |status| ExecutionOutcome {
+            type_args: type_args.clone(),
+            ..ExecutionOutcome::new(args.clone(), status, gas_used)
+        };
 
         match result {
-            Ok(_values) => Ok(Some(())),
+            Ok(values) => {
+                let mut outcome = new_outcome(ExecutionStatus::Returned);
+                (outcome.returns, outcome.mutated) = decode_returns(&values);
+                if self.config.oracle_bool && outcome.returns.first() == Some(&ReturnValue::Value(MoveValue::Bool(false))) {
+                    let message = format!(
+                        "{}::{} returned false",
+                        self.module.self_id(),
+                        self.target_function.name
+                    );
+                    return Err((Some(outcome), Error::PropertyViolated { message }));
+                }
+                match &self.invariant {
+                    Some(invariant) => match self.check_invariant(&mut session, invariant, report) {
+                        Ok(()) => Ok(Some(outcome)),
+                        Err(error) => Err((Some(outcome), error)),
+                    },
+                    None => Ok(Some(outcome)),
+                }
+            }
+            Err(err) if err.major_status() == StatusCode::ABORTED
+                && err.sub_status().map_or(false, |code| self.config.allowed_abort_codes.contains(&code)) => {
+                let code = err.sub_status().unwrap_or_default();
+                Ok(Some(new_outcome(ExecutionStatus::Aborted(code))))
+            }
+            // The target ran, only its results couldn't be serialized
+            Err(err) if err.major_status() == StatusCode::VALUE_SERIALIZATION_ERROR => {
+                let reason = format!("the VM could not serialize the results: {}", self.error_message(&err));
+                warn_unsupported_returns(&reason);
+                let mut outcome = new_outcome(ExecutionStatus::Returned);
+                outcome.returns = vec![ReturnValue::Unsupported(reason)];
+                Ok(Some(outcome))
+            }
             Err(err) => {
-                println!("{:?}", err);
-                let mut message = String::from("");
-                if let Some(m) = err.message() {
-                    message = m.to_string();
+                let error = self.classify_error(&err, report);
+                if !type_args.is_empty() && self.reports(report, &error) {
+                    let type_args = type_args.iter().map(ToString::to_string).collect::<Vec<_>>();
+                    eprintln!("{}Type arguments: {}", crate::MOVE_REPORT_PREFIX, type_args.join(", "));
                 }
-                let error = match err.major_status() {
-                    StatusCode::ABORTED => Error::Abort { message },
-                    StatusCode::ARITHMETIC_ERROR => Error::ArithmeticError { message },
-                    StatusCode::MEMORY_LIMIT_EXCEEDED => Error::MemoryLimitExceeded { message },
-                    StatusCode::OUT_OF_GAS => Error::OutOfGas { message },
-                    _ => Error::Unknown { message },
-                };
-                Err((Some(()), error))
+                Err((Some(new_outcome(failed_status(&err))), error))
             }
         }
     } 
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+
+use move_core_types::account_address::AccountAddress;
+use move_core_types::language_storage::StructTag;
+
+/// Resources read from a live node, through its REST API, the first time the
+/// fuzzed code accesses them, so that the target runs against a fork of the
+/// on-chain state. Each resource is fetched once, then served from the cache.
+#[derive(Debug)]
+pub struct ForkedState {
+    /// REST API root, e.g. `https://fullnode.mainnet.aptoslabs.com/v1`
+    url: String,
+    /// Ledger version the state is read at, the latest if not set
+    version: Option<u64>,
+    /// Directory the fetched resources are kept in across runs
+    cache_dir: Option<PathBuf>,
+    cache: Mutex<HashMap<(AccountAddress, StructTag), Option<Vec<u8>>>>,
+}
+
+/// Escapes the characters of a type that can't appear in a URL path.
+fn encode_tag(tag: &StructTag) -> String {
+    tag.to_string()
+        .replace(' ', "")
+        .replace('<', "%3C")
+        .replace('>', "%3E")
+        .replace(',', "%2C")
+}
+
+impl ForkedState {
+    pub fn new(url: &str, version: Option<u64>, cache_dir: Option<PathBuf>) -> Result<Self, String> {
+        if let Some(dir) = &cache_dir {
+            fs::create_dir_all(dir).map_err(|e| format!("Can't create the fork cache {}: {}", dir.display(), e))?;
+        }
+        Ok(ForkedState {
+            url: url.trim_end_matches('/').to_owned(),
+            version,
+            cache_dir,
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The BCS bytes of the resource `tag` stored at `address`, `None` if it doesn't exist.
+    pub fn get_resource(&self, address: &AccountAddress, tag: &StructTag) -> Result<Option<Vec<u8>>, String> {
+        let key = (*address, tag.clone());
+        if let Some(cached) = self.cache.lock().unwrap_or_else(PoisonError::into_inner).get(&key) {
+            return Ok(cached.clone());
+        }
+        let value = match self.read_cached(address, tag) {
+            Some(value) => value,
+            None => {
+                let value = self.fetch(address, tag)?;
+                self.write_cached(address, tag, &value);
+                value
+            }
+        };
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner).insert(key, value.clone());
+        Ok(value)
+    }
+
+    fn fetch(&self, address: &AccountAddress, tag: &StructTag) -> Result<Option<Vec<u8>>, String> {
+        let url = format!("{}/accounts/{}/resource/{}", self.url, address.to_hex_literal(), encode_tag(tag));
+        log::debug!("Fetching {}", url);
+        let mut request = ureq::get(&url).set("Accept", "application/x-bcs");
+        if let Some(version) = self.version {
+            request = request.query("ledger_version", &version.to_string());
+        }
+        match request.call() {
+            Ok(response) => {
+                let mut bytes = vec![];
+                response
+                    .into_reader()
+                    .read_to_end(&mut bytes)
+                    .map_err(|e| format!("Can't read {}: {}", url, e))?;
+                Ok(Some(bytes))
+            }
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(e) => Err(format!("Can't fetch {}: {}", url, e)),
+        }
+    }
+
+    /// File a resource is cached in, at the ledger version of the fork.
+    fn cache_path(&self, address: &AccountAddress, tag: &StructTag) -> Option<PathBuf> {
+        let name: String = format!("{}-{}", address.to_hex_literal(), tag)
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let version = self.version.map_or(String::from("latest"), |v| v.to_string());
+        Some(self.cache_dir.as_ref()?.join(version).join(name))
+    }
+
+    /// A missing resource is cached as an empty file, resources never being empty.
+    fn read_cached(&self, address: &AccountAddress, tag: &StructTag) -> Option<Option<Vec<u8>>> {
+        let bytes = fs::read(self.cache_path(address, tag)?).ok()?;
+        Some(if bytes.is_empty() { None } else { Some(bytes) })
+    }
+
+    fn write_cached(&self, address: &AccountAddress, tag: &StructTag, value: &Option<Vec<u8>>) {
+        if let Some(path) = self.cache_path(address, tag) {
+            let written = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&path, value.as_deref().unwrap_or_default()));
+            if let Err(e) = written {
+                log::warn!("Can't cache {}: {}", path.display(), e);
+            }
+        }
+    }
+}
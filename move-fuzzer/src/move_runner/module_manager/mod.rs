@@ -1,2 +1,4 @@
+pub mod fork;
 pub mod module_loader;
-pub mod module_store;
\ No newline at end of file
+pub mod module_store;
+pub mod snapshot;
@@ -1,44 +1,116 @@
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 use move_binary_format::CompiledModule;
 use move_command_line_common::files::MOVE_COMPILED_EXTENSION;
+use move_core_types::language_storage::ModuleId;
+use rayon::prelude::*;
 use walkdir::WalkDir;
 
-use crate::move_runner::utils::load_compiled_module;
+use crate::move_runner::{addresses::relocate, utils::load_compiled_module};
+use crate::STARTUP_LOG_TARGET;
 
 pub struct ModuleLoader {
     module_path: String,
     module: CompiledModule,
-    dependencies: Vec<CompiledModule>
+    dependencies: Vec<CompiledModule>,
+    /// Highest bytecode version the modules are read with, the latest when `None`
+    max_version: Option<u32>,
 }
 
 impl ModuleLoader {
-    pub fn new(module_path: String) -> Self {
-        let module = load_compiled_module(module_path.as_str());
-        ModuleLoader {
+    pub fn new(module_path: String, max_version: Option<u32>) -> Result<Self, String> {
+        let module = load_compiled_module(module_path.as_str(), max_version)?;
+        Ok(ModuleLoader {
             module_path,
             module,
             dependencies: vec![],
-        }
+            max_version,
+        })
     }
 
     fn get_root_dir(&self) -> &Path {
-        Path::new(self.module_path.as_str()).parent().unwrap()
+        Path::new(self.module_path.as_str()).parent().unwrap_or_else(|| Path::new("."))
+    }
+
+    /// Reads the modules at `paths` in parallel.
+    fn load_all(&self, paths: &[PathBuf]) -> Result<Vec<CompiledModule>, String> {
+        paths
+            .par_iter()
+            .map(|path| load_compiled_module(&path.to_string_lossy(), self.max_version))
+            .collect()
     }
 
-    pub fn load_depencencies(&mut self) {
-        // Iterate over all entries in the directory recursively
-        for entry in WalkDir::new(self.get_root_dir()).into_iter().filter_map(|e| e.ok()) {
+    /// Loads the modules of the package, the ones next to the target module,
+    /// and the dependencies they need transitively; the other compiled
+    /// dependencies are never read. Modules are read in parallel, one level
+    /// of the dependency graph at a time.
+    pub fn load_depencencies(&mut self) -> Result<(), String> {
+        let start = Instant::now();
+        let root_dir = self.get_root_dir().to_owned();
+        let target = Path::new(self.module_path.as_str()).to_owned();
+
+        // Compiled modules are named after the module, their address is only
+        // known once read
+        let mut by_name: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        let mut package = vec![];
+        let mut available = 0;
+        for entry in WalkDir::new(&root_dir).into_iter().filter_map(|e| e.ok()) {
             let path = entry.path();
-            if path.is_file() && path != Path::new(self.module_path.as_str()) {
-                // Check if the file is a Move compiled module
-                if let Some(ext) = path.extension() {
-                    if ext == MOVE_COMPILED_EXTENSION{
-                        self.dependencies.push(load_compiled_module(path.to_str().unwrap()));
-                    }
+            if !path.is_file() || path == target || path.extension().map_or(true, |ext| ext != MOVE_COMPILED_EXTENSION) {
+                continue;
+            }
+            available += 1;
+            if entry.depth() == 1 {
+                package.push(path.to_owned());
+            } else if let Some(name) = path.file_stem() {
+                by_name.entry(name.to_string_lossy().into_owned()).or_default().push(path.to_owned());
+            }
+        }
+
+        let mut loaded: HashSet<ModuleId> = HashSet::from([self.module.self_id()]);
+        // Modules read for their name, at an address no module needed yet
+        let mut spare: HashMap<ModuleId, CompiledModule> = HashMap::new();
+        let mut level = self.load_all(&package)?;
+        let mut pending = self.module.immediate_dependencies();
+        while !level.is_empty() || !pending.is_empty() {
+            for module in level.drain(..) {
+                if loaded.insert(module.self_id()) {
+                    pending.extend(module.immediate_dependencies());
+                    self.dependencies.push(module);
+                }
+            }
+            let wanted: HashSet<ModuleId> = pending.drain(..).filter(|id| !loaded.contains(id)).collect();
+            level.extend(wanted.iter().filter_map(|id| spare.remove(id)));
+            let candidates = wanted
+                .iter()
+                .flat_map(|id| by_name.remove(id.name().as_str()).unwrap_or_default())
+                .collect::<Vec<_>>();
+            for module in self.load_all(&candidates)? {
+                if wanted.contains(&module.self_id()) {
+                    level.push(module);
+                } else {
+                    spare.insert(module.self_id(), module);
                 }
             }
         }
+
+        log::debug!(
+            target: STARTUP_LOG_TARGET,
+            "Loaded {} of {} modules in {:.2?}",
+            self.dependencies.len() + 1,
+            available + 1,
+            start.elapsed()
+        );
+        Ok(())
+    }
+
+    /// Moves the loaded modules to the addresses given as `name=0x...`.
+    pub fn relocate(&mut self, assignments: &[String]) -> Result<(), String> {
+        let modules_dir = self.get_root_dir().to_owned();
+        relocate(std::slice::from_mut(&mut self.module), &modules_dir, assignments)?;
+        relocate(&mut self.dependencies, &modules_dir, assignments)
     }
 
     pub fn get_module(&self) -> CompiledModule {
@@ -54,4 +126,4 @@ impl ModuleLoader {
         res.insert(0, self.get_module());
         res
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,4 @@
-use move_binary_format::errors::VMError;
+use move_binary_format::errors::{Location, PartialVMError, VMError};
 use move_binary_format::CompiledModule;
 
 use move_core_types::account_address::AccountAddress;
@@ -7,23 +7,45 @@ use move_core_types::language_storage::StructTag;
 use move_core_types::resolver::LinkageResolver;
 use move_core_types::resolver::ModuleResolver;
 use move_core_types::resolver::ResourceResolver;
+use move_core_types::vm_status::StatusCode;
 
 use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::fork::ForkedState;
+use super::snapshot::StorageSnapshot;
 
 #[derive(Clone, Debug)]
 pub struct ModuleStore {
     modules: HashMap<ModuleId, Vec<u8>>,
+    /// On-chain state resources are read from, none without `--fork-url`
+    fork: Option<Arc<ForkedState>>,
+    /// State left by the setup functions, read before the forked one
+    snapshot: Option<Arc<StorageSnapshot>>,
 }
 
 impl ModuleStore {
     pub fn new(root_module: CompiledModule) -> Self {
         let mut loader = Self {
             modules: HashMap::new(),
+            fork: None,
+            snapshot: None,
         };
         loader.add_module(root_module);
         loader
     }
 
+    /// A store of `dependencies` only, that modules depending on them can be published in.
+    pub fn from_dependencies(dependencies: &Vec<CompiledModule>) -> Self {
+        let mut loader = Self {
+            modules: HashMap::new(),
+            fork: None,
+            snapshot: None,
+        };
+        loader.add_dependencies(dependencies);
+        loader
+    }
+
     fn add_module(&mut self, compiled_module: CompiledModule) {
         let id = compiled_module.self_id();
         let mut bytes = vec![];
@@ -31,6 +53,14 @@ impl ModuleStore {
         self.modules.insert(id, bytes);
     }
 
+    pub fn set_fork(&mut self, fork: Option<Arc<ForkedState>>) {
+        self.fork = fork;
+    }
+
+    pub fn set_snapshot(&mut self, snapshot: Option<Arc<StorageSnapshot>>) {
+        self.snapshot = snapshot;
+    }
+
     pub fn add_dependencies(&mut self, dependencies: &Vec<CompiledModule>) {
         for dep in dependencies {
             self.add_module(dep.clone()); 
@@ -54,9 +84,19 @@ impl ResourceResolver for ModuleStore {
 
     fn get_resource(
         &self,
-        _address: &AccountAddress,
-        _tag: &StructTag,
+        address: &AccountAddress,
+        tag: &StructTag,
     ) -> Result<Option<Vec<u8>>, Self::Error> {
-        Ok(None)
+        if let Some(resource) = self.snapshot.as_ref().and_then(|snapshot| snapshot.get(address, tag)) {
+            return Ok(resource);
+        }
+        match &self.fork {
+            Some(fork) => fork.get_resource(address, tag).map_err(|message| {
+                PartialVMError::new(StatusCode::STORAGE_ERROR)
+                    .with_message(message)
+                    .finish(Location::Undefined)
+            }),
+            None => Ok(None),
+        }
     }
 }
\ No newline at end of file
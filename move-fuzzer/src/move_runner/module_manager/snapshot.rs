@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+
+use move_core_types::account_address::AccountAddress;
+use move_core_types::effects::{ChangeSet, Op};
+use move_core_types::language_storage::StructTag;
+
+/// Resources written by the setup functions, taken once per process and
+/// shared read-only by the stores of every execution. An execution's writes
+/// stay in its session, so the next one starts from the snapshot again
+/// without copying nor re-running the setup.
+#[derive(Debug, Default)]
+pub struct StorageSnapshot {
+    /// `None` for the resources the setup deleted, hiding the forked ones
+    resources: HashMap<(AccountAddress, StructTag), Option<Vec<u8>>>,
+}
+
+impl StorageSnapshot {
+    pub fn from_changes(changes: ChangeSet) -> Self {
+        let mut resources = HashMap::new();
+        for (address, account) in changes.accounts() {
+            for (tag, op) in account.resources() {
+                let value = match op {
+                    Op::New(bytes) | Op::Modify(bytes) => Some(bytes.clone()),
+                    Op::Delete => None,
+                };
+                resources.insert((*address, tag.clone()), value);
+            }
+        }
+        StorageSnapshot { resources }
+    }
+
+    /// The resource as the setup left it, `None` if the setup didn't touch it.
+    pub fn get(&self, address: &AccountAddress, tag: &StructTag) -> Option<Option<Vec<u8>>> {
+        self.resources.get(&(*address, tag.clone())).cloned()
+    }
+
+    pub fn resource_count(&self) -> usize {
+        self.resources.len()
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::VecDeque;
+
+use move_core_types::language_storage::ModuleId;
+use move_core_types::runtime_value::MoveValue;
+
+use super::arbitrary_inputs::ValueConstructor;
+use super::types::{Constructor, Error, FuzzerType, TextEncoding};
+
+/// Wraps a constructor to remember the Move expression that built each value,
+/// so that constructed arguments can be rendered as constructor calls.
+pub struct RecordingConstructor<'a> {
+    inner: &'a mut dyn ValueConstructor,
+    expressions: Vec<String>,
+}
+
+impl<'a> RecordingConstructor<'a> {
+    pub fn new(inner: &'a mut dyn ValueConstructor) -> Self {
+        RecordingConstructor {
+            inner,
+            expressions: vec![],
+        }
+    }
+
+    /// Removes the expressions of the constructed values nested in `values`,
+    /// which are the last ones recorded since values are built depth first.
+    fn take(&mut self, types: &[FuzzerType], values: &[MoveValue]) -> VecDeque<String> {
+        let count: usize = types.iter().zip(values).map(|(t, v)| count_constructed(t, v)).sum();
+        let start = self.expressions.len().saturating_sub(count);
+        self.expressions.drain(start..).collect()
+    }
+
+    /// Renders every value as a Move expression.
+    pub fn render_all(&mut self, types: &[FuzzerType], values: &[MoveValue]) -> Vec<Result<String, String>> {
+        let mut pending = self.take(types, values);
+        types.iter().zip(values).map(|(t, v)| render(t, v, &mut pending)).collect()
+    }
+}
+
+impl ValueConstructor for RecordingConstructor<'_> {
+    fn construct(&mut self, constructor: &Constructor, args: Vec<MoveValue>) -> Result<MoveValue, Error> {
+        let value = self.inner.construct(constructor, args.clone())?;
+        let rendered = self
+            .render_all(&constructor.args, &args)
+            .into_iter()
+            .map(|arg| arg.unwrap_or_else(|e| format!("/* TODO: {} */", e)))
+            .collect::<Vec<_>>();
+        self.expressions.push(format!(
+            "{}::{}({})",
+            module_path(&constructor.module),
+            constructor.function,
+            rendered.join(", ")
+        ));
+        Ok(value)
+    }
+}
+
+fn module_path(module: &ModuleId) -> String {
+    format!("{}::{}", module.address().to_hex_literal(), module.name())
+}
+
+/// Escapes bytes for a Move byte string literal.
+fn escape_bytes(values: &[MoveValue]) -> String {
+    values
+        .iter()
+        .map(|v| match v {
+            MoveValue::U8(b'"') => String::from("\\\""),
+            MoveValue::U8(b'\\') => String::from("\\\\"),
+            MoveValue::U8(b) if b.is_ascii_graphic() || *b == b' ' => char::from(*b).to_string(),
+            MoveValue::U8(b) => format!("\\x{:02x}", b),
+            _ => String::new(),
+        })
+        .collect()
+}
+
+fn count_constructed(ty: &FuzzerType, value: &MoveValue) -> usize {
+    match (ty, value) {
+        (FuzzerType::Constructor(_), _) => 1,
+        (FuzzerType::Vector(t), MoveValue::Vector(values)) => values.iter().map(|v| count_constructed(t, v)).sum(),
+        (FuzzerType::Struct(types), MoveValue::Struct(s)) => types.iter().zip(&s.0).map(|(t, v)| count_constructed(t, v)).sum(),
+        (FuzzerType::Enum(variants), MoveValue::Variant(v)) => variants
+            .get(v.tag as usize)
+            .map(|types| types.iter().zip(&v.fields).map(|(t, v)| count_constructed(t, v)).sum())
+            .unwrap_or(0),
+        (FuzzerType::Option(t), MoveValue::Struct(s)) => match s.0.as_slice() {
+            [MoveValue::Vector(values)] => values.iter().map(|v| count_constructed(t, v)).sum(),
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
+
+/// Renders a generated value as a Move expression, using `pending` for the
+/// values that were built by calling a constructor.
+fn render(ty: &FuzzerType, value: &MoveValue, pending: &mut VecDeque<String>) -> Result<String, String> {
+    match (ty, value) {
+        (FuzzerType::Constructor(_), _) => pending.pop_front().ok_or_else(|| String::from("missing constructor call")),
+        (_, MoveValue::Bool(b)) => Ok(b.to_string()),
+        (_, MoveValue::U8(n)) => Ok(format!("{}u8", n)),
+        (_, MoveValue::U16(n)) => Ok(format!("{}u16", n)),
+        (_, MoveValue::U32(n)) => Ok(format!("{}u32", n)),
+        (_, MoveValue::U64(n)) => Ok(format!("{}u64", n)),
+        (_, MoveValue::U128(n)) => Ok(format!("{}u128", n)),
+        (_, MoveValue::U256(n)) => Ok(format!("{}u256", n)),
+        (_, MoveValue::Address(a)) => Ok(format!("@{}", a.to_hex_literal())),
+        (FuzzerType::Text(_), MoveValue::Vector(values)) => Ok(format!("b\"{}\"", escape_bytes(values))),
+        (FuzzerType::Option(t), MoveValue::Struct(s)) => match s.0.as_slice() {
+            [MoveValue::Vector(values)] if values.is_empty() => Ok(String::from("0x1::option::none()")),
+            [MoveValue::Vector(values)] if values.len() == 1 => {
+                Ok(format!("0x1::option::some({})", render(t, &values[0], pending)?))
+            }
+            _ => Err(format!("{} is not an option", value)),
+        },
+        (FuzzerType::FixedPoint32, MoveValue::Struct(s)) => match s.0.as_slice() {
+            [MoveValue::U64(raw)] => Ok(format!("0x1::fixed_point32::create_from_raw_value({}u64)", raw)),
+            _ => Err(format!("{} is not a fixed-point number", value)),
+        },
+        (FuzzerType::Struct(fields), MoveValue::Struct(s)) if matches!(fields.as_slice(), [FuzzerType::Text(_)]) => {
+            let function = match fields[0] {
+                FuzzerType::Text(TextEncoding::Ascii) => "0x1::ascii::string",
+                _ => "0x1::string::utf8",
+            };
+            let bytes = match s.0.as_slice() {
+                [MoveValue::Vector(values)] => escape_bytes(values),
+                _ => return Err(format!("{} is not a string", value)),
+            };
+            Ok(format!("{}(b\"{}\")", function, bytes))
+        }
+        (FuzzerType::Vector(t), MoveValue::Vector(values)) if **t == FuzzerType::U8 => {
+            let hex = values
+                .iter()
+                .map(|v| match v {
+                    MoveValue::U8(b) => format!("{:02x}", b),
+                    _ => String::new(),
+                })
+                .collect::<String>();
+            Ok(format!("x\"{}\"", hex))
+        }
+        (FuzzerType::Vector(t), MoveValue::Vector(values)) => {
+            // Render every element, even after a failure, to consume their constructor calls
+            let elements = values.iter().map(|v| render(t, v, pending)).collect::<Vec<_>>();
+            let elements = elements.into_iter().collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("vector[{}]", elements.join(", ")))
+        }
+        (_, MoveValue::Signer(a)) => Err(format!("signer {} can only be passed as a test parameter", a.to_hex_literal())),
+        _ => {
+            for _ in 0..count_constructed(ty, value) {
+                pending.pop_front();
+            }
+            Err(format!("{} can't be built outside of its module", value))
+        }
+    }
+}
+
+/// Generates a Move test module calling the target function with `args`.
+///
+/// Top level signers become parameters of the test, reference parameters are
+/// borrowed from locals and values that can't be expressed in Move are left as
+/// `TODO` comments for the user to fill in.
+pub fn render_test(
+    module: &ModuleId,
+    function: &str,
+    test_name: &str,
+    references: &[Option<bool>],
+    args: &[MoveValue],
+    rendered: Vec<Result<String, String>>,
+) -> String {
+    let mut signers = vec![];
+    let mut params = vec![];
+    let mut body = vec![];
+    let mut call_args = vec![];
+
+    for (i, (arg, expression)) in args.iter().zip(rendered).enumerate() {
+        let name = format!("arg{}", i);
+        let borrow = match references.get(i).copied().flatten() {
+            Some(true) => "&mut ",
+            Some(false) => "&",
+            None => "",
+        };
+        if let MoveValue::Signer(address) = arg {
+            signers.push(format!("{} = @{}", name, address.to_hex_literal()));
+            params.push(format!("{}: signer", name));
+        } else {
+            let expression = expression.unwrap_or_else(|e| format!("/* TODO: {} */", e));
+            let binding = if borrow == "&mut " { "let mut" } else { "let" };
+            body.push(format!("        {} {} = {};", binding, name, expression));
+        }
+        call_args.push(format!("{}{}", borrow, name));
+    }
+
+    let attribute = if signers.is_empty() {
+        String::from("#[test]")
+    } else {
+        format!("#[test({})]", signers.join(", "))
+    };
+
+    let mut test = String::new();
+    test.push_str("#[test_only]\n");
+    test.push_str(&format!("module fuzz::{} {{\n", test_name));
+    test.push_str(&format!("    {}\n", attribute));
+    test.push_str(&format!("    fun {}({}) {{\n", test_name, params.join(", ")));
+    for line in body {
+        test.push_str(&line);
+        test.push('\n');
+    }
+    test.push_str(&format!("        {}::{}({});\n", module_path(module), function, call_args.join(", ")));
+    test.push_str("    }\n");
+    test.push_str("}\n");
+    test
+}
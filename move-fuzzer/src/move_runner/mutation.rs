@@ -0,0 +1,255 @@
+use arbitrary::Unstructured;
+
+use move_core_types::account_address::AccountAddress;
+
+use super::arbitrary_inputs::InputConfig;
+use super::shrink::{parse_all, Sample};
+use super::swarm::SplitMix64;
+use super::types::FuzzerType;
+
+/// Kinds of mutations made to an input, counted in the worker statistics by
+/// how often they produced an input libFuzzer kept for its new coverage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MutationKind {
+    /// An integer replaced by a boundary value
+    IntBoundary,
+    /// An integer moved by a small amount
+    IntStep,
+    BoolFlip,
+    /// An element of a vector or text duplicated, or a first one added
+    VectorGrow,
+    /// An element of a vector or text removed
+    VectorShrink,
+    /// An address or signer picked again
+    AddressSwap,
+    /// An enum switched to another variant, with default fields
+    VariantSwitch,
+    /// libFuzzer's own mutation of the raw bytes
+    Bytes,
+}
+
+impl MutationKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MutationKind::IntBoundary => "int-boundary",
+            MutationKind::IntStep => "int-step",
+            MutationKind::BoolFlip => "bool-flip",
+            MutationKind::VectorGrow => "vector-grow",
+            MutationKind::VectorShrink => "vector-shrink",
+            MutationKind::AddressSwap => "address-swap",
+            MutationKind::VariantSwitch => "variant-switch",
+            MutationKind::Bytes => "bytes",
+        }
+    }
+}
+
+/// Number of values in the tree of `sample`, itself included.
+fn count(ty: &FuzzerType, sample: &Sample) -> usize {
+    match (ty, sample) {
+        (FuzzerType::Option(_) | FuzzerType::FixedPoint32 | FuzzerType::TxContext | FuzzerType::Clock, sample) => {
+            count(&ty.decoded_as(), sample)
+        }
+        (FuzzerType::Vector(t), Sample::Vector(values)) => 1 + values.iter().map(|v| count(t, v)).sum::<usize>(),
+        (FuzzerType::Struct(types), Sample::Fields(values)) => 1 + count_all(types, values),
+        (FuzzerType::Constructor(c), Sample::Fields(values)) => 1 + count_all(&c.args, values),
+        (FuzzerType::Enum(variants), Sample::Variant(tag, values)) => 1 + count_all(&variants[*tag], values),
+        _ => 1,
+    }
+}
+
+fn count_all(types: &[FuzzerType], samples: &[Sample]) -> usize {
+    types.iter().zip(samples).map(|(ty, sample)| count(ty, sample)).sum()
+}
+
+/// A boundary value of the `bits` wide integer type, or `n` moved by up to 16.
+fn mutate_int(n: u128, bits: u32, rng: &mut SplitMix64, config: &InputConfig) -> (u128, MutationKind) {
+    let values = config.special_ints.values(bits);
+    if !values.is_empty() && rng.below(2) == 0 {
+        return (rng.choose(values), MutationKind::IntBoundary);
+    }
+    let max = u128::MAX >> (128 - bits);
+    let delta = 1 + rng.below(16) as u128;
+    let n = if rng.below(2) == 0 { n.wrapping_add(delta) } else { n.wrapping_sub(delta) };
+    (n & max, MutationKind::IntStep)
+}
+
+fn random_address(rng: &mut SplitMix64) -> AccountAddress {
+    let mut buf = [0; AccountAddress::LENGTH];
+    for chunk in buf.chunks_mut(8) {
+        chunk.copy_from_slice(&rng.next_u64().to_le_bytes()[..chunk.len()]);
+    }
+    AccountAddress::new(buf)
+}
+
+/// Another index than `index` below `len`, which must be at least 2.
+fn other_index(index: usize, len: usize, rng: &mut SplitMix64) -> usize {
+    let other = rng.below(len - 1);
+    if other >= index {
+        other + 1
+    } else {
+        other
+    }
+}
+
+/// Removes an element of `values`, or adds a copy of one, or `fresh` if there
+/// is none.
+fn resize<T: Clone>(values: &mut Vec<T>, fresh: impl FnOnce() -> Option<T>, rng: &mut SplitMix64) -> Option<MutationKind> {
+    if !values.is_empty() && rng.below(2) == 0 {
+        values.remove(rng.below(values.len()));
+        return Some(MutationKind::VectorShrink);
+    }
+    let value = if values.is_empty() { fresh()? } else { values[rng.below(values.len())].clone() };
+    values.insert(rng.below(values.len() + 1), value);
+    Some(MutationKind::VectorGrow)
+}
+
+/// Mutates `sample` itself, without looking at the values it holds. `None`
+/// if it can't be, e.g. a struct or an address with a single choice.
+fn mutate_value(ty: &FuzzerType, sample: &mut Sample, rng: &mut SplitMix64, config: &InputConfig) -> Option<MutationKind> {
+    let kind = match (ty, sample) {
+        (_, Sample::Bool(b)) => {
+            *b = !*b;
+            MutationKind::BoolFlip
+        }
+        (_, Sample::U8(n)) => {
+            let (m, kind) = mutate_int(*n as u128, 8, rng, config);
+            *n = m as u8;
+            kind
+        }
+        (_, Sample::U16(n)) => {
+            let (m, kind) = mutate_int(*n as u128, 16, rng, config);
+            *n = m as u16;
+            kind
+        }
+        (_, Sample::U32(n)) => {
+            let (m, kind) = mutate_int(*n as u128, 32, rng, config);
+            *n = m as u32;
+            kind
+        }
+        (_, Sample::U64(n)) => {
+            let (m, kind) = mutate_int(*n as u128, 64, rng, config);
+            *n = m as u64;
+            kind
+        }
+        (_, Sample::U128(n)) => {
+            let (m, kind) = mutate_int(*n, 128, rng, config);
+            *n = m;
+            kind
+        }
+        (_, Sample::U256(n)) => {
+            let values = config.special_ints.u256_values();
+            if values.is_empty() {
+                return None;
+            }
+            *n = rng.choose(values);
+            MutationKind::IntBoundary
+        }
+        (_, Sample::Address { choice, choices, address }) => {
+            if *choices > 1 {
+                let pool = if *ty == FuzzerType::Signer { &config.signers } else { &config.addresses };
+                *choice = other_index(*choice, *choices, rng);
+                *address = pool.get(*choice).map_or_else(|| Some(random_address(rng)), |_| None);
+            } else if address.is_some() {
+                *address = Some(random_address(rng));
+            } else {
+                return None;
+            }
+            MutationKind::AddressSwap
+        }
+        (FuzzerType::Vector(t), Sample::Vector(values)) => {
+            let fresh = || parse_all(std::slice::from_ref(&**t), &mut Unstructured::new(&[]), config).pop();
+            return resize(values, fresh, rng);
+        }
+        (FuzzerType::Text(encoding), Sample::Text(text)) => {
+            let fresh = || Some(encoding.decode(&mut Unstructured::new(&[])).unwrap_or_default());
+            return resize(text, fresh, rng);
+        }
+        (FuzzerType::Enum(variants), Sample::Variant(tag, values)) if variants.len() > 1 => {
+            *tag = other_index(*tag, variants.len(), rng);
+            // The fields of the variant decoded from an exhausted input
+            *values = parse_all(&variants[*tag], &mut Unstructured::new(&[]), config);
+            MutationKind::VariantSwitch
+        }
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// Mutates the `n`th value of the tree of `sample`, depth first, or the first
+/// one after it that can be mutated, counting `n` down as values are passed.
+fn mutate_nth(ty: &FuzzerType, sample: &mut Sample, n: &mut usize, rng: &mut SplitMix64, config: &InputConfig) -> Option<MutationKind> {
+    if let FuzzerType::Option(_) | FuzzerType::FixedPoint32 | FuzzerType::TxContext | FuzzerType::Clock = ty {
+        return mutate_nth(&ty.decoded_as(), sample, n, rng, config);
+    }
+    if *n == 0 {
+        if let Some(kind) = mutate_value(ty, sample, rng, config) {
+            return Some(kind);
+        }
+    } else {
+        *n -= 1;
+    }
+    match (ty, sample) {
+        (FuzzerType::Vector(t), Sample::Vector(values)) => values.iter_mut().find_map(|v| mutate_nth(t, v, n, rng, config)),
+        (FuzzerType::Struct(types), Sample::Fields(values)) => mutate_nth_all(types, values, n, rng, config),
+        (FuzzerType::Constructor(c), Sample::Fields(values)) => mutate_nth_all(&c.args, values, n, rng, config),
+        (FuzzerType::Enum(variants), Sample::Variant(tag, values)) => mutate_nth_all(&variants[*tag], values, n, rng, config),
+        _ => None,
+    }
+}
+
+fn mutate_nth_all(
+    types: &[FuzzerType],
+    samples: &mut [Sample],
+    n: &mut usize,
+    rng: &mut SplitMix64,
+    config: &InputConfig,
+) -> Option<MutationKind> {
+    types.iter().zip(samples.iter_mut()).find_map(|(ty, sample)| mutate_nth(ty, sample, n, rng, config))
+}
+
+/// Mutates one of the values decoded from an input, picked uniformly among
+/// the arguments and everything they hold. Returns the kind of mutation
+/// made, `None` if no value could be mutated.
+pub fn mutate(types: &[FuzzerType], samples: &mut [Sample], seed: u32, config: &InputConfig) -> Option<MutationKind> {
+    let total = count_all(types, samples);
+    if total == 0 {
+        return None;
+    }
+    let mut rng = SplitMix64(seed as u64);
+    let mut n = rng.below(total);
+    mutate_nth_all(types, samples, &mut n, &mut rng, config)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::move_runner::addresses::AddressPool;
+    use crate::move_runner::arbitrary_inputs::InputLimits;
+    use crate::move_runner::integers::SpecialInts;
+    use crate::move_runner::shrink::encode_all;
+
+    #[test]
+    fn mutated_arguments_survive_encoding() {
+        let config = InputConfig {
+            signers: AddressPool::default(),
+            addresses: AddressPool::default(),
+            special_ints: SpecialInts::new(0),
+            limits: InputLimits { max_depth: 32, max_values: 100_000 },
+            vector_end: 128,
+        };
+        let params = [
+            FuzzerType::Bool,
+            FuzzerType::Vector(Box::new(FuzzerType::U8)),
+            FuzzerType::Enum(vec![vec![], vec![FuzzerType::U64]]),
+        ];
+        let original = parse_all(&params, &mut Unstructured::new(&[1, 1, 7, 1, 9, 0, 1, 42]), &config);
+        for seed in 0..256 {
+            let mut samples = original.clone();
+            let kind = mutate(&params, &mut samples, seed, &config);
+            assert!(kind.is_some() && kind != Some(MutationKind::Bytes));
+            assert_ne!(samples, original, "{:?}", kind);
+            let bytes = encode_all(&params, &samples, &config);
+            assert_eq!(parse_all(&params, &mut Unstructured::new(&bytes), &config), samples, "{:?}", kind);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::fmt::{Display, Formatter};
+
+use move_core_types::language_storage::TypeTag;
+use move_core_types::runtime_value::MoveValue;
+use move_core_types::vm_status::StatusCode;
+
+use super::events::MoveEvent;
+
+/// How the call to the target ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionStatus {
+    /// The target returned normally
+    Returned,
+    /// The target aborted with this code
+    Aborted(u64),
+    /// The VM stopped the execution with another error
+    Failed(StatusCode),
+}
+
+impl Display for ExecutionStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExecutionStatus::Returned => write!(f, "returned"),
+            ExecutionStatus::Aborted(code) => write!(f, "aborted with code {}", code),
+            ExecutionStatus::Failed(status) => write!(f, "failed with {:?}", status),
+        }
+    }
+}
+
+/// A value the target returned, or wrote through a `&mut` parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReturnValue {
+    Value(MoveValue),
+    /// A value the runner couldn't decode, with the reason; reported rather
+    /// than failing the execution
+    Unsupported(String),
+}
+
+impl Display for ReturnValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReturnValue::Value(value) => write!(f, "{}", value),
+            ReturnValue::Unsupported(reason) => write!(f, "<unsupported: {}>", reason),
+        }
+    }
+}
+
+/// What an execution of the target produced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionOutcome {
+    /// Type arguments of a generic target, see `--instantiate`
+    pub type_args: Vec<TypeTag>,
+    /// Arguments the target was called with, fixed ones included
+    pub args: Vec<MoveValue>,
+    /// Values returned by the target, empty unless it returned; returned
+    /// references are read through
+    pub returns: Vec<ReturnValue>,
+    /// Values of the `&mut` parameters after the call, by parameter index
+    pub mutated: Vec<(usize, ReturnValue)>,
+    /// Gas consumed by the target, `None` when executions are unmetered
+    pub gas_used: Option<u64>,
+    /// Events emitted while building the arguments and running the target
+    pub events: Vec<MoveEvent>,
+    /// How the call to the target ended
+    pub status: ExecutionStatus,
+}
+
+impl ExecutionOutcome {
+    pub(super) fn new(args: Vec<MoveValue>, status: ExecutionStatus, gas_used: Option<u64>) -> Self {
+        ExecutionOutcome { type_args: vec![], args, returns: vec![], mutated: vec![], gas_used, events: vec![], status }
+    }
+}
+
+impl Display for ExecutionOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if !self.type_args.is_empty() {
+            writeln!(f, "Type arguments: {}", self.type_args.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))?;
+        }
+        writeln!(f, "Status: {}", self.status)?;
+        for (i, value) in self.returns.iter().enumerate() {
+            writeln!(f, "Return {}: {}", i, value)?;
+        }
+        for (i, value) in &self.mutated {
+            writeln!(f, "Argument {} after the call: {}", i, value)?;
+        }
+        if let Some(gas) = self.gas_used {
+            writeln!(f, "Gas used: {}", gas)?;
+        }
+        for event in &self.events {
+            writeln!(f, "Event: {}", event)?;
+        }
+        Ok(())
+    }
+}
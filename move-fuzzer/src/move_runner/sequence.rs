@@ -0,0 +1,203 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+
+use arbitrary::Unstructured;
+use move_binary_format::CompiledModule;
+use move_core_types::language_storage::ModuleId;
+use move_core_types::runtime_value::MoveValue;
+use serde::Deserialize;
+
+use super::fixed_args::parse_fixed_args;
+use super::types::{FuzzerType, StringGeneration, StructGeneration};
+use super::utils::generate_abi_from_bin;
+
+fn default_max_calls() -> usize {
+    8
+}
+
+fn default_weight() -> usize {
+    1
+}
+
+/// A state machine model of the package, as written in the `--model` file:
+///
+/// ```toml
+/// max-calls = 8
+///
+/// [[setup]]
+/// function = "vault::create"
+/// fix = ["arg0=1000"]
+///
+/// [[calls]]
+/// function = "vault::deposit"
+/// weight = 3
+///
+/// [[calls]]
+/// function = "vault::withdraw"
+/// after = ["vault::deposit"]
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ModelFile {
+    /// Largest number of calls of a sequence, setup calls excluded
+    #[serde(default = "default_max_calls")]
+    max_calls: usize,
+    /// Calls made in order at the start of every sequence
+    #[serde(default)]
+    setup: Vec<CallFile>,
+    /// Calls sequences are made of
+    calls: Vec<CallFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct CallFile {
+    /// Function called, as `module::function`
+    function: String,
+    /// How often the call is picked relative to the others
+    #[serde(default = "default_weight")]
+    weight: usize,
+    /// Parameters pinned to a constant value, as `argN=value`
+    #[serde(default)]
+    fix: Vec<String>,
+    /// Calls that must have returned earlier in the sequence for this one to be picked
+    #[serde(default)]
+    after: Vec<String>,
+}
+
+/// A call of the model, resolved against the loaded modules.
+#[derive(Debug, Clone)]
+pub struct ModelCall {
+    pub module: ModuleId,
+    pub function: String,
+    /// Types of all the parameters, fixed ones included
+    pub params: Vec<FuzzerType>,
+    pub fixed_args: BTreeMap<usize, MoveValue>,
+    pub weight: usize,
+    /// Indices of the calls that must have returned before this one
+    pub after: Vec<usize>,
+}
+
+impl ModelCall {
+    /// Types of the parameters generated from the input, i.e. the ones not fixed.
+    pub fn generated_params(&self) -> Vec<FuzzerType> {
+        self.params
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.fixed_args.contains_key(i))
+            .map(|(_, t)| t.clone())
+            .collect()
+    }
+}
+
+impl Display for ModelCall {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}::{}", self.module.name(), self.function)
+    }
+}
+
+/// Calls made by an input interpreted with the model: the setup calls, then
+/// calls picked among the enabled ones until `max_calls` or the end of the input.
+#[derive(Debug, Clone)]
+pub struct SequenceModel {
+    pub max_calls: usize,
+    pub setup: Vec<ModelCall>,
+    pub calls: Vec<ModelCall>,
+}
+
+impl SequenceModel {
+    /// Reads the model at `path`, as JSON if its extension is `.json` and as
+    /// TOML otherwise, and resolves its calls among `modules`.
+    pub fn load(
+        path: &Path,
+        modules: &[CompiledModule],
+        struct_generation: StructGeneration,
+        string_generation: StringGeneration,
+        raw_std_types: bool,
+    ) -> Result<Self, String> {
+        let data = fs::read_to_string(path).map_err(|e| format!("Could not read model {}: {}", path.display(), e))?;
+        let file: ModelFile = if path.extension().map_or(false, |ext| ext == "json") {
+            serde_json::from_str(&data).map_err(|e| format!("Invalid model {}: {}", path.display(), e))?
+        } else {
+            toml::from_str(&data).map_err(|e| format!("Invalid model {}: {}", path.display(), e))?
+        };
+        if file.calls.is_empty() {
+            return Err(format!("The model {} has no calls", path.display()));
+        }
+
+        let resolve = |call: &CallFile| -> Result<ModelCall, String> {
+            let (module_name, function) = call
+                .function
+                .rsplit_once("::")
+                .ok_or_else(|| format!("Invalid model call `{}`, expected `module::function`", call.function))?;
+            let module = modules
+                .iter()
+                .find(|m| m.self_id().name().as_str() == module_name)
+                .ok_or_else(|| format!("Could not find the module of model call `{}`", call.function))?;
+            let params = generate_abi_from_bin(
+                modules.to_vec(),
+                module_name,
+                function,
+                struct_generation,
+                string_generation,
+                raw_std_types,
+            )?
+            .0;
+            let fixed_args = parse_fixed_args(&params, &call.fix).map_err(|e| format!("{}: {}", call.function, e))?;
+            if let Some((i, param)) = params
+                .iter()
+                .enumerate()
+                .find(|(i, t)| !fixed_args.contains_key(i) && t.is_unsupported())
+            {
+                return Err(format!("{}: unsupported type for parameter arg{}: {}", call.function, i, param));
+            }
+            Ok(ModelCall {
+                module: module.self_id(),
+                function: function.to_string(),
+                params,
+                fixed_args,
+                weight: call.weight,
+                after: vec![],
+            })
+        };
+
+        let setup = file.setup.iter().map(resolve).collect::<Result<Vec<_>, _>>()?;
+        let mut calls = file.calls.iter().map(resolve).collect::<Result<Vec<_>, _>>()?;
+        for (call, spec) in calls.iter_mut().zip(&file.calls) {
+            for after in &spec.after {
+                let index = file
+                    .calls
+                    .iter()
+                    .position(|other| other.function == *after)
+                    .ok_or_else(|| format!("{}: `{}` is not a call of the model", spec.function, after))?;
+                call.after.push(index);
+            }
+        }
+        Ok(SequenceModel { max_calls: file.max_calls, setup, calls })
+    }
+
+    /// Picks the next call among the ones whose `after` calls all returned,
+    /// `None` when the input is exhausted or no call is enabled.
+    pub fn next_call(&self, data: &mut Unstructured, returned: &[bool]) -> Option<usize> {
+        let enabled = self
+            .calls
+            .iter()
+            .enumerate()
+            .filter(|(_, call)| call.weight > 0 && call.after.iter().all(|&i| returned[i]))
+            .collect::<Vec<_>>();
+        let total: usize = enabled.iter().map(|(_, call)| call.weight).sum();
+        if total == 0 || data.is_empty() {
+            return None;
+        }
+        let mut choice = data.int_in_range(0..=total - 1).ok()?;
+        for (i, call) in enabled {
+            if choice < call.weight {
+                return Some(i);
+            }
+            choice -= call.weight;
+        }
+        None
+    }
+}
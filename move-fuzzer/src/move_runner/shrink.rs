@@ -0,0 +1,348 @@
+use std::cmp::Ordering;
+use std::mem;
+
+use arbitrary::{Arbitrary, Unstructured};
+
+use move_core_types::account_address::AccountAddress;
+use move_core_types::u256::U256 as MoveU256;
+
+use super::addresses::AddressPool;
+use super::arbitrary_inputs::{keep_going, InputConfig};
+use super::types::FuzzerType;
+
+/// An input decoded as a tree of generated values. Unlike `MoveValue` it keeps
+/// the arguments of constructed structs, so that it can be encoded back into
+/// the bytes the generator reads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sample {
+    Bool(bool),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    U256(MoveU256),
+    /// An address or signer picked among `choices` pool entries, with the
+    /// address read from the input when it isn't a pooled one
+    Address { choice: usize, choices: usize, address: Option<AccountAddress> },
+    Vector(Vec<Sample>),
+    /// Characters of a text value
+    Text(Vec<char>),
+    /// Struct fields or constructor arguments
+    Fields(Vec<Sample>),
+    Variant(usize, Vec<Sample>),
+    /// Unsupported types don't read any byte
+    Empty,
+}
+
+/// Decodes `u` the same way `arbitrary_inputs` does, without building values.
+fn parse(ty: &FuzzerType, u: &mut Unstructured, config: &InputConfig) -> Sample {
+    match ty {
+        FuzzerType::Bool => Sample::Bool(bool::arbitrary(u).unwrap_or_default()),
+        FuzzerType::U8 => Sample::U8(parse_int::<u8>(u, config) as u8),
+        FuzzerType::U16 => Sample::U16(parse_int::<u16>(u, config) as u16),
+        FuzzerType::U32 => Sample::U32(parse_int::<u32>(u, config) as u32),
+        FuzzerType::U64 => Sample::U64(parse_int::<u64>(u, config) as u64),
+        FuzzerType::U128 => Sample::U128(parse_int::<u128>(u, config)),
+        FuzzerType::U256 => {
+            let values = config.special_ints.u256_values();
+            if let Ok(Some(i)) = config.special_ints.pick(u, values.len()) {
+                return Sample::U256(values[i]);
+            }
+            let mut buf = [0; mem::size_of::<MoveU256>()];
+            let _ = u.fill_buffer(&mut buf);
+            Sample::U256(MoveU256::from_le_bytes(&buf))
+        }
+        FuzzerType::Address => parse_address(u, &config.addresses),
+        FuzzerType::Signer => parse_address(u, &config.signers),
+        FuzzerType::Vector(t) => {
+            let mut values = vec![];
+            while keep_going(u, config) {
+                values.push(parse(t, u, config));
+            }
+            Sample::Vector(values)
+        }
+        FuzzerType::Text(encoding) => {
+            let mut text = vec![];
+            while keep_going(u, config) {
+                text.push(encoding.decode(u).unwrap_or_default());
+            }
+            Sample::Text(text)
+        }
+        FuzzerType::Struct(fields) => Sample::Fields(parse_all(fields, u, config)),
+        FuzzerType::Constructor(c) => Sample::Fields(parse_all(&c.args, u, config)),
+        FuzzerType::Enum(variants) => {
+            let tag = u.choose_index(variants.len()).unwrap_or(0);
+            Sample::Variant(tag, parse_all(&variants[tag], u, config))
+        }
+        FuzzerType::Option(_) | FuzzerType::FixedPoint32 | FuzzerType::TxContext | FuzzerType::Clock => {
+            parse(&ty.decoded_as(), u, config)
+        }
+        FuzzerType::Uid | FuzzerType::Unsupported(_) => Sample::Empty,
+    }
+}
+
+fn parse_int<T: for<'a> Arbitrary<'a> + Into<u128> + Default>(u: &mut Unstructured, config: &InputConfig) -> u128 {
+    let values = config.special_ints.values(mem::size_of::<T>() as u32 * 8);
+    match config.special_ints.pick(u, values.len()) {
+        Ok(Some(i)) => values[i],
+        _ => T::arbitrary(u).unwrap_or_default().into(),
+    }
+}
+
+fn parse_address(u: &mut Unstructured, pool: &AddressPool) -> Sample {
+    let choices = pool.choices();
+    let choice = if choices > 0 { u.choose_index(choices).unwrap_or(0) } else { 0 };
+    let address = match pool.get(choice) {
+        Some(_) => None,
+        None => {
+            let mut buf = [0; AccountAddress::LENGTH];
+            let _ = u.fill_buffer(&mut buf);
+            Some(AccountAddress::new(buf))
+        }
+    };
+    Sample::Address { choice, choices, address }
+}
+
+pub fn parse_all(types: &[FuzzerType], u: &mut Unstructured, config: &InputConfig) -> Vec<Sample> {
+    types.iter().map(|t| parse(t, u, config)).collect()
+}
+
+/// Writes the bytes `int_in_range` reads to pick `index` among `len` choices.
+fn encode_index(index: usize, len: usize, out: &mut Vec<u8>) {
+    let range = len.saturating_sub(1) as u64;
+    let mut bytes = 0;
+    while bytes < mem::size_of::<u64>() && (range >> (bytes * 8)) > 0 {
+        bytes += 1;
+    }
+    let index = index as u64;
+    for i in (0..bytes).rev() {
+        out.push((index >> (i * 8)) as u8);
+    }
+}
+
+/// Writes the selector `SpecialInts::pick` reads, returning whether `index`
+/// picked a special value so that no uniform value has to follow.
+fn encode_special(index: Option<usize>, count: usize, config: &InputConfig, out: &mut Vec<u8>) -> bool {
+    if !config.special_ints.enabled() {
+        return false;
+    }
+    match index {
+        Some(index) => {
+            out.push(0);
+            encode_index(index, count, out);
+            true
+        }
+        None => {
+            out.push(u8::MAX);
+            false
+        }
+    }
+}
+
+fn encode_int(n: u128, bytes: &[u8], config: &InputConfig, out: &mut Vec<u8>) {
+    let values = config.special_ints.values(bytes.len() as u32 * 8);
+    if !encode_special(values.iter().position(|v| *v == n), values.len(), config, out) {
+        out.extend(bytes);
+    }
+}
+
+fn encode(ty: &FuzzerType, sample: &Sample, config: &InputConfig, out: &mut Vec<u8>) {
+    match (ty, sample) {
+        (_, Sample::Bool(b)) => out.push(*b as u8),
+        (_, Sample::U8(n)) => encode_int(*n as u128, &n.to_le_bytes(), config, out),
+        (_, Sample::U16(n)) => encode_int(*n as u128, &n.to_le_bytes(), config, out),
+        (_, Sample::U32(n)) => encode_int(*n as u128, &n.to_le_bytes(), config, out),
+        (_, Sample::U64(n)) => encode_int(*n as u128, &n.to_le_bytes(), config, out),
+        (_, Sample::U128(n)) => encode_int(*n, &n.to_le_bytes(), config, out),
+        (_, Sample::U256(n)) => {
+            let values = config.special_ints.u256_values();
+            if !encode_special(values.iter().position(|v| v == n), values.len(), config, out) {
+                out.extend(n.to_le_bytes());
+            }
+        }
+        (_, Sample::Address { choice, choices, address }) => {
+            if *choices > 0 {
+                encode_index(*choice, *choices, out);
+            }
+            if let Some(a) = address {
+                out.extend(a.to_vec());
+            }
+        }
+        (FuzzerType::Vector(t), Sample::Vector(values)) => {
+            for value in values {
+                out.push(1);
+                encode(t, value, config, out);
+            }
+            out.push(0);
+        }
+        (FuzzerType::Text(encoding), Sample::Text(text)) => {
+            for c in text {
+                out.push(1);
+                encoding.encode(*c, out);
+            }
+            out.push(0);
+        }
+        (FuzzerType::Struct(types), Sample::Fields(values)) => encode_all_into(types, values, config, out),
+        (FuzzerType::Constructor(c), Sample::Fields(values)) => encode_all_into(&c.args, values, config, out),
+        (FuzzerType::Enum(variants), Sample::Variant(tag, values)) => {
+            encode_index(*tag, variants.len(), out);
+            encode_all_into(&variants[*tag], values, config, out);
+        }
+        (FuzzerType::Option(_) | FuzzerType::FixedPoint32 | FuzzerType::TxContext | FuzzerType::Clock, sample) => encode(&ty.decoded_as(), sample, config, out),
+        _ => {}
+    }
+}
+
+fn encode_all_into(types: &[FuzzerType], samples: &[Sample], config: &InputConfig, out: &mut Vec<u8>) {
+    for (ty, sample) in types.iter().zip(samples) {
+        encode(ty, sample, config, out);
+    }
+}
+
+/// Encodes the samples into the bytes they were decoded from, minus the
+/// trailing zeros: the generator reads zeros once the input is exhausted.
+pub fn encode_all(types: &[FuzzerType], samples: &[Sample], config: &InputConfig) -> Vec<u8> {
+    let mut out = vec![];
+    encode_all_into(types, samples, config, &mut out);
+    while out.last() == Some(&0) {
+        out.pop();
+    }
+    out
+}
+
+fn magnitude(sample: &Sample) -> f64 {
+    fn bytes(bytes: &[u8]) -> f64 {
+        bytes.iter().rev().fold(0.0, |acc, b| acc * 256.0 + *b as f64)
+    }
+    match sample {
+        Sample::Bool(b) => *b as u8 as f64,
+        Sample::U8(n) => *n as f64,
+        Sample::U16(n) => *n as f64,
+        Sample::U32(n) => *n as f64,
+        Sample::U64(n) => *n as f64,
+        Sample::U128(n) => *n as f64,
+        Sample::U256(n) => bytes(&n.to_le_bytes()),
+        Sample::Address { choice, address, .. } => *choice as f64 + address.map_or(0.0, |a| bytes(&a.to_vec())),
+        Sample::Vector(values) | Sample::Fields(values) => values.iter().map(magnitude).sum(),
+        Sample::Text(text) => text.iter().map(|c| *c as u32 as f64).sum(),
+        Sample::Variant(tag, values) => *tag as f64 + values.iter().map(magnitude).sum::<f64>(),
+        Sample::Empty => 0.0,
+    }
+}
+
+/// Orders inputs by encoded length, then by the magnitude of their values, so
+/// that accepting only smaller candidates always terminates.
+pub fn compare(types: &[FuzzerType], a: &[Sample], b: &[Sample], config: &InputConfig) -> Ordering {
+    let len = |s| encode_all(types, s, config).len();
+    let mag = |s: &[Sample]| s.iter().map(magnitude).sum::<f64>();
+    len(a)
+        .cmp(&len(b))
+        .then_with(|| mag(a).partial_cmp(&mag(b)).unwrap_or(Ordering::Equal))
+}
+
+fn shrink_int(n: u128) -> Vec<u128> {
+    if n == 0 {
+        return vec![];
+    }
+    let mut res = vec![0, n / 2, n - 1];
+    res.dedup();
+    res
+}
+
+/// Simpler versions of a single value.
+fn shrink(ty: &FuzzerType, sample: &Sample, config: &InputConfig) -> Vec<Sample> {
+    match (ty, sample) {
+        (_, Sample::Bool(true)) => vec![Sample::Bool(false)],
+        (_, Sample::U8(n)) => shrink_int(*n as u128).into_iter().map(|n| Sample::U8(n as u8)).collect(),
+        (_, Sample::U16(n)) => shrink_int(*n as u128).into_iter().map(|n| Sample::U16(n as u16)).collect(),
+        (_, Sample::U32(n)) => shrink_int(*n as u128).into_iter().map(|n| Sample::U32(n as u32)).collect(),
+        (_, Sample::U64(n)) => shrink_int(*n as u128).into_iter().map(|n| Sample::U64(n as u64)).collect(),
+        (_, Sample::U128(n)) => shrink_int(*n).into_iter().map(Sample::U128).collect(),
+        (_, Sample::U256(n)) if *n != MoveU256::zero() => {
+            let mut res = vec![Sample::U256(MoveU256::zero()), Sample::U256(*n >> 1)];
+            res.dedup();
+            res
+        }
+        (_, Sample::Address { choice, choices, address }) => {
+            let mut res = vec![];
+            if *choice != 0 {
+                let pool = if *ty == FuzzerType::Signer { &config.signers } else { &config.addresses };
+                let address = pool.get(0).map_or(Some(AccountAddress::ZERO), |_| None);
+                res.push(Sample::Address { choice: 0, choices: *choices, address });
+            }
+            if matches!(address, Some(a) if *a != AccountAddress::ZERO) {
+                res.push(Sample::Address { choice: *choice, choices: *choices, address: Some(AccountAddress::ZERO) });
+            }
+            res
+        }
+        (FuzzerType::Vector(t), Sample::Vector(values)) => {
+            let mut res = vec![];
+            if !values.is_empty() {
+                res.push(Sample::Vector(vec![]));
+            }
+            if values.len() > 1 {
+                let half = values.len() / 2;
+                res.push(Sample::Vector(values[..half].to_vec()));
+                res.push(Sample::Vector(values[half..].to_vec()));
+            }
+            for i in 0..values.len() {
+                let mut dropped = values.clone();
+                dropped.remove(i);
+                res.push(Sample::Vector(dropped));
+            }
+            for (i, value) in values.iter().enumerate() {
+                for smaller in shrink(t, value, config) {
+                    let mut values = values.clone();
+                    values[i] = smaller;
+                    res.push(Sample::Vector(values));
+                }
+            }
+            res
+        }
+        (FuzzerType::Text(_), Sample::Text(text)) => {
+            let mut res = vec![];
+            if !text.is_empty() {
+                res.push(Sample::Text(vec![]));
+            }
+            if text.len() > 1 {
+                let half = text.len() / 2;
+                res.push(Sample::Text(text[..half].to_vec()));
+                res.push(Sample::Text(text[half..].to_vec()));
+            }
+            for i in 0..text.len() {
+                let mut dropped = text.clone();
+                dropped.remove(i);
+                res.push(Sample::Text(dropped));
+            }
+            res
+        }
+        (FuzzerType::Struct(types), Sample::Fields(values)) => shrink_all(types, values, config).into_iter().map(Sample::Fields).collect(),
+        (FuzzerType::Constructor(c), Sample::Fields(values)) => shrink_all(&c.args, values, config).into_iter().map(Sample::Fields).collect(),
+        (FuzzerType::Enum(variants), Sample::Variant(tag, values)) => {
+            let mut res = vec![];
+            if *tag != 0 {
+                // The fields of the first variant decoded from an exhausted input
+                let fields = parse_all(&variants[0], &mut Unstructured::new(&[]), config);
+                res.push(Sample::Variant(0, fields));
+            }
+            res.extend(shrink_all(&variants[*tag], values, config).into_iter().map(|v| Sample::Variant(*tag, v)));
+            res
+        }
+        (FuzzerType::Option(_) | FuzzerType::FixedPoint32 | FuzzerType::TxContext | FuzzerType::Clock, sample) => shrink(&ty.decoded_as(), sample, config),
+        _ => vec![],
+    }
+}
+
+/// Simpler versions of a list of values, changing one value at a time.
+pub fn shrink_all(types: &[FuzzerType], samples: &[Sample], config: &InputConfig) -> Vec<Vec<Sample>> {
+    let mut res = vec![];
+    for (i, (ty, sample)) in types.iter().zip(samples).enumerate() {
+        for smaller in shrink(ty, sample, config) {
+            let mut samples = samples.to_vec();
+            samples[i] = smaller;
+            res.push(samples);
+        }
+    }
+    res
+}
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use move_binary_format::file_format::{CodeOffset, FunctionDefinitionIndex};
+use move_binary_format::CompiledModule;
+use move_bytecode_source_map::source_map::SourceMap;
+use move_bytecode_source_map::utils::source_map_from_file;
+use move_command_line_common::files::FileHash;
+use move_core_types::language_storage::ModuleId;
+use walkdir::WalkDir;
+
+use crate::STARTUP_LOG_TARGET;
+
+/// A source file the source maps point into.
+#[derive(Debug)]
+struct SourceFile {
+    /// File name, e.g. `coin.move`
+    name: String,
+    /// Byte offset of the start of each line
+    line_starts: Vec<u32>,
+}
+
+impl SourceFile {
+    fn new(path: &Path, contents: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(contents.match_indices('\n').map(|(i, _)| i as u32 + 1))
+            .collect();
+        SourceFile {
+            name: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            line_starts,
+        }
+    }
+
+    /// Line, starting from 1, of the byte at `offset`.
+    fn line(&self, offset: u32) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset)
+    }
+}
+
+/// Files under `dir` with the extension `ext`, by file stem.
+fn files_by_stem(dir: &Path, ext: &str) -> HashMap<String, Vec<PathBuf>> {
+    let mut files: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.extension().map_or(false, |e| e == ext) {
+            if let Some(stem) = path.file_stem() {
+                files.entry(stem.to_string_lossy().into_owned()).or_default().push(path.to_owned());
+            }
+        }
+    }
+    files
+}
+
+/// Source maps of the loaded modules and the sources they point into, as
+/// the package build leaves them next to the bytecode modules. Both are
+/// optional in the build output, modules without them are reported with
+/// bytecode offsets only.
+#[derive(Debug, Default)]
+pub struct SourceMaps {
+    maps: HashMap<ModuleId, SourceMap>,
+    files: HashMap<FileHash, SourceFile>,
+}
+
+impl SourceMaps {
+    /// Reads the source maps and sources of `modules`, from the build
+    /// directory holding the `bytecode_modules` the module at `module_path`
+    /// is in: `build/<package>/source_maps/<module>.mvsm` and
+    /// `build/<package>/sources/<module>.move`, dependencies being in their
+    /// `dependencies` subdirectories.
+    pub fn load(module_path: &Path, modules: &[CompiledModule]) -> Self {
+        let mut source_maps = SourceMaps::default();
+        let build_dir = match module_path.parent().and_then(Path::parent) {
+            Some(build_dir) => build_dir,
+            None => return source_maps,
+        };
+
+        let map_files = files_by_stem(&build_dir.join("source_maps"), "mvsm");
+        for module in modules {
+            let id = module.self_id();
+            // Modules of different packages may share a name, the map of this
+            // one has its constants and functions
+            let map = map_files
+                .get(id.name().as_str())
+                .into_iter()
+                .flatten()
+                .filter_map(|path| source_map_from_file(path).ok())
+                .find(|map| {
+                    map.function_map.len() == module.function_defs().len()
+                        && map.constant_map.values().all(|&idx| (idx as usize) < module.constant_pool().len())
+                });
+            if let Some(map) = map {
+                source_maps.maps.insert(id, map);
+            }
+        }
+
+        for paths in files_by_stem(&build_dir.join("sources"), "move").values() {
+            for path in paths {
+                if let Ok(contents) = fs::read_to_string(path) {
+                    source_maps.files.insert(FileHash::new(&contents), SourceFile::new(path, &contents));
+                }
+            }
+        }
+        log::debug!(
+            target: STARTUP_LOG_TARGET,
+            "Read {} source maps and {} source files",
+            source_maps.maps.len(),
+            source_maps.files.len()
+        );
+        source_maps
+    }
+
+    /// The source map of module `id`.
+    pub fn get(&self, id: &ModuleId) -> Option<&SourceMap> {
+        self.maps.get(id)
+    }
+
+    /// Source line of the instruction at `offset` in function `fdef_idx` of
+    /// module `id`, as `file.move:line`.
+    pub fn line(&self, id: &ModuleId, fdef_idx: FunctionDefinitionIndex, offset: CodeOffset) -> Option<String> {
+        let loc = self.get(id)?.get_code_location(fdef_idx, offset).ok()?;
+        let file = self.files.get(&loc.file_hash())?;
+        Some(format!("{}:{}", file.name, file.line(loc.start())))
+    }
+}
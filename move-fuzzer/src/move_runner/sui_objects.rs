@@ -0,0 +1,60 @@
+use move_core_types::account_address::AccountAddress;
+use move_core_types::runtime_value::{MoveStruct, MoveValue};
+
+use super::types::FuzzerType;
+
+/// Address of the shared `sui::clock::Clock` object.
+const CLOCK_ADDRESS: AccountAddress = {
+    let mut bytes = [0; AccountAddress::LENGTH];
+    bytes[AccountAddress::LENGTH - 1] = 0x6;
+    AccountAddress::new(bytes)
+};
+
+/// First object id handed out as a fresh `UID`, past the system objects and
+/// the test accounts.
+const FRESH_UIDS_START: u64 = 0x2000;
+
+/// Length of a transaction digest, which `tx_context::derive_id` hashes.
+const TX_HASH_LENGTH: usize = 32;
+
+/// Recognizes the Sui framework objects entry functions commonly take, by
+/// full struct name, returning how their values are generated. A `Coin<T>` or
+/// `Balance<T>` is built field by field, its balance being generated.
+pub fn object_type(name: &str) -> Option<FuzzerType> {
+    match name {
+        "0x2::object::UID" => Some(FuzzerType::Uid),
+        "0x2::tx_context::TxContext" => Some(FuzzerType::TxContext),
+        "0x2::clock::Clock" => Some(FuzzerType::Clock),
+        "0x2::balance::Balance" => Some(FuzzerType::Struct(vec![FuzzerType::U64])),
+        "0x2::coin::Coin" => Some(FuzzerType::Struct(vec![FuzzerType::Uid, FuzzerType::Struct(vec![FuzzerType::U64])])),
+        _ => None,
+    }
+}
+
+/// A `UID` holding `address`, through its `ID`.
+fn uid(address: AccountAddress) -> MoveValue {
+    MoveValue::Struct(MoveStruct(vec![MoveValue::Struct(MoveStruct(vec![MoveValue::Address(address)]))]))
+}
+
+/// The `index`-th fresh `UID` of an input, distinct from the others it holds.
+pub fn fresh_uid(index: u64) -> MoveValue {
+    let mut bytes = [0; AccountAddress::LENGTH];
+    bytes[AccountAddress::LENGTH - 8..].copy_from_slice(&(FRESH_UIDS_START + index).to_be_bytes());
+    uid(AccountAddress::new(bytes))
+}
+
+/// The clock object, at the given time.
+pub fn clock(timestamp_ms: u64) -> MoveValue {
+    MoveValue::Struct(MoveStruct(vec![uid(CLOCK_ADDRESS), MoveValue::U64(timestamp_ms)]))
+}
+
+/// A transaction context sent by `sender`, with no object created yet.
+pub fn tx_context(sender: AccountAddress, epoch: u64, epoch_timestamp_ms: u64) -> MoveValue {
+    MoveValue::Struct(MoveStruct(vec![
+        MoveValue::Address(sender),
+        MoveValue::vector_u8(vec![0; TX_HASH_LENGTH]),
+        MoveValue::U64(epoch),
+        MoveValue::U64(epoch_timestamp_ms),
+        MoveValue::U64(0),
+    ]))
+}
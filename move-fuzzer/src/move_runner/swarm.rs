@@ -0,0 +1,84 @@
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+
+use super::addresses::AddressWeights;
+
+/// Vector end thresholds to sample from, from about one element per vector
+/// on average up to a few hundred.
+const VECTOR_ENDS: [u8; 4] = [128, 32, 8, 1];
+
+/// Percentages of special integers to sample from.
+const SPECIAL_INT_PERCENTS: [u8; 4] = [0, 10, 25, 50];
+
+/// Weights of each kind of address to sample from.
+const ADDRESS_WEIGHTS: [usize; 3] = [0, 1, 4];
+
+/// Generation settings sampled once per worker, so that the workers started
+/// with `-jobs` each explore inputs of a different shape instead of all
+/// sharing the same distribution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwarmProfile {
+    /// See `InputConfig::vector_end`
+    pub vector_end: u8,
+    pub special_int_percent: u8,
+    pub address_weights: AddressWeights,
+}
+
+/// A small deterministic generator, so that a profile is reproduced from its
+/// seed and a crossover from libFuzzer's.
+pub struct SplitMix64(pub u64);
+
+impl SplitMix64 {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`, `n` being positive.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn choose<T: Copy>(&mut self, values: &[T]) -> T {
+        values[self.below(values.len())]
+    }
+}
+
+impl SwarmProfile {
+    /// Samples the profile identified by `seed`.
+    pub fn sample(seed: u64) -> Self {
+        let mut rng = SplitMix64(seed);
+        let vector_end = rng.choose(&VECTOR_ENDS);
+        let special_int_percent = rng.choose(&SPECIAL_INT_PERCENTS);
+        let mut address_weights = AddressWeights {
+            named: rng.choose(&ADDRESS_WEIGHTS),
+            core: rng.choose(&ADDRESS_WEIGHTS),
+            zero: rng.choose(&ADDRESS_WEIGHTS),
+            random: rng.choose(&ADDRESS_WEIGHTS),
+        };
+        if address_weights.random == 0 && address_weights.named == 0 {
+            address_weights.random = 1;
+        }
+        SwarmProfile { vector_end, special_int_percent, address_weights }
+    }
+}
+
+impl fmt::Display for SwarmProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let weights = self.address_weights;
+        write!(
+            f,
+            "vector-end={}, special-int-percent={}, address-weights=named={},core={},zero={},random={}",
+            self.vector_end, self.special_int_percent, weights.named, weights.core, weights.zero, weights.random,
+        )
+    }
+}
+
+/// A seed that differs between processes, for workers not given one.
+pub fn random_seed() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
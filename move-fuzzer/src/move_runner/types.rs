@@ -1,11 +1,19 @@
 use std::fmt::Display;
 
+use arbitrary::{Arbitrary, Result as ArbitraryResult, Unstructured};
+use clap::ValueEnum;
 use enum_as_inner::EnumAsInner;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
+use move_core_types::account_address::AccountAddress;
+use move_core_types::language_storage::{ModuleId, TypeTag};
+use move_core_types::runtime_value::{MoveStruct, MoveValue};
 use move_model::{model::{GlobalEnv, ModuleId as ModelModuleId, StructId}, symbol::SymbolPool, ty::{PrimitiveType, Type as MoveType}};
 
+use crate::move_runner::sui_objects::{clock, object_type, tx_context};
+use crate::move_runner::utils::{find_constructor, find_enum_variants};
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash, EnumAsInner)]
 pub enum FuzzerType {
     U8,
@@ -17,10 +25,124 @@ pub enum FuzzerType {
     Bool,
     Vector(Box<FuzzerType>),
     Struct(Vec<FuzzerType>),
+    Constructor(Constructor),
+    Enum(Vec<Vec<FuzzerType>>),
     Signer,
     Address,
+    /// A `vector<u8>` holding valid text
+    Text(TextEncoding),
+    /// A `std::option::Option<T>`, either `none` or `some` of a value
+    Option(Box<FuzzerType>),
+    /// A `std::fixed_point32::FixedPoint32` whose integer part fits in a
+    /// `u16`, so that arithmetic on it doesn't overflow on most inputs
+    FixedPoint32,
+    /// A `sui::object::UID`, a fresh object id that doesn't read any input
+    Uid,
+    /// A `sui::tx_context::TxContext` sent by an account of the signer pool
+    TxContext,
+    /// The `sui::clock::Clock` object, at a generated time
+    Clock,
+    /// A parameter type we can't generate values for
+    Unsupported(String),
+}
+
+/// A public function returning a struct, used to build struct arguments that
+/// respect the invariants enforced by the defining module.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub struct Constructor {
+    pub module: ModuleId,
+    pub function: String,
+    pub args: Vec<FuzzerType>,
+}
+
+/// How struct arguments are generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum StructGeneration {
+    /// Fill the struct fields directly with arbitrary values
+    #[default]
+    Fields,
+    /// Call a public constructor of the struct, failing if none exists
+    Constructors,
+    /// Call a public constructor of the struct, filling the fields directly if none exists
+    ConstructorsOrFields,
+}
+
+/// A concrete instantiation of a generic target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instantiation {
+    pub type_args: Vec<TypeTag>,
+    /// Parameters of the target instantiated with `type_args`
+    pub params: Vec<FuzzerType>,
+    /// `Some(mutable)` for the parameters taken by reference
+    pub references: Vec<Option<bool>>,
+}
+
+/// How the instantiation of a generic target is picked for each execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum TypeArgSelection {
+    /// Don't instantiate generic targets, their type parameters are unsupported
+    #[default]
+    None,
+    /// Select the instantiation with a hash of the input, which spreads them
+    /// evenly over the inputs
+    Hash,
+    /// Select the instantiation with the first byte of the input
+    Input,
+}
+
+/// Which functions are accepted as the target, by visibility.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum TargetVisibility {
+    /// Only `public` functions, callable from other modules and transactions
+    Public,
+    /// Only `entry` functions, callable from transactions
+    Entry,
+    /// Any function, including private helpers
+    #[default]
+    Any,
 }
 
+/// How byte vectors holding text are generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum StringGeneration {
+    /// Generate every `vector<u8>` as arbitrary bytes
+    Bytes,
+    /// Generate valid text for `std::string::String` and `std::ascii::String` values
+    #[default]
+    Strings,
+    /// Also generate every other `vector<u8>` as valid UTF-8
+    All,
+}
+
+/// The characters a text value is made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub enum TextEncoding {
+    Ascii,
+    Utf8,
+}
+
+impl TextEncoding {
+    /// Reads a character: a single byte for ASCII ones, a marker byte followed
+    /// by a code point for the others.
+    pub fn decode(&self, u: &mut Unstructured) -> ArbitraryResult<char> {
+        let byte = u8::arbitrary(u)?;
+        if byte < 0x80 || *self == TextEncoding::Ascii {
+            return Ok(char::from(byte & 0x7f));
+        }
+        let code = u32::arbitrary(u)? % (char::MAX as u32 + 1);
+        Ok(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+
+    /// Writes the bytes `decode` reads to produce `c`.
+    pub fn encode(&self, c: char, out: &mut Vec<u8>) {
+        if c.is_ascii() {
+            out.push(c as u8);
+        } else {
+            out.push(0x80);
+            out.extend((c as u32).to_le_bytes());
+        }
+    }
+}
 
 impl From<FuzzerType> for MoveType {
     fn from(value: FuzzerType) -> Self {
@@ -32,11 +154,24 @@ impl From<FuzzerType> for MoveType {
             FuzzerType::U128 => MoveType::Primitive(PrimitiveType::U128),
             FuzzerType::Bool => MoveType::Primitive(PrimitiveType::Bool),
             FuzzerType::Vector(t) => MoveType::Vector(Box::new(MoveType::from(*t))),
+            FuzzerType::Text(_) => MoveType::Vector(Box::new(MoveType::Primitive(PrimitiveType::U8))),
             FuzzerType::Struct(types) => MoveType::Struct(
                 ModelModuleId::new(42),
                 StructId::new(SymbolPool::new().make("")),
                 types.into_iter().map(|t| MoveType::from(t)).collect_vec(),
             ),
+            FuzzerType::Unsupported(_) => MoveType::Error,
+            FuzzerType::Constructor(_)
+            | FuzzerType::Enum(_)
+            | FuzzerType::Option(_)
+            | FuzzerType::FixedPoint32
+            | FuzzerType::Uid
+            | FuzzerType::TxContext
+            | FuzzerType::Clock => MoveType::Struct(
+                ModelModuleId::new(42),
+                StructId::new(SymbolPool::new().make("")),
+                vec![],
+            ),
             FuzzerType::U256 => MoveType::Primitive(PrimitiveType::U256),
             FuzzerType::Signer => MoveType::Primitive(PrimitiveType::Signer),
             FuzzerType::Address => MoveType::Primitive(PrimitiveType::Address),
@@ -45,7 +180,14 @@ impl From<FuzzerType> for MoveType {
 }
 
 impl FuzzerType {
-    pub fn from(env: &GlobalEnv, value: MoveType) -> Self {
+    pub fn from(
+        env: &GlobalEnv,
+        value: MoveType,
+        struct_generation: StructGeneration,
+        string_generation: StringGeneration,
+        raw_std_types: bool,
+        constructing: &[(ModelModuleId, StructId)],
+    ) -> Self {
         match value {
             MoveType::Primitive(p) => match p {
                 move_model::ty::PrimitiveType::Bool => FuzzerType::Bool,
@@ -57,27 +199,213 @@ impl FuzzerType {
                 move_model::ty::PrimitiveType::U256 => FuzzerType::U256,
                 move_model::ty::PrimitiveType::Address => FuzzerType::Address,
                 move_model::ty::PrimitiveType::Signer => FuzzerType::Signer,
-                move_model::ty::PrimitiveType::Num => todo!(),
-                move_model::ty::PrimitiveType::Range => todo!(),
-                move_model::ty::PrimitiveType::EventStore => todo!(),
+                move_model::ty::PrimitiveType::Num
+                | move_model::ty::PrimitiveType::Range
+                | move_model::ty::PrimitiveType::EventStore => FuzzerType::Unsupported(format!("{:?}", p)),
             },
+            MoveType::Vector(vec) if string_generation == StringGeneration::All
+                && *vec == MoveType::Primitive(PrimitiveType::U8) => FuzzerType::Text(TextEncoding::Utf8),
             MoveType::Vector(vec) => {
-                FuzzerType::Vector(Box::new(FuzzerType::from(env, *vec)))
+                FuzzerType::Vector(Box::new(FuzzerType::from(env, *vec, struct_generation, string_generation, raw_std_types, constructing)))
             },
-            MoveType::Struct(module_id, struct_id, _) => {
-                let module_env = env.get_modules().find(|m| m.get_id() == module_id).unwrap();
+            MoveType::Struct(module_id, struct_id, type_args) => {
+                let module_env = match env.get_modules().find(|m| m.get_id() == module_id) {
+                    Some(module_env) => module_env,
+                    None => return FuzzerType::Unsupported(format!("no module for datatype {}", struct_id.display(env.symbol_pool()))),
+                };
+                if module_env.find_struct(struct_id.symbol()).is_none() {
+                    // Enums are not part of the model, so they are resolved from the bytecode
+                    let variants = match find_enum_variants(env, module_id, struct_id) {
+                        Some(variants) => variants,
+                        None => return FuzzerType::Unsupported(format!("could not find datatype {}", struct_id.display(env.symbol_pool()))),
+                    };
+                    return FuzzerType::Enum(
+                        variants
+                            .into_iter()
+                            .map(|fields| {
+                                fields
+                                    .into_iter()
+                                    .map(|t| FuzzerType::from(env, t.instantiate(&type_args), struct_generation, string_generation, raw_std_types, constructing))
+                                    .collect_vec()
+                            })
+                            .collect_vec()
+                    );
+                }
                 let struct_env = module_env.get_struct(struct_id);
-                let fields = struct_env.get_fields().map(|f| f.get_type()).collect::<Vec<MoveType>>();
-                FuzzerType::Struct(fields.into_iter().map(|t| FuzzerType::from(env, t)).collect_vec())
+                if string_generation != StringGeneration::Bytes {
+                    // Their constructors abort on invalid text, so the bytes field is filled directly
+                    match struct_env.get_full_name_str().as_str() {
+                        "0x1::string::String" => return FuzzerType::Struct(vec![FuzzerType::Text(TextEncoding::Utf8)]),
+                        "0x1::ascii::String" => return FuzzerType::Struct(vec![FuzzerType::Text(TextEncoding::Ascii)]),
+                        _ => {}
+                    }
+                }
+                if !raw_std_types {
+                    // Sui objects come from the store in transactions, they are synthesized instead
+                    if let Some(object) = object_type(&struct_env.get_full_name_str()) {
+                        return object;
+                    }
+                    // Their fields allow values the std functions reject, e.g. an option holding two elements
+                    match struct_env.get_full_name_str().as_str() {
+                        "0x1::option::Option" => {
+                            let element = type_args.into_iter().next().unwrap_or(MoveType::Error);
+                            return FuzzerType::Option(Box::new(FuzzerType::from(env, element, struct_generation, string_generation, raw_std_types, constructing)));
+                        }
+                        "0x1::fixed_point32::FixedPoint32" => return FuzzerType::FixedPoint32,
+                        _ => {}
+                    }
+                }
+                if struct_generation != StructGeneration::Fields {
+                    if let Some(constructor) = find_constructor(env, module_id, struct_id, struct_generation, string_generation, raw_std_types, constructing) {
+                        return FuzzerType::Constructor(constructor);
+                    }
+                    if struct_generation == StructGeneration::Constructors {
+                        return FuzzerType::Unsupported(format!("no public constructor for {}", struct_env.get_full_name_str()));
+                    }
+                }
+                let fields = struct_env.get_fields().map(|f| f.get_type().instantiate(&type_args)).collect::<Vec<MoveType>>();
+                FuzzerType::Struct(fields.into_iter().map(|t| FuzzerType::from(env, t, struct_generation, string_generation, raw_std_types, constructing)).collect_vec())
             }
-            MoveType::Tuple(_) => todo!(),
-            MoveType::TypeParameter(_) => todo!(),
-            MoveType::Reference(_, _) => todo!(),
-            MoveType::Fun(_, _) => todo!(),
-            MoveType::TypeDomain(_) => todo!(),
-            MoveType::ResourceDomain(_, _, _) => todo!(),
-            MoveType::Error => todo!(),
-            MoveType::Var(_) => todo!(),
+            // The VM takes reference arguments serialized as their referenced value and
+            // borrows them itself, so we generate the owned value. The values written
+            // through `&mut` parameters are returned by the session and decoded into
+            // `ExecutionOutcome::mutated`.
+            MoveType::Reference(_, ty) => FuzzerType::from(env, *ty, struct_generation, string_generation, raw_std_types, constructing),
+            unsupported => FuzzerType::Unsupported(format!("{:?}", unsupported)),
+        }
+    }
+}
+
+impl FuzzerType {
+    /// The type values of this one are decoded as: std types read like an
+    /// enum of `none` and `some` or like the integer and fractional parts of a
+    /// number, and are converted by `std_value`; other types as themselves.
+    pub fn decoded_as(&self) -> FuzzerType {
+        match self {
+            FuzzerType::Option(t) => FuzzerType::Enum(vec![vec![], vec![(**t).clone()]]),
+            FuzzerType::FixedPoint32 => FuzzerType::Struct(vec![FuzzerType::U16, FuzzerType::U32]),
+            // The sender, epoch and epoch timestamp
+            FuzzerType::TxContext => FuzzerType::Struct(vec![FuzzerType::Signer, FuzzerType::U64, FuzzerType::U64]),
+            FuzzerType::Clock => FuzzerType::Struct(vec![FuzzerType::U64]),
+            _ => self.clone(),
+        }
+    }
+
+    /// Converts a value generated for `decoded_as` into a value of this type.
+    pub fn std_value(&self, value: MoveValue) -> MoveValue {
+        match (self, value) {
+            (FuzzerType::Option(_), MoveValue::Variant(variant)) => {
+                MoveValue::Struct(MoveStruct(vec![MoveValue::Vector(variant.fields)]))
+            }
+            (FuzzerType::FixedPoint32, MoveValue::Struct(MoveStruct(parts))) => match parts.as_slice() {
+                [MoveValue::U16(integer), MoveValue::U32(fraction)] => {
+                    MoveValue::Struct(MoveStruct(vec![MoveValue::U64(((*integer as u64) << 32) | *fraction as u64)]))
+                }
+                _ => MoveValue::Struct(MoveStruct(parts)),
+            },
+            (FuzzerType::TxContext, MoveValue::Struct(MoveStruct(parts))) => match parts.as_slice() {
+                [MoveValue::Signer(sender), MoveValue::U64(epoch), MoveValue::U64(timestamp)] => {
+                    tx_context(*sender, *epoch, *timestamp)
+                }
+                _ => MoveValue::Struct(MoveStruct(parts)),
+            },
+            (FuzzerType::Clock, MoveValue::Struct(MoveStruct(parts))) => match parts.as_slice() {
+                [MoveValue::U64(timestamp)] => clock(*timestamp),
+                _ => MoveValue::Struct(MoveStruct(parts)),
+            },
+            (_, value) => value,
+        }
+    }
+
+    /// Number of input bytes consumed to generate a value of this type when
+    /// every vector is empty and every enum picks its smallest variant.
+    pub fn min_input_size(&self) -> usize {
+        match self {
+            FuzzerType::U8 | FuzzerType::Bool => 1,
+            FuzzerType::U16 => 2,
+            FuzzerType::U32 => 4,
+            FuzzerType::U64 => 8,
+            FuzzerType::U128 => 16,
+            FuzzerType::U256 => 32,
+            FuzzerType::Signer | FuzzerType::Address => AccountAddress::LENGTH,
+            // A single `false` terminates the vector
+            FuzzerType::Vector(_) | FuzzerType::Text(_) => 1,
+            FuzzerType::Struct(fields) => fields.iter().map(|f| f.min_input_size()).sum(),
+            FuzzerType::Constructor(constructor) => constructor.args.iter().map(|a| a.min_input_size()).sum(),
+            FuzzerType::Enum(variants) => {
+                // The variant index takes a byte, unless there is a single variant
+                let tag_size = if variants.len() > 1 { 1 } else { 0 };
+                tag_size + variants
+                    .iter()
+                    .map(|fields| fields.iter().map(|f| f.min_input_size()).sum::<usize>())
+                    .min()
+                    .unwrap_or(0)
+            }
+            FuzzerType::Option(_) | FuzzerType::FixedPoint32 | FuzzerType::TxContext | FuzzerType::Clock => {
+                self.decoded_as().min_input_size()
+            }
+            FuzzerType::Uid | FuzzerType::Unsupported(_) => 0,
+        }
+    }
+
+    /// Bound on the input bytes consumed to generate a value of this type when
+    /// every vector and text has `vector_len` elements.
+    pub fn max_input_size(&self, vector_len: usize) -> usize {
+        match self {
+            FuzzerType::Bool => 1,
+            // A byte deciding whether a boundary value is picked, then the pick
+            FuzzerType::U8
+            | FuzzerType::U16
+            | FuzzerType::U32
+            | FuzzerType::U64
+            | FuzzerType::U128
+            | FuzzerType::U256 => self.min_input_size() + 1 + 4,
+            // An index in the address pool, then a random address
+            FuzzerType::Signer | FuzzerType::Address => 4 + AccountAddress::LENGTH,
+            // Each element follows a continuation byte, a last byte ends the vector
+            FuzzerType::Vector(t) => 1 + vector_len * (1 + t.max_input_size(vector_len)),
+            // Characters take a marker byte and a code point at most
+            FuzzerType::Text(_) => 1 + vector_len * (1 + 1 + 4),
+            FuzzerType::Struct(fields) => fields.iter().map(|f| f.max_input_size(vector_len)).sum(),
+            FuzzerType::Constructor(constructor) => constructor.args.iter().map(|a| a.max_input_size(vector_len)).sum(),
+            FuzzerType::Enum(variants) => {
+                4 + variants
+                    .iter()
+                    .map(|fields| fields.iter().map(|f| f.max_input_size(vector_len)).sum::<usize>())
+                    .max()
+                    .unwrap_or(0)
+            }
+            FuzzerType::Option(_) | FuzzerType::FixedPoint32 | FuzzerType::TxContext | FuzzerType::Clock => {
+                self.decoded_as().max_input_size(vector_len)
+            }
+            FuzzerType::Uid | FuzzerType::Unsupported(_) => 0,
+        }
+    }
+
+    /// Whether every value of this type consumes the same number of input bytes.
+    pub fn is_fixed_size(&self) -> bool {
+        match self {
+            FuzzerType::Vector(_)
+            | FuzzerType::Text(_)
+            | FuzzerType::Enum(_)
+            | FuzzerType::Option(_)
+            | FuzzerType::Unsupported(_) => false,
+            FuzzerType::Struct(fields) => fields.iter().all(|f| f.is_fixed_size()),
+            FuzzerType::Constructor(constructor) => constructor.args.iter().all(|a| a.is_fixed_size()),
+            _ => true,
+        }
+    }
+
+    /// Whether values of this type, or of any nested type, can't be generated.
+    pub fn is_unsupported(&self) -> bool {
+        match self {
+            FuzzerType::Unsupported(_) => true,
+            FuzzerType::Vector(t) => t.is_unsupported(),
+            FuzzerType::Struct(fields) => fields.iter().any(|f| f.is_unsupported()),
+            FuzzerType::Constructor(constructor) => constructor.args.iter().any(|a| a.is_unsupported()),
+            FuzzerType::Enum(variants) => variants.iter().flatten().any(|f| f.is_unsupported()),
+            FuzzerType::Option(t) => t.is_unsupported(),
+            _ => false,
         }
     }
 }
@@ -93,15 +421,21 @@ impl Display for FuzzerType {
             | FuzzerType::U256 
             | FuzzerType::Bool 
             | FuzzerType::Vector(_)
+            | FuzzerType::Text(_)
             | FuzzerType::Signer
-            | FuzzerType::Address => write!(f, "{:?}", self),
+            | FuzzerType::Address
+            | FuzzerType::FixedPoint32
+            | FuzzerType::Uid
+            | FuzzerType::TxContext
+            | FuzzerType::Clock
+            | FuzzerType::Unsupported(_) => write!(f, "{:?}", self),
+            FuzzerType::Option(t) => write!(f, "Option({})", t),
             FuzzerType::Struct(types) => {
                 if types.is_empty() {
                     write!(f, "Struct([])")
                 } else {
                     write!(f, "Struct([ ").unwrap();
                     for (i, t) in types.iter().enumerate() {
-                        eprintln!("{:?}", t);
                         write!(f, "{}", t).unwrap();
                         if i != types.len() - 1 {
                             write!(f, ", ").unwrap();
@@ -110,6 +444,19 @@ impl Display for FuzzerType {
                     write!(f, " ])")
                 }
             }
+            FuzzerType::Constructor(constructor) => {
+                write!(f, "Constructor({}::{}, {})", constructor.module, constructor.function, Parameters(constructor.args.clone()))
+            }
+            FuzzerType::Enum(variants) => {
+                write!(f, "Enum([ ").unwrap();
+                for (i, fields) in variants.iter().enumerate() {
+                    write!(f, "{}", Parameters(fields.clone())).unwrap();
+                    if i != variants.len() - 1 {
+                        write!(f, ", ").unwrap();
+                    }
+                }
+                write!(f, " ])")
+            }
         }
     }
 }
@@ -143,20 +490,70 @@ pub enum Error {
     ArithmeticError { message: String },
     MemoryLimitExceeded { message: String },
     Unknown { message: String },
-    AccountAddressParseError { message: String }
+    AccountAddressParseError { message: String },
+    ConstructorFailed { message: String },
+    PropertyViolated { message: String },
+    InvariantViolated { message: String },
+    /// The input generates values beyond the configured limits
+    InputLimitExceeded { message: String },
+}
+
+impl Error {
+    /// Names of the error classes, as used by `--on-error`.
+    pub const CLASSES: [&'static str; 12] = [
+        "abort",
+        "runtime",
+        "out-of-bound",
+        "out-of-gas",
+        "arithmetic-error",
+        "memory-limit-exceeded",
+        "unknown",
+        "account-address-parse-error",
+        "constructor-failed",
+        "property-violated",
+        "invariant-violated",
+        "input-limit-exceeded",
+    ];
+
+    /// The class of the error, one of `CLASSES`.
+    pub fn class(&self) -> &'static str {
+        let index = match self {
+            Error::Abort { .. } => 0,
+            Error::Runtime { .. } => 1,
+            Error::OutOfBound { .. } => 2,
+            Error::OutOfGas { .. } => 3,
+            Error::ArithmeticError { .. } => 4,
+            Error::MemoryLimitExceeded { .. } => 5,
+            Error::Unknown { .. } => 6,
+            Error::AccountAddressParseError { .. } => 7,
+            Error::ConstructorFailed { .. } => 8,
+            Error::PropertyViolated { .. } => 9,
+            Error::InvariantViolated { .. } => 10,
+            Error::InputLimitExceeded { .. } => 11,
+        };
+        Self::CLASSES[index]
+    }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::Abort { message  } => write!(f, "Abort - {}", message),
-            Error::OutOfBound { message: _ } => write!(f, "OutOfBound"),
-            Error::OutOfGas { message: _ } => write!(f, "OutOfGas"),
-            Error::ArithmeticError { message: _ } => write!(f, "ArithmeticError"),
-            Error::MemoryLimitExceeded { message: _ } => write!(f, "MemoryLimitExceeded"),
+            Error::OutOfBound { message } if message.is_empty() => write!(f, "OutOfBound"),
+            Error::OutOfBound { message } => write!(f, "OutOfBound - {}", message),
+            Error::OutOfGas { message } if message.is_empty() => write!(f, "OutOfGas"),
+            Error::OutOfGas { message } => write!(f, "OutOfGas - {}", message),
+            Error::ArithmeticError { message } if message.is_empty() => write!(f, "ArithmeticError"),
+            Error::ArithmeticError { message } => write!(f, "ArithmeticError - {}", message),
+            Error::MemoryLimitExceeded { message } if message.is_empty() => write!(f, "MemoryLimitExceeded"),
+            Error::MemoryLimitExceeded { message } => write!(f, "MemoryLimitExceeded - {}", message),
             Error::Unknown { message } => write!(f, "Unknown - {}", message),
             Error::Runtime { message } => write!(f, "Runtime - {}", message),
             Error::AccountAddressParseError { message } => write!(f, "AccountAddressParseError - {}", message),
+            Error::ConstructorFailed { message } => write!(f, "ConstructorFailed - {}", message),
+            Error::PropertyViolated { message } => write!(f, "PropertyViolated - {}", message),
+            Error::InvariantViolated { message } => write!(f, "InvariantViolated - {}", message),
+            Error::InputLimitExceeded { message } => write!(f, "InputLimitExceeded - {}", message),
         }
     }
 }
\ No newline at end of file
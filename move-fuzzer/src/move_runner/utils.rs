@@ -1,20 +1,29 @@
 use std::fs::File;
 use std::io::Read;
 
-use move_binary_format::file_format::{FunctionDefinitionIndex, StructDefinitionIndex};
-use move_binary_format::CompiledModule;use move_model::addr_to_big_uint;
+use move_binary_format::file_format::{AbilitySet, FunctionDefinitionIndex, StructDefinitionIndex};
+use move_binary_format::binary_config::BinaryConfig;
+use move_binary_format::file_format_common::{BinaryConstants, BinaryFlavor, VERSION_MAX, VERSION_MIN};
+use move_binary_format::CompiledModule;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use move_model::addr_to_big_uint;
 use move_model::ast::ModuleName;
 use move_model::model::FunId;
 use move_model::model::FunctionData;
+use move_model::model::FunctionVisibility;
 use move_model::model::GlobalEnv;
 use move_model::model::Loc;
 use move_model::model::ModuleData;
 use move_model::model::ModuleId as ModelModuleId;
 use move_model::model::StructId;
-use move_model::ty::Type as MoveType;
+use move_model::ty::{PrimitiveType, Type as MoveType};
+use itertools::Itertools;
 use move_bytecode_utils::Modules;
+use move_bytecode_verifier::verify_module_with_config_unmetered;
+use move_vm_config::runtime::VMConfig;
 
-use crate::move_runner::types::FuzzerType;
+use crate::move_runner::types::{Constructor, FuzzerType, Instantiation, StringGeneration, StructGeneration};
 
 /// From https://github.com/kunalabs-io/sui-client-gen
 pub fn add_modules_to_model<'a>(
@@ -55,21 +64,158 @@ pub fn add_modules_to_model<'a>(
     }
 }
 
+/// Builds the model of `modules`, added in dependency order.
+fn model_of(modules: &[CompiledModule]) -> Result<GlobalEnv, String> {
+    let module_map = Modules::new(modules.iter());
+    let dep_graph = module_map.compute_dependency_graph();
+    let topo_order = dep_graph
+        .compute_topological_order()
+        .map_err(|e| format!("Could not order the module dependencies: {}", e))?;
+
+    let mut env = GlobalEnv::new();
+    add_modules_to_model(&mut env, topo_order);
+    Ok(env)
+}
+
+/// Types a type parameter can be instantiated with: the primitives and the
+/// non-generic structs declared in the package at `address`, with their
+/// abilities.
+fn instantiation_candidates(env: &GlobalEnv, address: &AccountAddress) -> Vec<(TypeTag, MoveType, AbilitySet)> {
+    let primitives = [
+        (TypeTag::Bool, PrimitiveType::Bool),
+        (TypeTag::U8, PrimitiveType::U8),
+        (TypeTag::U16, PrimitiveType::U16),
+        (TypeTag::U32, PrimitiveType::U32),
+        (TypeTag::U64, PrimitiveType::U64),
+        (TypeTag::U128, PrimitiveType::U128),
+        (TypeTag::U256, PrimitiveType::U256),
+        (TypeTag::Address, PrimitiveType::Address),
+    ];
+    let mut candidates = primitives
+        .into_iter()
+        .map(|(tag, ty)| (tag, MoveType::Primitive(ty), AbilitySet::PRIMITIVES))
+        .collect::<Vec<_>>();
+    for module_env in env.get_modules() {
+        let module = module_env.get_verified_module();
+        if module.address() != address {
+            continue;
+        }
+        for def in module.struct_defs() {
+            let handle = module.datatype_handle_at(def.struct_handle);
+            if !handle.type_parameters.is_empty() {
+                continue;
+            }
+            let name = module.identifier_at(handle.name);
+            let struct_env = match module_env.find_struct(env.symbol_pool().make(name.as_str())) {
+                Some(struct_env) => struct_env,
+                None => continue,
+            };
+            let tag = TypeTag::Struct(Box::new(StructTag {
+                address: *address,
+                module: module.self_id().name().to_owned(),
+                name: name.to_owned(),
+                type_params: vec![],
+            }));
+            let ty = MoveType::Struct(module_env.get_id(), struct_env.get_id(), vec![]);
+            candidates.push((tag, ty, handle.abilities));
+        }
+    }
+    candidates
+}
+
+/// Enumerates concrete instantiations of the generic function
+/// `module_name::function_name` with the types of its package and the
+/// primitives satisfying the constraints of its type parameters, at most
+/// `limit` of them. Instantiations with parameters that can't be generated
+/// are left out; a function without type parameters has none.
+pub fn enumerate_instantiations(
+    modules: Vec<CompiledModule>,
+    module_name: &str,
+    function_name: &str,
+    struct_generation: StructGeneration,
+    string_generation: StringGeneration,
+    raw_std_types: bool,
+    limit: usize,
+) -> Result<Vec<Instantiation>, String> {
+    let env = model_of(&modules)?;
+    let module_env = env
+        .get_modules()
+        .find(|m| m.matches_name(module_name))
+        .ok_or_else(|| format!("Could not find target module `{}`", module_name))?;
+    let module = module_env.get_verified_module();
+    let constraints = module
+        .function_defs()
+        .iter()
+        .map(|def| module.function_handle_at(def.function))
+        .find(|handle| module.identifier_at(handle.name).as_str() == function_name)
+        .map(|handle| handle.type_parameters.clone())
+        .ok_or_else(|| format!("Could not find target function `{}` in module `{}`", function_name, module_name))?;
+    if constraints.is_empty() {
+        return Ok(vec![]);
+    }
+    let func = module_env
+        .get_functions()
+        .find(|f| f.get_name_str() == function_name)
+        .ok_or_else(|| format!("Could not find target function `{}` in module `{}`", function_name, module_name))?;
+
+    let candidates = instantiation_candidates(&env, module.address());
+    let choices = constraints
+        .iter()
+        .map(|constraint| {
+            candidates
+                .iter()
+                .filter(|(_, _, abilities)| constraint.is_subset(*abilities))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let mut instantiations = vec![];
+    for combination in choices.into_iter().multi_cartesian_product() {
+        if instantiations.len() == limit {
+            log::warn!("Only the first {} instantiations of `{}` are fuzzed", limit, function_name);
+            break;
+        }
+        let type_args = combination.iter().map(|(_, ty, _)| ty.clone()).collect::<Vec<_>>();
+        let params = func
+            .get_parameter_types()
+            .into_iter()
+            .map(|param| param.instantiate(&type_args))
+            .collect::<Vec<_>>();
+        let references = params
+            .iter()
+            .map(|p| match p {
+                MoveType::Reference(mutable, _) => Some(*mutable),
+                _ => None,
+            })
+            .collect();
+        let params = transform_params(&env, params, struct_generation, string_generation, raw_std_types, &[]);
+        if params.iter().any(FuzzerType::is_unsupported) {
+            continue;
+        }
+        instantiations.push(Instantiation {
+            type_args: combination.into_iter().map(|(tag, _, _)| tag.clone()).collect(),
+            params,
+            references,
+        });
+    }
+    if instantiations.is_empty() {
+        return Err(format!("No instantiation of `{}` has parameters that can be generated", function_name));
+    }
+    log::debug!("{} instantiations of `{}`", instantiations.len(), function_name);
+    Ok(instantiations)
+}
+
 pub fn generate_abi_from_bin(
     modules: Vec<CompiledModule>,
     module_name: &str,
     function_name: &str,
-) -> (Vec<FuzzerType>, usize) {
+    struct_generation: StructGeneration,
+    string_generation: StringGeneration,
+    raw_std_types: bool,
+) -> Result<(Vec<FuzzerType>, Vec<Option<bool>>, usize), String> {
     let params;
     let max_coverage;
 
-    let module_map = Modules::new(modules.iter());
-    let dep_graph = module_map.compute_dependency_graph();
-    let topo_order = dep_graph.compute_topological_order().unwrap();
-
-    let mut env = GlobalEnv::new();
-    add_modules_to_model(&mut env, topo_order);
-
+    let env = model_of(&modules)?;
     let module_env = env.get_modules().find(|m| m.matches_name(module_name));
     if let Some(env) = module_env {
 
@@ -80,26 +226,188 @@ pub fn generate_abi_from_bin(
             max_coverage = f.get_bytecode().len();
             params = f.get_parameter_types();
         } else {
-            panic!("Could not find target function !");
+            return Err(format!("Could not find target function `{}` in module `{}`", function_name, module_name));
         }
     } else {
-        panic!("Could not find target module !")
+        return Err(format!("Could not find target module `{}`", module_name));
     }
-    println!("ABI generation completed...");
-    (transform_params(&env, params), max_coverage)
+    log::debug!("ABI generation completed");
+    let references = params
+        .iter()
+        .map(|p| match p {
+            MoveType::Reference(mutable, _) => Some(*mutable),
+            _ => None,
+        })
+        .collect();
+    Ok((transform_params(&env, params, struct_generation, string_generation, raw_std_types, &[]), references, max_coverage))
 }
 
-pub fn load_compiled_module(path: &str) -> CompiledModule {
-    let mut f = File::open(path).unwrap();
+/// Bytecode version of a serialized module, `None` if it isn't one.
+fn bytecode_version(bytes: &[u8]) -> Option<u32> {
+    let magic = BinaryConstants::MOVE_MAGIC_SIZE;
+    if bytes.len() < magic + 4 || bytes[..magic] != BinaryConstants::MOVE_MAGIC {
+        return None;
+    }
+    let flavored = u32::from_le_bytes(bytes[magic..magic + 4].try_into().ok()?);
+    Some(BinaryFlavor::decode_version(flavored))
+}
+
+/// Reads the compiled module at `path`, accepting bytecode versions up to
+/// `max_version`, the latest the runner supports when `None`.
+pub fn load_compiled_module(path: &str, max_version: Option<u32>) -> Result<CompiledModule, String> {
     let mut buffer = Vec::new();
-    f.read_to_end(&mut buffer).unwrap();
-    CompiledModule::deserialize_with_defaults(&buffer).unwrap()
+    File::open(path)
+        .and_then(|mut f| f.read_to_end(&mut buffer))
+        .map_err(|e| format!("Could not read module {}: {}", path, e))?;
+    let max_version = max_version.unwrap_or(VERSION_MAX);
+    if max_version > VERSION_MAX || max_version < VERSION_MIN {
+        return Err(format!(
+            "Unsupported bytecode version {}, the runner supports versions {} to {}",
+            max_version, VERSION_MIN, VERSION_MAX
+        ));
+    }
+    let config = BinaryConfig::legacy(max_version, VERSION_MIN, true);
+    CompiledModule::deserialize_with_config(&buffer, &config).map_err(|e| match bytecode_version(&buffer) {
+        Some(version) if version > VERSION_MAX || version < VERSION_MIN => format!(
+            "Module {} has bytecode version {}, the runner supports versions {} to {}; rebuild the package for a supported version",
+            path, version, VERSION_MIN, VERSION_MAX
+        ),
+        Some(version) if version > max_version => format!(
+            "Module {} has bytecode version {}, above --bytecode-version {}",
+            path, version, max_version
+        ),
+        _ => format!("Could not deserialize module {}: {:?}", path, e.major_status()),
+    })
 }
 
-fn transform_params(env: &GlobalEnv, params: Vec<MoveType>) -> Vec<FuzzerType> {
+/// Runs the bytecode verifier over `modules` with the checks the VM applies
+/// when it loads them, so that a module it would reject is reported at
+/// startup instead of failing every execution.
+pub fn verify_modules(modules: &[CompiledModule], config: &VMConfig) -> Result<(), String> {
+    for module in modules {
+        if let Err(e) = verify_module_with_config_unmetered(&config.verifier, module) {
+            let mut reason = format!("{:?}", e.major_status());
+            if let Some(message) = e.message() {
+                reason.push_str(&format!(": {}", message));
+            }
+            if let Some((fdef_idx, offset)) = e.offsets().first() {
+                let handle = module.function_handle_at(module.function_def_at(*fdef_idx).function);
+                reason.push_str(&format!(" at {}+{}", module.identifier_at(handle.name), offset));
+            }
+            return Err(format!(
+                "Module {} fails bytecode verification: {}",
+                module.self_id().short_str_lossless(),
+                reason
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn transform_params(
+    env: &GlobalEnv,
+    params: Vec<MoveType>,
+    struct_generation: StructGeneration,
+    string_generation: StringGeneration,
+    raw_std_types: bool,
+    constructing: &[(ModelModuleId, StructId)],
+) -> Vec<FuzzerType> {
     let mut res = vec![];
     for param in params {
-        res.push(FuzzerType::from(env, param));
+        res.push(FuzzerType::from(env, param, struct_generation, string_generation, raw_std_types, constructing));
     }
     res
+}
+
+/// Returns the field types of every variant of the given enum, in tag order.
+pub fn find_enum_variants(
+    env: &GlobalEnv,
+    module_id: ModelModuleId,
+    struct_id: StructId,
+) -> Option<Vec<Vec<MoveType>>> {
+    let module_env = env.get_module(module_id);
+    let module = module_env.get_verified_module();
+    let name = env.symbol_pool().string(struct_id.symbol());
+    let enum_def = module.enum_defs().iter().find(|def| {
+        module.identifier_at(module.datatype_handle_at(def.enum_handle).name).as_str() == name.as_str()
+    })?;
+
+    Some(
+        enum_def
+            .variants
+            .iter()
+            .map(|variant| {
+                variant
+                    .fields
+                    .iter()
+                    .map(|field| module_env.globalize_signature(&field.signature.0))
+                    .collect()
+            })
+            .collect(),
+    )
+}
+
+fn mentions_struct(ty: &MoveType, module_id: ModelModuleId, struct_id: StructId) -> bool {
+    match ty {
+        MoveType::Struct(m, s, type_args) => {
+            (*m == module_id && *s == struct_id)
+                || type_args.iter().any(|t| mentions_struct(t, module_id, struct_id))
+        }
+        MoveType::Vector(t) | MoveType::Reference(_, t) => mentions_struct(t, module_id, struct_id),
+        _ => false,
+    }
+}
+
+fn is_generable(ty: &MoveType) -> bool {
+    match ty {
+        MoveType::Primitive(_) => true,
+        MoveType::Vector(t) | MoveType::Reference(_, t) => is_generable(t),
+        MoveType::Struct(_, _, type_args) => type_args.iter().all(is_generable),
+        _ => false,
+    }
+}
+
+/// Looks for a public, non generic function returning exactly the given struct
+/// and taking only parameters we are able to generate. Functions taking the
+/// struct itself as a parameter are skipped, since they can't bootstrap a value,
+/// and so are the ones taking a struct in `constructing`, whose constructors
+/// are being looked up for the arguments of this one.
+pub fn find_constructor(
+    env: &GlobalEnv,
+    module_id: ModelModuleId,
+    struct_id: StructId,
+    struct_generation: StructGeneration,
+    string_generation: StringGeneration,
+    raw_std_types: bool,
+    constructing: &[(ModelModuleId, StructId)],
+) -> Option<Constructor> {
+    let constructing = [constructing, &[(module_id, struct_id)]].concat();
+    let module_env = env.get_module(module_id);
+    let candidate = module_env.get_functions().find(|f| {
+        if f.visibility() != FunctionVisibility::Public || f.get_type_parameter_count() != 0 {
+            return false;
+        }
+        let returns = f.get_return_types();
+        if returns.len() != 1 {
+            return false;
+        }
+        let returns_struct = matches!(&returns[0], MoveType::Struct(m, s, type_args) if *m == module_id && *s == struct_id && type_args.is_empty());
+        let params = f.get_parameter_types();
+        returns_struct
+            && params.iter().all(is_generable)
+            && !params.iter().any(|p| constructing.iter().any(|(m, s)| mentions_struct(p, *m, *s)))
+    })?;
+
+    Some(Constructor {
+        module: module_env.get_verified_module().self_id(),
+        function: candidate.get_name_str(),
+        args: transform_params(
+            env,
+            candidate.get_parameter_types(),
+            struct_generation,
+            string_generation,
+            raw_std_types,
+            &constructing,
+        ),
+    })
 }
\ No newline at end of file